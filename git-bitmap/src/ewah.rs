@@ -0,0 +1,255 @@
+//! Bitmaps compressed with _EWAH_, the _Enhanced Word-Aligned Hybrid_ run-length encoding used by git.
+//!
+//! A compressed bitmap is a sequence of 64 bit words, each _marker word_ describing a run of words with all bits set or unset,
+//! followed by a given amount of literal words that are stored as is.
+//!
+//! Bitmaps are built by setting bits in ascending order just like git does, which makes the serialized form byte-for-byte
+//! identical to what git would produce for the same bits.
+use std::{convert::TryInto, io, iter::FromIterator};
+
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+
+///
+pub mod decode {
+    /// Returned by [`decode()`][super::decode()]
+    #[derive(thiserror::Error, Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("{message}")]
+        Corrupt { message: &'static str },
+    }
+}
+
+const BITS_IN_WORD: usize = 64;
+const RUNNING_LEN_BITS: u32 = 32;
+const LARGEST_RUNNING_COUNT: u64 = (1 << RUNNING_LEN_BITS) - 1;
+const LARGEST_LITERAL_COUNT: u64 = (1 << (BITS_IN_WORD as u32 - 1 - RUNNING_LEN_BITS)) - 1;
+
+mod rlw {
+    use super::{LARGEST_LITERAL_COUNT, LARGEST_RUNNING_COUNT, RUNNING_LEN_BITS};
+
+    pub fn running_bit(w: u64) -> bool {
+        w & 1 == 1
+    }
+    pub fn running_len(w: u64) -> u64 {
+        (w >> 1) & LARGEST_RUNNING_COUNT
+    }
+    pub fn literal_words(w: u64) -> u64 {
+        w >> (1 + RUNNING_LEN_BITS)
+    }
+    pub fn set_running_bit(w: &mut u64, bit: bool) {
+        *w = (*w & !1) | bit as u64;
+    }
+    pub fn set_running_len(w: &mut u64, len: u64) {
+        *w = (*w & !(LARGEST_RUNNING_COUNT << 1)) | (len & LARGEST_RUNNING_COUNT) << 1;
+    }
+    pub fn set_literal_words(w: &mut u64, count: u64) {
+        *w = (*w & ((1 << (1 + RUNNING_LEN_BITS)) - 1)) | (count & LARGEST_LITERAL_COUNT) << (1 + RUNNING_LEN_BITS);
+    }
+}
+
+/// A bitmap compressed with EWAH.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vec {
+    num_bits: usize,
+    words: std::vec::Vec<u64>,
+    /// The position of the last marker word in `words`.
+    rlw: usize,
+}
+
+impl Default for Vec {
+    fn default() -> Self {
+        Vec {
+            num_bits: 0,
+            words: vec![0],
+            rlw: 0,
+        }
+    }
+}
+
+/// Decode a compressed bitmap from the beginning of `data`, returning it along with all bytes following it.
+pub fn decode(data: &[u8]) -> Result<(Vec, &[u8]), decode::Error> {
+    let corrupt = |message| decode::Error::Corrupt { message };
+    if data.len() < 8 {
+        return Err(corrupt("EWAH bitmap header is truncated"));
+    }
+    let num_bits = BigEndian::read_u32(&data[..4]) as usize;
+    let num_words = BigEndian::read_u32(&data[4..8]) as usize;
+    let words_end = num_words
+        .checked_mul(8)
+        .and_then(|len| len.checked_add(8))
+        .filter(|end| end + 4 <= data.len())
+        .ok_or_else(|| corrupt("EWAH bitmap is truncated"))?;
+    let words: std::vec::Vec<u64> = data[8..words_end].chunks(8).map(BigEndian::read_u64).collect();
+    let rlw = BigEndian::read_u32(&data[words_end..words_end + 4]) as usize;
+
+    let mut pos = 0;
+    while pos < words.len() {
+        pos += 1 + rlw::literal_words(words[pos]) as usize;
+    }
+    if pos != words.len() {
+        return Err(corrupt("EWAH bitmap has more literal words than it contains"));
+    }
+    if rlw >= words.len() {
+        return Err(corrupt("EWAH bitmap refers to a marker word it doesn't contain"));
+    }
+
+    Ok((Vec { num_bits, words, rlw }, &data[words_end + 4..]))
+}
+
+/// Access and serialization
+impl Vec {
+    /// The amount of bits in the uncompressed bitmap, which is one past the highest set bit if it was built by git.
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+
+    /// Call `f` with the index of each set bit in ascending order, stopping early if it returns `false`.
+    pub fn for_each_set_bit(&self, mut f: impl FnMut(usize) -> bool) {
+        let mut index = 0;
+        let mut pos = 0;
+        while pos < self.words.len() {
+            let marker = self.words[pos];
+            pos += 1;
+            let running_len = rlw::running_len(marker) as usize;
+            if rlw::running_bit(marker) {
+                for bit in index..index + running_len * BITS_IN_WORD {
+                    if !f(bit) {
+                        return;
+                    }
+                }
+            }
+            index += running_len * BITS_IN_WORD;
+            let literal_count = rlw::literal_words(marker) as usize;
+            for word in &self.words[pos..pos + literal_count] {
+                for bit in 0..BITS_IN_WORD {
+                    if word & (1 << bit) != 0 && !f(index + bit) {
+                        return;
+                    }
+                }
+                index += BITS_IN_WORD;
+            }
+            pos += literal_count;
+        }
+    }
+
+    /// Write this instance to `out` in the format understood by [`decode()`].
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        let to_u32 = |num: usize| -> io::Result<u32> {
+            num.try_into()
+                .map_err(|_| io::Error::new(io::ErrorKind::Other, "EWAH bitmaps can't exceed 2^32 bits or words"))
+        };
+        out.write_u32::<BigEndian>(to_u32(self.num_bits)?)?;
+        out.write_u32::<BigEndian>(to_u32(self.words.len())?)?;
+        for word in &self.words {
+            out.write_u64::<BigEndian>(*word)?;
+        }
+        out.write_u32::<BigEndian>(to_u32(self.rlw)?)
+    }
+}
+
+/// Construction
+impl Vec {
+    /// Set the bit at `index`, which must be larger than any bit set previously.
+    pub fn set(&mut self, index: usize) {
+        assert!(index >= self.num_bits, "bits must be set in ascending order");
+        let dist = div_round_up(index + 1, BITS_IN_WORD) - div_round_up(self.num_bits, BITS_IN_WORD);
+        self.num_bits = index + 1;
+        let bit = 1 << (index % BITS_IN_WORD);
+
+        if dist > 0 {
+            if dist > 1 {
+                self.add_empty_words(false, (dist - 1) as u64);
+            }
+            self.add_literal(bit);
+            return;
+        }
+
+        if rlw::literal_words(self.words[self.rlw]) == 0 {
+            let len = rlw::running_len(self.words[self.rlw]);
+            rlw::set_running_len(&mut self.words[self.rlw], len - 1);
+            self.add_literal(bit);
+            return;
+        }
+
+        let last = self.words.len() - 1;
+        self.words[last] |= bit;
+        if self.words[last] == u64::MAX {
+            self.words.pop();
+            let count = rlw::literal_words(self.words[self.rlw]);
+            rlw::set_literal_words(&mut self.words[self.rlw], count - 1);
+            self.add_empty_word(true);
+        }
+    }
+
+    fn push_rlw(&mut self) {
+        self.words.push(0);
+        self.rlw = self.words.len() - 1;
+    }
+
+    fn add_literal(&mut self, word: u64) {
+        let count = rlw::literal_words(self.words[self.rlw]);
+        if count >= LARGEST_LITERAL_COUNT {
+            self.push_rlw();
+            rlw::set_literal_words(&mut self.words[self.rlw], 1);
+        } else {
+            rlw::set_literal_words(&mut self.words[self.rlw], count + 1);
+        }
+        self.words.push(word);
+    }
+
+    fn add_empty_word(&mut self, bit: bool) {
+        let marker = self.words[self.rlw];
+        let no_literal = rlw::literal_words(marker) == 0;
+        let running_len = rlw::running_len(marker);
+        if no_literal && running_len == 0 {
+            rlw::set_running_bit(&mut self.words[self.rlw], bit);
+        }
+        if no_literal && rlw::running_bit(self.words[self.rlw]) == bit && running_len < LARGEST_RUNNING_COUNT {
+            rlw::set_running_len(&mut self.words[self.rlw], running_len + 1);
+        } else {
+            self.push_rlw();
+            rlw::set_running_bit(&mut self.words[self.rlw], bit);
+            rlw::set_running_len(&mut self.words[self.rlw], 1);
+        }
+    }
+
+    fn add_empty_words(&mut self, bit: bool, mut count: u64) {
+        let marker = self.words[self.rlw];
+        if rlw::running_bit(marker) != bit && rlw::running_len(marker) == 0 && rlw::literal_words(marker) == 0 {
+            rlw::set_running_bit(&mut self.words[self.rlw], bit);
+        } else if rlw::literal_words(marker) != 0 || rlw::running_bit(marker) != bit {
+            self.push_rlw();
+            rlw::set_running_bit(&mut self.words[self.rlw], bit);
+        }
+
+        let running_len = rlw::running_len(self.words[self.rlw]);
+        let can_add = count.min(LARGEST_RUNNING_COUNT - running_len);
+        rlw::set_running_len(&mut self.words[self.rlw], running_len + can_add);
+        count -= can_add;
+
+        while count > 0 {
+            let len = count.min(LARGEST_RUNNING_COUNT);
+            self.push_rlw();
+            rlw::set_running_bit(&mut self.words[self.rlw], bit);
+            rlw::set_running_len(&mut self.words[self.rlw], len);
+            count -= len;
+        }
+    }
+}
+
+fn div_round_up(n: usize, d: usize) -> usize {
+    (n + d - 1) / d
+}
+
+impl FromIterator<usize> for Vec {
+    /// Build a bitmap from the indices of all set bits, which must be in ascending order.
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut bitmap = Vec::default();
+        for index in iter {
+            bitmap.set(index);
+        }
+        bitmap
+    }
+}
@@ -0,0 +1,6 @@
+//! Compressed bitmaps in the formats used by git, shared by the index and pack crates.
+#![forbid(unsafe_code, rust_2018_idioms)]
+#![deny(missing_docs)]
+
+///
+pub mod ewah;
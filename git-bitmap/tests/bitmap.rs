@@ -0,0 +1,64 @@
+mod ewah {
+    use git_bitmap::ewah;
+
+    fn set_bits(bitmap: &ewah::Vec) -> Vec<usize> {
+        let mut bits = Vec::new();
+        bitmap.for_each_set_bit(|bit| {
+            bits.push(bit);
+            true
+        });
+        bits
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        for bits in [
+            vec![],
+            vec![0, 1, 2],
+            vec![3],
+            (0..64).collect(),
+            (0..200).collect(),
+            vec![1, 500, 501, 10_000],
+            (64..128).chain(Some(1000)).collect::<Vec<_>>(),
+        ] {
+            let bitmap: ewah::Vec = bits.iter().copied().collect();
+            assert_eq!(set_bits(&bitmap), bits);
+
+            let mut buf = Vec::new();
+            bitmap.write_to(&mut buf).expect("write to memory");
+            let (decoded, rest) = ewah::decode(&buf).expect("valid bitmap");
+            assert!(rest.is_empty(), "everything was consumed");
+            assert_eq!(decoded, bitmap);
+        }
+    }
+
+    #[test]
+    fn matches_git_serialization() {
+        let bitmap: ewah::Vec = vec![0, 1, 2].into_iter().collect();
+        let mut buf = Vec::new();
+        bitmap.write_to(&mut buf).expect("write to memory");
+        assert_eq!(
+            buf,
+            [0, 0, 0, 3, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn num_bits_is_one_past_the_highest_set_bit_like_in_git() {
+        assert_eq!(Some(70).into_iter().collect::<ewah::Vec>().num_bits(), 71);
+        assert_eq!(ewah::Vec::default().num_bits(), 0);
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let mut buf = Vec::new();
+        vec![1, 3, 5, 64, 66]
+            .into_iter()
+            .collect::<ewah::Vec>()
+            .write_to(&mut buf)
+            .expect("write to memory");
+        for len in 0..buf.len() {
+            assert!(ewah::decode(&buf[..len]).is_err(), "{} bytes aren't enough", len);
+        }
+    }
+}
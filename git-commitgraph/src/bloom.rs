@@ -0,0 +1,140 @@
+//! Changed-path Bloom filters, which tell for each commit whether a path was possibly changed compared to its first parent.
+use bstr::{BStr, ByteSlice};
+
+/// The parameters of the Bloom filters in a graph file, as stored in the header of its Bloom filter data chunk.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde1", derive(serde::Deserialize, serde::Serialize))]
+pub struct Settings {
+    /// The version of the hash function used to compute keys, either 1 or 2.
+    ///
+    /// Version 1 is what git writes by default, and it sign-extends path bytes larger than 0x7f like git does
+    /// on platforms with a signed `char`. Version 2 hashes these bytes correctly.
+    pub hash_version: u32,
+    /// The amount of bits set in a filter for each path.
+    pub num_hashes: u32,
+    /// The amount of bits per path in a filter, determining the filter's size.
+    pub bits_per_entry: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            hash_version: 1,
+            num_hashes: 7,
+            bits_per_entry: 10,
+        }
+    }
+}
+
+impl Settings {
+    /// Return true if filters with these settings can be read.
+    pub fn is_supported(&self) -> bool {
+        matches!(self.hash_version, 1 | 2) && self.num_hashes > 0
+    }
+}
+
+/// A Bloom filter of the paths changed by a single commit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Filter<'a> {
+    data: &'a [u8],
+    settings: Settings,
+}
+
+impl<'a> Filter<'a> {
+    pub(crate) fn new(data: &'a [u8], settings: Settings) -> Self {
+        Filter { data, settings }
+    }
+
+    /// Return the settings this filter was created with.
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// Return the raw bytes of this filter.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Return false if `path` was definitely not changed, or true if it may have been changed.
+    ///
+    /// Leading directories of `path` are checked as well, reducing the rate of false positives.
+    pub fn maybe_contains(&self, path: impl AsRef<BStr>) -> bool {
+        let path = path.as_ref().trim_end_with(|c| c == '/');
+        std::iter::once(path.len())
+            .chain(path.rfind_iter(b"/"))
+            .all(|end| self.contains_key(&key(path[..end].as_bstr(), self.settings)))
+    }
+
+    fn contains_key(&self, key: &[u32]) -> bool {
+        let num_bits = self.data.len() as u64 * 8;
+        key.iter().all(|hash| {
+            let bit = u64::from(*hash) % num_bits;
+            self.data[(bit / 8) as usize] & (1 << (bit % 8)) != 0
+        })
+    }
+}
+
+/// Compute the filter over `paths`, which have to include the leading directories of all changed paths and mustn't
+/// contain duplicates.
+pub(crate) fn filter<'a>(paths: impl ExactSizeIterator<Item = &'a BStr>, settings: Settings) -> Vec<u8> {
+    let len = (paths.len() * settings.bits_per_entry as usize + 7) / 8;
+    let mut data = vec![0; len.max(1)];
+    let num_bits = data.len() as u64 * 8;
+    for path in paths {
+        for hash in key(path, settings) {
+            let bit = u64::from(hash) % num_bits;
+            data[(bit / 8) as usize] |= 1 << (bit % 8);
+        }
+    }
+    data
+}
+
+/// The filter of commits whose changes exceed the configured limit, which contains everything.
+pub(crate) const TOO_LARGE_FILTER: &[u8] = &[0xff];
+
+fn key(path: &BStr, settings: Settings) -> Vec<u32> {
+    let hash0 = murmur3(0x293a_e76f, path, settings.hash_version);
+    let hash1 = murmur3(0x7e64_6e2c, path, settings.hash_version);
+    (0..settings.num_hashes)
+        .map(|i| hash0.wrapping_add(i.wrapping_mul(hash1)))
+        .collect()
+}
+
+/// The 32 bit murmur3 hash of `data` with the given `seed`, sign-extending bytes larger than 0x7f in `version` 1.
+fn murmur3(seed: u32, data: &[u8], version: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    let byte = |b: u8| -> u32 {
+        if version == 1 {
+            b as i8 as i32 as u32
+        } else {
+            u32::from(b)
+        }
+    };
+
+    let mut hash = seed;
+    let mut blocks = data.chunks_exact(4);
+    for block in blocks.by_ref() {
+        let mut k = byte(block[0]) | byte(block[1]) << 8 | byte(block[2]) << 16 | byte(block[3]) << 24;
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash ^= k;
+        hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0u32;
+        for (index, b) in tail.iter().enumerate().rev() {
+            k ^= byte(*b) << (8 * index);
+        }
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash ^= k;
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^= hash >> 16;
+    hash
+}
@@ -4,9 +4,12 @@ use std::{
     path::Path,
 };
 
-use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+use byteorder::{BigEndian, ByteOrder};
 
-use crate::file::{self, commit::Commit, File, COMMIT_DATA_ENTRY_SIZE};
+use crate::{
+    bloom,
+    file::{self, commit::Commit, File, COMMIT_DATA_ENTRY_SIZE_SANS_HASH},
+};
 
 /// Access
 impl File {
@@ -15,6 +18,11 @@ impl File {
         self.base_graph_count
     }
 
+    /// The settings of the changed-path Bloom filters stored in this file, or `None` if there are none we can read.
+    pub fn bloom_settings(&self) -> Option<bloom::Settings> {
+        self.bloom_filters.as_ref().map(|filters| filters.settings)
+    }
+
     /// Returns the commit data for the commit located at the given lexigraphical position.
     ///
     /// `pos` must range from 0 to self.num_commits().
@@ -30,10 +38,10 @@ impl File {
     ///
     /// Note that it is always conforming to the hash used in the owning repository.
     pub fn hash_kind(&self) -> git_hash::Kind {
-        git_hash::Kind::Sha1
+        self.hash_kind
     }
 
-    /// Returns 20 bytes sha1 at the given index in our list of (sorted) sha1 hashes.
+    /// Returns the object hash at the given index in our list of (sorted) hashes.
     /// The position ranges from 0 to self.num_commits()
     // copied from git-odb/src/pack/index/ext
     pub fn id_at(&self, pos: file::Position) -> &git_hash::oid {
@@ -47,17 +55,17 @@ impl File {
             .0
             .try_into()
             .expect("an architecture able to hold 32 bits of integer");
-        let start = self.oid_lookup_offset + (pos * SHA1_SIZE);
-        git_hash::oid::try_from(&self.data[start..start + SHA1_SIZE]).expect("20 bytes SHA1 to be alright")
+        let start = self.oid_lookup_offset + (pos * self.hash_len());
+        git_hash::oid::try_from(&self.data[start..start + self.hash_len()]).expect("a hash of the size of our kind")
     }
 
     /// Return an iterator over all object hashes stored in the base graph.
     pub fn iter_base_graph_ids(&self) -> impl Iterator<Item = &git_hash::oid> {
         let start = self.base_graphs_list_offset.unwrap_or(0);
-        let base_graphs_list = &self.data[start..start + (SHA1_SIZE * usize::from(self.base_graph_count))];
+        let base_graphs_list = &self.data[start..start + (self.hash_len() * usize::from(self.base_graph_count))];
         base_graphs_list
-            .chunks(SHA1_SIZE)
-            .map(|bytes| git_hash::oid::try_from(bytes).expect("20 bytes SHA1 to be alright"))
+            .chunks(self.hash_len())
+            .map(|bytes| git_hash::oid::try_from(bytes).expect("a hash of the size of our kind"))
     }
 
     /// return an iterator over all commits in this file.
@@ -111,6 +119,10 @@ impl File {
 }
 
 impl File {
+    pub(crate) fn hash_len(&self) -> usize {
+        self.hash_kind.len_in_bytes()
+    }
+
     /// Returns the byte slice for the given commit in this file's Commit Data (CDAT) chunk.
     pub(crate) fn commit_data_bytes(&self, pos: file::Position) -> &[u8] {
         assert!(
@@ -123,8 +135,26 @@ impl File {
             .0
             .try_into()
             .expect("an architecture able to hold 32 bits of integer");
-        let start = self.commit_data_offset + (pos * COMMIT_DATA_ENTRY_SIZE);
-        &self.data[start..start + COMMIT_DATA_ENTRY_SIZE]
+        let entry_size = self.hash_len() + COMMIT_DATA_ENTRY_SIZE_SANS_HASH;
+        let start = self.commit_data_offset + (pos * entry_size);
+        &self.data[start..start + entry_size]
+    }
+
+    /// Returns the changed-path Bloom filter of the given commit, or `None` if there is none.
+    pub(crate) fn bloom_filter(&self, pos: file::Position) -> Option<bloom::Filter<'_>> {
+        let filters = self.bloom_filters.as_ref()?;
+        let pos = pos.0 as usize;
+        let end_at = |pos: usize| {
+            let ofs = filters.index_offset + pos * 4;
+            BigEndian::read_u32(&self.data[ofs..ofs + 4]) as usize
+        };
+        let start = if pos == 0 { 0 } else { end_at(pos - 1) };
+        let end = end_at(pos);
+        let data = &self.data[filters.data_range.clone()];
+        if start >= end || end > data.len() {
+            return None;
+        }
+        Some(bloom::Filter::new(&data[start..end], filters.settings))
     }
 
     /// Returns the byte slice for this file's entire Extra Edge List (EDGE) chunk.
@@ -5,10 +5,11 @@ use std::{
     slice::Chunks,
 };
 
+use bstr::BStr;
 use byteorder::{BigEndian, ByteOrder};
-use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
 
 use crate::{
+    bloom,
     file::{self, File, EXTENDED_EDGES_MASK, LAST_EXTENDED_EDGE_MASK, NO_PARENT},
     graph,
 };
@@ -42,17 +43,31 @@ pub struct Commit<'a> {
 impl<'a> Commit<'a> {
     pub(crate) fn new(file: &'a File, pos: file::Position) -> Self {
         let bytes = file.commit_data_bytes(pos);
+        let hash_len = file.hash_len();
         Commit {
             file,
             pos,
-            root_tree_id: git_hash::oid::try_from(&bytes[..SHA1_SIZE]).expect("20 bytes SHA1 to be alright"),
-            parent1: ParentEdge::from_raw(BigEndian::read_u32(&bytes[SHA1_SIZE..SHA1_SIZE + 4])),
-            parent2: ParentEdge::from_raw(BigEndian::read_u32(&bytes[SHA1_SIZE + 4..SHA1_SIZE + 8])),
-            generation: BigEndian::read_u32(&bytes[SHA1_SIZE + 8..SHA1_SIZE + 12]) >> 2,
-            commit_timestamp: BigEndian::read_u64(&bytes[SHA1_SIZE + 8..SHA1_SIZE + 16]) & 0x0003_ffff_ffff,
+            root_tree_id: git_hash::oid::try_from(&bytes[..hash_len]).expect("a hash of the size of our kind"),
+            parent1: ParentEdge::from_raw(BigEndian::read_u32(&bytes[hash_len..hash_len + 4])),
+            parent2: ParentEdge::from_raw(BigEndian::read_u32(&bytes[hash_len + 4..hash_len + 8])),
+            generation: BigEndian::read_u32(&bytes[hash_len + 8..hash_len + 12]) >> 2,
+            commit_timestamp: BigEndian::read_u64(&bytes[hash_len + 8..hash_len + 16]) & 0x0003_ffff_ffff,
         }
     }
 
+    /// Returns the changed-path Bloom filter of this commit, or `None` if the graph file doesn't have one for it.
+    pub fn bloom_filter(&self) -> Option<bloom::Filter<'a>> {
+        self.file.bloom_filter(self.pos)
+    }
+
+    /// Returns `Some(false)` if `path` definitely wasn't changed by this commit compared to its first parent,
+    /// `Some(true)` if it may have been changed, or `None` if there is no [Bloom filter][Commit::bloom_filter()] to tell.
+    ///
+    /// `path` is slash-separated and relative to the root of the repository, like `dir/file`.
+    pub fn maybe_changed_path(&self, path: impl AsRef<BStr>) -> Option<bool> {
+        self.bloom_filter().map(|filter| filter.maybe_contains(path))
+    }
+
     /// Returns the committer timestamp of this commit.
     ///
     /// The value is the number of seconds since 1970-01-01 00:00:00 UTC.
@@ -7,12 +7,15 @@ use std::{
 use bstr::ByteSlice;
 use byteorder::{BigEndian, ByteOrder};
 use filebuffer::FileBuffer;
-use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
 
-use crate::file::{
-    ChunkId, File, BASE_GRAPHS_LIST_CHUNK_ID, CHUNK_LOOKUP_SIZE, COMMIT_DATA_CHUNK_ID, COMMIT_DATA_ENTRY_SIZE,
-    EXTENDED_EDGES_LIST_CHUNK_ID, FAN_LEN, HEADER_LEN, OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, OID_LOOKUP_ENTRY_SIZE,
-    SENTINEL_CHUNK_ID, SIGNATURE,
+use crate::{
+    bloom,
+    file::{
+        BloomFilters, ChunkId, File, BASE_GRAPHS_LIST_CHUNK_ID, BLOOM_FILTER_DATA_CHUNK_ID,
+        BLOOM_FILTER_DATA_HEADER_LEN, BLOOM_FILTER_INDEX_CHUNK_ID, CHUNK_LOOKUP_SIZE, COMMIT_DATA_CHUNK_ID,
+        COMMIT_DATA_ENTRY_SIZE_SANS_HASH, EXTENDED_EDGES_LIST_CHUNK_ID, FAN_LEN, HEADER_LEN, OID_FAN_CHUNK_ID,
+        OID_LOOKUP_CHUNK_ID, SENTINEL_CHUNK_ID, SIGNATURE,
+    },
 };
 
 /// The error used in [`File::at()`].
@@ -53,8 +56,8 @@ pub enum Error {
     UnsupportedVersion(u8),
 }
 
-const TRAILER_LEN: usize = SHA1_SIZE;
-const MIN_FILE_SIZE: usize = HEADER_LEN + ((MIN_CHUNKS + 1) * CHUNK_LOOKUP_SIZE) + TRAILER_LEN;
+const MIN_FILE_SIZE: usize =
+    HEADER_LEN + ((MIN_CHUNKS + 1) * CHUNK_LOOKUP_SIZE) + git_hash::Kind::shortest().len_in_bytes();
 
 // Required chunks: OIDF, OIDL, CDAT
 const MIN_CHUNKS: usize = 3;
@@ -97,12 +100,9 @@ impl TryFrom<&Path> for File {
         };
         ofs += 1;
 
-        match data[ofs] {
-            1 => (),
-            x => {
-                return Err(Error::UnsupportedHashVersion(x));
-            }
-        };
+        let hash_kind = git_hash::Kind::from_version(data[ofs]).ok_or(Error::UnsupportedHashVersion(data[ofs]))?;
+        let hash_len = hash_kind.len_in_bytes();
+        let commit_data_entry_size = hash_len + COMMIT_DATA_ENTRY_SIZE_SANS_HASH;
         ofs += 1;
 
         let chunk_count = data[ofs];
@@ -122,6 +122,8 @@ impl TryFrom<&Path> for File {
         }
 
         let mut base_graphs_list_offset: Option<usize> = None;
+        let mut bloom_filter_data_range: Option<Range<usize>> = None;
+        let mut bloom_filter_index: Option<(usize, usize)> = None;
         let mut commit_data_offset: Option<usize> = None;
         let mut commit_data_count = 0u32;
         let mut extra_edges_list_range: Option<Range<usize>> = None;
@@ -171,13 +173,13 @@ impl TryFrom<&Path> for File {
                     if base_graphs_list_offset.is_some() {
                         return Err(Error::DuplicateChunk(chunk_id));
                     }
-                    if chunk_size % SHA1_SIZE != 0 {
+                    if chunk_size % hash_len != 0 {
                         return Err(Error::InvalidChunkSize {
                             id: chunk_id,
-                            msg: format!("chunk size {} is not a multiple of {}", chunk_size, SHA1_SIZE),
+                            msg: format!("chunk size {} is not a multiple of {}", chunk_size, hash_len),
                         });
                     }
-                    let chunk_base_graph_count: u32 = (chunk_size / SHA1_SIZE)
+                    let chunk_base_graph_count: u32 = (chunk_size / hash_len)
                         .try_into()
                         .expect("base graph count to fit in 32-bits");
                     if chunk_base_graph_count != u32::from(base_graph_count) {
@@ -188,21 +190,45 @@ impl TryFrom<&Path> for File {
                     }
                     base_graphs_list_offset = Some(chunk_offset);
                 }
+                BLOOM_FILTER_DATA_CHUNK_ID => {
+                    if bloom_filter_data_range.is_some() {
+                        return Err(Error::DuplicateChunk(chunk_id));
+                    }
+                    if chunk_size < BLOOM_FILTER_DATA_HEADER_LEN {
+                        return Err(Error::InvalidChunkSize {
+                            id: chunk_id,
+                            msg: format!(
+                                "chunk size {} is too small to hold a {} bytes header",
+                                chunk_size, BLOOM_FILTER_DATA_HEADER_LEN
+                            ),
+                        });
+                    }
+                    bloom_filter_data_range = Some(Range {
+                        start: chunk_offset,
+                        end: next_chunk_offset,
+                    });
+                }
+                BLOOM_FILTER_INDEX_CHUNK_ID => {
+                    if bloom_filter_index.is_some() {
+                        return Err(Error::DuplicateChunk(chunk_id));
+                    }
+                    bloom_filter_index = Some((chunk_offset, chunk_size));
+                }
                 COMMIT_DATA_CHUNK_ID => {
                     if commit_data_offset.is_some() {
                         return Err(Error::DuplicateChunk(chunk_id));
                     }
-                    if chunk_size % COMMIT_DATA_ENTRY_SIZE != 0 {
+                    if chunk_size % commit_data_entry_size != 0 {
                         return Err(Error::InvalidChunkSize {
                             id: chunk_id,
                             msg: format!(
                                 "chunk size {} is not a multiple of {}",
-                                chunk_size, COMMIT_DATA_ENTRY_SIZE
+                                chunk_size, commit_data_entry_size
                             ),
                         });
                     }
                     commit_data_offset = Some(chunk_offset);
-                    commit_data_count = (chunk_size / COMMIT_DATA_ENTRY_SIZE)
+                    commit_data_count = (chunk_size / commit_data_entry_size)
                         .try_into()
                         .expect("number of commits in CDAT chunk to fit in 32 bits");
                 }
@@ -233,17 +259,14 @@ impl TryFrom<&Path> for File {
                     if oid_lookup_offset.is_some() {
                         return Err(Error::DuplicateChunk(chunk_id));
                     }
-                    if chunk_size % OID_LOOKUP_ENTRY_SIZE != 0 {
+                    if chunk_size % hash_len != 0 {
                         return Err(Error::InvalidChunkSize {
                             id: chunk_id,
-                            msg: format!(
-                                "chunk size {} is not a multiple of {}",
-                                chunk_size, OID_LOOKUP_ENTRY_SIZE
-                            ),
+                            msg: format!("chunk size {} is not a multiple of {}", chunk_size, hash_len),
                         });
                     }
                     oid_lookup_offset = Some(chunk_offset);
-                    oid_lookup_count = (chunk_size / OID_LOOKUP_ENTRY_SIZE)
+                    oid_lookup_count = (chunk_size / hash_len)
                         .try_into()
                         .expect("number of commits in OIDL chunk to fit in 32 bits");
                     // TODO(ST): Figure out how to handle this. Don't know what to do with the commented code.
@@ -270,10 +293,10 @@ impl TryFrom<&Path> for File {
         }
 
         let actual_trailer_len = data_size.saturating_sub(chunk_offset);
-        if actual_trailer_len != TRAILER_LEN {
+        if actual_trailer_len != hash_len {
             return Err(Error::Trailer(format!(
                 "Expected commit-graph trailer to contain {} bytes, got {}",
-                TRAILER_LEN, actual_trailer_len
+                hash_len, actual_trailer_len
             )));
         }
 
@@ -301,13 +324,43 @@ impl TryFrom<&Path> for File {
                 chunk2_commits: commit_data_count,
             });
         }
+        let bloom_filters = match (bloom_filter_index, bloom_filter_data_range) {
+            (Some((index_offset, index_size)), Some(data_range)) => {
+                if index_size != fan[255] as usize * 4 {
+                    return Err(Error::InvalidChunkSize {
+                        id: BLOOM_FILTER_INDEX_CHUNK_ID,
+                        msg: format!(
+                            "expected chunk length {} for {} commits, got {}",
+                            fan[255] as usize * 4,
+                            fan[255],
+                            index_size
+                        ),
+                    });
+                }
+                let header = &data[data_range.start..data_range.start + BLOOM_FILTER_DATA_HEADER_LEN];
+                let settings = bloom::Settings {
+                    hash_version: BigEndian::read_u32(&header[..4]),
+                    num_hashes: BigEndian::read_u32(&header[4..8]),
+                    bits_per_entry: BigEndian::read_u32(&header[8..]),
+                };
+                // Like git, ignore filters we can't read instead of failing to load the whole graph.
+                settings.is_supported().then(|| BloomFilters {
+                    index_offset,
+                    data_range: data_range.start + BLOOM_FILTER_DATA_HEADER_LEN..data_range.end,
+                    settings,
+                })
+            }
+            _ => None,
+        };
         Ok(File {
             base_graph_count,
             base_graphs_list_offset,
+            bloom_filters,
             commit_data_offset,
             data,
             extra_edges_list_range,
             fan,
+            hash_kind,
             oid_lookup_offset,
             path: path.to_owned(),
         })
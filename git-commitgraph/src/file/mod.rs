@@ -7,7 +7,6 @@ use std::{
 };
 
 use filebuffer::FileBuffer;
-use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
 
 pub use self::{commit::Commit, init::Error};
 
@@ -15,17 +14,19 @@ mod access;
 pub mod commit;
 mod init;
 pub mod verify;
+pub(crate) mod write;
 
 const CHUNK_LOOKUP_SIZE: usize = 12;
-const COMMIT_DATA_ENTRY_SIZE: usize = SHA1_SIZE + 16;
+const COMMIT_DATA_ENTRY_SIZE_SANS_HASH: usize = 16;
 const FAN_LEN: usize = 256;
 const HEADER_LEN: usize = 8;
-const OID_LOOKUP_ENTRY_SIZE: usize = SHA1_SIZE;
 
 const SIGNATURE: &[u8] = b"CGPH";
 
 type ChunkId = [u8; 4];
 const BASE_GRAPHS_LIST_CHUNK_ID: ChunkId = *b"BASE";
+const BLOOM_FILTER_DATA_CHUNK_ID: ChunkId = *b"BDAT";
+const BLOOM_FILTER_INDEX_CHUNK_ID: ChunkId = *b"BIDX";
 const COMMIT_DATA_CHUNK_ID: ChunkId = *b"CDAT";
 const EXTENDED_EDGES_LIST_CHUNK_ID: ChunkId = *b"EDGE";
 const OID_FAN_CHUNK_ID: ChunkId = *b"OIDF";
@@ -37,6 +38,7 @@ const SENTINEL_CHUNK_ID: ChunkId = [0u8; 4];
 const NO_PARENT: u32 = 0x7000_0000;
 const EXTENDED_EDGES_MASK: u32 = 0x8000_0000;
 const LAST_EXTENDED_EDGE_MASK: u32 = 0x8000_0000;
+const BLOOM_FILTER_DATA_HEADER_LEN: usize = 12;
 
 /// A single commit-graph file.
 ///
@@ -45,14 +47,23 @@ const LAST_EXTENDED_EDGE_MASK: u32 = 0x8000_0000;
 pub struct File {
     base_graph_count: u8,
     base_graphs_list_offset: Option<usize>,
+    bloom_filters: Option<BloomFilters>,
     commit_data_offset: usize,
     data: FileBuffer,
     extra_edges_list_range: Option<Range<usize>>,
     fan: [u32; FAN_LEN],
+    hash_kind: git_hash::Kind,
     oid_lookup_offset: usize,
     path: PathBuf,
 }
 
+/// The location of the changed-path Bloom filters within a graph file.
+struct BloomFilters {
+    index_offset: usize,
+    data_range: Range<usize>,
+    settings: crate::bloom::Settings,
+}
+
 /// The position of a given commit within a graph file, starting at 0.
 ///
 /// Commits within a graph file are sorted in lexicographical order by OID; a commit's lexigraphical position
@@ -0,0 +1,150 @@
+use std::convert::TryInto;
+
+use byteorder::{BigEndian, ByteOrder};
+use git_hash::ObjectId;
+
+use crate::{
+    bloom,
+    file::{
+        ChunkId, BASE_GRAPHS_LIST_CHUNK_ID, BLOOM_FILTER_DATA_CHUNK_ID, BLOOM_FILTER_DATA_HEADER_LEN,
+        BLOOM_FILTER_INDEX_CHUNK_ID, CHUNK_LOOKUP_SIZE, COMMIT_DATA_CHUNK_ID, COMMIT_DATA_ENTRY_SIZE_SANS_HASH,
+        EXTENDED_EDGES_LIST_CHUNK_ID, EXTENDED_EDGES_MASK, FAN_LEN, HEADER_LEN, LAST_EXTENDED_EDGE_MASK, NO_PARENT,
+        OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, SENTINEL_CHUNK_ID, SIGNATURE,
+    },
+    graph,
+};
+
+/// A commit as it is written into a graph file, with its parents already resolved to their graph positions.
+pub(crate) struct Entry {
+    pub id: ObjectId,
+    pub root_tree_id: ObjectId,
+    pub parents: Vec<graph::Position>,
+    pub generation: u32,
+    pub committer_timestamp: u64,
+    /// The changed-path Bloom filter, only used if filters are written.
+    pub bloom_filter: Vec<u8>,
+}
+
+/// Write a graph file with `entries`, sorted by id, on top of the graph files with `base_graph_ids` into `out`,
+/// along with their Bloom filters if `bloom_settings` are set, and return its checksum.
+///
+/// All ids, including the checksum, are of the given `hash_kind`.
+pub(crate) fn write(
+    entries: &[Entry],
+    hash_kind: git_hash::Kind,
+    base_graph_ids: &[ObjectId],
+    bloom_settings: Option<bloom::Settings>,
+    out: &mut Vec<u8>,
+) -> ObjectId {
+    debug_assert!(
+        entries.windows(2).all(|w| w[0].id < w[1].id),
+        "entries are sorted by id"
+    );
+    let mut extra_edges = Vec::new();
+    let mut commit_data =
+        Vec::with_capacity(entries.len() * (hash_kind.len_in_bytes() + COMMIT_DATA_ENTRY_SIZE_SANS_HASH));
+    for entry in entries {
+        commit_data.extend_from_slice(entry.root_tree_id.as_bytes());
+        let (parent1, parent2) = match entry.parents.as_slice() {
+            [] => (NO_PARENT, NO_PARENT),
+            [parent1] => (parent1.0, NO_PARENT),
+            [parent1, parent2] => (parent1.0, parent2.0),
+            [parent1, more_parents @ ..] => {
+                let extra_edges_index: u32 = (extra_edges.len() / 4)
+                    .try_into()
+                    .expect("extra edges fit into 31 bits");
+                for (index, parent) in more_parents.iter().enumerate() {
+                    let mut edge = [0; 4];
+                    let last_edge_flag = if index + 1 == more_parents.len() {
+                        LAST_EXTENDED_EDGE_MASK
+                    } else {
+                        0
+                    };
+                    BigEndian::write_u32(&mut edge, parent.0 | last_edge_flag);
+                    extra_edges.extend_from_slice(&edge);
+                }
+                (parent1.0, EXTENDED_EDGES_MASK | extra_edges_index)
+            }
+        };
+        let mut buf = [0; 16];
+        BigEndian::write_u32(&mut buf[..4], parent1);
+        BigEndian::write_u32(&mut buf[4..8], parent2);
+        BigEndian::write_u64(
+            &mut buf[8..],
+            (u64::from(entry.generation) << 34) | (entry.committer_timestamp & 0x0003_ffff_ffff),
+        );
+        commit_data.extend_from_slice(&buf);
+    }
+
+    let mut fan = vec![0; FAN_LEN * 4];
+    for (first_byte, count) in fan.chunks_mut(4).enumerate() {
+        let num_commits = entries.partition_point(|entry| usize::from(entry.id.first_byte()) <= first_byte);
+        BigEndian::write_u32(count, num_commits.try_into().expect("commit count fits into 32 bits"));
+    }
+    let mut oid_lookup = Vec::with_capacity(entries.len() * hash_kind.len_in_bytes());
+    for entry in entries {
+        oid_lookup.extend_from_slice(entry.id.as_bytes());
+    }
+    let base_graphs_list: Vec<u8> = base_graph_ids
+        .iter()
+        .flat_map(|id| id.as_bytes().iter().cloned())
+        .collect();
+
+    let mut chunks: Vec<(ChunkId, Vec<u8>)> = vec![
+        (OID_FAN_CHUNK_ID, fan),
+        (OID_LOOKUP_CHUNK_ID, oid_lookup),
+        (COMMIT_DATA_CHUNK_ID, commit_data),
+    ];
+    if !extra_edges.is_empty() {
+        chunks.push((EXTENDED_EDGES_LIST_CHUNK_ID, extra_edges));
+    }
+    if let Some(settings) = bloom_settings {
+        let mut index = Vec::with_capacity(entries.len() * 4);
+        let mut data = Vec::new();
+        for value in &[settings.hash_version, settings.num_hashes, settings.bits_per_entry] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        for entry in entries {
+            data.extend_from_slice(&entry.bloom_filter);
+            let end: u32 = (data.len() - BLOOM_FILTER_DATA_HEADER_LEN)
+                .try_into()
+                .expect("Bloom filters fit into 32 bits");
+            index.extend_from_slice(&end.to_be_bytes());
+        }
+        chunks.push((BLOOM_FILTER_INDEX_CHUNK_ID, index));
+        chunks.push((BLOOM_FILTER_DATA_CHUNK_ID, data));
+    }
+    if !base_graphs_list.is_empty() {
+        chunks.push((BASE_GRAPHS_LIST_CHUNK_ID, base_graphs_list));
+    }
+
+    out.clear();
+    out.extend_from_slice(SIGNATURE);
+    out.push(1); // version
+    out.push(hash_kind.version());
+    out.push(chunks.len().try_into().expect("few chunks"));
+    out.push(base_graph_ids.len().try_into().expect("at most 255 base graphs"));
+    debug_assert_eq!(out.len(), HEADER_LEN);
+
+    let mut chunk_offset = (HEADER_LEN + (chunks.len() + 1) * CHUNK_LOOKUP_SIZE) as u64;
+    for (id, data) in chunks
+        .iter()
+        .map(|(id, data)| (id, data.len()))
+        .chain(std::iter::once((&SENTINEL_CHUNK_ID, 0)))
+    {
+        out.extend_from_slice(id);
+        let mut offset = [0; 8];
+        BigEndian::write_u64(&mut offset, chunk_offset);
+        out.extend_from_slice(&offset);
+        chunk_offset += data as u64;
+    }
+    for (_, data) in &chunks {
+        out.extend_from_slice(data);
+    }
+
+    let mut hasher = git_features::hash::hasher(hash_kind);
+    hasher.update(out);
+    let checksum = hasher.digest();
+    out.extend_from_slice(checksum.as_bytes());
+    checksum
+}
@@ -0,0 +1,670 @@
+//! Types used when writing commit graphs with [`Graph::write_to_info_dir()`].
+use std::{
+    cmp::{max, min, Ordering},
+    collections::{hash_map::Entry, BTreeSet, HashMap},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use bstr::{BStr, BString, ByteSlice};
+use git_hash::{oid, ObjectId};
+use git_object::{immutable, tree};
+
+use crate::{bloom, file, graph, Graph, GENERATION_NUMBER_MAX, MAX_COMMITS};
+
+/// The error returned by [`Graph::write_to_info_dir()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The existing commit graph could not be loaded")]
+    Graph(#[from] graph::Error),
+    #[error("The parents of a commit in the existing commit graph could not be read")]
+    ExistingParents(#[from] file::commit::Error),
+    #[error("Commit {0} could not be found")]
+    CommitNotFound(ObjectId),
+    #[error("Commit {id} could not be decoded")]
+    CommitDecode {
+        id: ObjectId,
+        #[source]
+        err: immutable::object::decode::Error,
+    },
+    #[error("Commit {0} lacks a tree or a committer")]
+    IncompleteCommit(ObjectId),
+    #[error("Tree {0} could not be found")]
+    TreeNotFound(ObjectId),
+    #[error("Tree {id} could not be decoded")]
+    TreeDecode {
+        id: ObjectId,
+        #[source]
+        err: immutable::object::decode::Error,
+    },
+    #[error("The paths changed by commit {id} could not be obtained")]
+    ChangedPaths {
+        id: ObjectId,
+        #[source]
+        err: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error(
+        "The commit graph would contain {0} commits altogether, but only {} commits are allowed",
+        MAX_COMMITS
+    )]
+    TooManyCommits(u64),
+    #[error("A graph file could not be locked for writing")]
+    Lock(#[from] git_lock::acquire::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Determines if and how commits are written into a chain of graph files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// Write all commits into a single `info/commit-graph` file and remove any existing chain of graph files.
+    No,
+    /// Write commits not yet in the existing chain into a new graph file on top of it, without merging existing layers.
+    NoMerge,
+    /// Write commits not yet in the existing chain into a new graph file on top of it, and merge existing layers into the new one
+    /// according to git's rules: the topmost layer is merged as long as it has at most `size_multiple` times as many commits as the
+    /// new layer, or as long as the new layer has more than `max_commits` commits.
+    Merge {
+        /// The factor by which an existing layer has to be larger than the new layer to be kept, 2 by default in git.
+        size_multiple: u32,
+        /// If set, layers are merged as long as the new layer has more than this amount of commits.
+        max_commits: Option<u32>,
+    },
+    /// Merge all existing layers and all new commits into a new chain consisting of a single graph file.
+    Replace,
+}
+
+impl Default for Split {
+    fn default() -> Self {
+        Split::No
+    }
+}
+
+/// Configuration for [`Graph::write_to_info_dir()`].
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// Whether to write a single graph file or a chain of graph files.
+    pub split: Split,
+    /// If set, write changed-path Bloom filters with the given settings for all commits in the new graph file.
+    ///
+    /// Filters of commits in existing graph files are reused if they were written with the same settings.
+    pub changed_paths: Option<bloom::Settings>,
+    /// Commits changing more paths than this, including their leading directories, get a filter that matches all paths.
+    ///
+    /// Defaults to 512, just like in git.
+    pub max_changed_paths: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            split: Split::default(),
+            changed_paths: None,
+            max_changed_paths: 512,
+        }
+    }
+}
+
+/// Information about a run of [`Graph::write_to_info_dir()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The path to the newly written graph file, or `None` if there were no commits to write and nothing was changed.
+    pub path: Option<PathBuf>,
+    /// The amount of commits in the newly written graph file.
+    pub num_commits: u32,
+    /// The amount of graph files that make up the commit graph after writing, which is 1 if no chain is used.
+    pub num_layers: usize,
+}
+
+struct CommitData {
+    root_tree_id: ObjectId,
+    parents: Vec<ObjectId>,
+    committer_timestamp: u64,
+    bloom_filter: Option<Vec<u8>>,
+}
+
+/// Write
+impl Graph {
+    /// Write a commit graph with all commits reachable from `tips` into the `.git/objects/info` directory `info_dir`,
+    /// either as monolithic `commit-graph` file or as chain of files in `commit-graphs/`, depending on [`Options::split`].
+    ///
+    /// * `tips` - the commits to start the traversal from, usually the peeled targets of all references.
+    /// * `find_commit` - look up the commit with the given id, writing its data into the provided buffer, returning `None` if it
+    ///    doesn't exist or isn't a commit. It's only called for commits that aren't already in an existing graph.
+    /// * `find_tree` - like `find_commit`, but for trees, which are only needed to compute [changed paths][Options::changed_paths].
+    ///
+    /// Files are written through lock files, but graph files which are no longer referenced are deleted right away,
+    /// which may fail readers that are still about to open them. An existing monolithic `commit-graph` file is removed
+    /// when writing a chain, and vice versa.
+    pub fn write_to_info_dir<FindCommit, FindTree>(
+        info_dir: impl AsRef<Path>,
+        tips: impl IntoIterator<Item = ObjectId>,
+        find_commit: FindCommit,
+        mut find_tree: FindTree,
+        options: Options,
+    ) -> Result<Outcome, Error>
+    where
+        FindCommit: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+        FindTree: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::TreeIter<'a>>,
+    {
+        let mut state = ChangedPathsState::default();
+        Self::write_to_info_dir_inner(
+            info_dir.as_ref(),
+            tips,
+            find_commit,
+            |_id, parent_tree_id, tree_id, settings| {
+                state.filter(
+                    parent_tree_id,
+                    tree_id,
+                    &mut find_tree,
+                    settings,
+                    options.max_changed_paths,
+                )
+            },
+            options,
+        )
+    }
+
+    /// Like [`write_to_info_dir()`][Graph::write_to_info_dir()], but obtain the paths changed by commits from `changed_paths`
+    /// instead of diffing their trees.
+    ///
+    /// * `changed_paths` - called with the root tree of a commit's first parent, if there is one, and the commit's root tree
+    ///    to push the paths of all non-tree entries that differ between them into the provided vector, like `git diff-tree -r`
+    ///    would list them. It's only called for commits which don't have a reusable filter yet, and may stop once it pushed
+    ///    more than [`Options::max_changed_paths`] paths.
+    pub fn write_to_info_dir_with_changed_paths<FindCommit, ChangedPaths, E>(
+        info_dir: impl AsRef<Path>,
+        tips: impl IntoIterator<Item = ObjectId>,
+        find_commit: FindCommit,
+        mut changed_paths: ChangedPaths,
+        options: Options,
+    ) -> Result<Outcome, Error>
+    where
+        FindCommit: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+        ChangedPaths: FnMut(Option<&oid>, &oid, &mut Vec<BString>) -> Result<(), E>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let mut paths = Vec::new();
+        Self::write_to_info_dir_inner(
+            info_dir.as_ref(),
+            tips,
+            find_commit,
+            |id, parent_tree_id, tree_id, settings| {
+                paths.clear();
+                changed_paths(parent_tree_id.as_deref(), &tree_id, &mut paths)
+                    .map_err(|err| Error::ChangedPaths { id, err: Box::new(err) })?;
+                Ok(filter_from_changed_paths(&paths, settings, options.max_changed_paths))
+            },
+            options,
+        )
+    }
+
+    fn write_to_info_dir_inner<FindCommit, Filter>(
+        info_dir: &Path,
+        tips: impl IntoIterator<Item = ObjectId>,
+        mut find_commit: FindCommit,
+        mut filter: Filter,
+        options: Options,
+    ) -> Result<Outcome, Error>
+    where
+        FindCommit: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+        Filter: FnMut(ObjectId, Option<ObjectId>, ObjectId, bloom::Settings) -> Result<Vec<u8>, Error>,
+    {
+        let graphs_dir = info_dir.join("commit-graphs");
+        let chain_path = graphs_dir.join("commit-graph-chain");
+
+        let (mut chain, monolithic) = match options.split {
+            Split::No => (None, Graph::from_info_dir(info_dir).ok()),
+            _ => (
+                chain_path
+                    .is_file()
+                    .then(|| Graph::from_commit_graphs_dir(&graphs_dir))
+                    .transpose()?,
+                Graph::from_file(info_dir.join("commit-graph")).ok(),
+            ),
+        };
+
+        let mut commits = HashMap::<ObjectId, CommitData>::new();
+        let mut next: Vec<ObjectId> = tips.into_iter().collect();
+        let mut buf = Vec::new();
+        while let Some(id) = next.pop() {
+            if chain.as_ref().map_or(false, |chain| chain.lookup(id).is_some()) {
+                continue;
+            }
+            let entry = match commits.entry(id) {
+                Entry::Occupied(_) => continue,
+                Entry::Vacant(entry) => entry,
+            };
+            let commit = match monolithic.as_ref().and_then(|graph| graph.lookup(id)) {
+                Some(pos) => commit_data_from_graph(monolithic.as_ref().expect("present"), pos, options.changed_paths)?,
+                None => {
+                    let iter = find_commit(&id, &mut buf).ok_or(Error::CommitNotFound(id))?;
+                    commit_data_from_iter(id, iter)?
+                }
+            };
+            next.extend(commit.parents.iter().cloned());
+            entry.insert(commit);
+        }
+
+        let mut num_layers = chain.as_ref().map_or(0, |chain| chain.files.len());
+        let mut num_new_commits = commits.len() as u64;
+        if let Some(graph) = chain.as_ref() {
+            let (merge_all, size_multiple, max_commits) = match options.split {
+                Split::No | Split::NoMerge => (false, 0, None),
+                Split::Merge {
+                    size_multiple,
+                    max_commits,
+                } => (false, u64::from(size_multiple), max_commits.map(u64::from)),
+                Split::Replace => (true, 0, None),
+            };
+            while num_layers > 0 {
+                let top_layer_commits = u64::from(graph.files[num_layers - 1].num_commits());
+                let should_merge = merge_all
+                    || top_layer_commits <= size_multiple.saturating_mul(num_new_commits)
+                    || max_commits.map_or(false, |max| num_new_commits > max)
+                    // a graph file can only refer to 255 base graphs
+                    || num_layers > usize::from(u8::MAX);
+                if !should_merge {
+                    break;
+                }
+                num_new_commits += top_layer_commits;
+                num_layers -= 1;
+            }
+            let num_kept_commits = graph.files[..num_layers]
+                .iter()
+                .map(|file| file.num_commits())
+                .sum::<u32>();
+            for pos in num_kept_commits..graph.num_commits() {
+                let pos = graph::Position(pos);
+                commits.insert(
+                    graph.id_at(pos).to_owned(),
+                    commit_data_from_graph(graph, pos, options.changed_paths)?,
+                );
+            }
+        }
+        if chain.is_some() && num_layers == 0 {
+            chain = None;
+        }
+        let base = chain.as_ref().map(|graph| Layers {
+            graph,
+            num_layers,
+            num_commits: graph.files[..num_layers].iter().map(|file| file.num_commits()).sum(),
+        });
+
+        let total_commits = commits.len() as u64 + base.as_ref().map_or(0, |base| u64::from(base.num_commits));
+        if total_commits > u64::from(MAX_COMMITS) {
+            return Err(Error::TooManyCommits(total_commits));
+        }
+
+        let mut ids: Vec<_> = commits.keys().cloned().collect();
+        ids.sort();
+        let generations = compute_generations(&ids, &commits, base.as_ref())?;
+        let base_commits = base.as_ref().map_or(0, |base| base.num_commits);
+        let position = |id: &ObjectId| -> graph::Position {
+            match ids.binary_search(id) {
+                Ok(index) => graph::Position(base_commits + index as u32),
+                Err(_) => base
+                    .as_ref()
+                    .and_then(|base| base.graph.lookup(id))
+                    .expect("parents are either new or in a kept layer"),
+            }
+        };
+        let mut entries = Vec::with_capacity(ids.len());
+        for id in &ids {
+            let commit = &commits[id];
+            let bloom_filter = match (options.changed_paths, commit.bloom_filter.as_ref()) {
+                (None, _) => Vec::new(),
+                (Some(_), Some(filter)) => filter.clone(),
+                (Some(settings), None) => {
+                    let parent_tree_id = commit.parents.first().map(|parent| match commits.get(parent) {
+                        Some(parent) => parent.root_tree_id,
+                        None => base
+                            .as_ref()
+                            .and_then(|base| base.graph.commit_by_id(parent))
+                            .expect("parents are either new or in a kept layer")
+                            .root_tree_id()
+                            .to_owned(),
+                    });
+                    filter(*id, parent_tree_id, commit.root_tree_id, settings)?
+                }
+            };
+            entries.push(file::write::Entry {
+                id: *id,
+                root_tree_id: commit.root_tree_id,
+                parents: commit.parents.iter().map(position).collect(),
+                generation: generations[id],
+                committer_timestamp: commit.committer_timestamp,
+                bloom_filter,
+            });
+        }
+        let base_graph_ids: Vec<_> = base
+            .as_ref()
+            .map(|base| {
+                base.graph.files[..base.num_layers]
+                    .iter()
+                    .map(|file| file.checksum().to_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Release all memory maps before files are replaced or deleted.
+        drop(monolithic);
+        drop(chain);
+
+        if entries.is_empty() {
+            return Ok(Outcome {
+                path: None,
+                num_commits: 0,
+                num_layers: base_graph_ids.len(),
+            });
+        }
+
+        let mut data = Vec::new();
+        let hash_kind = entries[0].id.kind();
+        let checksum = file::write::write(&entries, hash_kind, &base_graph_ids, options.changed_paths, &mut data);
+        let num_commits = entries.len() as u32;
+        let base_graph_ids_len = base_graph_ids.len();
+        let path = if options.split == Split::No {
+            let path = write_locked(&info_dir.join("commit-graph"), &data)?;
+            if chain_path.is_file() {
+                remove_file_if_present(&chain_path)?;
+                remove_unreferenced_graph_files(&graphs_dir, &[])?;
+            }
+            path
+        } else {
+            std::fs::create_dir_all(&graphs_dir)?;
+            let path = write_locked(&graphs_dir.join(format!("graph-{}.graph", checksum)), &data)?;
+            let mut chain_ids = base_graph_ids;
+            chain_ids.push(checksum);
+            let mut chain_file = Vec::new();
+            for id in &chain_ids {
+                writeln!(chain_file, "{}", id)?;
+            }
+            write_locked(&chain_path, &chain_file)?;
+            remove_unreferenced_graph_files(&graphs_dir, &chain_ids)?;
+            remove_file_if_present(&info_dir.join("commit-graph"))?;
+            path
+        };
+        Ok(Outcome {
+            path: Some(path),
+            num_commits,
+            num_layers: base_graph_ids_len + 1,
+        })
+    }
+}
+
+/// The layers of an existing chain which are kept as base of the newly written graph file.
+struct Layers<'a> {
+    graph: &'a Graph,
+    num_layers: usize,
+    num_commits: u32,
+}
+
+impl<'a> Layers<'a> {
+    fn generation(&self, id: &oid) -> Option<u32> {
+        self.graph
+            .lookup(id)
+            .filter(|pos| pos.0 < self.num_commits)
+            .map(|pos| self.graph.commit_at(pos).generation())
+    }
+}
+
+fn compute_generations(
+    ids: &[ObjectId],
+    commits: &HashMap<ObjectId, CommitData>,
+    base: Option<&Layers<'_>>,
+) -> Result<HashMap<ObjectId, u32>, Error> {
+    let mut generations = HashMap::<ObjectId, u32>::with_capacity(ids.len());
+    let mut stack = Vec::new();
+    for id in ids {
+        if generations.contains_key(id) {
+            continue;
+        }
+        stack.push(*id);
+        while let Some(id) = stack.last().cloned() {
+            let mut max_parent_generation = 0;
+            let mut parents_missing = false;
+            for parent in &commits[&id].parents {
+                match generations
+                    .get(parent)
+                    .cloned()
+                    .or_else(|| base.and_then(|base| base.generation(parent)))
+                {
+                    Some(generation) => max_parent_generation = max(max_parent_generation, generation),
+                    None if commits.contains_key(parent) => {
+                        stack.push(*parent);
+                        parents_missing = true;
+                    }
+                    None => return Err(Error::CommitNotFound(*parent)),
+                }
+            }
+            if !parents_missing {
+                generations.insert(id, min(max_parent_generation + 1, GENERATION_NUMBER_MAX));
+                stack.pop();
+            }
+        }
+    }
+    Ok(generations)
+}
+
+/// Buffers for computing the paths changed by commits.
+#[derive(Default)]
+struct ChangedPathsState {
+    buf: Vec<u8>,
+    path: BString,
+    changed_paths: Vec<BString>,
+}
+
+/// Compute the changed-path filter for the paths of all non-tree entries a commit changed.
+fn filter_from_changed_paths(
+    changed_paths: &[BString],
+    settings: bloom::Settings,
+    max_changed_paths: usize,
+) -> Vec<u8> {
+    if changed_paths.len() > max_changed_paths {
+        return bloom::TOO_LARGE_FILTER.to_owned();
+    }
+    let mut paths = BTreeSet::<&BStr>::new();
+    for path in changed_paths {
+        for pos in path.find_iter(b"/") {
+            paths.insert(path[..pos].as_bstr());
+        }
+        paths.insert(path.as_bstr());
+    }
+    if paths.len() > max_changed_paths {
+        return bloom::TOO_LARGE_FILTER.to_owned();
+    }
+    bloom::filter(paths.into_iter(), settings)
+}
+
+/// A tree entry which doesn't borrow from the buffer it was decoded from.
+struct TreeEntry {
+    name: BString,
+    mode: tree::EntryMode,
+    id: ObjectId,
+}
+
+impl ChangedPathsState {
+    /// Compute the changed-path filter of a commit with tree `tree_id` whose first parent has tree `parent_tree_id`.
+    fn filter<FindTree>(
+        &mut self,
+        parent_tree_id: Option<ObjectId>,
+        tree_id: ObjectId,
+        mut find_tree: FindTree,
+        settings: bloom::Settings,
+        max_changed_paths: usize,
+    ) -> Result<Vec<u8>, Error>
+    where
+        FindTree: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::TreeIter<'a>>,
+    {
+        self.path.clear();
+        self.changed_paths.clear();
+        self.diff(parent_tree_id, Some(tree_id), &mut find_tree, max_changed_paths)?;
+        Ok(filter_from_changed_paths(
+            &self.changed_paths,
+            settings,
+            max_changed_paths,
+        ))
+    }
+
+    /// Record the paths of all non-tree entries that differ between the trees `lhs` and `rhs` below `self.path`,
+    /// like `git diff-tree -r` does, stopping once there are more than `max_changed_paths`.
+    fn diff<FindTree>(
+        &mut self,
+        lhs: Option<ObjectId>,
+        rhs: Option<ObjectId>,
+        find_tree: &mut FindTree,
+        max_changed_paths: usize,
+    ) -> Result<(), Error>
+    where
+        FindTree: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::TreeIter<'a>>,
+    {
+        let lhs = self.tree_entries(lhs, find_tree)?;
+        let rhs = self.tree_entries(rhs, find_tree)?;
+        let (mut lhs, mut rhs) = (lhs.iter().peekable(), rhs.iter().peekable());
+        while self.changed_paths.len() <= max_changed_paths {
+            let (old, new) = match (lhs.peek(), rhs.peek()) {
+                (None, None) => break,
+                (Some(_), None) => (lhs.next(), None),
+                (None, Some(_)) => (None, rhs.next()),
+                (Some(l), Some(r)) => match tree_entry_order(l, r) {
+                    Ordering::Less => (lhs.next(), None),
+                    Ordering::Greater => (None, rhs.next()),
+                    Ordering::Equal => {
+                        let (l, r) = (lhs.next().expect("peeked"), rhs.next().expect("peeked"));
+                        if l.id == r.id && l.mode == r.mode {
+                            continue;
+                        }
+                        (Some(l), Some(r))
+                    }
+                },
+            };
+            let entry = new.or(old).expect("at least one side is set");
+            let path_len = self.path.len();
+            if path_len != 0 {
+                self.path.push(b'/');
+            }
+            self.path.extend_from_slice(&entry.name);
+            if entry.mode.is_tree() {
+                self.diff(old.map(|e| e.id), new.map(|e| e.id), find_tree, max_changed_paths)?;
+            } else {
+                self.changed_paths.push(self.path.clone());
+            }
+            self.path.truncate(path_len);
+        }
+        Ok(())
+    }
+
+    fn tree_entries<FindTree>(
+        &mut self,
+        id: Option<ObjectId>,
+        find_tree: &mut FindTree,
+    ) -> Result<Vec<TreeEntry>, Error>
+    where
+        FindTree: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::TreeIter<'a>>,
+    {
+        let id = match id {
+            Some(id) => id,
+            None => return Ok(Vec::new()),
+        };
+        find_tree(&id, &mut self.buf)
+            .ok_or(Error::TreeNotFound(id))?
+            .map(|entry| {
+                entry
+                    .map(|entry| TreeEntry {
+                        name: entry.filename.to_owned(),
+                        mode: entry.mode,
+                        id: entry.oid.to_owned(),
+                    })
+                    .map_err(|err| Error::TreeDecode { id, err })
+            })
+            .collect()
+    }
+}
+
+/// Compare tree entries in the order git sorts them, where trees are compared as if their name ended with a slash.
+fn tree_entry_order(lhs: &TreeEntry, rhs: &TreeEntry) -> Ordering {
+    let common_len = lhs.name.len().min(rhs.name.len());
+    lhs.name[..common_len].cmp(&rhs.name[..common_len]).then_with(|| {
+        let terminator = |entry: &TreeEntry| {
+            entry
+                .name
+                .get(common_len)
+                .cloned()
+                .unwrap_or(if entry.mode.is_tree() { b'/' } else { 0 })
+        };
+        terminator(lhs).cmp(&terminator(rhs))
+    })
+}
+
+fn commit_data_from_graph(
+    graph: &Graph,
+    pos: graph::Position,
+    bloom_settings: Option<bloom::Settings>,
+) -> Result<CommitData, Error> {
+    let commit = graph.commit_at(pos);
+    Ok(CommitData {
+        bloom_filter: bloom_settings
+            .and_then(|settings| commit.bloom_filter().filter(|filter| filter.settings() == settings))
+            .map(|filter| filter.as_bytes().to_owned()),
+        root_tree_id: commit.root_tree_id().to_owned(),
+        parents: commit
+            .iter_parents()
+            .map(|parent| parent.map(|pos| graph.id_at(pos).to_owned()))
+            .collect::<Result<_, _>>()?,
+        committer_timestamp: commit.committer_timestamp(),
+    })
+}
+
+fn commit_data_from_iter(id: ObjectId, iter: immutable::CommitIter<'_>) -> Result<CommitData, Error> {
+    let mut root_tree_id = None;
+    let mut parents = Vec::new();
+    for token in iter {
+        match token.map_err(|err| Error::CommitDecode { id, err })? {
+            immutable::commit::iter::Token::Tree { id } => root_tree_id = Some(id),
+            immutable::commit::iter::Token::Parent { id } => parents.push(id),
+            immutable::commit::iter::Token::Author { .. } => {}
+            immutable::commit::iter::Token::Committer { signature } => {
+                return Ok(CommitData {
+                    bloom_filter: None,
+                    root_tree_id: root_tree_id.ok_or(Error::IncompleteCommit(id))?,
+                    parents,
+                    committer_timestamp: signature.time.time.into(),
+                })
+            }
+            _ => break,
+        }
+    }
+    Err(Error::IncompleteCommit(id))
+}
+
+fn write_locked(path: &Path, data: &[u8]) -> Result<PathBuf, Error> {
+    let mut lock = git_lock::File::acquire_to_update_resource(path, git_lock::acquire::Fail::Immediately, None)?;
+    lock.write_all(data)?;
+    Ok(lock.commit().map_err(|err| err.error)?.0)
+}
+
+fn remove_unreferenced_graph_files(graphs_dir: &Path, referenced: &[ObjectId]) -> io::Result<()> {
+    for entry in std::fs::read_dir(graphs_dir)? {
+        let path = entry?.path();
+        let is_referenced = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("graph-"))
+            .and_then(|name| name.strip_suffix(".graph"))
+            .and_then(|hex| ObjectId::from_hex(hex.as_bytes()).ok())
+            .map(|id| referenced.contains(&id));
+        if is_referenced == Some(false) {
+            remove_file_if_present(&path)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_file_if_present(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
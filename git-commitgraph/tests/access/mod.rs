@@ -69,3 +69,19 @@ fn two_parents() -> crate::Result {
 
     Ok(())
 }
+
+#[test]
+fn sha256() -> crate::Result {
+    let repo_dir = make_readonly_repo("sha256.sh");
+    let refs = inspect_refs(&repo_dir, &["parent1", "parent2", "child"]);
+    let cg = Graph::from_info_dir(repo_dir.join(".git").join("objects").join("info"))?;
+    check_common(&cg, &refs);
+
+    let file = git_commitgraph::file::File::at(repo_dir.join(".git/objects/info/commit-graph"))?;
+    assert_eq!(file.hash_kind(), git_hash::Kind::Sha256);
+    assert_eq!(cg.commit_at(refs["parent1"].pos()).generation(), 1);
+    assert_eq!(cg.commit_at(refs["child"].pos()).generation(), 2);
+    cg.verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))?;
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git checkout -q -b main
+
+mkdir -p dir/sub
+echo a > a
+echo b > dir/b
+echo c > dir/sub/c
+git add . && git commit -q -m root
+
+echo changed > dir/sub/c && git commit -q -am "modify dir/sub/c"
+chmod +x a && git commit -q -am "make a executable"
+git rm -q dir/b && git commit -q -m "delete dir/b"
+echo u > "dir/ümlaut" && git add . && git commit -q -m "add a path with bytes above 0x7f"
+
+git checkout -q -b side main~2
+echo s > side-file && git add . && git commit -q -m side
+git checkout -q main
+git merge -q --no-ff -m merge side
+
+mkdir many
+for i in $(seq 600); do echo $i > many/$i; done
+git add . && git commit -q -m "change too many paths"
@@ -0,0 +1,28 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+git commit -q --allow-empty -m c1
+git commit -q --allow-empty -m c2
+git commit -q --allow-empty -m c3
+
+git checkout -q -b a main~1
+git commit -q --allow-empty -m a
+git checkout -q -b b main~1
+git commit -q --allow-empty -m b
+git checkout -q -b c main~2
+git commit -q --allow-empty -m c
+
+git checkout -q main
+git merge -q -m octopus --no-ff a b c >/dev/null
+git commit -q --allow-empty -m after-octopus
+
+git checkout -q -b small main
+git commit -q --allow-empty -m small
+
+git checkout -q -b later small
+git commit -q --allow-empty -m later1
+git commit -q --allow-empty -m later2
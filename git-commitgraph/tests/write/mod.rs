@@ -0,0 +1,417 @@
+use std::{collections::HashMap, path::Path, process::Command};
+
+use git_commitgraph::{
+    graph::write::{Options, Split},
+    Graph,
+};
+use git_hash::ObjectId;
+use git_odb::FindExt;
+
+use crate::hex_to_id;
+
+type CommitInfo = (ObjectId, Vec<ObjectId>, u32, u64);
+
+fn commits_by_id(graph: &Graph) -> HashMap<ObjectId, CommitInfo> {
+    graph
+        .iter_commits()
+        .map(|commit| {
+            let parents = commit
+                .iter_parents()
+                .map(|pos| graph.id_at(pos.expect("valid parent")).to_owned())
+                .collect();
+            (
+                commit.id().to_owned(),
+                (
+                    commit.root_tree_id().to_owned(),
+                    parents,
+                    commit.generation(),
+                    commit.committer_timestamp(),
+                ),
+            )
+        })
+        .collect()
+}
+
+fn git(repo_dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .args(args)
+        .env_remove("GIT_DIR")
+        .output()
+        .expect("git can be executed");
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("valid UTF-8")
+}
+
+fn rev_parse(repo_dir: &Path, names: &[&str]) -> Vec<ObjectId> {
+    let mut args = vec!["rev-parse"];
+    args.extend_from_slice(names);
+    git(repo_dir, &args)
+        .lines()
+        .map(|line| hex_to_id(line.as_bytes()))
+        .collect()
+}
+
+fn write(repo_dir: &Path, tips: &[&str], split: Split) -> git_commitgraph::graph::write::Outcome {
+    write_with_options(
+        repo_dir,
+        tips,
+        Options {
+            split,
+            ..Default::default()
+        },
+    )
+}
+
+fn write_with_options(repo_dir: &Path, tips: &[&str], options: Options) -> git_commitgraph::graph::write::Outcome {
+    let objects_dir = repo_dir.join(".git").join("objects");
+    let db = git_odb::linked::Store::at(&objects_dir, git_hash::Kind::Sha1).expect("valid object database");
+    let outcome = Graph::write_to_info_dir(
+        objects_dir.join("info"),
+        rev_parse(repo_dir, tips),
+        |id, buf| {
+            db.find_existing_commit_iter(id, buf, &mut git_odb::pack::cache::Never)
+                .ok()
+        },
+        |id, buf| {
+            db.find_existing_tree_iter(id, buf, &mut git_odb::pack::cache::Never)
+                .ok()
+        },
+        options,
+    )
+    .expect("writing succeeds");
+    git(repo_dir, &["commit-graph", "verify", "--no-progress"]);
+    let graph = Graph::from_info_dir(objects_dir.join("info")).expect("written graph can be read");
+    graph
+        .verify_integrity(|_| Ok::<_, std::convert::Infallible>(()))
+        .expect("written graph is valid");
+    outcome
+}
+
+fn num_graph_files(repo_dir: &Path) -> usize {
+    std::fs::read_dir(repo_dir.join(".git/objects/info/commit-graphs"))
+        .map(|dir| {
+            dir.filter_map(Result::ok)
+                .filter(|entry| entry.file_name().to_string_lossy().ends_with(".graph"))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+#[test]
+fn single_file_matches_the_graph_written_by_git() -> crate::Result {
+    let tmp = git_testtools::scripted_fixture_repo_writable("write_graphs.sh")?;
+    let repo_dir = tmp.path();
+    let info_dir = repo_dir.join(".git/objects/info");
+
+    let outcome = write(repo_dir, &["main", "later", "a"], Split::No);
+    assert_eq!(outcome.num_commits, 11);
+    assert_eq!(outcome.num_layers, 1);
+    assert_eq!(outcome.path.as_deref(), Some(info_dir.join("commit-graph").as_path()));
+    let ours = commits_by_id(&Graph::from_info_dir(&info_dir)?);
+
+    let octopus = rev_parse(repo_dir, &["main~1"])[0];
+    assert_eq!(ours[&octopus].1.len(), 4, "the extra edges list is used");
+    assert_eq!(
+        ours[&octopus].2, 4,
+        "the generation is one more than the one of the longest parent chain"
+    );
+
+    git(repo_dir, &["commit-graph", "write", "--no-progress", "--reachable"]);
+    assert_eq!(commits_by_id(&Graph::from_info_dir(&info_dir)?), ours);
+    Ok(())
+}
+
+#[test]
+fn sha256_graphs_match_the_ones_written_by_git() -> crate::Result {
+    let tmp = git_testtools::scripted_fixture_repo_writable("sha256.sh")?;
+    let repo_dir = tmp.path();
+    let info_dir = repo_dir.join(".git/objects/info");
+    let expected = commits_by_id(&Graph::from_info_dir(&info_dir)?);
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+
+    let outcome = write(repo_dir, &["child"], Split::No);
+    assert_eq!(outcome.num_commits, 3);
+    let path = outcome.path.expect("a graph was written");
+    assert_eq!(
+        git_commitgraph::file::File::at(&path)?.hash_kind(),
+        git_hash::Kind::Sha256
+    );
+    assert_eq!(commits_by_id(&Graph::from_info_dir(&info_dir)?), expected);
+
+    let outcome = write(repo_dir, &["child"], Split::Replace);
+    assert_eq!(outcome.num_layers, 1);
+    assert_eq!(commits_by_id(&Graph::from_info_dir(&info_dir)?), expected);
+    Ok(())
+}
+
+#[test]
+fn split_chains_are_appended_to_and_merged() -> crate::Result {
+    let tmp = git_testtools::scripted_fixture_repo_writable("write_graphs.sh")?;
+    let repo_dir = tmp.path();
+    let info_dir = repo_dir.join(".git/objects/info");
+
+    let outcome = write(repo_dir, &["main"], Split::NoMerge);
+    assert_eq!((outcome.num_commits, outcome.num_layers), (8, 1));
+    let outcome = write(repo_dir, &["small"], Split::NoMerge);
+    assert_eq!((outcome.num_commits, outcome.num_layers), (1, 2));
+    assert_eq!(num_graph_files(repo_dir), 2);
+
+    let outcome = write(repo_dir, &["main", "small"], Split::NoMerge);
+    assert_eq!(outcome.path, None, "there is nothing new to write");
+    assert_eq!(outcome.num_layers, 2);
+
+    let outcome = write(
+        repo_dir,
+        &["later"],
+        Split::Merge {
+            size_multiple: 2,
+            max_commits: None,
+        },
+    );
+    assert_eq!(
+        (outcome.num_commits, outcome.num_layers),
+        (3, 2),
+        "the small layer is merged into the new one, but the large one at the bottom is kept"
+    );
+    assert_eq!(num_graph_files(repo_dir), 2, "the merged layer was removed");
+    let graph = Graph::from_info_dir(&info_dir)?;
+    assert_eq!(graph.num_commits(), 11);
+    let layered = commits_by_id(&graph);
+    drop(graph);
+
+    let outcome = write(repo_dir, &["later"], Split::Replace);
+    assert_eq!((outcome.num_commits, outcome.num_layers), (11, 1));
+    assert_eq!(num_graph_files(repo_dir), 1);
+    assert_eq!(commits_by_id(&Graph::from_info_dir(&info_dir)?), layered);
+
+    let outcome = write(repo_dir, &["later"], Split::No);
+    assert_eq!((outcome.num_commits, outcome.num_layers), (11, 1));
+    assert_eq!(num_graph_files(repo_dir), 0, "the chain is replaced by a single file");
+    assert!(!info_dir.join("commit-graphs/commit-graph-chain").exists());
+    assert_eq!(commits_by_id(&Graph::from_info_dir(&info_dir)?), layered);
+
+    let outcome = write(repo_dir, &["later"], Split::NoMerge);
+    assert_eq!((outcome.num_commits, outcome.num_layers), (11, 1));
+    assert!(
+        !info_dir.join("commit-graph").exists(),
+        "the single file is replaced by a chain"
+    );
+    Ok(())
+}
+
+mod changed_paths {
+    use std::collections::HashMap;
+
+    use bstr::BString;
+    use git_commitgraph::{
+        bloom,
+        graph::write::{Options, Split},
+        Graph,
+    };
+    use git_hash::{oid, ObjectId};
+    use git_odb::FindExt;
+
+    use super::{git, rev_parse, write_with_options};
+
+    fn filters_by_id(graph: &Graph) -> HashMap<ObjectId, Vec<u8>> {
+        graph
+            .iter_commits()
+            .map(|commit| {
+                (
+                    commit.id().to_owned(),
+                    commit
+                        .bloom_filter()
+                        .expect("every commit has a filter")
+                        .as_bytes()
+                        .to_owned(),
+                )
+            })
+            .collect()
+    }
+
+    /// Push the paths of all non-tree entries that differ between the trees `lhs` and `rhs` into `out`.
+    fn diff_tree_paths(
+        db: &git_odb::linked::Store,
+        lhs: Option<&oid>,
+        rhs: &oid,
+        out: &mut Vec<BString>,
+    ) -> Result<(), git_diff::tree::changes::Error> {
+        let (mut lhs_buf, mut rhs_buf) = (Vec::new(), Vec::new());
+        let lhs = lhs.map(|id| {
+            db.find_existing_tree_iter(id, &mut lhs_buf, &mut git_odb::pack::cache::Never)
+                .expect("parent tree exists")
+        });
+        let rhs = db
+            .find_existing_tree_iter(rhs, &mut rhs_buf, &mut git_odb::pack::cache::Never)
+            .expect("tree exists");
+        let mut recorder = git_diff::tree::Recorder::default();
+        git_diff::tree::Changes::from(lhs).needed_to_obtain(
+            rhs,
+            git_diff::tree::State::default(),
+            |id, buf| {
+                db.find_existing_tree_iter(id, buf, &mut git_odb::pack::cache::Never)
+                    .ok()
+            },
+            &mut recorder,
+        )?;
+        out.extend(recorder.records.into_iter().filter_map(|change| {
+            use git_diff::tree::recorder::Change::*;
+            match change {
+                Addition { entry_mode, path, .. }
+                | Deletion { entry_mode, path, .. }
+                | Modification { entry_mode, path, .. }
+                    if !entry_mode.is_tree() =>
+                {
+                    Some(path)
+                }
+                _ => None,
+            }
+        }));
+        Ok(())
+    }
+
+    fn options(split: Split) -> Options {
+        Options {
+            split,
+            changed_paths: Some(bloom::Settings::default()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn filters_match_the_ones_written_by_git() -> crate::Result {
+        let tmp = git_testtools::scripted_fixture_repo_writable("changed_paths.sh")?;
+        let repo_dir = tmp.path();
+        let info_dir = repo_dir.join(".git/objects/info");
+
+        write_with_options(repo_dir, &["main"], options(Split::No));
+        let graph = Graph::from_info_dir(&info_dir)?;
+        assert_eq!(graph.num_commits(), 8);
+        let ours = filters_by_id(&graph);
+        drop(graph);
+
+        let too_large = rev_parse(repo_dir, &["main"])[0];
+        assert_eq!(ours[&too_large], [0xff], "this commit changes more than 512 paths");
+
+        git(
+            repo_dir,
+            &[
+                "commit-graph",
+                "write",
+                "--no-progress",
+                "--reachable",
+                "--changed-paths",
+            ],
+        );
+        assert_eq!(filters_by_id(&Graph::from_info_dir(&info_dir)?), ours);
+        Ok(())
+    }
+
+    #[test]
+    fn changed_paths_can_be_provided_by_the_caller() -> crate::Result {
+        let tmp = git_testtools::scripted_fixture_repo_writable("changed_paths.sh")?;
+        let repo_dir = tmp.path();
+        let info_dir = repo_dir.join(".git/objects/info");
+
+        write_with_options(repo_dir, &["main"], options(Split::No));
+        let computed = filters_by_id(&Graph::from_info_dir(&info_dir)?);
+        std::fs::remove_file(info_dir.join("commit-graph"))?;
+
+        let db = git_odb::linked::Store::at(repo_dir.join(".git/objects"), git_hash::Kind::Sha1)?;
+        Graph::write_to_info_dir_with_changed_paths(
+            &info_dir,
+            rev_parse(repo_dir, &["main"]),
+            |id, buf| {
+                db.find_existing_commit_iter(id, buf, &mut git_odb::pack::cache::Never)
+                    .ok()
+            },
+            |lhs, rhs, out| diff_tree_paths(&db, lhs, rhs, out),
+            options(Split::No),
+        )?;
+        assert_eq!(filters_by_id(&Graph::from_info_dir(&info_dir)?), computed);
+        Ok(())
+    }
+
+    #[test]
+    fn maybe_changed_path() -> crate::Result {
+        let tmp = git_testtools::scripted_fixture_repo_writable("changed_paths.sh")?;
+        let repo_dir = tmp.path();
+        write_with_options(repo_dir, &["main"], options(Split::No));
+        let graph = Graph::from_info_dir(repo_dir.join(".git/objects/info"))?;
+
+        let ids = rev_parse(repo_dir, &["main~5", "main~4", "main~3", "main~1"]);
+        let changed = |id: &ObjectId, path: &str| graph.commit_by_id(id).expect("in graph").maybe_changed_path(path);
+        let (modify_c, make_executable, delete_b, merge) = (&ids[0], &ids[1], &ids[2], &ids[3]);
+
+        assert_eq!(changed(modify_c, "dir/sub/c"), Some(true));
+        assert_eq!(
+            changed(modify_c, "dir/sub/"),
+            Some(true),
+            "trailing slashes are ignored"
+        );
+        assert_eq!(
+            changed(modify_c, "dir"),
+            Some(true),
+            "leading directories are contained"
+        );
+        assert_eq!(changed(modify_c, "a"), Some(false));
+        assert_eq!(changed(modify_c, "dir/b"), Some(false));
+
+        assert_eq!(changed(make_executable, "a"), Some(true), "mode changes are changes");
+        assert_eq!(changed(make_executable, "dir/sub/c"), Some(false));
+
+        assert_eq!(changed(delete_b, "dir/b"), Some(true));
+        assert_eq!(changed(delete_b, "dir/sub/c"), Some(false));
+
+        assert_eq!(
+            changed(merge, "side-file"),
+            Some(true),
+            "merges are compared to their first parent"
+        );
+        assert_eq!(changed(merge, "dir/ümlaut"), Some(false));
+        Ok(())
+    }
+
+    #[test]
+    fn filters_are_written_into_split_chains_and_reused() -> crate::Result {
+        let tmp = git_testtools::scripted_fixture_repo_writable("changed_paths.sh")?;
+        let repo_dir = tmp.path();
+        let info_dir = repo_dir.join(".git/objects/info");
+
+        write_with_options(repo_dir, &["side"], options(Split::NoMerge));
+        let outcome = write_with_options(repo_dir, &["main"], options(Split::NoMerge));
+        assert_eq!(outcome.num_layers, 2);
+        let layered = filters_by_id(&Graph::from_info_dir(&info_dir)?);
+        assert_eq!(layered.len(), 8);
+
+        let outcome = write_with_options(
+            repo_dir,
+            &["main"],
+            Options {
+                max_changed_paths: 0,
+                ..options(Split::Replace)
+            },
+        );
+        assert_eq!(outcome.num_layers, 1);
+        assert_eq!(
+            filters_by_id(&Graph::from_info_dir(&info_dir)?),
+            layered,
+            "existing filters are reused instead of being recomputed with different limits"
+        );
+
+        write_with_options(repo_dir, &["main"], Options::default());
+        let graph = Graph::from_info_dir(&info_dir)?;
+        assert!(
+            graph.iter_commits().all(|commit| commit.bloom_filter().is_none()),
+            "filters are only written if enabled"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Reading multiple `git-config` files as one, resolving their `include` and
+//! `includeIf` sections along the way.
+
+use std::{
+    error::Error as StdError,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    file::{resolve_includes, GitConfig},
+    parser::ParserOrIoError,
+};
+
+/// Options to control how [`GitConfig::from_paths`] resolves includes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Options<'a> {
+    /// The maximum amount of nested includes. Exceeding it is an error, which
+    /// is also how include cycles are detected. Defaults to `10`, just like in
+    /// `git`.
+    pub max_depth: u8,
+    /// The `.git` directory of the repository the configuration belongs to.
+    /// `gitdir:` and `gitdir/i:` conditions never match if it is not set.
+    pub git_dir: Option<&'a Path>,
+    /// The full name of the branch `HEAD` points to, like `refs/heads/main`.
+    /// `onbranch:` conditions never match if it is not set, which is also
+    /// the case if `HEAD` is detached.
+    pub branch_name: Option<&'a str>,
+    /// The directory to substitute for a leading `~/` in include paths and
+    /// conditions. If not set, the home directory of the current user is used.
+    pub home_dir: Option<&'a Path>,
+}
+
+impl Default for Options<'_> {
+    fn default() -> Self {
+        Options {
+            max_depth: 10,
+            git_dir: None,
+            branch_name: None,
+            home_dir: None,
+        }
+    }
+}
+
+/// Represents the errors that may occur when calling [`GitConfig::from_paths`].
+#[derive(Debug)]
+pub enum Error {
+    /// The configuration file at `path` could not be read or parsed.
+    Read {
+        /// The file that failed to be read.
+        path: PathBuf,
+        /// The underlying parser or IO error.
+        source: ParserOrIoError<'static>,
+    },
+    /// Includes were nested more than `max_depth` times, which typically is
+    /// due to an include cycle.
+    IncludeDepthExceeded {
+        /// The maximum amount of nested includes that was exceeded.
+        max_depth: u8,
+    },
+    /// A relative include path or a `gitdir:./` condition was used in
+    /// configuration that wasn't read from a file.
+    MissingConfigPath,
+    /// A path started with `~/`, but the home directory could not be
+    /// determined.
+    MissingHomeDir,
+    /// An include path wasn't valid UTF-8.
+    IllformedUtf8 {
+        /// The include path as found in the configuration.
+        path: Vec<u8>,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Read { path, source } => write!(f, "Could not read config file at '{}': {}", path.display(), source),
+            Error::IncludeDepthExceeded { max_depth } => write!(
+                f,
+                "Exceeded the maximum include depth of {}, which may be due to an include cycle.",
+                max_depth
+            ),
+            Error::MissingConfigPath => write!(f, "Relative includes and conditions must come from files."),
+            Error::MissingHomeDir => write!(f, "The home directory could not be determined to expand '~/'."),
+            Error::IllformedUtf8 { path } => {
+                write!(
+                    f,
+                    "Include path '{}' is not valid UTF-8.",
+                    String::from_utf8_lossy(path)
+                )
+            }
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Read { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl GitConfig<'static> {
+    /// Constructs a `git-config` file from all files at `paths`, in order, as
+    /// if they were a single file. This is how the system, global and
+    /// repository configuration files are combined.
+    ///
+    /// Sections that include other files are resolved recursively, and the
+    /// included sections are placed right after the including section so that
+    /// following values override them, just like in `git`:
+    ///
+    /// - `include.path` always includes the file it points to.
+    /// - `includeIf "<condition>".path` includes the file only if the condition
+    ///   matches. Supported conditions are `gitdir:`, `gitdir/i:` and
+    ///   `onbranch:`, which are evaluated using `options`. Unknown conditions
+    ///   never match.
+    ///
+    /// Relative include paths are resolved against the directory of the
+    /// including file, and included files that don't exist are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use git_config::file::{from_paths, GitConfig};
+    /// # use std::path::Path;
+    /// let config = GitConfig::from_paths(
+    ///     &["/etc/gitconfig", "/home/user/.gitconfig"],
+    ///     &from_paths::Options {
+    ///         git_dir: Some(Path::new("/home/user/work/project/.git")),
+    ///         ..Default::default()
+    ///     },
+    /// )?;
+    /// let email = config.get_raw_value("user", None, "email");
+    /// # Ok::<(), from_paths::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the files at `paths` or any existing included
+    /// file could not be read or parsed, if includes are nested too deeply, or
+    /// if an include path couldn't be resolved.
+    pub fn from_paths(paths: impl IntoIterator<Item = impl AsRef<Path>>, options: &Options<'_>) -> Result<Self, Error> {
+        let mut target = GitConfig::new();
+        for path in paths {
+            let path = path.as_ref();
+            let config = GitConfig::open(path).map_err(|source| Error::Read {
+                path: path.to_owned(),
+                source,
+            })?;
+            resolve_includes::append(&mut target, config, Some(path), 0, options)?;
+        }
+        Ok(target)
+    }
+}
@@ -98,7 +98,7 @@ pub struct GitConfig<'event> {
     /// The list of events that occur before an actual section. Since a
     /// `git-config` file prohibits global values, this vec is limited to only
     /// comment, newline, and whitespace events.
-    frontmatter_events: SectionBody<'event>,
+    pub(super) frontmatter_events: SectionBody<'event>,
     /// Section name and subsection name to section id lookup tree. This is
     /// effectively a n-tree (opposed to a binary tree) that can have a height
     /// of at most three (including an implicit root node).
@@ -109,11 +109,11 @@ pub struct GitConfig<'event> {
     /// This indirection with the SectionId as the key is critical to flexibly
     /// supporting `git-config` sections, as duplicated keys are permitted.
     pub(super) sections: HashMap<SectionId, SectionBody<'event>>,
-    section_headers: HashMap<SectionId, ParsedSectionHeader<'event>>,
+    pub(super) section_headers: HashMap<SectionId, ParsedSectionHeader<'event>>,
     /// Internal monotonically increasing counter for section ids.
     section_id_counter: usize,
     /// Section order for output ordering.
-    section_order: VecDeque<SectionId>,
+    pub(super) section_order: VecDeque<SectionId>,
 }
 
 impl<'event> GitConfig<'event> {
@@ -126,6 +126,10 @@ impl<'event> GitConfig<'event> {
 
     /// Constructs a `git-config` file from the provided path.
     ///
+    /// `include` and `includeIf` sections are kept as they are, which keeps
+    /// the file suitable for editing. Use [`GitConfig::from_paths`] to have
+    /// them resolved instead.
+    ///
     /// # Errors
     ///
     /// Returns an error if there was an IO error or if the file wasn't a valid
@@ -981,7 +985,7 @@ impl<'event> GitConfig<'event> {
 /// Private helper functions
 impl<'event> GitConfig<'event> {
     /// Adds a new section to the config file.
-    fn push_section_internal(
+    pub(super) fn push_section_internal(
         &mut self,
         // current_section_name: Option<SectionHeaderName<'event>>,
         // current_subsection_name: Option<Cow<'event, str>>,
@@ -1,7 +1,9 @@
 //! This module provides a high level wrapper around a single `git-config` file.
 
 mod error;
+pub mod from_paths;
 mod git_config;
+mod resolve_includes;
 mod resolved;
 mod section;
 mod value;
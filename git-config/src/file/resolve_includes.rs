@@ -0,0 +1,313 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    file::{
+        from_paths::{Error, Options},
+        GitConfig, SectionBody,
+    },
+    parser::{Key, ParsedSectionHeader, ParserOrIoError},
+};
+
+/// Appends all sections of `config`, read from `config_path` at the given
+/// include `depth`, to `target`, recursively appending the sections of all
+/// files included by them right after the including section.
+pub(super) fn append(
+    target: &mut GitConfig<'static>,
+    mut config: GitConfig<'static>,
+    config_path: Option<&Path>,
+    depth: u8,
+    options: &Options<'_>,
+) -> Result<(), Error> {
+    if target.sections.is_empty() && target.frontmatter_events.as_ref().is_empty() {
+        target.frontmatter_events = std::mem::replace(&mut config.frontmatter_events, SectionBody::new());
+    }
+
+    for section_id in std::mem::take(&mut config.section_order) {
+        let header = config
+            .section_headers
+            .remove(&section_id)
+            .expect("section headers and order are in sync");
+        let body = config
+            .sections
+            .remove(&section_id)
+            .expect("sections and order are in sync");
+        let include_paths = if includes(&header, config_path, options)? {
+            body.values(&Key("path".into()))
+        } else {
+            Vec::new()
+        };
+        target.push_section_internal(header, body);
+
+        for include_path in include_paths {
+            let path = resolve_include_path(&include_path, config_path, options)?;
+            let included = match GitConfig::open(&path) {
+                Ok(included) => included,
+                Err(ParserOrIoError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(source) => return Err(Error::Read { path, source }),
+            };
+            if depth >= options.max_depth {
+                return Err(Error::IncludeDepthExceeded {
+                    max_depth: options.max_depth,
+                });
+            }
+            append(target, included, Some(&path), depth + 1, options)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if the section with `header` includes the files listed as its
+/// `path` values.
+fn includes(
+    header: &ParsedSectionHeader<'_>,
+    config_path: Option<&Path>,
+    options: &Options<'_>,
+) -> Result<bool, Error> {
+    let name = &header.name.0;
+    Ok(match header.subsection_name.as_deref() {
+        None => name.eq_ignore_ascii_case("include"),
+        Some(condition) if name.eq_ignore_ascii_case("includeIf") => {
+            if let Some(pattern) = condition.strip_prefix("gitdir:") {
+                git_dir_matches(pattern, false, config_path, options)?
+            } else if let Some(pattern) = condition.strip_prefix("gitdir/i:") {
+                git_dir_matches(pattern, true, config_path, options)?
+            } else if let Some(pattern) = condition.strip_prefix("onbranch:") {
+                on_branch_matches(pattern, options)
+            } else {
+                false
+            }
+        }
+        Some(_) => false,
+    })
+}
+
+fn git_dir_matches(
+    pattern: &str,
+    ignore_case: bool,
+    config_path: Option<&Path>,
+    options: &Options<'_>,
+) -> Result<bool, Error> {
+    let git_dir = match options.git_dir {
+        Some(git_dir) => git_dir,
+        None => return Ok(false),
+    };
+
+    let mut pattern = expand_home_dir(pattern, options)?.into_owned();
+    // A pattern relative to the including file is matched literally up to and including that file's directory.
+    let mut literal_prefix_len = 0;
+    if let Some(relative) = pattern.strip_prefix("./") {
+        let config_dir = config_path
+            .ok_or(Error::MissingConfigPath)?
+            .canonicalize()
+            .ok()
+            .and_then(|path| path.parent().map(to_slash_string));
+        let config_dir = match config_dir {
+            Some(dir) => dir,
+            None => return Ok(false),
+        };
+        pattern = format!("{}/{}", config_dir, relative);
+        literal_prefix_len = config_dir.len() + 1;
+    } else if !Path::new(&pattern).is_absolute() {
+        pattern.insert_str(0, "**/");
+    }
+    if pattern.ends_with('/') {
+        pattern.push_str("**");
+    }
+
+    let matches = |git_dir: &str| {
+        let (prefix, pattern) = pattern.as_bytes().split_at(literal_prefix_len);
+        git_dir.len() >= literal_prefix_len && {
+            let (git_dir_prefix, git_dir) = git_dir.as_bytes().split_at(literal_prefix_len);
+            let prefix_matches = if ignore_case {
+                prefix.eq_ignore_ascii_case(git_dir_prefix)
+            } else {
+                prefix == git_dir_prefix
+            };
+            prefix_matches && wildmatch(pattern, git_dir, ignore_case)
+        }
+    };
+
+    // Try the canonical path first, but fall back to the absolute one to let patterns match directories
+    // that are reached through symlinks.
+    if let Ok(canonical_git_dir) = git_dir.canonicalize() {
+        if matches(&to_slash_string(&canonical_git_dir)) {
+            return Ok(true);
+        }
+    }
+    let absolute_git_dir = if git_dir.is_absolute() {
+        git_dir.to_owned()
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => cwd.join(git_dir),
+            Err(_) => return Ok(false),
+        }
+    };
+    Ok(matches(&to_slash_string(&absolute_git_dir)))
+}
+
+fn on_branch_matches(pattern: &str, options: &Options<'_>) -> bool {
+    let branch_name = match options.branch_name.and_then(|name| name.strip_prefix("refs/heads/")) {
+        Some(name) => name,
+        None => return false,
+    };
+    let mut pattern = Cow::Borrowed(pattern);
+    if pattern.ends_with('/') {
+        pattern.to_mut().push_str("**");
+    }
+    wildmatch(pattern.as_bytes(), branch_name.as_bytes(), false)
+}
+
+fn resolve_include_path(path: &[u8], config_path: Option<&Path>, options: &Options<'_>) -> Result<PathBuf, Error> {
+    let path = std::str::from_utf8(path).map_err(|_| Error::IllformedUtf8 { path: path.to_vec() })?;
+    let path = PathBuf::from(expand_home_dir(path, options)?.as_ref());
+    if path.is_absolute() {
+        return Ok(path);
+    }
+    let config_path = config_path.ok_or(Error::MissingConfigPath)?;
+    Ok(match config_path.parent() {
+        Some(config_dir) => config_dir.join(path),
+        None => path,
+    })
+}
+
+fn expand_home_dir<'a>(path: &'a str, options: &Options<'_>) -> Result<Cow<'a, str>, Error> {
+    let relative = match path.strip_prefix("~/") {
+        Some(relative) => relative,
+        None => return Ok(Cow::Borrowed(path)),
+    };
+    let home_dir = options
+        .home_dir
+        .map(ToOwned::to_owned)
+        .or_else(dirs::home_dir)
+        .ok_or(Error::MissingHomeDir)?;
+    let mut home_dir = to_slash_string(&home_dir);
+    if !home_dir.ends_with('/') {
+        home_dir.push('/');
+    }
+    home_dir.push_str(relative);
+    Ok(Cow::Owned(home_dir))
+}
+
+fn to_slash_string(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if std::path::MAIN_SEPARATOR == '/' {
+        path.into_owned()
+    } else {
+        path.replace(std::path::MAIN_SEPARATOR, "/")
+    }
+}
+
+/// Matches `text` against the glob `pattern` like `git`'s `wildmatch()` does in
+/// pathname mode, i.e. `*`, `?` and bracket expressions never match a slash,
+/// while `**` surrounded by slashes matches any amount of directories.
+fn wildmatch(pattern: &[u8], text: &[u8], ignore_case: bool) -> bool {
+    let eq = |a: u8, b: u8| {
+        if ignore_case {
+            a.eq_ignore_ascii_case(&b)
+        } else {
+            a == b
+        }
+    };
+    let (mut p, mut t) = (0, 0);
+    while p < pattern.len() {
+        match pattern[p] {
+            b'*' => {
+                let mut stars_end = p + 1;
+                while pattern.get(stars_end) == Some(&b'*') {
+                    stars_end += 1;
+                }
+                let starts_component = p == 0 || pattern[p - 1] == b'/';
+                let ends_component = stars_end == pattern.len() || pattern[stars_end] == b'/';
+                if stars_end - p > 1 && starts_component && ends_component {
+                    if stars_end == pattern.len() {
+                        return true;
+                    }
+                    let rest = &pattern[stars_end + 1..];
+                    return wildmatch(rest, &text[t..], ignore_case)
+                        || text[t..]
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, b)| **b == b'/')
+                            .any(|(slash, _)| wildmatch(rest, &text[t + slash + 1..], ignore_case));
+                }
+                let rest = &pattern[stars_end..];
+                for end in t..=text.len() {
+                    if wildmatch(rest, &text[end..], ignore_case) {
+                        return true;
+                    }
+                    if text.get(end) == Some(&b'/') {
+                        break;
+                    }
+                }
+                return false;
+            }
+            b'?' => match text.get(t) {
+                Some(b'/') | None => return false,
+                Some(_) => {
+                    p += 1;
+                    t += 1;
+                }
+            },
+            b'[' => match (text.get(t), bracket_expression(&pattern[p + 1..])) {
+                (Some(&c), Some((negated, set, len))) if c != b'/' => {
+                    if bracket_matches(set, c, ignore_case) == negated {
+                        return false;
+                    }
+                    p += 1 + len;
+                    t += 1;
+                }
+                _ => return false,
+            },
+            b'\\' if p + 1 < pattern.len() => match text.get(t) {
+                Some(&c) if eq(pattern[p + 1], c) => {
+                    p += 2;
+                    t += 1;
+                }
+                _ => return false,
+            },
+            expected => match text.get(t) {
+                Some(&c) if eq(expected, c) => {
+                    p += 1;
+                    t += 1;
+                }
+                _ => return false,
+            },
+        }
+    }
+    t == text.len()
+}
+
+/// Parses the bracket expression following an opening `[` and returns whether it is
+/// negated, its set of characters and ranges, and its length including the closing `]`.
+fn bracket_expression(pattern: &[u8]) -> Option<(bool, &[u8], usize)> {
+    let negated = matches!(pattern.first(), Some(b'!') | Some(b'^'));
+    let start = usize::from(negated);
+    // A `]` right at the start is part of the set.
+    let end = start + 1 + pattern.get(start + 1..)?.iter().position(|b| *b == b']')?;
+    Some((negated, &pattern[start..end], end + 1))
+}
+
+fn bracket_matches(set: &[u8], c: u8, ignore_case: bool) -> bool {
+    let in_range = |lo: u8, hi: u8, c: u8| lo <= c && c <= hi;
+    let mut i = 0;
+    while i < set.len() {
+        let lo = set[i];
+        let matched = if set.get(i + 1) == Some(&b'-') && i + 2 < set.len() {
+            let hi = set[i + 2];
+            i += 3;
+            in_range(lo, hi, c)
+                || (ignore_case
+                    && (in_range(lo, hi, c.to_ascii_lowercase()) || in_range(lo, hi, c.to_ascii_uppercase())))
+        } else {
+            i += 1;
+            lo == c || (ignore_case && lo.eq_ignore_ascii_case(&c))
+        };
+        if matched {
+            return true;
+        }
+    }
+    false
+}
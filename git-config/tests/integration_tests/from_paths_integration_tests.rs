@@ -0,0 +1,209 @@
+use std::{borrow::Cow, fs, path::Path};
+
+use git_config::file::{from_paths, GitConfig};
+use tempfile::tempdir;
+
+fn write(path: impl AsRef<Path>, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn value<'a>(config: &'a GitConfig<'_>, section: &str, key: &str) -> Option<Cow<'a, [u8]>> {
+    config.get_raw_value(section, None, key).ok()
+}
+
+#[test]
+fn multiple_paths_are_read_in_order() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+    write(&a, "[core]\na = system\nb = system")?;
+    write(&b, "[core]\na = global")?;
+
+    let config = GitConfig::from_paths(&[a, b], &Default::default())?;
+    assert_eq!(value(&config, "core", "a"), Some(Cow::Borrowed(&b"global"[..])));
+    assert_eq!(value(&config, "core", "b"), Some(Cow::Borrowed(&b"system"[..])));
+    Ok(())
+}
+
+#[test]
+fn included_values_override_preceding_ones_and_are_overridden_by_following_ones(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let config_path = dir.path().join("config");
+    let absolute_include = dir.path().join("absolute");
+    write(
+        &config_path,
+        &format!(
+            "[core]\na = before\nb = before\nc = before\n[include]\npath = nested/relative\npath = {}\n[core]\nc = after",
+            absolute_include.display()
+        ),
+    )?;
+    write(
+        dir.path().join("nested/relative"),
+        "[core]\na = relative\nb = relative\n[include]\npath = sibling",
+    )?;
+    write(dir.path().join("nested/sibling"), "[core]\nd = sibling")?;
+    write(&absolute_include, "[core]\nb = absolute\nc = absolute")?;
+
+    let config = GitConfig::from_paths(Some(&config_path), &Default::default())?;
+    assert_eq!(value(&config, "core", "a"), Some(Cow::Borrowed(&b"relative"[..])));
+    assert_eq!(value(&config, "core", "b"), Some(Cow::Borrowed(&b"absolute"[..])));
+    assert_eq!(value(&config, "core", "c"), Some(Cow::Borrowed(&b"after"[..])));
+    assert_eq!(
+        value(&config, "core", "d"),
+        Some(Cow::Borrowed(&b"sibling"[..])),
+        "relative paths are resolved against the directory of the including file"
+    );
+    assert_eq!(
+        config.get_raw_multi_value("include", None, "path")?.len(),
+        3,
+        "include sections remain accessible"
+    );
+    Ok(())
+}
+
+#[test]
+fn missing_includes_are_ignored_but_missing_paths_are_not() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let config_path = dir.path().join("config");
+    write(&config_path, "[include]\npath = does-not-exist\n[core]\na = b")?;
+
+    let config = GitConfig::from_paths(Some(&config_path), &Default::default())?;
+    assert_eq!(value(&config, "core", "a"), Some(Cow::Borrowed(&b"b"[..])));
+
+    assert!(matches!(
+        GitConfig::from_paths(Some(dir.path().join("does-not-exist")), &Default::default()),
+        Err(from_paths::Error::Read { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn include_cycles_and_too_deeply_nested_includes_are_errors() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+    write(&a, "[include]\npath = b")?;
+    write(&b, "[include]\npath = a")?;
+    assert!(matches!(
+        GitConfig::from_paths(Some(&a), &Default::default()),
+        Err(from_paths::Error::IncludeDepthExceeded { max_depth: 10 })
+    ));
+
+    for level in 0..4 {
+        write(
+            dir.path().join(level.to_string()),
+            &format!("[core]\nlevel = {}\n[include]\npath = {}", level, level + 1),
+        )?;
+    }
+    let options = from_paths::Options {
+        max_depth: 2,
+        ..Default::default()
+    };
+    let config = GitConfig::from_paths(Some(dir.path().join("1")), &options)?;
+    assert_eq!(value(&config, "core", "level"), Some(Cow::Borrowed(&b"3"[..])));
+    assert!(matches!(
+        GitConfig::from_paths(Some(dir.path().join("0")), &options),
+        Err(from_paths::Error::IncludeDepthExceeded { max_depth: 2 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn include_if_gitdir() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let home = dir.path().canonicalize()?;
+    let config_path = home.join(".gitconfig");
+    write(
+        &config_path,
+        r#"[user]
+    email = private@example.com
+[includeIf "gitdir:~/work/"]
+    path = .gitconfig-work
+[includeIf "gitdir/i:~/CLIENT/"]
+    path = .gitconfig-client
+[includeIf "gitdir:./relative/*/.git"]
+    path = .gitconfig-relative
+[includeIf "gitdir:project/.git"]
+    path = .gitconfig-project
+[includeIf "unknown:condition"]
+    path = .gitconfig-unknown"#,
+    )?;
+    for name in &["work", "client", "relative", "project", "unknown"] {
+        write(
+            home.join(format!(".gitconfig-{}", name)),
+            &format!("[user]\nemail = {}@example.com", name),
+        )?;
+    }
+
+    let email_for_git_dir = |git_dir: &Path| -> Result<String, from_paths::Error> {
+        fs::create_dir_all(git_dir).expect("git dir can be created");
+        let config = GitConfig::from_paths(
+            Some(&config_path),
+            &from_paths::Options {
+                git_dir: Some(git_dir),
+                home_dir: Some(&home),
+                ..Default::default()
+            },
+        )?;
+        let email = value(&config, "user", "email").expect("email is set");
+        Ok(String::from_utf8(email.into_owned()).expect("valid UTF-8"))
+    };
+
+    assert_eq!(email_for_git_dir(&home.join("work/repo/.git"))?, "work@example.com");
+    assert_eq!(email_for_git_dir(&home.join("client/repo/.git"))?, "client@example.com");
+    assert_eq!(
+        email_for_git_dir(&home.join("relative/a/.git"))?,
+        "relative@example.com"
+    );
+    assert_eq!(
+        email_for_git_dir(&home.join("other/project/.git"))?,
+        "project@example.com",
+        "patterns that aren't absolute match anywhere"
+    );
+    assert_eq!(email_for_git_dir(&home.join("other/.git"))?, "private@example.com");
+    assert_eq!(
+        email_for_git_dir(&home.join("relative/a/b/.git"))?,
+        "private@example.com",
+        "a single star doesn't match across directories"
+    );
+    Ok(())
+}
+
+#[test]
+fn include_if_onbranch() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let config_path = dir.path().join("config");
+    write(
+        &config_path,
+        r#"[core]
+    branch = other
+[includeIf "onbranch:main"]
+    path = main
+[includeIf "onbranch:feature/"]
+    path = feature"#,
+    )?;
+    write(dir.path().join("main"), "[core]\nbranch = main")?;
+    write(dir.path().join("feature"), "[core]\nbranch = feature")?;
+
+    let branch_for = |branch_name: Option<&str>| -> Result<Vec<u8>, from_paths::Error> {
+        let config = GitConfig::from_paths(
+            Some(&config_path),
+            &from_paths::Options {
+                branch_name,
+                ..Default::default()
+            },
+        )?;
+        Ok(value(&config, "core", "branch").expect("always set").into_owned())
+    };
+
+    assert_eq!(branch_for(Some("refs/heads/main"))?, b"main");
+    assert_eq!(branch_for(Some("refs/heads/feature/a/b"))?, b"feature");
+    assert_eq!(branch_for(Some("refs/heads/mainline"))?, b"other");
+    assert_eq!(branch_for(Some("refs/remotes/origin/main"))?, b"other");
+    assert_eq!(branch_for(None)?, b"other", "detached heads never match");
+    Ok(())
+}
@@ -5,4 +5,5 @@
 // less build artifacts.
 
 mod file_integeration_test;
+mod from_paths_integration_tests;
 mod parser_integration_tests;
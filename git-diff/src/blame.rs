@@ -0,0 +1,479 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use git_hash::{oid, ObjectId};
+use git_object::{
+    bstr::{BStr, BString, ByteSlice},
+    immutable,
+    tree::EntryMode,
+};
+use quick_error::quick_error;
+
+use crate::{blob, tree};
+
+quick_error! {
+    /// The error returned by [`file()`] and [`parse_ignore_revs()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        NotFound { oid: ObjectId } {
+            display("The object {} could not be found or is not of the expected kind", oid)
+        }
+        FileNotFound { path: BString, commit_id: ObjectId } {
+            display("The file '{}' does not exist in commit {}", path, commit_id)
+        }
+        InvalidRange { range: Range<u32>, num_lines: u32 } {
+            display("The lines {}..{} are out of bounds for a file with {} lines", range.start, range.end, num_lines)
+        }
+        InvalidIgnoreRev { line: BString } {
+            display("'{}' is not a full hexadecimal object id", line)
+        }
+        Traverse(err: git_traverse::commit::ancestors::Error) {
+            display("The history of the commit could not be traversed")
+            source(err)
+            from()
+        }
+        Diff(err: tree::changes::Error) {
+            display("The trees of a commit and its parent could not be compared")
+            source(err)
+            from()
+        }
+    }
+}
+
+/// Options to configure how [`file()`] attributes lines to commits.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// The lines to blame as 0-based line indices, or all lines if `None`, similar to `git blame -L`.
+    pub range: Option<Range<u32>>,
+    /// Commits whose changes should be ignored, like those passed with `git blame --ignore-rev` or listed in the file
+    /// configured with `blame.ignoreRevsFile`, see [`parse_ignore_revs()`].
+    ///
+    /// Lines changed by these commits are attributed to the lines at the same position in the previous version of the
+    /// file. Lines that have no such counterpart, like those that were added in addition to the ones they replaced,
+    /// remain attributed to the ignored commit.
+    pub ignore_revs: HashSet<ObjectId>,
+    /// The algorithm to use to find the lines that changed between commits.
+    pub algorithm: blob::Algorithm,
+}
+
+/// Lines of the blamed file along with the commit that introduced them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Entry {
+    /// The commit that last changed these lines.
+    pub commit_id: ObjectId,
+    /// The lines in the blamed file, as 0-based line indices.
+    pub range_in_blamed_file: Range<u32>,
+    /// The same lines in the version of the file in `commit_id`, as 0-based line indices.
+    pub range_in_original_file: Range<u32>,
+}
+
+/// Lines of the blamed file that have yet to be attributed to a commit, along with the position of the first of them
+/// in the version of the file in the commit currently suspected to have introduced them.
+#[derive(Debug, Clone)]
+struct UnblamedHunk {
+    range_in_blamed_file: Range<u32>,
+    suspect_start: u32,
+}
+
+impl UnblamedHunk {
+    fn range_in_suspect(&self) -> Range<u32> {
+        self.suspect_start..self.suspect_start + self.range_in_blamed_file.len() as u32
+    }
+}
+
+/// A commit suspected to have introduced some lines, along with the id of the blamed file in it.
+struct Suspect {
+    blob_id: ObjectId,
+    hunks: Vec<UnblamedHunk>,
+}
+
+/// The information we need about each commit.
+struct Commit {
+    tree_id: ObjectId,
+    parent_ids: Vec<ObjectId>,
+}
+
+/// Attribute each line of the file at `path` in the commit with `commit_id` to the commit that last changed it, and
+/// return entries covering all blamed lines ordered by their position, configured by `options`.
+///
+/// `find` is a function `f(object_id, &mut buffer) -> Option<immutable::Object>` to obtain the commits, trees and blobs
+/// needed to follow the history of the file. It writes the data of the object into the buffer, and returns it decoded.
+///
+/// # Notes
+///
+/// * The ancestors of `commit_id` are [traversed][git_traverse::commit::Ancestors] in topological order, so each commit
+///   is visited after all of its children. If a file is unchanged in one of the parents of a commit, all of its lines are
+///   passed on to that parent. Otherwise the lines of each parent are matched in order, and the lines that don't match any
+///   parent are attributed to the commit itself.
+/// * Whether the file changed is determined by [comparing the trees][tree::Changes::needed_to_obtain()] of a commit and
+///   its parents.
+/// * Renames are not followed, the history of a file ends where it doesn't exist under `path` anymore.
+pub fn file<Find>(
+    commit_id: impl Into<ObjectId>,
+    path: &BStr,
+    mut find: Find,
+    options: &Options,
+) -> Result<Vec<Entry>, Error>
+where
+    Find: for<'b> FnMut(&oid, &'b mut Vec<u8>) -> Option<immutable::Object<'b>>,
+{
+    let commit_id = commit_id.into();
+    let (mut buf, mut parent_buf) = (Vec::new(), Vec::new());
+    let mut diff = FileDiff::new(path);
+    let commit = find_commit(&mut find, &commit_id, &mut buf)?;
+    let blob_id = match diff.change(None, commit.tree_id, &mut find)? {
+        Some(tree::recorder::Change::Addition {
+            entry_mode: EntryMode::Blob,
+            oid,
+            ..
+        })
+        | Some(tree::recorder::Change::Addition {
+            entry_mode: EntryMode::BlobExecutable,
+            oid,
+            ..
+        }) => oid,
+        _ => {
+            return Err(Error::FileNotFound {
+                path: path.to_owned(),
+                commit_id,
+            })
+        }
+    };
+    let num_lines = blob::lines(find_blob(&mut find, &blob_id, &mut buf)?).count() as u32;
+    let range = options.range.clone().unwrap_or(0..num_lines);
+    if range.start > range.end || range.end > num_lines {
+        return Err(Error::InvalidRange { range, num_lines });
+    }
+
+    let mut out = Vec::new();
+    if range.is_empty() {
+        return Ok(out);
+    }
+    let commits = git_traverse::commit::Ancestors::new(
+        Some(commit_id),
+        git_traverse::commit::ancestors::State::default(),
+        |id, buf| find_commit_iter(&mut find, id, buf),
+    )
+    .sorting(git_traverse::commit::ancestors::Sorting::Topological)
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let mut suspects = HashMap::new();
+    pass_to(
+        &mut suspects,
+        (commit_id, blob_id),
+        vec![UnblamedHunk {
+            suspect_start: range.start,
+            range_in_blamed_file: range,
+        }],
+    );
+    for suspect_id in commits {
+        if suspects.is_empty() {
+            break;
+        }
+        let Suspect { blob_id, mut hunks } = match suspects.remove(&suspect_id) {
+            Some(suspect) => suspect,
+            None => continue,
+        };
+        let commit = find_commit(&mut find, &suspect_id, &mut buf)?;
+        let mut parents = Vec::with_capacity(commit.parent_ids.len());
+        for parent_id in commit.parent_ids {
+            let parent = find_commit(&mut find, &parent_id, &mut buf)?;
+            let parent_blob_id = match diff.change(Some(parent.tree_id), commit.tree_id, &mut find)? {
+                None => Some(blob_id),
+                Some(tree::recorder::Change::Modification {
+                    previous_entry_mode: EntryMode::Blob,
+                    previous_oid,
+                    ..
+                })
+                | Some(tree::recorder::Change::Modification {
+                    previous_entry_mode: EntryMode::BlobExecutable,
+                    previous_oid,
+                    ..
+                }) => Some(previous_oid),
+                Some(_) => None,
+            };
+            if let Some(parent_blob_id) = parent_blob_id {
+                parents.push((parent_id, parent_blob_id));
+            }
+        }
+        if let Some(parent) = parents.iter().find(|(_, parent_blob_id)| *parent_blob_id == blob_id) {
+            pass_to(&mut suspects, *parent, hunks);
+            continue;
+        }
+
+        let is_ignored = options.ignore_revs.contains(&suspect_id);
+        let data = find_blob(&mut find, &blob_id, &mut buf)?;
+        let num_lines = blob::lines(data).count() as u32;
+        for parent in parents {
+            if hunks.is_empty() {
+                break;
+            }
+            let parent_data = find_blob(&mut find, &parent.1, &mut parent_buf)?;
+            let segments = segments(&blob::diff(parent_data, data, options.algorithm), num_lines, is_ignored);
+            let (passed, remaining) = split(hunks, &segments);
+            hunks = remaining;
+            pass_to(&mut suspects, parent, passed);
+        }
+        out.extend(hunks.into_iter().map(|hunk| Entry {
+            commit_id: suspect_id,
+            range_in_original_file: hunk.range_in_suspect(),
+            range_in_blamed_file: hunk.range_in_blamed_file,
+        }));
+    }
+
+    out.sort_by_key(|entry| entry.range_in_blamed_file.start);
+    Ok(coalesce(out))
+}
+
+/// Parse `data` in the format of the file configured with `blame.ignoreRevsFile` into the ids of commits to ignore.
+///
+/// Each line holds a full hexadecimal object id, and everything following a `#` as well as empty lines are ignored.
+pub fn parse_ignore_revs(data: &[u8]) -> Result<HashSet<ObjectId>, Error> {
+    data.lines()
+        .map(|line| {
+            line.find_byte(b'#')
+                .map_or(line, |pos| &line[..pos])
+                .trim_with(|c| c.is_ascii_whitespace())
+        })
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            ObjectId::from_hex(line).map_err(|_| Error::InvalidIgnoreRev {
+                line: line.as_bstr().to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Add `hunks` to the lines suspected to be introduced by the commit with the given id and id of the blamed file.
+fn pass_to(
+    suspects: &mut HashMap<ObjectId, Suspect>,
+    (commit_id, blob_id): (ObjectId, ObjectId),
+    hunks: Vec<UnblamedHunk>,
+) {
+    if hunks.is_empty() {
+        return;
+    }
+    let suspect = suspects.entry(commit_id).or_insert_with(|| Suspect {
+        blob_id,
+        hunks: Vec::new(),
+    });
+    suspect.hunks.extend(hunks);
+}
+
+/// A contiguous region of lines in the file of a suspect, and the position of the first of these lines in the file of
+/// its parent if they are considered the same lines.
+type Segment = (Range<u32>, Option<u32>);
+
+/// Turn `changes` from the file of a parent to the file of a suspect with `num_lines` lines into segments covering all
+/// lines of the latter, in order, with changed lines being mapped to the lines they replaced if the suspect `is_ignored`.
+fn segments(changes: &[blob::Change], num_lines: u32, is_ignored: bool) -> Vec<Segment> {
+    let mut out = Vec::with_capacity(changes.len() * 2 + 1);
+    let (mut before_pos, mut after_pos) = (0, 0);
+    for change in changes {
+        if after_pos < change.after.start {
+            out.push((after_pos..change.after.start, Some(before_pos)));
+        }
+        let num_mapped = if is_ignored {
+            change.before.len().min(change.after.len()) as u32
+        } else {
+            0
+        };
+        let first_unmapped = change.after.start + num_mapped;
+        if num_mapped != 0 {
+            out.push((change.after.start..first_unmapped, Some(change.before.start)));
+        }
+        if first_unmapped < change.after.end {
+            out.push((first_unmapped..change.after.end, None));
+        }
+        before_pos = change.before.end;
+        after_pos = change.after.end;
+    }
+    if after_pos < num_lines {
+        out.push((after_pos..num_lines, Some(before_pos)));
+    }
+    out
+}
+
+/// Split `hunks` along `segments` and return the hunks that are passed on to the parent, in its coordinates, along with
+/// those that remain with the suspect.
+fn split(hunks: Vec<UnblamedHunk>, segments: &[Segment]) -> (Vec<UnblamedHunk>, Vec<UnblamedHunk>) {
+    let (mut passed, mut remaining) = (Vec::new(), Vec::new());
+    for hunk in hunks {
+        let range = hunk.range_in_suspect();
+        let first = segments.partition_point(|(lines, _)| lines.end <= range.start);
+        for (lines, parent_start) in segments[first..]
+            .iter()
+            .take_while(|(lines, _)| lines.start < range.end)
+        {
+            let start = lines.start.max(range.start);
+            let end = lines.end.min(range.end);
+            let blamed_start = hunk.range_in_blamed_file.start + (start - range.start);
+            let range_in_blamed_file = blamed_start..blamed_start + (end - start);
+            match parent_start {
+                Some(parent_start) => passed.push(UnblamedHunk {
+                    range_in_blamed_file,
+                    suspect_start: parent_start + (start - lines.start),
+                }),
+                None => remaining.push(UnblamedHunk {
+                    range_in_blamed_file,
+                    suspect_start: start,
+                }),
+            }
+        }
+    }
+    (passed, remaining)
+}
+
+/// Merge adjacent entries of the same commit whose lines are contiguous in both files.
+fn coalesce(entries: Vec<Entry>) -> Vec<Entry> {
+    let mut out = Vec::<Entry>::with_capacity(entries.len());
+    for entry in entries {
+        match out.last_mut() {
+            Some(previous)
+                if previous.commit_id == entry.commit_id
+                    && previous.range_in_blamed_file.end == entry.range_in_blamed_file.start
+                    && previous.range_in_original_file.end == entry.range_in_original_file.start =>
+            {
+                previous.range_in_blamed_file.end = entry.range_in_blamed_file.end;
+                previous.range_in_original_file.end = entry.range_in_original_file.end;
+            }
+            _ => out.push(entry),
+        }
+    }
+    out
+}
+
+fn find_commit<Find>(find: &mut Find, id: &oid, buf: &mut Vec<u8>) -> Result<Commit, Error>
+where
+    Find: for<'b> FnMut(&oid, &'b mut Vec<u8>) -> Option<immutable::Object<'b>>,
+{
+    let commit = find(id, buf)
+        .and_then(immutable::Object::into_commit)
+        .ok_or_else(|| Error::NotFound { oid: id.to_owned() })?;
+    Ok(Commit {
+        tree_id: commit.tree(),
+        parent_ids: commit.parents().collect(),
+    })
+}
+
+fn find_commit_iter<'b, Find>(find: &mut Find, id: &oid, buf: &'b mut Vec<u8>) -> Option<immutable::CommitIter<'b>>
+where
+    Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::Object<'a>>,
+{
+    let is_commit = find(id, buf)?.as_commit().is_some();
+    is_commit.then(move || immutable::CommitIter::from_bytes(buf))
+}
+
+fn find_tree_iter<'b, Find>(find: &mut Find, id: &oid, buf: &'b mut Vec<u8>) -> Option<immutable::TreeIter<'b>>
+where
+    Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::Object<'a>>,
+{
+    let is_tree = find(id, buf)?.as_tree().is_some();
+    is_tree.then(move || immutable::TreeIter::from_bytes(buf))
+}
+
+fn find_blob<'b, Find>(find: &mut Find, id: &oid, buf: &'b mut Vec<u8>) -> Result<&'b [u8], Error>
+where
+    Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::Object<'a>>,
+{
+    find(id, buf)
+        .and_then(immutable::Object::into_blob)
+        .map(|blob| blob.data)
+        .ok_or_else(|| Error::NotFound { oid: id.to_owned() })
+}
+
+/// Finds the change of a single file between two trees.
+struct FileDiff<'a> {
+    path: &'a BStr,
+    recorder: tree::Recorder,
+    change: Option<tree::recorder::Change>,
+    state: tree::State,
+    lhs_buf: Vec<u8>,
+    rhs_buf: Vec<u8>,
+}
+
+impl<'a> FileDiff<'a> {
+    fn new(path: &'a BStr) -> Self {
+        FileDiff {
+            path,
+            recorder: Default::default(),
+            change: None,
+            state: Default::default(),
+            lhs_buf: Vec::new(),
+            rhs_buf: Vec::new(),
+        }
+    }
+
+    /// Return the change of the file at our path from the tree with `lhs_id`, or an empty tree if `None`, to the tree
+    /// with `rhs_id`, or `None` if it didn't change.
+    fn change<Find>(
+        &mut self,
+        lhs_id: Option<ObjectId>,
+        rhs_id: ObjectId,
+        find: &mut Find,
+    ) -> Result<Option<tree::recorder::Change>, Error>
+    where
+        Find: for<'b> FnMut(&oid, &'b mut Vec<u8>) -> Option<immutable::Object<'b>>,
+    {
+        let lhs = match lhs_id {
+            Some(id) => Some(find_tree_iter(find, &id, &mut self.lhs_buf).ok_or(Error::NotFound { oid: id })?),
+            None => None,
+        };
+        let rhs = find_tree_iter(find, &rhs_id, &mut self.rhs_buf).ok_or(Error::NotFound { oid: rhs_id })?;
+        self.recorder = Default::default();
+        self.change = None;
+        let res = tree::Changes::from(lhs).needed_to_obtain(
+            rhs,
+            &mut self.state,
+            |id, buf| find_tree_iter(find, id, buf),
+            &mut FileDiffDelegate {
+                path: self.path,
+                recorder: &mut self.recorder,
+                change: &mut self.change,
+            },
+        );
+        match res {
+            Ok(()) | Err(tree::changes::Error::Cancelled) => Ok(self.change.take()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// A [`tree::Visit`] implementation which only keeps the change of the entry at `path`, and stops once it was seen.
+struct FileDiffDelegate<'a, 'b> {
+    path: &'a BStr,
+    recorder: &'b mut tree::Recorder,
+    change: &'b mut Option<tree::recorder::Change>,
+}
+
+impl<'a, 'b> tree::Visit for FileDiffDelegate<'a, 'b> {
+    fn pop_front_tracked_path_and_set_current(&mut self) {
+        self.recorder.pop_front_tracked_path_and_set_current()
+    }
+
+    fn push_back_tracked_path_component(&mut self, component: &BStr) {
+        self.recorder.push_back_tracked_path_component(component)
+    }
+
+    fn push_path_component(&mut self, component: &BStr) {
+        self.recorder.push_path_component(component)
+    }
+
+    fn pop_path_component(&mut self) {
+        self.recorder.pop_path_component()
+    }
+
+    fn visit(&mut self, change: tree::visit::Change) -> tree::visit::Action {
+        self.recorder.visit(change);
+        match self.recorder.records.pop() {
+            Some(change) if change.path() == self.path => {
+                *self.change = Some(change);
+                tree::visit::Action::Cancel
+            }
+            _ => tree::visit::Action::Continue,
+        }
+    }
+}
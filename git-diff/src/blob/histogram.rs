@@ -0,0 +1,114 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::blob::{myers, trim_common_lines, Input};
+
+/// Lines occurring more often than this on the _before_ side aren't used as anchors, just like in git.
+const MAX_CHAIN_LENGTH: usize = 64;
+
+/// A region of lines common to both sides.
+struct Region {
+    before: Range<usize>,
+    after: Range<usize>,
+    /// The smallest amount of times any of its lines occurs on the _before_ side.
+    min_occurrences: usize,
+}
+
+/// Mark the lines that differ between `before` and `after` in `input` by splitting the diff at the longest region of
+/// common lines that contains the least frequent lines, recursively, and falling back to Myers if all common lines are
+/// too frequent.
+pub(crate) fn diff(input: &mut Input<'_>, before: Range<usize>, after: Range<usize>) {
+    let (before, after) = trim_common_lines(input.before, before, input.after, after);
+    if before.is_empty() || after.is_empty() {
+        input.mark_changed(before, after);
+        return;
+    }
+
+    let mut occurrences = HashMap::<u32, Vec<usize>>::new();
+    for pos in before.clone() {
+        occurrences.entry(input.before[pos]).or_default().push(pos);
+    }
+    let mut best: Option<Region> = None;
+    let mut has_common_lines = false;
+    let mut after_pos = after.start;
+    while after_pos < after.end {
+        let mut next_after_pos = after_pos + 1;
+        let positions = match occurrences.get(&input.after[after_pos]) {
+            Some(positions) => positions,
+            None => {
+                after_pos = next_after_pos;
+                continue;
+            }
+        };
+        has_common_lines = true;
+        let min_occurrences_to_beat = best.as_ref().map_or(MAX_CHAIN_LENGTH, |best| best.min_occurrences);
+        if positions.len() > min_occurrences_to_beat {
+            after_pos = next_after_pos;
+            continue;
+        }
+        for &before_pos in positions {
+            let region = extend_region(
+                input,
+                &occurrences,
+                before.clone(),
+                after.clone(),
+                before_pos,
+                after_pos,
+            );
+            next_after_pos = next_after_pos.max(region.after.end);
+            let is_better = best.as_ref().map_or(true, |best| {
+                region.before.len() > best.before.len() || region.min_occurrences < best.min_occurrences
+            });
+            if is_better {
+                best = Some(region);
+            }
+        }
+        after_pos = next_after_pos;
+    }
+
+    match best {
+        Some(region) => {
+            diff(
+                input,
+                before.start..region.before.start,
+                after.start..region.after.start,
+            );
+            diff(input, region.before.end..before.end, region.after.end..after.end);
+        }
+        None if has_common_lines => myers::diff(input, before, after),
+        None => input.mark_changed(before, after),
+    }
+}
+
+/// Grow the region of common lines starting at `before_pos` and `after_pos` in both directions, within the given bounds.
+fn extend_region(
+    input: &Input<'_>,
+    occurrences: &HashMap<u32, Vec<usize>>,
+    before: Range<usize>,
+    after: Range<usize>,
+    before_pos: usize,
+    after_pos: usize,
+) -> Region {
+    let num_occurrences = |pos: usize| occurrences[&input.before[pos]].len();
+    let mut region = Region {
+        before: before_pos..before_pos + 1,
+        after: after_pos..after_pos + 1,
+        min_occurrences: num_occurrences(before_pos),
+    };
+    while region.before.start > before.start
+        && region.after.start > after.start
+        && input.before[region.before.start - 1] == input.after[region.after.start - 1]
+    {
+        region.before.start -= 1;
+        region.after.start -= 1;
+        region.min_occurrences = region.min_occurrences.min(num_occurrences(region.before.start));
+    }
+    while region.before.end < before.end
+        && region.after.end < after.end
+        && input.before[region.before.end] == input.after[region.after.end]
+    {
+        region.min_occurrences = region.min_occurrences.min(num_occurrences(region.before.end));
+        region.before.end += 1;
+        region.after.end += 1;
+    }
+    region
+}
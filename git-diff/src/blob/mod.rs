@@ -0,0 +1,178 @@
+use std::{collections::HashMap, ops::Range};
+
+use git_object::bstr::ByteSlice;
+
+mod histogram;
+mod myers;
+mod patience;
+
+///
+pub mod unified;
+
+/// The algorithm to use to find the lines that differ between two blobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// Find a minimal diff with the algorithm by Eugene W. Myers, which is what git uses by default.
+    Myers,
+    /// Anchor the diff at lines that occur exactly once on both sides, like `git diff --patience` does.
+    ///
+    /// This tends to produce diffs that are easier to read for humans, for instance when functions are moved.
+    Patience,
+    /// Anchor the diff at the lines that occur least frequently, like `git diff --histogram` does.
+    ///
+    /// It produces results similar to [`Patience`][Algorithm::Patience], but is usually faster.
+    Histogram,
+}
+
+impl Default for Algorithm {
+    fn default() -> Self {
+        Algorithm::Myers
+    }
+}
+
+/// A region of lines in the _before_ blob that was replaced by a region of lines in the _after_ blob.
+///
+/// Both ranges are 0-based line indices, and at most one of them is empty.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Change {
+    /// The lines in the _before_ blob that were removed.
+    pub before: Range<u32>,
+    /// The lines in the _after_ blob that were added in their place.
+    pub after: Range<u32>,
+}
+
+/// The amount of bytes git inspects to determine if a blob is binary.
+const FIRST_FEW_BYTES: usize = 8000;
+
+/// Return true if `data` is considered binary, which is the case if it contains a null-byte within the first 8000 bytes,
+/// just like git does it.
+pub fn is_binary(data: &[u8]) -> bool {
+    data[..data.len().min(FIRST_FEW_BYTES)].contains(&0)
+}
+
+/// Return an iterator over all lines in `data`, including their line terminator if there is one.
+pub fn lines(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    data.lines_with_terminator()
+}
+
+/// Compute the lines that differ between `before` and `after` using `algorithm`, with changes ordered by their position.
+///
+/// Lines are compared byte by byte, including their line terminator. Like git, ambiguous changes are moved to the last
+/// possible position, but unlike git, no further heuristics are applied to make diffs more readable.
+pub fn diff(before: &[u8], after: &[u8], algorithm: Algorithm) -> Vec<Change> {
+    let (before, after) = tokenize(before, after);
+    let mut removed = vec![false; before.len()];
+    let mut added = vec![false; after.len()];
+    let (before_range, after_range) = (0..before.len(), 0..after.len());
+    let mut input = Input {
+        before: &before,
+        after: &after,
+        removed: &mut removed,
+        added: &mut added,
+    };
+    match algorithm {
+        Algorithm::Myers => myers::diff(&mut input, before_range, after_range),
+        Algorithm::Patience => patience::diff(&mut input, before_range, after_range),
+        Algorithm::Histogram => histogram::diff(&mut input, before_range, after_range),
+    }
+    slide_down(&before, &mut removed);
+    slide_down(&after, &mut added);
+    changes(&removed, &added)
+}
+
+/// The lines of both sides as tokens, along with the lines marked as changed by an algorithm.
+struct Input<'a> {
+    before: &'a [u32],
+    after: &'a [u32],
+    removed: &'a mut [bool],
+    added: &'a mut [bool],
+}
+
+impl Input<'_> {
+    /// Mark all lines in `before` as removed and all lines in `after` as added.
+    fn mark_changed(&mut self, before: Range<usize>, after: Range<usize>) {
+        self.removed[before].iter_mut().for_each(|line| *line = true);
+        self.added[after].iter_mut().for_each(|line| *line = true);
+    }
+}
+
+/// Turn each line of `before` and `after` into a token so that equal lines have equal tokens.
+fn tokenize(before: &[u8], after: &[u8]) -> (Vec<u32>, Vec<u32>) {
+    let mut tokens = HashMap::<&[u8], u32>::new();
+    let mut tokenize = |data| -> Vec<u32> {
+        lines(data)
+            .map(|line| {
+                let next_token = tokens.len() as u32;
+                *tokens.entry(line).or_insert(next_token)
+            })
+            .collect()
+    };
+    let before = tokenize(before);
+    let after = tokenize(after);
+    (before, after)
+}
+
+/// Shrink the given ranges by the lines they have in common at their start and end.
+fn trim_common_lines(
+    before: &[u32],
+    mut before_range: Range<usize>,
+    after: &[u32],
+    mut after_range: Range<usize>,
+) -> (Range<usize>, Range<usize>) {
+    while !before_range.is_empty() && !after_range.is_empty() && before[before_range.start] == after[after_range.start]
+    {
+        before_range.start += 1;
+        after_range.start += 1;
+    }
+    while !before_range.is_empty()
+        && !after_range.is_empty()
+        && before[before_range.end - 1] == after[after_range.end - 1]
+    {
+        before_range.end -= 1;
+        after_range.end -= 1;
+    }
+    (before_range, after_range)
+}
+
+/// Move each group of `changed` lines down for as long as the line following it equals its first line, merging it with
+/// groups it runs into.
+fn slide_down(lines: &[u32], changed: &mut [bool]) {
+    let mut start = 0;
+    while start < lines.len() {
+        if !changed[start] {
+            start += 1;
+            continue;
+        }
+        let mut end = start + changed[start..].iter().take_while(|changed| **changed).count();
+        while end < lines.len() && lines[start] == lines[end] {
+            changed[start] = false;
+            changed[end] = true;
+            start += 1;
+            end += 1;
+            end += changed[end..].iter().take_while(|changed| **changed).count();
+        }
+        start = end;
+    }
+}
+
+/// Turn the lines marked as `removed` and `added` into a list of changes.
+fn changes(removed: &[bool], added: &[bool]) -> Vec<Change> {
+    let mut out = Vec::new();
+    let (mut before, mut after) = (0, 0);
+    while before < removed.len() || after < added.len() {
+        let num_removed = removed[before..].iter().take_while(|removed| **removed).count();
+        let num_added = added[after..].iter().take_while(|added| **added).count();
+        if num_removed == 0 && num_added == 0 {
+            before += 1;
+            after += 1;
+            continue;
+        }
+        out.push(Change {
+            before: before as u32..(before + num_removed) as u32,
+            after: after as u32..(after + num_added) as u32,
+        });
+        before += num_removed;
+        after += num_added;
+    }
+    out
+}
@@ -0,0 +1,105 @@
+use std::ops::{Index, IndexMut, Range};
+
+use crate::blob::{trim_common_lines, Input};
+
+/// Mark the lines that differ between `before` and `after` in `input` so that the amount of changed lines is minimal,
+/// using the linear space variant of the algorithm by Eugene W. Myers.
+pub(crate) fn diff(input: &mut Input<'_>, before: Range<usize>, after: Range<usize>) {
+    let max_cost = (before.len() + after.len() + 1) / 2 + 1;
+    let mut forward = V::new(max_cost);
+    let mut backward = V::new(max_cost);
+    divide(input, before, after, &mut forward, &mut backward);
+}
+
+/// The furthest reaching paths on each diagonal `k`, which may be negative.
+struct V {
+    offset: isize,
+    x: Vec<usize>,
+}
+
+impl V {
+    fn new(max_cost: usize) -> Self {
+        V {
+            offset: max_cost as isize + 1,
+            x: vec![0; 2 * max_cost + 3],
+        }
+    }
+}
+
+impl Index<isize> for V {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &Self::Output {
+        &self.x[(k + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut Self::Output {
+        &mut self.x[(k + self.offset) as usize]
+    }
+}
+
+/// Split the problem at a point on an optimal path between `before` and `after` and solve both halves recursively.
+fn divide(input: &mut Input<'_>, before: Range<usize>, after: Range<usize>, forward: &mut V, backward: &mut V) {
+    let (before, after) = trim_common_lines(input.before, before, input.after, after);
+    if before.is_empty() || after.is_empty() {
+        input.mark_changed(before, after);
+        return;
+    }
+    let (x, y) = middle_snake(
+        &input.before[before.clone()],
+        &input.after[after.clone()],
+        forward,
+        backward,
+    );
+    let (x, y) = (before.start + x, after.start + y);
+    divide(input, before.start..x, after.start..y, forward, backward);
+    divide(input, x..before.end, y..after.end, forward, backward);
+}
+
+/// Find the middle snake of the shortest edit script between `a` and `b`, both non-empty and without common lines at
+/// their start and end, and return the point in `a` and `b` at which it starts.
+fn middle_snake(a: &[u32], b: &[u32], forward: &mut V, backward: &mut V) -> (usize, usize) {
+    let (n, m) = (a.len(), b.len());
+    let delta = n as isize - m as isize;
+    let is_odd = delta & 1 == 1;
+    forward[1] = 0;
+    backward[1] = 0;
+    for d in 0..=((n + m + 1) / 2) as isize {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && forward[k - 1] < forward[k + 1]) {
+                forward[k + 1]
+            } else {
+                forward[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            let start = (x, y);
+            while x < n && y < m && a[x] == b[y] {
+                x += 1;
+                y += 1;
+            }
+            forward[k] = x;
+            if is_odd && (k - delta).abs() < d && forward[k] + backward[delta - k] >= n {
+                return start;
+            }
+        }
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && backward[k - 1] < backward[k + 1]) {
+                backward[k + 1]
+            } else {
+                backward[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            while x < n && y < m && a[n - x - 1] == b[m - y - 1] {
+                x += 1;
+                y += 1;
+            }
+            backward[k] = x;
+            if !is_odd && (k - delta).abs() <= d && backward[k] + forward[delta - k] >= n {
+                return (n - x, m - y);
+            }
+        }
+    }
+    unreachable!("the paths always meet after at most (n + m + 1) / 2 steps")
+}
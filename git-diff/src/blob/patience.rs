@@ -0,0 +1,82 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::blob::{myers, trim_common_lines, Input};
+
+/// Mark the lines that differ between `before` and `after` in `input` by anchoring the diff at the longest sequence of
+/// lines that occur exactly once on both sides, recursively, and falling back to Myers if there are none.
+pub(crate) fn diff(input: &mut Input<'_>, before: Range<usize>, after: Range<usize>) {
+    let (before, after) = trim_common_lines(input.before, before, input.after, after);
+    if before.is_empty() || after.is_empty() {
+        input.mark_changed(before, after);
+        return;
+    }
+
+    let anchors = unique_common_lines(input, before.clone(), after.clone());
+    if anchors.is_empty() {
+        myers::diff(input, before, after);
+        return;
+    }
+    let (mut before_start, mut after_start) = (before.start, after.start);
+    for (before_pos, after_pos) in anchors {
+        diff(input, before_start..before_pos, after_start..after_pos);
+        before_start = before_pos + 1;
+        after_start = after_pos + 1;
+    }
+    diff(input, before_start..before.end, after_start..after.end);
+}
+
+/// Return the positions of the longest sequence of lines that occur exactly once in both `before` and `after`, and in
+/// the same order.
+fn unique_common_lines(input: &Input<'_>, before: Range<usize>, after: Range<usize>) -> Vec<(usize, usize)> {
+    /// The position of a line on each side, if it occurs exactly once.
+    #[derive(Default)]
+    struct Occurrence {
+        before: Option<usize>,
+        after: Option<usize>,
+        is_unique: bool,
+    }
+    let mut occurrences = HashMap::<u32, Occurrence>::new();
+    for pos in before {
+        let occurrence = occurrences.entry(input.before[pos]).or_default();
+        occurrence.is_unique = occurrence.before.is_none();
+        occurrence.before = Some(pos);
+    }
+    for pos in after {
+        if let Some(occurrence) = occurrences.get_mut(&input.after[pos]) {
+            occurrence.is_unique &= occurrence.after.is_none();
+            occurrence.after = Some(pos);
+        }
+    }
+    let mut unique: Vec<_> = occurrences
+        .values()
+        .filter(|occurrence| occurrence.is_unique)
+        .filter_map(|occurrence| Some((occurrence.before?, occurrence.after?)))
+        .collect();
+    unique.sort_unstable();
+    longest_increasing_subsequence(&unique)
+}
+
+/// Return the longest subsequence of `lines`, which are ordered by their position in _before_, whose positions in
+/// _after_ are increasing too, using patience sorting.
+fn longest_increasing_subsequence(lines: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // The index of the last line of each pile, and the index of the line preceding each line.
+    let mut piles = Vec::<usize>::new();
+    let mut predecessors = vec![None; lines.len()];
+    for (index, (_, after_pos)) in lines.iter().enumerate() {
+        let pile = piles.partition_point(|top| lines[*top].1 < *after_pos);
+        predecessors[index] = pile.checked_sub(1).map(|previous| piles[previous]);
+        if pile == piles.len() {
+            piles.push(index);
+        } else {
+            piles[pile] = index;
+        }
+    }
+    let mut out = Vec::with_capacity(piles.len());
+    let mut index = piles.last().copied();
+    while let Some(line) = index {
+        out.push(lines[line]);
+        index = predecessors[line];
+    }
+    out.reverse();
+    out
+}
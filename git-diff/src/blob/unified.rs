@@ -0,0 +1,192 @@
+use std::{io, ops::Range};
+
+use git_object::bstr::{BStr, ByteSlice};
+
+use crate::blob::{self, Algorithm, Change};
+
+/// Options to configure how unified diffs are produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// The algorithm to use to find the lines that changed.
+    pub algorithm: Algorithm,
+    /// The amount of unchanged lines to show before and after each change, like `git diff -U<n>`. Defaults to 3.
+    pub context_lines: u32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            algorithm: Algorithm::default(),
+            context_lines: 3,
+        }
+    }
+}
+
+/// A hunk of a unified diff, which is a region of lines in both blobs with one or more changes and the unchanged lines
+/// around them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hunk {
+    /// The lines of the _before_ blob covered by the hunk, as 0-based line indices.
+    pub before: Range<u32>,
+    /// The lines of the _after_ blob covered by the hunk, as 0-based line indices.
+    pub after: Range<u32>,
+    /// The indices of the changes within this hunk.
+    pub changes: Range<usize>,
+}
+
+/// Group `changes` between a _before_ blob with `num_before_lines` lines and an _after_ blob into hunks, with up to
+/// `context_lines` unchanged lines around each change.
+///
+/// Like git, changes separated by no more than twice the amount of context lines are placed in the same hunk.
+pub fn hunks(changes: &[Change], num_before_lines: u32, context_lines: u32) -> Vec<Hunk> {
+    let mut out = Vec::<Hunk>::new();
+    for (index, change) in changes.iter().enumerate() {
+        let leading_context = context_lines.min(change.before.start);
+        let trailing_context = context_lines.min(num_before_lines - change.before.end);
+        match out.last_mut() {
+            Some(hunk) if change.before.start - changes[hunk.changes.end - 1].before.end <= 2 * context_lines => {
+                hunk.before.end = change.before.end + trailing_context;
+                hunk.after.end = change.after.end + trailing_context;
+                hunk.changes.end = index + 1;
+            }
+            _ => out.push(Hunk {
+                before: change.before.start - leading_context..change.before.end + trailing_context,
+                after: change.after.start - leading_context..change.after.end + trailing_context,
+                changes: index..index + 1,
+            }),
+        }
+    }
+    out
+}
+
+/// Write all `changes` between `before` and `after` as hunks of a unified diff to `out`, with up to `context_lines`
+/// unchanged lines around each change.
+///
+/// Each hunk header is followed by the closest preceding line of `before` that starts with a letter, `_` or `$`, which
+/// is how git finds function names by default.
+pub fn write_hunks(
+    out: &mut impl io::Write,
+    before: &[u8],
+    after: &[u8],
+    changes: &[Change],
+    context_lines: u32,
+) -> io::Result<()> {
+    let before: Vec<_> = blob::lines(before).collect();
+    let after: Vec<_> = blob::lines(after).collect();
+    let mut previous_hunk_start = 0;
+    for hunk in hunks(changes, before.len() as u32, context_lines) {
+        out.write_all(b"@@ -")?;
+        write_range(out, &hunk.before)?;
+        out.write_all(b" +")?;
+        write_range(out, &hunk.after)?;
+        out.write_all(b" @@")?;
+        if let Some(name) = before[previous_hunk_start..hunk.before.start as usize]
+            .iter()
+            .rev()
+            .copied()
+            .find_map(function_name)
+        {
+            out.write_all(b" ")?;
+            out.write_all(name)?;
+        }
+        out.write_all(b"\n")?;
+        previous_hunk_start = hunk.before.start as usize;
+
+        let mut before_pos = hunk.before.start as usize;
+        for change in &changes[hunk.changes] {
+            for line in &before[before_pos..change.before.start as usize] {
+                write_line(out, b' ', line)?;
+            }
+            for line in &before[change.before.start as usize..change.before.end as usize] {
+                write_line(out, b'-', line)?;
+            }
+            for line in &after[change.after.start as usize..change.after.end as usize] {
+                write_line(out, b'+', line)?;
+            }
+            before_pos = change.before.end as usize;
+        }
+        for line in &before[before_pos..hunk.before.end as usize] {
+            write_line(out, b' ', line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a unified diff from `before` at `before_path` to `after` at `after_path` to `out`, configured by `options`,
+/// with a path of `None` denoting a blob that doesn't exist on that side, like when a file was added or deleted.
+///
+/// The output starts with the `---` and `+++` lines naming both paths, followed by all hunks as written by
+/// [`write_hunks()`], and nothing is written if both blobs are equal. If any of the blobs [is binary][blob::is_binary()],
+/// only a single line stating that the binary files differ is written instead, just like git does.
+pub fn write_to(
+    out: &mut impl io::Write,
+    before_path: Option<&BStr>,
+    before: &[u8],
+    after_path: Option<&BStr>,
+    after: &[u8],
+    options: Options,
+) -> io::Result<()> {
+    if before == after {
+        return Ok(());
+    }
+    if blob::is_binary(before) || blob::is_binary(after) {
+        out.write_all(b"Binary files ")?;
+        write_path(out, b"a/", before_path)?;
+        out.write_all(b" and ")?;
+        write_path(out, b"b/", after_path)?;
+        return out.write_all(b" differ\n");
+    }
+
+    let changes = blob::diff(before, after, options.algorithm);
+    for (prefix, header_prefix, path) in [(b"--- ", b"a/", before_path), (b"+++ ", b"b/", after_path)] {
+        out.write_all(prefix)?;
+        write_path(out, header_prefix, path)?;
+        if path.map_or(false, |path| path.contains(&b' ')) {
+            out.write_all(b"\t")?;
+        }
+        out.write_all(b"\n")?;
+    }
+    write_hunks(out, before, after, &changes, options.context_lines)
+}
+
+/// Write the `range` of lines of one side of a hunk header, using git's notation.
+fn write_range(out: &mut impl io::Write, range: &Range<u32>) -> io::Result<()> {
+    match range.len() {
+        0 => write!(out, "{},0", range.start),
+        1 => write!(out, "{}", range.start + 1),
+        len => write!(out, "{},{}", range.start + 1, len),
+    }
+}
+
+/// Write `line` preceded by `prefix`, and mark it if it lacks a line terminator.
+fn write_line(out: &mut impl io::Write, prefix: u8, line: &[u8]) -> io::Result<()> {
+    out.write_all(&[prefix])?;
+    out.write_all(line)?;
+    if !line.ends_with(b"\n") {
+        out.write_all(b"\n\\ No newline at end of file\n")?;
+    }
+    Ok(())
+}
+
+/// Write `path` with `prefix` in front of it, or `/dev/null` if there is no path.
+fn write_path(out: &mut impl io::Write, prefix: &[u8], path: Option<&BStr>) -> io::Result<()> {
+    match path {
+        Some(path) => {
+            out.write_all(prefix)?;
+            out.write_all(path)
+        }
+        None => out.write_all(b"/dev/null"),
+    }
+}
+
+/// Return `line` without trailing whitespace and truncated to 80 bytes if it looks like the start of a function,
+/// using git's default heuristic.
+fn function_name(line: &[u8]) -> Option<&[u8]> {
+    const MAX_LEN: usize = 80;
+    match line.first() {
+        Some(first) if first.is_ascii_alphabetic() || *first == b'_' || *first == b'$' => {
+            Some(line[..line.len().min(MAX_LEN)].trim_end_with(|c| c.is_ascii_whitespace()))
+        }
+        _ => None,
+    }
+}
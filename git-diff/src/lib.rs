@@ -1,6 +1,17 @@
-//! Algorithms for diffing various git object types and for generating patches, highly optimized for performance.
+//! Algorithms for diffing various git object types, indices and working trees and for generating patches, highly optimized for performance.
 #![forbid(unsafe_code, rust_2018_idioms)]
 #[deny(missing_docs)]
 
 ///
 pub mod tree;
+
+///
+pub mod blame;
+
+///
+pub mod blob;
+
+///
+pub mod worktree;
+
+mod util;
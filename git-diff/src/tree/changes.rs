@@ -1,7 +1,10 @@
-use std::{borrow::BorrowMut, collections::VecDeque};
+use std::{
+    borrow::BorrowMut,
+    collections::{HashMap, VecDeque},
+};
 
 use git_hash::{oid, ObjectId};
-use git_object::immutable;
+use git_object::{bstr::BString, immutable};
 use quick_error::quick_error;
 
 use crate::{
@@ -46,7 +49,11 @@ impl<'a> tree::Changes<'a> {
     /// * does not do rename tracking but attempts to reduce allocations to zero (so performance is mostly determined
     ///   by the delegate implementation which should be as specific as possible. Rename tracking can be computed on top of the changes
     ///   received by the `delegate`.
+    /// * subtrees with the same id on both sides are skipped without looking them up. For repeatedly diffing consecutive trees,
+    ///   `find` can additionally use an object cache, like the one provided by `git_pack::cache::object`.
     /// * cycle checking is not performed, but can be performed in the delegate which can return [`tree::visit::Action::Cancel`] to stop the traversal.
+    /// * If [case is ignored][tree::Changes::ignore_case()], all entries of each pair of trees are collected to match them
+    ///   by their case-folded names, which needs more allocations.
     /// * [std::mem::ManuallyDrop] is used because `Peekable` is needed. When using it as wrapper around our no-drop iterators, all of the sudden
     ///   borrowcheck complains as Drop is present (even though it's not)
     ///
@@ -66,7 +73,10 @@ impl<'a> tree::Changes<'a> {
     {
         let state = state.borrow_mut();
         state.clear();
-        let mut lhs_entries = peekable(self.0.take().unwrap_or_default());
+        if self.ignore_case {
+            return changes_ignoring_case(self.tree.take().unwrap_or_default(), other, state, find, delegate);
+        }
+        let mut lhs_entries = peekable(self.tree.take().unwrap_or_default());
         let mut rhs_entries = peekable(other);
         let mut pop_path = false;
 
@@ -119,6 +129,147 @@ impl<'a> tree::Changes<'a> {
     }
 }
 
+/// Like [`needed_to_obtain()`][tree::Changes::needed_to_obtain()], but match entries by their case-folded names.
+fn changes_ignoring_case<FindFn, R>(
+    lhs: immutable::TreeIter<'_>,
+    rhs: immutable::TreeIter<'_>,
+    state: &mut tree::State,
+    mut find: FindFn,
+    delegate: &mut R,
+) -> Result<(), Error>
+where
+    FindFn: for<'b> FnMut(&oid, &'b mut Vec<u8>) -> Option<immutable::tree::TreeIter<'b>>,
+    R: tree::Visit,
+{
+    handle_entries_ignoring_case(
+        lhs.collect::<Result<_, _>>()?,
+        rhs.collect::<Result<_, _>>()?,
+        &mut state.trees,
+        delegate,
+    )?;
+    while let Some((lhs, rhs)) = state.trees.pop_front() {
+        delegate.pop_front_tracked_path_and_set_current();
+        let mut entries = |id: Option<ObjectId>, buf| -> Result<Vec<_>, Error> {
+            match id {
+                Some(id) => Ok(find(&id, buf)
+                    .ok_or(Error::NotFound { oid: id })?
+                    .collect::<Result<_, _>>()?),
+                None => Ok(Vec::new()),
+            }
+        };
+        let lhs_entries = entries(lhs, &mut state.buf1)?;
+        let rhs_entries = entries(rhs, &mut state.buf2)?;
+        handle_entries_ignoring_case(lhs_entries, rhs_entries, &mut state.trees, delegate)?;
+    }
+    Ok(())
+}
+
+/// Pair `lhs` and `rhs` entries with equal names first, followed by those whose case-folded names are equal, and pass
+/// all of them to `delegate` ordered by name.
+fn handle_entries_ignoring_case<R: tree::Visit>(
+    lhs: Vec<immutable::tree::Entry<'_>>,
+    rhs: Vec<immutable::tree::Entry<'_>>,
+    queue: &mut VecDeque<TreeInfoPair>,
+    delegate: &mut R,
+) -> Result<(), Error> {
+    let mut rhs_by_folded_name = HashMap::<BString, VecDeque<usize>>::new();
+    for (rhs_index, rhs_entry) in rhs.iter().enumerate() {
+        rhs_by_folded_name
+            .entry(rhs_entry.filename.to_ascii_lowercase().into())
+            .or_default()
+            .push_back(rhs_index);
+    }
+    let mut rhs_is_paired = vec![false; rhs.len()];
+    let mut pairs = Vec::with_capacity(lhs.len().max(rhs.len()));
+    let mut unpaired_lhs = Vec::new();
+    for (lhs_index, lhs_entry) in lhs.iter().enumerate() {
+        let folded_name = BString::from(lhs_entry.filename.to_ascii_lowercase());
+        let rhs_index = rhs_by_folded_name.get_mut(&folded_name).and_then(|candidates| {
+            let position = candidates
+                .iter()
+                .position(|rhs_index| rhs[*rhs_index].filename == lhs_entry.filename)?;
+            candidates.remove(position)
+        });
+        match rhs_index {
+            Some(rhs_index) => {
+                rhs_is_paired[rhs_index] = true;
+                pairs.push((Some(lhs_index), Some(rhs_index)));
+            }
+            None => unpaired_lhs.push((lhs_index, folded_name)),
+        }
+    }
+    for (lhs_index, folded_name) in unpaired_lhs {
+        let rhs_index = rhs_by_folded_name.get_mut(&folded_name).and_then(VecDeque::pop_front);
+        if let Some(rhs_index) = rhs_index {
+            rhs_is_paired[rhs_index] = true;
+        }
+        pairs.push((Some(lhs_index), rhs_index));
+    }
+    pairs.extend(
+        (0..rhs.len())
+            .filter(|rhs_index| !rhs_is_paired[*rhs_index])
+            .map(|rhs_index| (None, Some(rhs_index))),
+    );
+    pairs.sort_by_key(|(lhs_index, rhs_index)| {
+        rhs_index
+            .map(|index| rhs[index].filename)
+            .or_else(|| lhs_index.map(|index| lhs[index].filename))
+    });
+
+    for pair in pairs {
+        match pair {
+            (Some(lhs_index), Some(rhs_index)) => {
+                let (lhs, rhs) = (lhs[lhs_index].clone(), rhs[rhs_index].clone());
+                if lhs.filename == rhs.filename {
+                    handle_lhs_and_rhs_with_equal_filenames(lhs, rhs, queue, delegate)?;
+                } else {
+                    handle_lhs_and_rhs_with_filenames_differing_in_case(lhs, rhs, queue, delegate)?;
+                }
+            }
+            (Some(lhs_index), None) => delete_entry_schedule_recursion(lhs[lhs_index].clone(), queue, delegate)?,
+            (None, Some(rhs_index)) => add_entry_schedule_recursion(rhs[rhs_index].clone(), queue, delegate)?,
+            (None, None) => unreachable!("BUG: each pair has at least one entry"),
+        }
+        delegate.pop_path_component();
+    }
+    Ok(())
+}
+
+fn handle_lhs_and_rhs_with_filenames_differing_in_case<R: tree::Visit>(
+    lhs: immutable::tree::Entry<'_>,
+    rhs: immutable::tree::Entry<'_>,
+    queue: &mut VecDeque<TreeInfoPair>,
+    delegate: &mut R,
+) -> Result<(), Error> {
+    if lhs.mode.is_tree() != rhs.mode.is_tree() {
+        delete_entry_schedule_recursion(lhs, queue, delegate)?;
+        delegate.pop_path_component();
+        return add_entry_schedule_recursion(rhs, queue, delegate);
+    }
+    let needs_recursion = lhs.mode.is_tree() && lhs.oid != rhs.oid;
+    if needs_recursion {
+        delegate.push_back_tracked_path_component(rhs.filename);
+    } else {
+        delegate.push_path_component(rhs.filename);
+    }
+    if delegate
+        .visit(Change::Rename {
+            previous_filename: lhs.filename.to_owned(),
+            previous_entry_mode: lhs.mode,
+            previous_oid: lhs.oid.to_owned(),
+            entry_mode: rhs.mode,
+            oid: rhs.oid.to_owned(),
+        })
+        .cancelled()
+    {
+        return Err(Error::Cancelled);
+    }
+    if needs_recursion {
+        queue.push_back((Some(lhs.oid.to_owned()), Some(rhs.oid.to_owned())));
+    }
+    Ok(())
+}
+
 fn delete_entry_schedule_recursion<R: tree::Visit>(
     entry: immutable::tree::Entry<'_>,
     queue: &mut VecDeque<TreeInfoPair>,
@@ -252,17 +403,19 @@ fn handle_lhs_and_rhs_with_equal_filenames<R: tree::Visit>(
 ) -> Result<(), Error> {
     use git_object::tree::EntryMode::*;
     match (lhs.mode, rhs.mode) {
+        (Tree, Tree) if lhs.oid == rhs.oid => {
+            delegate.push_path_component(lhs.filename);
+        }
         (Tree, Tree) => {
             delegate.push_back_tracked_path_component(lhs.filename);
-            if lhs.oid != rhs.oid
-                && delegate
-                    .visit(Change::Modification {
-                        previous_entry_mode: lhs.mode,
-                        previous_oid: lhs.oid.to_owned(),
-                        entry_mode: rhs.mode,
-                        oid: rhs.oid.to_owned(),
-                    })
-                    .cancelled()
+            if delegate
+                .visit(Change::Modification {
+                    previous_entry_mode: lhs.mode,
+                    previous_oid: lhs.oid.to_owned(),
+                    entry_mode: rhs.mode,
+                    oid: rhs.oid.to_owned(),
+                })
+                .cancelled()
             {
                 return Err(Error::Cancelled);
             }
@@ -315,7 +468,7 @@ fn handle_lhs_and_rhs_with_equal_filenames<R: tree::Visit>(
         (lhs_non_tree, rhs_non_tree) => {
             delegate.push_path_component(lhs.filename);
             debug_assert!(lhs_non_tree.is_no_tree() && rhs_non_tree.is_no_tree());
-            if lhs.oid != rhs.oid
+            if (lhs.oid != rhs.oid || lhs.mode != rhs.mode)
                 && delegate
                     .visit(Change::Modification {
                         previous_entry_mode: lhs.mode,
@@ -0,0 +1,455 @@
+use std::{borrow::BorrowMut, collections::VecDeque, ops::Range};
+
+use git_hash::{oid, ObjectId};
+use git_index::{entry, extension, Entry};
+use git_object::{
+    bstr::{BStr, BString, ByteSlice},
+    immutable, mutable,
+    tree::EntryMode,
+};
+
+use crate::{
+    tree,
+    tree::{changes::Error, visit::Change},
+    util,
+};
+
+/// A directory whose entries are compared.
+#[derive(Clone)]
+enum Subtree<'t> {
+    /// A directory of the index whose entries are in `range`, all of which share a prefix of `prefix_len` bytes, with `node`
+    /// holding the ids of all of its trees.
+    Index {
+        range: Range<usize>,
+        prefix_len: usize,
+        node: &'t extension::Tree,
+    },
+    /// A sparse directory of the index whose entries are in the tree with the given id.
+    Tree(ObjectId),
+}
+
+/// An entry of a tree as seen by the index.
+struct IndexEntry<'t> {
+    filename: BString,
+    /// The mode and id of the entry, or `None` if there are only conflicting entries at this path which aren't part of any tree.
+    object: Option<(EntryMode, ObjectId)>,
+    subtree: Option<Subtree<'t>>,
+}
+
+type QueueItem<'t> = (Option<ObjectId>, Option<Subtree<'t>>);
+
+impl<'a> tree::Changes<'a> {
+    /// Calculate the changes that would need to be applied to `self` to get the tree that would be written from `index`,
+    /// which is what `git diff --cached` shows.
+    ///
+    /// The parameters are the same as for [`needed_to_obtain()`][tree::Changes::needed_to_obtain()], and `delegate` receives
+    /// changes just as if it compared `self` with a tree of `index`.
+    ///
+    /// # Notes
+    ///
+    /// * Subtrees whose ids are known from the cache-tree extension of `index` are skipped entirely if they didn't change.
+    ///   The ids of all other subtrees are computed by hashing their entries.
+    /// * `find` is used to obtain the trees of `self` and those of sparse directories in the index.
+    /// * Conflicting entries are not part of any tree and no change is reported for their paths. Entries which are only
+    ///   intended to be added are not part of a tree either, just like in git.
+    /// * Changes are reported breadth first and in order of their file names within each tree.
+    /// * Other than with [`needed_to_obtain()`][tree::Changes::needed_to_obtain()], unchanged subtrees aren't entered, and
+    ///   changes of the mode of files with the same content are reported as modification as well.
+    pub fn needed_to_obtain_index<FindFn, R, StateMut>(
+        mut self,
+        index: &git_index::State,
+        mut state: StateMut,
+        mut find: FindFn,
+        delegate: &mut R,
+    ) -> Result<(), Error>
+    where
+        FindFn: for<'b> FnMut(&oid, &'b mut Vec<u8>) -> Option<immutable::tree::TreeIter<'b>>,
+        R: tree::Visit,
+        StateMut: BorrowMut<tree::State>,
+    {
+        let state = state.borrow_mut();
+        state.clear();
+        let entries = index.entries();
+        let root = tree_with_ids(entries, 0..entries.len(), 0, "".into(), index.tree()).unwrap_or(extension::Tree {
+            name: Default::default(),
+            id: None,
+            children: Vec::new(),
+        });
+
+        let mut queue = VecDeque::<QueueItem<'_>>::new();
+        let mut lhs_entries = match self.tree.take() {
+            Some(iter) => tree_entries(iter)?,
+            None => Vec::new(),
+        };
+        let mut rhs_entries = index_entries(
+            Subtree::Index {
+                range: 0..entries.len(),
+                prefix_len: 0,
+                node: &root,
+            },
+            entries,
+            &mut find,
+            &mut state.buf2,
+        )?;
+        loop {
+            lhs_entries.sort_by(|a, b| a.filename.cmp(&b.filename));
+            rhs_entries.sort_by(|a, b| a.filename.cmp(&b.filename));
+            let mut lhs_iter = lhs_entries.drain(..).peekable();
+            let mut rhs_iter = rhs_entries.drain(..).peekable();
+            loop {
+                use std::cmp::Ordering::*;
+                let ordering = match (lhs_iter.peek(), rhs_iter.peek()) {
+                    (None, None) => break,
+                    (Some(_), None) => Less,
+                    (None, Some(_)) => Greater,
+                    (Some(lhs), Some(rhs)) => lhs.filename.cmp(&rhs.filename),
+                };
+                match ordering {
+                    Less => delete_entry_schedule_recursion(lhs_iter.next().expect("peeked"), &mut queue, delegate)?,
+                    Greater => add_entry_schedule_recursion(rhs_iter.next().expect("peeked"), &mut queue, delegate)?,
+                    Equal => handle_lhs_and_rhs_with_equal_filenames(
+                        lhs_iter.next().expect("peeked"),
+                        rhs_iter.next().expect("peeked"),
+                        &mut queue,
+                        delegate,
+                    )?,
+                }
+                delegate.pop_path_component();
+            }
+            drop((lhs_iter, rhs_iter));
+
+            let (lhs, rhs) = match queue.pop_front() {
+                Some(trees) => trees,
+                None => return Ok(()),
+            };
+            delegate.pop_front_tracked_path_and_set_current();
+            lhs_entries = match lhs {
+                Some(lhs) => tree_entries(find(&lhs, &mut state.buf1).ok_or(Error::NotFound { oid: lhs })?)?,
+                None => Vec::new(),
+            };
+            rhs_entries = match rhs {
+                Some(rhs) => index_entries(rhs, entries, &mut find, &mut state.buf2)?,
+                None => Vec::new(),
+            };
+        }
+    }
+}
+
+fn delete_entry_schedule_recursion<R: tree::Visit>(
+    entry: mutable::tree::Entry,
+    queue: &mut VecDeque<QueueItem<'_>>,
+    delegate: &mut R,
+) -> Result<(), Error> {
+    delegate.push_path_component(entry.filename.as_bstr());
+    if delegate
+        .visit(Change::Deletion {
+            entry_mode: entry.mode,
+            oid: entry.oid,
+        })
+        .cancelled()
+    {
+        return Err(Error::Cancelled);
+    }
+    if entry.mode.is_tree() {
+        delegate.pop_path_component();
+        delegate.push_back_tracked_path_component(entry.filename.as_bstr());
+        queue.push_back((Some(entry.oid), None));
+    }
+    Ok(())
+}
+
+fn add_entry_schedule_recursion<'t, R: tree::Visit>(
+    entry: IndexEntry<'t>,
+    queue: &mut VecDeque<QueueItem<'t>>,
+    delegate: &mut R,
+) -> Result<(), Error> {
+    delegate.push_path_component(entry.filename.as_bstr());
+    let (entry_mode, oid) = match entry.object {
+        Some(object) => object,
+        None => return Ok(()),
+    };
+    if delegate.visit(Change::Addition { entry_mode, oid }).cancelled() {
+        return Err(Error::Cancelled);
+    }
+    if let Some(subtree) = entry.subtree {
+        delegate.pop_path_component();
+        delegate.push_back_tracked_path_component(entry.filename.as_bstr());
+        queue.push_back((None, Some(subtree)));
+    }
+    Ok(())
+}
+
+fn handle_lhs_and_rhs_with_equal_filenames<'t, R: tree::Visit>(
+    lhs: mutable::tree::Entry,
+    rhs: IndexEntry<'t>,
+    queue: &mut VecDeque<QueueItem<'t>>,
+    delegate: &mut R,
+) -> Result<(), Error> {
+    let (rhs_mode, rhs_oid) = match rhs.object {
+        Some(object) => object,
+        None => {
+            delegate.push_path_component(lhs.filename.as_bstr());
+            return Ok(());
+        }
+    };
+    match (lhs.mode.is_tree(), rhs_mode.is_tree()) {
+        (true, true) if lhs.oid == rhs_oid => {
+            delegate.push_path_component(lhs.filename.as_bstr());
+        }
+        (true, true) => {
+            delegate.push_back_tracked_path_component(lhs.filename.as_bstr());
+            visit(
+                delegate,
+                Change::Modification {
+                    previous_entry_mode: lhs.mode,
+                    previous_oid: lhs.oid,
+                    entry_mode: rhs_mode,
+                    oid: rhs_oid,
+                },
+            )?;
+            queue.push_back((Some(lhs.oid), rhs.subtree));
+        }
+        (lhs_is_tree, rhs_is_tree) if lhs_is_tree != rhs_is_tree => {
+            delegate.push_back_tracked_path_component(lhs.filename.as_bstr());
+            visit(
+                delegate,
+                Change::Deletion {
+                    entry_mode: lhs.mode,
+                    oid: lhs.oid,
+                },
+            )?;
+            visit(
+                delegate,
+                Change::Addition {
+                    entry_mode: rhs_mode,
+                    oid: rhs_oid,
+                },
+            )?;
+            queue.push_back(if lhs_is_tree {
+                (Some(lhs.oid), None)
+            } else {
+                (None, rhs.subtree)
+            });
+        }
+        _ => {
+            delegate.push_path_component(lhs.filename.as_bstr());
+            if lhs.oid != rhs_oid || lhs.mode != rhs_mode {
+                visit(
+                    delegate,
+                    Change::Modification {
+                        previous_entry_mode: lhs.mode,
+                        previous_oid: lhs.oid,
+                        entry_mode: rhs_mode,
+                        oid: rhs_oid,
+                    },
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn visit<R: tree::Visit>(delegate: &mut R, change: Change) -> Result<(), Error> {
+    if delegate.visit(change).cancelled() {
+        Err(Error::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+fn tree_entries(iter: immutable::TreeIter<'_>) -> Result<Vec<mutable::tree::Entry>, Error> {
+    iter.map(|entry| {
+        entry
+            .map(|entry| mutable::tree::Entry {
+                mode: entry.mode,
+                filename: entry.filename.to_owned(),
+                oid: entry.oid.to_owned(),
+            })
+            .map_err(|err| Error::EntriesDecode(err.to_owned()))
+    })
+    .collect()
+}
+
+/// Return the entries of `subtree` as they would be written as tree, using `find` to obtain trees of sparse directories.
+fn index_entries<'t, FindFn>(
+    subtree: Subtree<'t>,
+    entries: &[Entry],
+    find: &mut FindFn,
+    buf: &mut Vec<u8>,
+) -> Result<Vec<IndexEntry<'t>>, Error>
+where
+    FindFn: for<'b> FnMut(&oid, &'b mut Vec<u8>) -> Option<immutable::tree::TreeIter<'b>>,
+{
+    let (range, prefix_len, node) = match subtree {
+        Subtree::Index {
+            range,
+            prefix_len,
+            node,
+        } => (range, prefix_len, node),
+        Subtree::Tree(id) => {
+            return tree_entries(find(&id, buf).ok_or(Error::NotFound { oid: id })?).map(|entries| {
+                entries
+                    .into_iter()
+                    .map(|entry| IndexEntry {
+                        subtree: entry.mode.is_tree().then(|| Subtree::Tree(entry.oid)),
+                        object: Some((entry.mode, entry.oid)),
+                        filename: entry.filename,
+                    })
+                    .collect()
+            })
+        }
+    };
+
+    let mut out = Vec::new();
+    let mut index = range.start;
+    while index < range.end {
+        let entry = &entries[index];
+        let name = &entry.path[prefix_len..];
+        match name.find_byte(b'/') {
+            Some(pos) if pos + 1 == name.len() => {
+                out.push(IndexEntry {
+                    filename: name[..pos].into(),
+                    object: Some((EntryMode::Tree, entry.id)),
+                    subtree: Some(Subtree::Tree(entry.id)),
+                });
+                index += 1;
+            }
+            Some(pos) => {
+                let end = directory_end(entries, index..range.end, prefix_len, &name[..=pos]);
+                let filename = &name[..pos];
+                let child = node.children.iter().find(|child| child.name == filename);
+                out.push(match child.and_then(|child| child.id.map(|id| (child, id))) {
+                    Some((child, id)) => IndexEntry {
+                        filename: filename.into(),
+                        object: Some((EntryMode::Tree, id.id)),
+                        subtree: Some(Subtree::Index {
+                            range: index..end,
+                            prefix_len: prefix_len + pos + 1,
+                            node: child,
+                        }),
+                    },
+                    None => IndexEntry {
+                        filename: filename.into(),
+                        object: None,
+                        subtree: None,
+                    },
+                });
+                index = end;
+            }
+            None => {
+                let end = index
+                    + entries[index..range.end]
+                        .iter()
+                        .take_while(|other| other.path == entry.path)
+                        .count();
+                if entry.stage() != 0 {
+                    out.push(IndexEntry {
+                        filename: name.into(),
+                        object: None,
+                        subtree: None,
+                    });
+                } else if !entry.flags.contains(entry::Flags::INTENT_TO_ADD) {
+                    out.push(IndexEntry {
+                        filename: name.into(),
+                        object: Some((entry.mode.into(), entry.id)),
+                        subtree: None,
+                    });
+                }
+                index = end;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Return the end of the range of `entries` starting at `range.start` whose paths continue with `directory` after `prefix_len` bytes.
+fn directory_end(entries: &[Entry], range: Range<usize>, prefix_len: usize, directory: &[u8]) -> usize {
+    range.start
+        + entries[range]
+            .iter()
+            .take_while(|entry| entry.path[prefix_len..].starts_with(directory))
+            .count()
+}
+
+/// Return the cache-tree named `name` for the `entries` in `range` which share a prefix of `prefix_len` bytes, with the ids
+/// of all trees known, or `None` if none of the entries would be part of a tree.
+///
+/// Valid trees of `cached` are used as is, all others are computed by hashing the tree that would be written.
+fn tree_with_ids(
+    entries: &[Entry],
+    range: Range<usize>,
+    prefix_len: usize,
+    name: &BStr,
+    cached: Option<&extension::Tree>,
+) -> Option<extension::Tree> {
+    if let Some(cached) = cached.filter(|cached| cached.id.is_some()) {
+        return Some(cached.clone());
+    }
+    let mut items = Vec::<(EntryMode, &[u8], ObjectId)>::new();
+    let mut children = Vec::new();
+    let mut entry_count = 0;
+    let mut index = range.start;
+    while index < range.end {
+        let entry = &entries[index];
+        let name = &entry.path[prefix_len..];
+        match name.find_byte(b'/') {
+            Some(pos) if pos + 1 == name.len() => {
+                items.push((EntryMode::Tree, &name[..pos], entry.id));
+                children.push(extension::Tree {
+                    name: name[..pos].into(),
+                    id: Some(extension::tree::NodeId {
+                        entry_count: 1,
+                        id: entry.id,
+                    }),
+                    children: Vec::new(),
+                });
+                entry_count += 1;
+                index += 1;
+            }
+            Some(pos) => {
+                let end = directory_end(entries, index..range.end, prefix_len, &name[..=pos]);
+                let filename = &name[..pos];
+                let cached_child =
+                    cached.and_then(|cached| cached.children.iter().find(|child| child.name == filename));
+                if let Some(child) = tree_with_ids(
+                    entries,
+                    index..end,
+                    prefix_len + pos + 1,
+                    filename.as_bstr(),
+                    cached_child,
+                ) {
+                    let id = child.id.expect("computed");
+                    items.push((EntryMode::Tree, filename, id.id));
+                    entry_count += id.entry_count;
+                    children.push(child);
+                }
+                index = end;
+            }
+            None => {
+                if entry.stage() == 0 && !entry.flags.contains(entry::Flags::INTENT_TO_ADD) {
+                    items.push((entry.mode.into(), name, entry.id));
+                    entry_count += 1;
+                }
+                index += 1;
+            }
+        }
+    }
+    let hash_kind = items.first()?.2.kind();
+
+    let mut data = Vec::new();
+    for (mode, filename, id) in items {
+        data.extend_from_slice(mode.as_bytes());
+        data.push(b' ');
+        data.extend_from_slice(filename);
+        data.push(0);
+        data.extend_from_slice(id.as_slice());
+    }
+    Some(extension::Tree {
+        name: name.to_owned(),
+        id: Some(extension::tree::NodeId {
+            entry_count,
+            id: util::object_id(git_object::Kind::Tree, &data, hash_kind),
+        }),
+        children,
+    })
+}
@@ -22,19 +22,40 @@ impl State {
 }
 
 /// An iterator over changes of a tree, instantiated using `Changes::from(…)`.
-pub struct Changes<'a>(Option<immutable::TreeIter<'a>>);
+pub struct Changes<'a> {
+    tree: Option<immutable::TreeIter<'a>>,
+    ignore_case: bool,
+}
 
 impl<'a, T> From<T> for Changes<'a>
 where
     T: Into<Option<immutable::TreeIter<'a>>>,
 {
     fn from(v: T) -> Self {
-        Changes(v.into())
+        Changes {
+            tree: v.into(),
+            ignore_case: false,
+        }
+    }
+}
+
+impl<'a> Changes<'a> {
+    /// If `toggle` is true, match the entries of both trees by their case-folded names, similar to how repositories
+    /// with `core.ignorecase` enabled treat paths, which is off by default.
+    ///
+    /// Entries whose names differ only in case are then reported as [renames][visit::Change::Rename] instead of a
+    /// deletion followed by an addition, unless one of them is a tree and the other isn't.
+    ///
+    /// This only affects [`needed_to_obtain()`][Changes::needed_to_obtain()].
+    pub fn ignore_case(mut self, toggle: bool) -> Self {
+        self.ignore_case = toggle;
+        self
     }
 }
 
 ///
 pub mod changes;
+mod index;
 
 ///
 pub mod visit;
@@ -45,3 +66,6 @@ pub use visit::Visit;
 pub mod recorder;
 #[doc(inline)]
 pub use recorder::Recorder;
+
+/// Find renames and copies among changes recorded by a [`Recorder`].
+pub mod rewrites;
@@ -10,6 +10,9 @@ use crate::tree::visit;
 
 /// A Change as observed by a call to [`visit(…)`][visit::Visit::visit()], enhanced with the path affected by the change.
 /// Its similar to [visit::Change] but includes the path that changed.
+///
+/// Renames and copies are produced by [`rewrites::detect()`][crate::tree::rewrites::detect()], and renames are observed directly
+/// only if they change the case of a name while [case is ignored][crate::tree::Changes::ignore_case()].
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[allow(missing_docs)]
 pub enum Change {
@@ -32,6 +35,47 @@ pub enum Change {
 
         path: BString,
     },
+    /// The entry at `source_path` was moved to `path`, possibly with modifications.
+    Rename {
+        source_entry_mode: tree::EntryMode,
+        source_oid: ObjectId,
+        source_path: BString,
+
+        entry_mode: tree::EntryMode,
+        oid: ObjectId,
+        path: BString,
+
+        /// How similar the content at `path` is to the one at `source_path`, in percent.
+        ///
+        /// Content isn't compared for observed renames, which is why it's 100 if it is unchanged and 0 otherwise.
+        similarity: u8,
+    },
+    /// The entry at `path` was added as copy of the one at `source_path`, possibly with modifications.
+    Copy {
+        source_entry_mode: tree::EntryMode,
+        source_oid: ObjectId,
+        source_path: BString,
+
+        entry_mode: tree::EntryMode,
+        oid: ObjectId,
+        path: BString,
+
+        /// How similar the content at `path` is to the one at `source_path`, in percent.
+        similarity: u8,
+    },
+}
+
+impl Change {
+    /// The path affected by this change, which for renames and copies is their destination.
+    pub fn path(&self) -> &BStr {
+        match self {
+            Change::Addition { path, .. }
+            | Change::Deletion { path, .. }
+            | Change::Modification { path, .. }
+            | Change::Rename { path, .. }
+            | Change::Copy { path, .. } => path.as_bstr(),
+        }
+    }
 }
 
 /// A [Visit][visit::Visit] implementation to record every observed change and keep track of the changed paths.
@@ -109,6 +153,29 @@ impl visit::Visit for Recorder {
                 oid,
                 path: self.path_clone(),
             },
+            Rename {
+                previous_filename,
+                previous_entry_mode,
+                previous_oid,
+                entry_mode,
+                oid,
+            } => {
+                let mut source_path = self.path_clone();
+                match source_path.rfind_byte(b'/') {
+                    Some(pos) => source_path.truncate(pos + 1),
+                    None => source_path.clear(),
+                }
+                source_path.push_str(&previous_filename);
+                Change::Rename {
+                    source_entry_mode: previous_entry_mode,
+                    source_oid: previous_oid,
+                    source_path,
+                    entry_mode,
+                    oid,
+                    path: self.path_clone(),
+                    similarity: if previous_oid == oid { 100 } else { 0 },
+                }
+            }
         });
         visit::Action::Continue
     }
@@ -0,0 +1,287 @@
+use std::collections::{HashMap, HashSet};
+
+use git_hash::{oid, ObjectId};
+use git_object::{immutable, tree::EntryMode};
+use quick_error::quick_error;
+
+use crate::tree::recorder::Change;
+
+quick_error! {
+    /// The error returned by [`detect()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        NotFound { oid: ObjectId } {
+            display("The blob {} could not be found to compare its content", oid)
+        }
+    }
+}
+
+/// Options to configure how [`detect()`] finds renames and copies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// The similarity in percent at which the content of two files is considered a rename or copy, like the `<n>` in
+    /// `git diff -M<n>%`. Defaults to 50.
+    pub similarity_threshold: u8,
+    /// If true, modified files are considered sources of copies along with deleted ones, like `git diff -C`.
+    pub copies: bool,
+    /// If the amount of possible sources times the amount of possible destinations exceeds this value squared, only
+    /// exact renames and copies are detected. `0` disables the limit. Defaults to 1000, just like `diff.renameLimit`.
+    pub limit: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            similarity_threshold: 50,
+            copies: false,
+            limit: 1000,
+        }
+    }
+}
+
+/// The similarity score of identical content, as used by git.
+const MAX_SCORE: u64 = 60000;
+
+/// A deleted or modified file whose previous content may have been renamed or copied.
+struct Source {
+    /// The index of the change in the input.
+    index: usize,
+    entry_mode: EntryMode,
+    oid: ObjectId,
+    is_deletion: bool,
+}
+
+/// A pairing of a destination with a source.
+#[derive(Clone, Copy)]
+struct Pair {
+    source: usize,
+    similarity: u8,
+    is_rename: bool,
+}
+
+/// Replace pairs of [deletions][Change::Deletion] and [additions][Change::Addition] of files in `changes` with
+/// [renames][Change::Rename], and additions that are copies of other files with [copies][Change::Copy] if enabled
+/// in `options`.
+///
+/// `find` is a function `f(blob_id, &mut buffer) -> Option<Blob>` to obtain the content of blobs to compare them.
+///
+/// # Notes
+///
+/// * Just like git, files with the same id are paired first, followed by files whose content is similar enough.
+///   Similarity is computed exactly like git does, which is the amount of bytes found in both files in chunks of lines,
+///   relative to the size of the larger file.
+/// * Each deleted file is renamed at most once, additional pairings are considered copies.
+/// * Symbolic links are only paired if their targets are identical, and trees and submodules aren't paired at all.
+/// * Renames and copies take the place of the addition they replace, and deletions of renamed files are removed.
+pub fn detect<FindFn>(changes: Vec<Change>, mut find: FindFn, options: Options) -> Result<Vec<Change>, Error>
+where
+    FindFn: for<'b> FnMut(&oid, &'b mut Vec<u8>) -> Option<immutable::Blob<'b>>,
+{
+    let mut sources = Vec::new();
+    let mut destinations = Vec::new();
+    for (index, change) in changes.iter().enumerate() {
+        match change {
+            Change::Deletion { entry_mode, oid, .. } if is_file(*entry_mode) => sources.push(Source {
+                index,
+                entry_mode: *entry_mode,
+                oid: *oid,
+                is_deletion: true,
+            }),
+            Change::Modification {
+                previous_entry_mode,
+                previous_oid,
+                ..
+            } if options.copies && is_file(*previous_entry_mode) => sources.push(Source {
+                index,
+                entry_mode: *previous_entry_mode,
+                oid: *previous_oid,
+                is_deletion: false,
+            }),
+            Change::Addition { entry_mode, oid, .. } if is_file(*entry_mode) => {
+                destinations.push((index, *entry_mode, *oid))
+            }
+            _ => {}
+        }
+    }
+
+    let mut sources_by_oid = HashMap::<ObjectId, Vec<usize>>::new();
+    for (source_index, source) in sources.iter().enumerate() {
+        sources_by_oid.entry(source.oid).or_default().push(source_index);
+    }
+    let mut pairs = vec![None::<Pair>; destinations.len()];
+    let mut renamed = vec![false; sources.len()];
+    for (destination, (_, entry_mode, oid)) in destinations.iter().enumerate() {
+        let candidates = sources_by_oid.get(oid).map(Vec::as_slice).unwrap_or_default();
+        let is_exact_match = |source: &usize| is_link(sources[*source].entry_mode) == is_link(*entry_mode);
+        let source = candidates
+            .iter()
+            .copied()
+            .find(|source| sources[*source].is_deletion && !renamed[*source] && is_exact_match(source))
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .copied()
+                    .find(is_exact_match)
+                    .filter(|_| options.copies)
+            });
+        if let Some(source) = source {
+            pairs[destination] = pair(&sources, &mut renamed, source, 100, options.copies);
+        }
+    }
+
+    let remaining_destinations: Vec<_> = (0..destinations.len())
+        .filter(|destination| pairs[*destination].is_none() && !is_link(destinations[*destination].1))
+        .collect();
+    let remaining_sources: Vec<_> = (0..sources.len())
+        .filter(|source| {
+            !is_link(sources[*source].entry_mode)
+                && (options.copies || (sources[*source].is_deletion && !renamed[*source]))
+        })
+        .collect();
+    let is_within_limit =
+        options.limit == 0 || remaining_sources.len() * remaining_destinations.len() <= options.limit * options.limit;
+    if !remaining_sources.is_empty() && !remaining_destinations.is_empty() && is_within_limit {
+        let minimum_score = options.similarity_threshold.min(100) as u64 * MAX_SCORE / 100;
+        let mut buf = Vec::new();
+        let mut load = |id: &oid| -> Result<(u64, HashMap<u32, u64>), Error> {
+            let blob = find(id, &mut buf).ok_or_else(|| Error::NotFound { oid: id.to_owned() })?;
+            Ok((blob.data.len() as u64, spans(blob.data)))
+        };
+        let source_spans = remaining_sources
+            .iter()
+            .map(|source| load(&sources[*source].oid))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut candidates = Vec::new();
+        for destination in remaining_destinations {
+            let (destination_size, destination_spans) = load(&destinations[destination].2)?;
+            for (source, (source_size, source_spans)) in remaining_sources.iter().zip(&source_spans) {
+                let max_size = destination_size.max(*source_size);
+                let delta_size = max_size - destination_size.min(*source_size);
+                if max_size == 0 || max_size * (MAX_SCORE - minimum_score) < delta_size * MAX_SCORE {
+                    continue;
+                }
+                let copied: u64 = source_spans
+                    .iter()
+                    .map(|(hash, count)| destination_spans.get(hash).map_or(0, |other| *count.min(other)))
+                    .sum();
+                let score = copied * MAX_SCORE / max_size;
+                if score >= minimum_score {
+                    candidates.push((score, destination, *source));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| (a.1, a.2).cmp(&(b.1, b.2))));
+        for (score, destination, source) in candidates {
+            if pairs[destination].is_none() {
+                let similarity = (score * 100 / MAX_SCORE) as u8;
+                pairs[destination] = pair(&sources, &mut renamed, source, similarity, options.copies);
+            }
+        }
+    }
+
+    let mut rewrites = HashMap::new();
+    let mut renamed_deletions = HashSet::new();
+    for ((index, entry_mode, oid), pair) in destinations.into_iter().zip(pairs) {
+        let Pair {
+            source,
+            similarity,
+            is_rename,
+        } = match pair {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let source = &sources[source];
+        let source_path = changes[source.index].path().to_owned();
+        let path = changes[index].path().to_owned();
+        let (source_entry_mode, source_oid) = (source.entry_mode, source.oid);
+        rewrites.insert(
+            index,
+            if is_rename {
+                renamed_deletions.insert(source.index);
+                Change::Rename {
+                    source_entry_mode,
+                    source_oid,
+                    source_path,
+                    entry_mode,
+                    oid,
+                    path,
+                    similarity,
+                }
+            } else {
+                Change::Copy {
+                    source_entry_mode,
+                    source_oid,
+                    source_path,
+                    entry_mode,
+                    oid,
+                    path,
+                    similarity,
+                }
+            },
+        );
+    }
+    Ok(changes
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !renamed_deletions.contains(index))
+        .map(|(index, change)| rewrites.remove(&index).unwrap_or(change))
+        .collect())
+}
+
+/// Pair a destination with `source` at the given `similarity`, unless that would make it a copy and `copies` are disabled.
+///
+/// The first pairing of a deleted file is a rename, and `renamed` keeps track of that.
+fn pair(sources: &[Source], renamed: &mut [bool], source: usize, similarity: u8, copies: bool) -> Option<Pair> {
+    let is_rename = sources[source].is_deletion && !renamed[source];
+    if !is_rename && !copies {
+        return None;
+    }
+    renamed[source] |= is_rename;
+    Some(Pair {
+        source,
+        similarity,
+        is_rename,
+    })
+}
+
+fn is_file(mode: EntryMode) -> bool {
+    matches!(mode, EntryMode::Blob | EntryMode::BlobExecutable | EntryMode::Link)
+}
+
+fn is_link(mode: EntryMode) -> bool {
+    mode == EntryMode::Link
+}
+
+/// Split `data` into chunks ending at a newline or after 64 bytes and return the amount of bytes for each hash of a chunk,
+/// exactly like git does to estimate the similarity of files.
+fn spans(data: &[u8]) -> HashMap<u32, u64> {
+    const HASH_BASE: u32 = 107927;
+    let is_text = !data[..data.len().min(8000)].contains(&0);
+    let mut out = HashMap::<u32, u64>::new();
+    let (mut accum1, mut accum2, mut num_bytes) = (0u32, 0u32, 0u64);
+    for (pos, &byte) in data.iter().enumerate() {
+        if is_text && byte == b'\r' && data.get(pos + 1) == Some(&b'\n') {
+            continue;
+        }
+        let previous_accum1 = accum1;
+        accum1 = (accum1 << 7) ^ (accum2 >> 25);
+        accum2 = (accum2 << 7) ^ (previous_accum1 >> 25);
+        accum1 = accum1.wrapping_add(byte as u32);
+        num_bytes += 1;
+        if num_bytes < 64 && byte != b'\n' {
+            continue;
+        }
+        *out.entry(accum1.wrapping_add(accum2.wrapping_mul(0x61)) % HASH_BASE)
+            .or_default() += num_bytes;
+        accum1 = 0;
+        accum2 = 0;
+        num_bytes = 0;
+    }
+    if num_bytes > 0 {
+        *out.entry(accum1.wrapping_add(accum2.wrapping_mul(0x61)) % HASH_BASE)
+            .or_default() += num_bytes;
+    }
+    out
+}
@@ -1,5 +1,8 @@
 use git_hash::ObjectId;
-use git_object::{bstr::BStr, tree};
+use git_object::{
+    bstr::{BStr, BString},
+    tree,
+};
 
 /// Represents any possible change in order to turn one tree into another.
 pub enum Change {
@@ -30,6 +33,23 @@ pub enum Change {
         /// The object id of the after before the modification.
         oid: ObjectId,
     },
+    /// An entry was renamed by changing the case of its name, which is only observed if
+    /// [case is ignored][crate::tree::Changes::ignore_case()]. Its mode and object id may have changed as well.
+    ///
+    /// The current path ends with the new name.
+    Rename {
+        /// The name of the entry before it was renamed.
+        previous_filename: BString,
+        /// The mode of the entry before it was renamed.
+        previous_entry_mode: tree::EntryMode,
+        /// The object id of the entry before it was renamed.
+        previous_oid: ObjectId,
+
+        /// The mode of the entry after it was renamed.
+        entry_mode: tree::EntryMode,
+        /// The object id of the entry after it was renamed.
+        oid: ObjectId,
+    },
 }
 
 /// What to do after a [Change] was [recorded][Visit::visit()].
@@ -73,7 +93,7 @@ mod tests {
     fn size_of_change() {
         assert_eq!(
             std::mem::size_of::<Change>(),
-            46,
+            96,
             "this type shouldn't grow without us knowing"
         )
     }
@@ -0,0 +1,12 @@
+use git_features::hash;
+
+/// Compute the id of an object of the given `kind` with `data`, using a hash of `hash_kind`.
+pub(crate) fn object_id(kind: git_object::Kind, data: &[u8], hash_kind: git_hash::Kind) -> git_hash::ObjectId {
+    let mut hasher = hash::hasher(hash_kind);
+    hasher.update(kind.as_bytes());
+    hasher.update(b" ");
+    hasher.update(data.len().to_string().as_bytes());
+    hasher.update(b"\0");
+    hasher.update(data);
+    hasher.digest()
+}
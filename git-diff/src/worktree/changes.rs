@@ -0,0 +1,272 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use git_features::parallel;
+use git_index::{entry, Entry};
+use git_object::bstr::{BStr, BString, ByteSlice, ByteVec};
+use quick_error::quick_error;
+
+use crate::{
+    util,
+    worktree::{visit::Change, Options, Visit},
+};
+
+quick_error! {
+    /// The error returned by [`changes()`][crate::worktree::changes()].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io { path: PathBuf, err: io::Error } {
+            display("Could not obtain information about '{}'", path.display())
+            source(err)
+        }
+        IllformedPath { path: BString } {
+            display("The path '{}' can't be represented on this platform", path)
+        }
+        WalkDir(err: git_features::fs::walkdir::Error) {
+            display("The working tree could not be traversed")
+            from()
+            source(err)
+        }
+        Cancelled {
+            display("The delegate cancelled the operation")
+        }
+    }
+}
+
+/// How the file of an entry differs from what's recorded in the index.
+#[derive(Debug, Clone, Copy)]
+enum Status {
+    Modified,
+    Deleted,
+    TypeChanged,
+}
+
+/// Compare all entries of `index` with the files in the `work_tree` it belongs to and pass the differences to `delegate`,
+/// configured by `options`.
+///
+/// Tracked files are checked first, in parallel and in the order of their entries in the index, followed by all
+/// untracked files sorted by path.
+///
+/// # Notes
+///
+/// * Only stat information is compared to detect modifications, unless the entry is _racily clean_, i.e. it was modified
+///   in the same second its index was written. Then its content is hashed to see if it actually changed.
+///   Files whose stat information changed are reported as modified without looking at their content, so entries
+///   should be refreshed to avoid reporting files that were merely touched.
+/// * Conflicting entries, entries that are assumed to be valid or excluded from the working tree as well as submodules
+///   are not checked.
+/// * Untracked files are listed one by one, similar to `git status --untracked-files=all`. Directories named `.git`
+///   are never entered, and ignore rules are not applied.
+/// * Content is hashed as is without applying any filters, like those for line-ending conversion.
+pub fn changes<V>(index: &git_index::State, work_tree: &Path, options: Options, delegate: &mut V) -> Result<(), Error>
+where
+    V: Visit,
+{
+    let entries = index.entries();
+    let index_written_at = index
+        .timestamp()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs() as u32)
+        .unwrap_or(0);
+    let (chunk_size, thread_limit, _) =
+        parallel::optimize_chunk_size_and_thread_limit(100, Some(entries.len()), options.thread_limit, None);
+    let chunk_size = chunk_size.max(1);
+    let chunks = (0..entries.len())
+        .step_by(chunk_size)
+        .map(|start| start..(start + chunk_size).min(entries.len()));
+    let statuses = parallel::in_parallel_if(
+        || entries.len() > chunk_size,
+        chunks,
+        thread_limit,
+        |_| Vec::new(),
+        |chunk, buf| -> Result<Vec<(usize, Status)>, Error> {
+            let mut out = Vec::new();
+            for entry_index in chunk {
+                if let Some(status) = entry_status(&entries[entry_index], work_tree, &options, index_written_at, buf)? {
+                    out.push((entry_index, status));
+                }
+            }
+            Ok(out)
+        },
+        Statuses::default(),
+    )?;
+
+    for (entry_index, status) in statuses {
+        let entry = &entries[entry_index];
+        let change = match status {
+            Status::Modified => Change::Modification { entry },
+            Status::Deleted => Change::Deletion { entry },
+            Status::TypeChanged => Change::TypeChange { entry },
+        };
+        if delegate.visit(change).cancelled() {
+            return Err(Error::Cancelled);
+        }
+    }
+
+    if options.skip_untracked {
+        return Ok(());
+    }
+    let mut untracked = Vec::new();
+    for dir_entry in git_features::fs::walkdir_new_skipping(work_tree, |name| name == ".git") {
+        let dir_entry = dir_entry?;
+        if dir_entry.file_type().is_dir() {
+            continue;
+        }
+        let path = dir_entry.path();
+        let relative_path = path
+            .strip_prefix(work_tree)
+            .expect("prefix-stripping cannot fail as prefix is our root");
+        let relative_path = <[u8]>::from_path(relative_path).ok_or_else(|| Error::IllformedPath {
+            path: Vec::from_path_lossy(relative_path).into_owned().into(),
+        })?;
+        #[cfg(windows)]
+        let relative_path = relative_path.replace(b"\\", b"/");
+        if !is_tracked(entries, relative_path.as_bstr()) {
+            untracked.push(BString::from(relative_path.to_owned()));
+        }
+    }
+    untracked.sort();
+    for path in &untracked {
+        if delegate.visit(Change::Untracked { path: path.as_bstr() }).cancelled() {
+            return Err(Error::Cancelled);
+        }
+    }
+    Ok(())
+}
+
+/// Return true if `path` is tracked by an entry, or if it is within a submodule or a sparse directory.
+fn is_tracked(entries: &[Entry], path: &BStr) -> bool {
+    let find = |path: &[u8]| {
+        entries
+            .binary_search_by(|entry| entry.path.as_slice().cmp(path))
+            .ok()
+            .map(|entry_index| &entries[entry_index])
+    };
+    if find(path).is_some() {
+        return true;
+    }
+    path.find_iter("/").any(|pos| {
+        find(&path[..pos]).map_or(false, |entry| entry.mode == entry::Mode::Commit)
+            || find(&path[..=pos]).map_or(false, Entry::is_sparse_directory)
+    })
+}
+
+/// Determine how the file of `entry` in `work_tree` differs from the index written at `index_written_at` seconds since
+/// the unix epoch, using `buf` to hold its content if it needs to be hashed.
+fn entry_status(
+    entry: &Entry,
+    work_tree: &Path,
+    options: &Options,
+    index_written_at: u32,
+    buf: &mut Vec<u8>,
+) -> Result<Option<Status>, Error> {
+    if entry.stage() != 0
+        || entry.flags.contains(entry::Flags::ASSUME_VALID)
+        || entry.flags.contains(entry::Flags::SKIP_WORKTREE)
+        || matches!(entry.mode, entry::Mode::Commit | entry::Mode::Dir)
+    {
+        return Ok(None);
+    }
+    let path = work_tree.join(entry.path.to_path().map_err(|_| Error::IllformedPath {
+        path: entry.path.clone(),
+    })?);
+    let metadata = match std::fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(err) if is_missing(&err) => return Ok(Some(Status::Deleted)),
+        Err(err) => return Err(Error::Io { path, err }),
+    };
+
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        return Ok(Some(Status::Deleted));
+    }
+    let is_symlink = entry.mode == entry::Mode::Symlink;
+    if (is_symlink && !file_type.is_symlink()) || (!is_symlink && !file_type.is_file()) {
+        return Ok(Some(Status::TypeChanged));
+    }
+    #[cfg(unix)]
+    if !is_symlink && !options.ignore_executable_bit {
+        use std::os::unix::fs::PermissionsExt;
+        let is_executable = metadata.permissions().mode() & 0o100 != 0;
+        if is_executable != (entry.mode == entry::Mode::FileExecutable) {
+            return Ok(Some(Status::Modified));
+        }
+    }
+
+    let stat = entry::Stat::from_fs(&metadata);
+    let recorded = &entry.stat;
+    if stat.mtime.secs != recorded.mtime.secs
+        || (!options.ignore_ctime && stat.ctime.secs != recorded.ctime.secs)
+        || stat.ino != recorded.ino
+        || stat.uid != recorded.uid
+        || stat.gid != recorded.gid
+        || stat.size != recorded.size
+    {
+        return Ok(Some(Status::Modified));
+    }
+
+    let is_racily_clean = index_written_at != 0 && index_written_at <= recorded.mtime.secs;
+    if is_racily_clean {
+        buf.clear();
+        if is_symlink {
+            let target = std::fs::read_link(&path).map_err(|err| Error::Io {
+                path: path.clone(),
+                err,
+            })?;
+            buf.extend_from_slice(&Vec::from_path_lossy(&target));
+        } else {
+            buf.extend_from_slice(&std::fs::read(&path).map_err(|err| Error::Io {
+                path: path.clone(),
+                err,
+            })?);
+        }
+        if util::object_id(git_object::Kind::Blob, buf, entry.id.kind()) != entry.id {
+            return Ok(Some(Status::Modified));
+        }
+    }
+    Ok(None)
+}
+
+/// Return true if `err` indicates that a path doesn't exist, which includes a file in place of one of its directories.
+fn is_missing(err: &io::Error) -> bool {
+    /// The value of `ENOTDIR` on all unix platforms we know.
+    #[cfg(unix)]
+    const NOT_A_DIRECTORY: i32 = 20;
+    err.kind() == io::ErrorKind::NotFound || {
+        #[cfg(unix)]
+        {
+            err.raw_os_error() == Some(NOT_A_DIRECTORY)
+        }
+        #[cfg(not(unix))]
+        {
+            false
+        }
+    }
+}
+
+/// Collect statuses of chunks of entries in any order and put them back into the order of their entries.
+#[derive(Default)]
+struct Statuses {
+    items: Vec<(usize, Status)>,
+}
+
+impl parallel::Reduce for Statuses {
+    type Input = Result<Vec<(usize, Status)>, Error>;
+    type FeedProduce = ();
+    type Output = Vec<(usize, Status)>;
+    type Error = Error;
+
+    fn feed(&mut self, item: Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+        self.items.extend(item?);
+        Ok(())
+    }
+
+    fn finalize(mut self) -> Result<Self::Output, Self::Error> {
+        self.items.sort_by_key(|(entry_index, _)| *entry_index);
+        Ok(self.items)
+    }
+}
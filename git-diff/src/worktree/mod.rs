@@ -0,0 +1,27 @@
+/// Options to configure how [`changes()`] compares an index with the files in its working tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+    /// The amount of threads to use when obtaining stat information of tracked files, or `None` to use all logical cores.
+    pub thread_limit: Option<usize>,
+    /// If true, the time at which metadata of files changed isn't compared. It's what git does if `core.trustctime` is false.
+    pub ignore_ctime: bool,
+    /// If true, changes to the executable bit of files are ignored. It's what git does if `core.fileMode` is false.
+    pub ignore_executable_bit: bool,
+    /// If true, the working tree isn't traversed to find untracked files.
+    pub skip_untracked: bool,
+}
+
+///
+pub mod changes;
+#[doc(inline)]
+pub use changes::changes;
+
+///
+pub mod visit;
+#[doc(inline)]
+pub use visit::Visit;
+
+/// Useful for use as delegate implementing [`Visit`] to keep track of all seen changes. Useful for debugging or printing primarily.
+pub mod recorder;
+#[doc(inline)]
+pub use recorder::Recorder;
@@ -0,0 +1,33 @@
+use git_object::bstr::BString;
+
+use crate::worktree::visit;
+
+/// A Change as observed by a call to [`visit(…)`][visit::Visit::visit()], with the affected path owned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Change {
+    Modification { path: BString },
+    Deletion { path: BString },
+    TypeChange { path: BString },
+    Untracked { path: BString },
+}
+
+/// A [Visit][visit::Visit] implementation to record every observed change.
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    /// The observed changes.
+    pub records: Vec<Change>,
+}
+
+impl visit::Visit for Recorder {
+    fn visit(&mut self, change: visit::Change<'_>) -> visit::Action {
+        let path = change.path().to_owned();
+        self.records.push(match change {
+            visit::Change::Modification { .. } => Change::Modification { path },
+            visit::Change::Deletion { .. } => Change::Deletion { path },
+            visit::Change::TypeChange { .. } => Change::TypeChange { path },
+            visit::Change::Untracked { .. } => Change::Untracked { path },
+        });
+        visit::Action::Continue
+    }
+}
@@ -0,0 +1,45 @@
+use git_object::bstr::BStr;
+
+pub use crate::tree::visit::Action;
+
+/// Represents a difference between an entry in the index and the working tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change<'a> {
+    /// The file of a tracked `entry` was modified, which is when its stat information or its executable bit differ from
+    /// what's recorded in the index, or when its content changed.
+    Modification {
+        /// The entry in the index.
+        entry: &'a git_index::Entry,
+    },
+    /// The file of a tracked `entry` is missing from the working tree, or was replaced by a directory.
+    Deletion {
+        /// The entry in the index.
+        entry: &'a git_index::Entry,
+    },
+    /// The file of a tracked `entry` changed its type, like a file that became a symbolic link.
+    TypeChange {
+        /// The entry in the index.
+        entry: &'a git_index::Entry,
+    },
+    /// A file or symbolic link in the working tree is not tracked by the index.
+    Untracked {
+        /// The path to the file relative to the root of the working tree, using forward slashes as separators.
+        path: &'a BStr,
+    },
+}
+
+impl<'a> Change<'a> {
+    /// The path of the changed item relative to the root of the working tree.
+    pub fn path(&self) -> &'a BStr {
+        match self {
+            Change::Modification { entry } | Change::Deletion { entry } | Change::TypeChange { entry } => entry.path(),
+            Change::Untracked { path } => path,
+        }
+    }
+}
+
+/// A trait to allow responding to the [changes][Change] between an index and its working tree.
+pub trait Visit {
+    /// Record a `change` and return an instruction whether to continue or not.
+    fn visit(&mut self, change: Change<'_>) -> Action;
+}
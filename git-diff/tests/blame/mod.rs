@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+
+use git_diff::blame;
+use git_hash::ObjectId;
+use git_object::bstr::ByteSlice;
+use git_odb::{linked, pack, Find};
+
+fn repo() -> std::path::PathBuf {
+    git_testtools::scripted_fixture_repo_read_only("make_blame_repo.sh").expect("fixture script succeeds")
+}
+
+fn head_id(repo: &std::path::Path) -> ObjectId {
+    git_testtools::hex_to_id(
+        std::str::from_utf8(&git(repo, &["rev-parse", "HEAD"]))
+            .expect("valid UTF-8")
+            .trim(),
+    )
+}
+
+fn git(repo: &std::path::Path, args: &[&str]) -> Vec<u8> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .output()
+        .expect("git can be executed");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    output.stdout
+}
+
+/// Return the commit, the 1-based line number in the original file and the one in the blamed file for each line as
+/// reported by `git blame --porcelain` with `args`.
+fn git_blame(repo: &std::path::Path, args: &[&str]) -> Vec<(ObjectId, u32, u32)> {
+    let mut all_args = vec!["blame", "--porcelain"];
+    all_args.extend_from_slice(args);
+    all_args.extend_from_slice(&["--", "dir/file"]);
+    git(repo, &all_args)
+        .lines()
+        .filter_map(|line| {
+            let mut tokens = line.split_str(" ");
+            let id = ObjectId::from_hex(tokens.next()?).ok()?;
+            let mut number = || -> Option<u32> { tokens.next()?.to_str().ok()?.parse().ok() };
+            Some((id, number()?, number()?))
+        })
+        .collect()
+}
+
+/// Turn `entries` into one item per line, just like [`git_blame()`].
+fn lines(entries: &[blame::Entry]) -> Vec<(ObjectId, u32, u32)> {
+    entries
+        .iter()
+        .flat_map(|entry| {
+            entry
+                .range_in_original_file
+                .clone()
+                .zip(entry.range_in_blamed_file.clone())
+                .map(move |(original, blamed)| (entry.commit_id, original + 1, blamed + 1))
+        })
+        .collect()
+}
+
+fn blame_file(repo: &std::path::Path, options: &blame::Options) -> crate::Result<Vec<blame::Entry>> {
+    let db = linked::Store::at(repo.join(".git").join("objects"), git_hash::Kind::Sha1)?;
+    Ok(blame::file(
+        head_id(repo),
+        "dir/file".into(),
+        |oid, buf| {
+            db.find(oid, buf, &mut pack::cache::Never)
+                .ok()
+                .flatten()
+                .and_then(|obj| obj.decode().ok())
+        },
+        options,
+    )?)
+}
+
+#[test]
+fn all_lines_across_merges_match_git() -> crate::Result {
+    let repo = repo();
+    let entries = blame_file(&repo, &Default::default())?;
+    assert_eq!(lines(&entries), git_blame(&repo, &[]));
+    assert_eq!(entries.len(), 11, "adjacent lines of the same commit are merged");
+    Ok(())
+}
+
+#[test]
+fn line_ranges_match_git() -> crate::Result {
+    let repo = repo();
+    let entries = blame_file(
+        &repo,
+        &blame::Options {
+            range: Some(3..9),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(lines(&entries), git_blame(&repo, &["-L", "4,9"]));
+
+    assert!(matches!(
+        blame_file(
+            &repo,
+            &blame::Options {
+                range: Some(3..13),
+                ..Default::default()
+            },
+        ),
+        Err(err) if err.to_string() == "The lines 3..13 are out of bounds for a file with 12 lines"
+    ));
+    Ok(())
+}
+
+#[test]
+fn ignored_revisions_match_git() -> crate::Result {
+    let repo = repo();
+    let ignore_revs = blame::parse_ignore_revs(&std::fs::read(repo.join(".git").join("ignored-rev"))?)?;
+    let entries = blame_file(
+        &repo,
+        &blame::Options {
+            ignore_revs,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(
+        lines(&entries),
+        git_blame(&repo, &["--ignore-revs-file", ".git/ignored-rev"])
+    );
+    Ok(())
+}
+
+#[test]
+fn parse_ignore_revs_skips_comments_and_empty_lines() -> crate::Result {
+    let id = "0123456789012345678901234567890123456789";
+    let revs = blame::parse_ignore_revs(format!("# comment\n\n{} # trailing comment\n", id).as_bytes())?;
+    assert_eq!(revs, vec![crate::hex_to_id(id)].into_iter().collect::<HashSet<_>>());
+
+    assert_eq!(
+        blame::parse_ignore_revs(b"0123\n")
+            .expect_err("abbreviated ids are invalid")
+            .to_string(),
+        "'0123' is not a full hexadecimal object id"
+    );
+    Ok(())
+}
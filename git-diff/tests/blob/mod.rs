@@ -0,0 +1,201 @@
+use git_diff::blob::{self, unified, Algorithm};
+use git_object::bstr::{BStr, ByteSlice};
+
+const ALGORITHMS: [Algorithm; 3] = [Algorithm::Myers, Algorithm::Patience, Algorithm::Histogram];
+
+mod unified_diff {
+    use super::*;
+
+    fn fixture() -> std::path::PathBuf {
+        git_testtools::scripted_fixture_repo_read_only("make_blob_diff_files.sh").expect("fixture script succeeds")
+    }
+
+    /// Return the output of `git diff --no-index` for the `before` and `after` files of `case`, without its leading
+    /// `diff --git` and `index` lines.
+    fn git_diff(root: &std::path::Path, case: &str, algorithm: Algorithm, context_lines: u32) -> String {
+        let algorithm = match algorithm {
+            Algorithm::Myers => "myers",
+            Algorithm::Patience => "patience",
+            Algorithm::Histogram => "histogram",
+        };
+        let output = std::process::Command::new("git")
+            .args(&["-c", "diff.noprefix=false", "diff", "--no-index", "--no-color"])
+            .arg(format!("--diff-algorithm={}", algorithm))
+            .arg(format!("-U{}", context_lines))
+            .arg(format!("{}/before", case))
+            .arg(format!("{}/after", case))
+            .current_dir(root)
+            .output()
+            .expect("git can be executed");
+        assert_eq!(
+            output.status.code(),
+            Some(1),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout)
+            .expect("valid UTF-8")
+            .lines()
+            .skip_while(|line| line.starts_with("diff --git") || line.starts_with("index "))
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+
+    fn diff(root: &std::path::Path, case: &str, options: unified::Options) -> crate::Result<String> {
+        let before_path = format!("{}/before", case);
+        let after_path = format!("{}/after", case);
+        let mut out = Vec::new();
+        unified::write_to(
+            &mut out,
+            Some(before_path.as_bytes().as_bstr()),
+            &std::fs::read(root.join(&before_path))?,
+            Some(after_path.as_bytes().as_bstr()),
+            &std::fs::read(root.join(&after_path))?,
+            options,
+        )?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn matches_git_for_all_algorithms_and_context_sizes() -> crate::Result {
+        let root = fixture();
+        for case in &["numbers", "functions", "binary"] {
+            for algorithm in ALGORITHMS.iter().copied() {
+                for context_lines in 0..=4 {
+                    let options = unified::Options {
+                        algorithm,
+                        context_lines,
+                    };
+                    assert_eq!(
+                        diff(&root, case, options)?,
+                        git_diff(&root, case, algorithm, context_lines),
+                        "{} with {:?}",
+                        case,
+                        options
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn additions_and_deletions_of_entire_files() -> crate::Result {
+        let after = std::fs::read(fixture().join("from-empty/after"))?;
+        let path: &BStr = "file".into();
+
+        let mut out = Vec::new();
+        unified::write_to(&mut out, None, b"", Some(path), &after, Default::default())?;
+        assert_eq!(
+            out.as_bstr(),
+            "--- /dev/null\n+++ b/file\n@@ -0,0 +1,3 @@\n+one\n+two\n+three\n"
+        );
+
+        out.clear();
+        unified::write_to(&mut out, Some(path), &after, None, b"", Default::default())?;
+        assert_eq!(
+            out.as_bstr(),
+            "--- a/file\n+++ /dev/null\n@@ -1,3 +0,0 @@\n-one\n-two\n-three\n"
+        );
+
+        out.clear();
+        unified::write_to(&mut out, Some(path), &after, Some(path), &after, Default::default())?;
+        assert!(out.is_empty(), "equal blobs have no diff");
+        Ok(())
+    }
+}
+
+mod diff {
+    use super::*;
+
+    /// A simple pseudo-random number generator to produce the same inputs on every run.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, max: u64) -> u64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 33) % max
+        }
+    }
+
+    fn random_lines(rng: &mut Lcg, alphabet: u64) -> Vec<u8> {
+        let num_lines = rng.next(40);
+        (0..num_lines)
+            .flat_map(|_| format!("{}\n", rng.next(alphabet)).into_bytes())
+            .collect()
+    }
+
+    fn longest_common_subsequence(a: &[&[u8]], b: &[&[u8]]) -> usize {
+        let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lengths[i][j] = if a[i] == b[j] {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+        lengths[0][0]
+    }
+
+    #[test]
+    fn changes_turn_before_into_after_and_myers_is_minimal() {
+        let mut rng = Lcg(42);
+        for _ in 0..500 {
+            let alphabet = rng.next(8) + 1;
+            let before = random_lines(&mut rng, alphabet);
+            let after = random_lines(&mut rng, alphabet);
+            let before_lines: Vec<_> = blob::lines(&before).collect();
+            let after_lines: Vec<_> = blob::lines(&after).collect();
+            for algorithm in ALGORITHMS.iter().copied() {
+                let changes = blob::diff(&before, &after, algorithm);
+                let mut patched = Vec::new();
+                let mut before_pos = 0;
+                for change in &changes {
+                    assert!(
+                        !change.before.is_empty() || !change.after.is_empty(),
+                        "changes are never empty"
+                    );
+                    assert!(change.before.start as usize >= before_pos, "changes are ordered");
+                    patched.extend_from_slice(&before_lines[before_pos..change.before.start as usize]);
+                    patched.extend_from_slice(&after_lines[change.after.start as usize..change.after.end as usize]);
+                    before_pos = change.before.end as usize;
+                }
+                patched.extend_from_slice(&before_lines[before_pos..]);
+                assert_eq!(
+                    patched, after_lines,
+                    "{:?} turns {:?} into {:?}",
+                    algorithm, before, after
+                );
+
+                if algorithm == Algorithm::Myers {
+                    let num_changed_lines: usize = changes
+                        .iter()
+                        .map(|change| change.before.len() + change.after.len())
+                        .sum();
+                    assert_eq!(
+                        num_changed_lines,
+                        before_lines.len() + after_lines.len()
+                            - 2 * longest_common_subsequence(&before_lines, &after_lines),
+                        "{:?} to {:?}",
+                        before,
+                        after
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn binary_detection_looks_for_null_bytes_at_the_start() {
+        assert!(!blob::is_binary(b"text\n"));
+        assert!(blob::is_binary(b"text\0"));
+        let mut late_null = vec![b'a'; 8000];
+        late_null.push(0);
+        assert!(!blob::is_binary(&late_null), "only the first 8000 bytes are checked");
+    }
+}
@@ -2,4 +2,10 @@ pub type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 pub use git_testtools::hex_to_id;
 
+mod blame;
+mod blob;
+mod ignore_case;
+mod index;
+mod rewrites;
 mod visit;
+mod worktree;
@@ -0,0 +1,38 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.autocrlf false
+
+function commit() {
+  local date="2000-01-0$1 00:00:00 +0000"
+  git add .
+  GIT_AUTHOR_DATE="$date" GIT_COMMITTER_DATE="$date" git commit -q -m "$2"
+}
+
+mkdir dir
+seq 1 10 > dir/file
+echo other > other
+commit 1 "initial"
+
+sed -i -e 's/^3$/three/' dir/file && echo 11 >> dir/file
+commit 2 "change line 3 and append line 11"
+
+echo unrelated >> other
+commit 3 "unrelated change"
+
+git checkout -q -b side
+sed -i -e 's/^8$/eight/' dir/file
+commit 4 "change line 8 on side"
+
+git checkout -q -
+sed -i -e 's/^5$/five/' -e '1i\
+zero' dir/file
+commit 5 "change line 5 and prepend line 0"
+
+GIT_AUTHOR_DATE="2000-01-06 00:00:00 +0000" GIT_COMMITTER_DATE="2000-01-06 00:00:00 +0000" git merge -q --no-edit side
+
+sed -i -e 's/^1$/  1/' -e 's/^6$/  6/' dir/file
+commit 7 "reformat lines 1 and 6"
+git rev-parse HEAD > .git/ignored-rev
@@ -0,0 +1,37 @@
+#!/bin/bash
+set -eu -o pipefail
+
+mkdir numbers
+seq 1 30 > numbers/before
+{ seq 1 4; echo five; seq 6 11; seq 13 19; echo inserted; seq 20 27; echo 28; printf "end"; } > numbers/after
+
+mkdir functions
+cat > functions/before <<EOS
+int first(void)
+{
+	int a = 1;
+	int b = 2;
+	int c = 3;
+	int d = 4;
+	int e = 5;
+	return a;
+}
+
+static int second(int x)
+{
+	int f = 6;
+	int g = 7;
+	int h = 8;
+	int i = 9;
+	return x;
+}
+EOS
+sed -e 's/int b = 2/int b = 20/' -e 's/int i = 9/int i = 90/' functions/before > functions/after
+
+mkdir from-empty
+touch from-empty/before
+printf "one\ntwo\nthree\n" > from-empty/after
+
+mkdir binary
+printf "text\n" > binary/before
+printf "text\0with null\n" > binary/after
@@ -0,0 +1,21 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.autocrlf false
+
+echo file > File
+mkdir Dir
+echo a > Dir/a
+echo b > Dir/b
+echo other > other
+git add .
+git commit -q -m "initial"
+
+git mv File file
+git mv Dir dir
+echo changed >> dir/a
+echo changed >> other
+git add .
+git commit -q -m "change case only"
@@ -0,0 +1,28 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.autocrlf false
+
+mkdir -p dir/sub keep gone
+echo a > a
+echo b > dir/b
+echo c > dir/sub/c
+echo x > keep/x
+echo e > gone/e
+echo exe > exe
+echo file > to-be-dir
+
+git add .
+git commit -q -m "initial"
+
+echo changed > a && git add a
+git rm -q dir/sub/c
+echo new > dir/new && git add dir/new
+chmod +x exe && git add exe
+git rm -q to-be-dir && mkdir to-be-dir && echo f > to-be-dir/f && git add to-be-dir
+git rm -q -r gone
+echo intended > intended && git add --intent-to-add intended
+
+git write-tree > .git/index-tree-id
@@ -0,0 +1,16 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.autocrlf false
+
+git checkout -q -b main
+
+mkdir dir
+touch dir/f
+git add dir
+git commit -qm 'dir/f added'
+
+chmod +x dir/f
+git commit -qam 'dir/f made executable'
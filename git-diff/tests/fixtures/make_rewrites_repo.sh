@@ -0,0 +1,22 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.autocrlf false
+
+seq 1 100 > a.txt
+seq 100 200 > b.txt
+seq 200 300 > same.txt
+seq 300 400 > c.txt
+git add .
+git commit -q -m "initial"
+
+mkdir moved
+git mv a.txt moved/a.txt
+git mv b.txt b2.txt && echo appended >> b2.txt
+cp same.txt copy.txt && echo appended >> same.txt
+git rm -q c.txt
+seq 500 520 > unrelated.txt
+git add .
+git commit -q -m "rewrites"
@@ -0,0 +1,29 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.autocrlf false
+
+mkdir dir
+echo unchanged > unchanged
+echo modified > modified
+echo deleted > deleted
+echo typechange > typechange
+echo exe > exe
+echo replaced > replaced
+echo sub > dir/sub
+echo racy > racy && touch -d @1600000000 racy
+
+git add .
+git commit -q -m "initial"
+
+echo modified with more content > modified
+rm deleted
+rm typechange && ln -s unchanged typechange
+chmod +x exe
+rm replaced && mkdir replaced && echo untracked > replaced/file
+echo RACY > racy && touch -d @1600000000 racy
+
+echo untracked > untracked
+echo untracked > dir/untracked
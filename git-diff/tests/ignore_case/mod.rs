@@ -0,0 +1,105 @@
+use git_diff::tree::{recorder, recorder::Change::*};
+use git_hash::ObjectId;
+use git_object::tree::EntryMode;
+use git_odb::{linked, pack, FindExt};
+
+fn repo() -> std::path::PathBuf {
+    git_testtools::scripted_fixture_repo_read_only("make_case_rename_repo.sh").expect("fixture script succeeds")
+}
+
+fn id(repo: &std::path::Path, spec: &str) -> ObjectId {
+    let output = std::process::Command::new("git")
+        .args(&["rev-parse", spec])
+        .current_dir(repo)
+        .output()
+        .expect("git can be executed");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    git_testtools::hex_to_id(std::str::from_utf8(&output.stdout).expect("valid UTF-8").trim())
+}
+
+/// Return the changes from `HEAD~1` to `HEAD`, matching entries by their case-folded names if `ignore_case` is set.
+fn changes(repo: &std::path::Path, ignore_case: bool) -> crate::Result<Vec<recorder::Change>> {
+    let db = linked::Store::at(repo.join(".git").join("objects"), git_hash::Kind::Sha1)?;
+    let (mut lhs_buf, mut rhs_buf) = (Vec::new(), Vec::new());
+    let lhs = db.find_existing_tree_iter(id(repo, "HEAD~1^{tree}"), &mut lhs_buf, &mut pack::cache::Never)?;
+    let rhs = db.find_existing_tree_iter(id(repo, "HEAD^{tree}"), &mut rhs_buf, &mut pack::cache::Never)?;
+    let mut recorder = recorder::Recorder::default();
+    git_diff::tree::Changes::from(Some(lhs))
+        .ignore_case(ignore_case)
+        .needed_to_obtain(
+            rhs,
+            git_diff::tree::State::default(),
+            |oid, buf| db.find_existing_tree_iter(oid, buf, &mut pack::cache::Never).ok(),
+            &mut recorder,
+        )?;
+    Ok(recorder.records)
+}
+
+#[test]
+fn entries_differing_only_in_case_are_renames() -> crate::Result {
+    let repo = repo();
+    assert_eq!(
+        changes(&repo, true)?,
+        vec![
+            Rename {
+                source_entry_mode: EntryMode::Tree,
+                source_oid: id(&repo, "HEAD~1:Dir"),
+                source_path: "Dir".into(),
+                entry_mode: EntryMode::Tree,
+                oid: id(&repo, "HEAD:dir"),
+                path: "dir".into(),
+                similarity: 0,
+            },
+            Rename {
+                source_entry_mode: EntryMode::Blob,
+                source_oid: id(&repo, "HEAD~1:File"),
+                source_path: "File".into(),
+                entry_mode: EntryMode::Blob,
+                oid: id(&repo, "HEAD:file"),
+                path: "file".into(),
+                similarity: 100,
+            },
+            Modification {
+                previous_entry_mode: EntryMode::Blob,
+                previous_oid: id(&repo, "HEAD~1:other"),
+                entry_mode: EntryMode::Blob,
+                oid: id(&repo, "HEAD:other"),
+                path: "other".into(),
+            },
+            Modification {
+                previous_entry_mode: EntryMode::Blob,
+                previous_oid: id(&repo, "HEAD~1:Dir/a"),
+                entry_mode: EntryMode::Blob,
+                oid: id(&repo, "HEAD:dir/a"),
+                path: "dir/a".into(),
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn entries_differing_only_in_case_are_deleted_and_added_by_default() -> crate::Result {
+    let repo = repo();
+    let changes = changes(&repo, false)?;
+    assert!(changes.iter().all(|change| !matches!(change, Rename { .. })));
+    for path in &["File", "Dir", "Dir/a", "Dir/b"] {
+        assert!(
+            changes
+                .iter()
+                .any(|change| matches!(change, Deletion { path: p, .. } if p == path)),
+            "{} was deleted",
+            path
+        );
+    }
+    for path in &["file", "dir", "dir/a", "dir/b"] {
+        assert!(
+            changes
+                .iter()
+                .any(|change| matches!(change, Addition { path: p, .. } if p == path)),
+            "{} was added",
+            path
+        );
+    }
+    Ok(())
+}
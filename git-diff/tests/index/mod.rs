@@ -0,0 +1,171 @@
+mod changes {
+    mod to_obtain_index {
+        use std::cell::Cell;
+
+        use git_diff::tree::{recorder, recorder::Change::*};
+        use git_hash::ObjectId;
+        use git_object::tree::EntryMode;
+        use git_odb::{linked, pack, FindExt};
+
+        fn repo() -> std::path::PathBuf {
+            git_testtools::scripted_fixture_repo_read_only("make_index_diff_repo.sh").expect("fixture script succeeds")
+        }
+
+        fn rev_parse(repo: &std::path::Path, spec: &str) -> ObjectId {
+            let output = std::process::Command::new("git")
+                .args(&["rev-parse", spec])
+                .current_dir(repo)
+                .output()
+                .expect("git can be executed");
+            assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+            git_testtools::hex_to_id(std::str::from_utf8(&output.stdout).expect("valid UTF-8").trim())
+        }
+
+        fn index(repo: &std::path::Path) -> git_index::File {
+            git_index::File::at(repo.join(".git").join("index"), Default::default()).expect("valid index")
+        }
+
+        /// Return all changes from the `HEAD` tree to `index` along with the trees that were looked up.
+        fn changes(repo: &std::path::Path, index: &git_index::State) -> crate::Result<(Vec<recorder::Change>, usize)> {
+            let db = linked::Store::at(repo.join(".git").join("objects"), git_hash::Kind::Sha1)?;
+            let mut buf = Vec::new();
+            let head_tree =
+                db.find_existing_tree_iter(rev_parse(repo, "HEAD^{tree}"), &mut buf, &mut pack::cache::Never)?;
+            let lookups = Cell::new(0);
+            let mut recorder = recorder::Recorder::default();
+            git_diff::tree::Changes::from(head_tree).needed_to_obtain_index(
+                index,
+                git_diff::tree::State::default(),
+                |oid, buf| {
+                    lookups.set(lookups.get() + 1);
+                    db.find_existing_tree_iter(oid, buf, &mut pack::cache::Never).ok()
+                },
+                &mut recorder,
+            )?;
+            Ok((recorder.records, lookups.get()))
+        }
+
+        fn expected(repo: &std::path::Path) -> Vec<recorder::Change> {
+            let index_tree = std::fs::read_to_string(repo.join(".git").join("index-tree-id")).expect("present");
+            let index_tree = index_tree.trim();
+            let id = |spec: &str| rev_parse(repo, spec);
+            vec![
+                Modification {
+                    previous_entry_mode: EntryMode::Blob,
+                    previous_oid: id("HEAD:a"),
+                    entry_mode: EntryMode::Blob,
+                    oid: id(":a"),
+                    path: "a".into(),
+                },
+                Modification {
+                    previous_entry_mode: EntryMode::Tree,
+                    previous_oid: id("HEAD:dir"),
+                    entry_mode: EntryMode::Tree,
+                    oid: id(&format!("{}:dir", index_tree)),
+                    path: "dir".into(),
+                },
+                Modification {
+                    previous_entry_mode: EntryMode::Blob,
+                    previous_oid: id("HEAD:exe"),
+                    entry_mode: EntryMode::BlobExecutable,
+                    oid: id(":exe"),
+                    path: "exe".into(),
+                },
+                Deletion {
+                    entry_mode: EntryMode::Tree,
+                    oid: id("HEAD:gone"),
+                    path: "gone".into(),
+                },
+                Deletion {
+                    entry_mode: EntryMode::Blob,
+                    oid: id("HEAD:to-be-dir"),
+                    path: "to-be-dir".into(),
+                },
+                Addition {
+                    entry_mode: EntryMode::Tree,
+                    oid: id(&format!("{}:to-be-dir", index_tree)),
+                    path: "to-be-dir".into(),
+                },
+                Addition {
+                    entry_mode: EntryMode::Blob,
+                    oid: id(":dir/new"),
+                    path: "dir/new".into(),
+                },
+                Deletion {
+                    entry_mode: EntryMode::Tree,
+                    oid: id("HEAD:dir/sub"),
+                    path: "dir/sub".into(),
+                },
+                Deletion {
+                    entry_mode: EntryMode::Blob,
+                    oid: id("HEAD:gone/e"),
+                    path: "gone/e".into(),
+                },
+                Addition {
+                    entry_mode: EntryMode::Blob,
+                    oid: id(":to-be-dir/f"),
+                    path: "to-be-dir/f".into(),
+                },
+                Deletion {
+                    entry_mode: EntryMode::Blob,
+                    oid: id("HEAD:dir/sub/c"),
+                    path: "dir/sub/c".into(),
+                },
+            ]
+        }
+
+        #[test]
+        fn unchanged_trees_are_skipped_using_the_cache_tree() -> crate::Result {
+            let repo = repo();
+            let index = index(&repo);
+            assert!(
+                index.tree().and_then(|tree| tree.find("keep".into())).is_some(),
+                "the cache-tree knows the unchanged tree"
+            );
+            let (changes, lookups) = changes(&repo, &index)?;
+            assert_eq!(changes, expected(&repo));
+            assert_eq!(lookups, 3, "dir, gone and dir/sub are looked up, but keep is not");
+            Ok(())
+        }
+
+        #[test]
+        fn tree_ids_are_computed_without_cache_tree() -> crate::Result {
+            let repo = repo();
+            let mut index = index(&repo);
+            index.set_tree(None);
+            let (changes, _lookups) = changes(&repo, &index)?;
+            assert_eq!(changes, expected(&repo));
+            Ok(())
+        }
+
+        #[test]
+        fn empty_tree_to_index() -> crate::Result {
+            let repo = repo();
+            let index = index(&repo);
+            let mut recorder = recorder::Recorder::default();
+            git_diff::tree::Changes::from(None).needed_to_obtain_index(
+                &index,
+                git_diff::tree::State::default(),
+                |_oid, _buf| None,
+                &mut recorder,
+            )?;
+            assert_eq!(
+                recorder
+                    .records
+                    .iter()
+                    .filter_map(|change| match change {
+                        Addition {
+                            entry_mode: EntryMode::Blob,
+                            path,
+                            ..
+                        } => Some(path.to_string()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>(),
+                vec!["a", "dir/b", "dir/new", "keep/x", "to-be-dir/f"],
+                "all entries are added, with the intended-to-add one being excluded"
+            );
+            Ok(())
+        }
+    }
+}
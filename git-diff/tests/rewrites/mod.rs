@@ -0,0 +1,135 @@
+use std::collections::BTreeSet;
+
+use git_diff::tree::{recorder, recorder::Change::*, rewrites};
+use git_object::bstr::{BString, ByteSlice};
+use git_odb::{linked, pack, FindExt};
+
+fn repo() -> std::path::PathBuf {
+    git_testtools::scripted_fixture_repo_read_only("make_rewrites_repo.sh").expect("fixture script succeeds")
+}
+
+fn git(repo: &std::path::Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .output()
+        .expect("git can be executed");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).expect("valid UTF-8")
+}
+
+/// Return the changes from `HEAD~1` to `HEAD` after detecting rewrites with `options`.
+fn changes(repo: &std::path::Path, options: rewrites::Options) -> crate::Result<Vec<recorder::Change>> {
+    let db = linked::Store::at(repo.join(".git").join("objects"), git_hash::Kind::Sha1)?;
+    let tree_id = |spec: &str| git_testtools::hex_to_id(git(repo, &["rev-parse", spec]).trim());
+    let (mut lhs_buf, mut rhs_buf) = (Vec::new(), Vec::new());
+    let lhs = db.find_existing_tree_iter(tree_id("HEAD~1^{tree}"), &mut lhs_buf, &mut pack::cache::Never)?;
+    let rhs = db.find_existing_tree_iter(tree_id("HEAD^{tree}"), &mut rhs_buf, &mut pack::cache::Never)?;
+    let mut recorder = recorder::Recorder::default();
+    git_diff::tree::Changes::from(Some(lhs)).needed_to_obtain(
+        rhs,
+        git_diff::tree::State::default(),
+        |oid, buf| db.find_existing_tree_iter(oid, buf, &mut pack::cache::Never).ok(),
+        &mut recorder,
+    )?;
+    Ok(rewrites::detect(
+        recorder.records,
+        |oid, buf| db.find_existing_blob(oid, buf, &mut pack::cache::Never).ok(),
+        options,
+    )?)
+}
+
+/// Turn `changes` into lines like those of `git diff --name-status`, ignoring trees.
+fn name_status(changes: &[recorder::Change]) -> BTreeSet<String> {
+    changes
+        .iter()
+        .filter_map(|change| {
+            let path = |path: &BString| path.to_str_lossy().into_owned();
+            Some(match change {
+                Addition {
+                    entry_mode, path: p, ..
+                } if !entry_mode.is_tree() => format!("A\t{}", path(p)),
+                Deletion {
+                    entry_mode, path: p, ..
+                } if !entry_mode.is_tree() => format!("D\t{}", path(p)),
+                Modification {
+                    entry_mode, path: p, ..
+                } if !entry_mode.is_tree() => format!("M\t{}", path(p)),
+                Rename {
+                    source_path,
+                    path: p,
+                    similarity,
+                    ..
+                } => format!("R{:03}\t{}\t{}", similarity, path(source_path), path(p)),
+                Copy {
+                    source_path,
+                    path: p,
+                    similarity,
+                    ..
+                } => format!("C{:03}\t{}\t{}", similarity, path(source_path), path(p)),
+                _ => return None,
+            })
+        })
+        .collect()
+}
+
+fn git_name_status(repo: &std::path::Path, flag: &str) -> BTreeSet<String> {
+    git(repo, &["diff", "--name-status", flag, "HEAD~1", "HEAD"])
+        .lines()
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+#[test]
+fn renames_match_git() -> crate::Result {
+    let repo = repo();
+    let changes = changes(&repo, rewrites::Options::default())?;
+    assert_eq!(name_status(&changes), git_name_status(&repo, "-M"));
+    assert!(
+        changes.iter().any(|c| matches!(c, Rename { similarity: 100, .. })),
+        "the exact rename is found"
+    );
+    Ok(())
+}
+
+#[test]
+fn copies_match_git() -> crate::Result {
+    let repo = repo();
+    let changes = changes(
+        &repo,
+        rewrites::Options {
+            copies: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(name_status(&changes), git_name_status(&repo, "-C"));
+    assert!(changes.iter().any(|c| matches!(c, Copy { .. })), "the copy is found");
+    Ok(())
+}
+
+#[test]
+fn similarity_threshold_and_limit_restrict_inexact_matches() -> crate::Result {
+    let repo = repo();
+    let strict = changes(
+        &repo,
+        rewrites::Options {
+            similarity_threshold: 100,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(name_status(&strict), git_name_status(&repo, "-M100%"));
+
+    let limited = changes(
+        &repo,
+        rewrites::Options {
+            limit: 1,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(
+        limited.iter().filter(|c| matches!(c, Rename { .. })).count(),
+        1,
+        "only the exact rename is found if the limit is exceeded"
+    );
+    Ok(())
+}
@@ -14,6 +14,7 @@ mod changes {
                 git_testtools::scripted_fixture_repo_read_only_with_args("make_diff_repo.sh", args)?
                     .join(".git")
                     .join("objects"),
+                git_hash::Kind::Sha1,
             )
             .map_err(Into::into)
         }
@@ -605,5 +606,92 @@ mod changes {
             );
             Ok(())
         }
+
+        #[test]
+        fn mode_changes_without_content_changes_are_modifications() -> crate::Result {
+            let db = linked::Store::at(
+                git_testtools::scripted_fixture_repo_read_only("make_mode_change_repo.sh")?
+                    .join(".git")
+                    .join("objects"),
+                git_hash::Kind::Sha1,
+            )?;
+            let all_commits = all_commits(&db);
+
+            assert_eq!(
+                diff_with_previous_commit_from(&db, &all_commits[1])?,
+                vec![
+                    Modification {
+                        previous_entry_mode: EntryMode::Tree,
+                        previous_oid: hex_to_id("3d5a503f4062d198b443db5065ca727f8354e7df"),
+                        entry_mode: EntryMode::Tree,
+                        oid: hex_to_id("1e8469d2398ceb689656cce028f7212e996ad2b3"),
+                        path: "dir".into()
+                    },
+                    Modification {
+                        previous_entry_mode: EntryMode::Blob,
+                        previous_oid: hex_to_id("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"),
+                        entry_mode: EntryMode::BlobExecutable,
+                        oid: hex_to_id("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"),
+                        path: "dir/f".into()
+                    }
+                ]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn with_object_cache() -> crate::Result {
+            use git_odb::FindExt;
+
+            struct CountingCache {
+                inner: pack::cache::object::MemoryCappedHashmap,
+                hits: usize,
+            }
+            impl pack::cache::Object for CountingCache {
+                fn put(&mut self, id: ObjectId, kind: git_object::Kind, data: &[u8]) {
+                    self.inner.put(id, kind, data)
+                }
+
+                fn get(&mut self, id: &oid, out: &mut Vec<u8>) -> Option<git_object::Kind> {
+                    let kind = self.inner.get(id, out);
+                    self.hits += kind.is_some() as usize;
+                    kind
+                }
+            }
+
+            let db = db(None)?;
+            let all_commits = all_commits(&db);
+            for memory_cap_in_bytes in &[0, 1024 * 1024] {
+                let mut cache = CountingCache {
+                    inner: pack::cache::object::MemoryCappedHashmap::new(*memory_cap_in_bytes),
+                    hits: 0,
+                };
+                for (lhs, rhs) in all_commits.iter().zip(all_commits.iter().skip(1)) {
+                    let (mut buf, mut buf2) = (Vec::new(), Vec::new());
+                    let lhs_tree = locate_tree_by_commit(&db, lhs, &mut buf)?;
+                    let rhs_tree = locate_tree_by_commit(&db, rhs, &mut buf2)?;
+                    let mut recorder = git_diff::tree::Recorder::default();
+                    git_diff::tree::Changes::from(lhs_tree).needed_to_obtain(
+                        rhs_tree,
+                        git_diff::tree::State::default(),
+                        |oid, buf| {
+                            db.find_existing_tree_iter_cached(oid, buf, &mut pack::cache::Never, &mut cache)
+                                .ok()
+                        },
+                        &mut recorder,
+                    )?;
+                    assert_eq!(recorder.records, diff_with_previous_commit_from(&db, rhs)?);
+                }
+                if *memory_cap_in_bytes == 0 {
+                    assert_eq!(cache.hits, 0, "a cache without memory caches nothing");
+                } else {
+                    assert!(
+                        cache.hits > 0,
+                        "subtrees of previous commits are obtained from the cache"
+                    );
+                }
+            }
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,111 @@
+mod changes {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use git_diff::worktree::{self, recorder, recorder::Change::*};
+
+    fn work_tree() -> std::path::PathBuf {
+        git_testtools::scripted_fixture_repo_read_only("make_worktree_changes.sh").expect("fixture script succeeds")
+    }
+
+    fn index_written_at(work_tree: &std::path::Path, timestamp: SystemTime) -> git_index::State {
+        let data = std::fs::read(work_tree.join(".git").join("index")).expect("index is present");
+        git_index::State::from_bytes(&data, timestamp, Default::default())
+            .expect("valid index")
+            .0
+    }
+
+    fn changes(
+        index: &git_index::State,
+        work_tree: &std::path::Path,
+        options: worktree::Options,
+    ) -> crate::Result<Vec<recorder::Change>> {
+        let mut recorder = worktree::Recorder::default();
+        worktree::changes(index, work_tree, options, &mut recorder)?;
+        Ok(recorder.records)
+    }
+
+    fn options() -> worktree::Options {
+        worktree::Options {
+            ignore_ctime: true,
+            thread_limit: Some(2),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tracked_and_untracked_files() -> crate::Result {
+        let work_tree = work_tree();
+        let index = index_written_at(&work_tree, SystemTime::now());
+        assert_eq!(
+            changes(&index, &work_tree, options())?,
+            vec![
+                Deletion { path: "deleted".into() },
+                Modification { path: "exe".into() },
+                Modification {
+                    path: "modified".into()
+                },
+                Deletion {
+                    path: "replaced".into()
+                },
+                TypeChange {
+                    path: "typechange".into()
+                },
+                Untracked {
+                    path: "dir/untracked".into()
+                },
+                Untracked {
+                    path: "replaced/file".into()
+                },
+                Untracked {
+                    path: "untracked".into()
+                },
+            ],
+            "racy has the same stat information and is assumed unchanged as it's not racily clean"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn racily_clean_entries_are_hashed() -> crate::Result {
+        let work_tree = work_tree();
+        let index = index_written_at(&work_tree, UNIX_EPOCH + Duration::from_secs(1600000000));
+        let options = worktree::Options {
+            ignore_executable_bit: true,
+            skip_untracked: true,
+            ..options()
+        };
+        assert_eq!(
+            changes(&index, &work_tree, options)?,
+            vec![
+                Deletion { path: "deleted".into() },
+                Modification {
+                    path: "modified".into()
+                },
+                Modification { path: "racy".into() },
+                Deletion {
+                    path: "replaced".into()
+                },
+                TypeChange {
+                    path: "typechange".into()
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cancellation() {
+        struct CancelImmediately;
+        impl worktree::Visit for CancelImmediately {
+            fn visit(&mut self, _change: worktree::visit::Change<'_>) -> worktree::visit::Action {
+                worktree::visit::Action::Cancel
+            }
+        }
+        let work_tree = work_tree();
+        let index = index_written_at(&work_tree, SystemTime::now());
+        assert!(matches!(
+            worktree::changes(&index, &work_tree, options(), &mut CancelImmediately),
+            Err(worktree::changes::Error::Cancelled)
+        ));
+    }
+}
@@ -26,6 +26,19 @@ pub mod walkdir {
         WalkDir::new(root).sort(true)
     }
 
+    /// Instantiate a new directory iterator which will not skip hidden files, but neither yields nor descends into entries
+    /// whose file name makes `skip` return true.
+    pub fn walkdir_new_skipping(
+        root: impl AsRef<Path>,
+        skip: fn(&std::ffi::OsStr) -> bool,
+    ) -> impl Iterator<Item = Result<DirEntry, Error>> {
+        walkdir_new(root)
+            .process_read_dir(move |_depth, _path, _state, children| {
+                children.retain(|entry| entry.as_ref().map_or(true, |entry| !skip(entry.file_name())))
+            })
+            .into_iter()
+    }
+
     /// The Iterator yielding directory items
     pub type DirEntryIter = DirEntryIterGeneric<((), ())>;
 }
@@ -47,9 +60,20 @@ pub mod walkdir {
         WalkDir::new(root).sort_by_file_name()
     }
 
+    /// Instantiate a new directory iterator which will not skip hidden files, but neither yields nor descends into entries
+    /// whose file name makes `skip` return true.
+    pub fn walkdir_new_skipping(
+        root: impl AsRef<Path>,
+        skip: fn(&std::ffi::OsStr) -> bool,
+    ) -> impl Iterator<Item = Result<DirEntry, Error>> {
+        walkdir_new(root)
+            .into_iter()
+            .filter_entry(move |entry| entry.depth() == 0 || !skip(entry.file_name()))
+    }
+
     /// The Iterator yielding directory items
     pub type DirEntryIter = walkdir::IntoIter;
 }
 
 #[cfg(any(feature = "walkdir", feature = "jwalk"))]
-pub use self::walkdir::{walkdir_new, walkdir_sorted_new, WalkDir};
+pub use self::walkdir::{walkdir_new, walkdir_new_skipping, walkdir_sorted_new, WalkDir};
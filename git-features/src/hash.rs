@@ -3,6 +3,9 @@
 //! With the `fast-sha1` feature, the `Sha1` hash type will use a more elaborate implementation utilizing hardware support
 //! in case it is available. Otherwise the `sha1` feature should be set. `fast-sha1` will take precedence.
 //! Otherwise, a minimal yet performant implementation is used instead for a decent trade-off between compile times and run-time performance.
+//!
+//! The `Sha256` hash type is available with the `sha2` feature, which is also needed along with either of the above for the
+//! [`Hasher`] supporting all [kinds of hashes][git_hash::Kind].
 #[cfg(all(feature = "rustsha1", not(feature = "fast-sha1")))]
 mod _impl {
     use super::Sha1Digest;
@@ -52,6 +55,64 @@ mod _impl {
 #[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
 pub use _impl::Sha1;
 
+/// A 32 bytes digest produced by a [`Sha256`] hash implementation.
+#[cfg(feature = "sha2")]
+pub type Sha256Digest = [u8; 32];
+
+#[cfg(feature = "sha2")]
+mod _sha256 {
+    use sha2::Digest;
+
+    use super::Sha256Digest;
+
+    /// A implementation of the Sha256 hash, which can be used once.
+    #[derive(Default, Clone)]
+    pub struct Sha256(sha2::Sha256);
+
+    impl Sha256 {
+        /// Digest the given `bytes`.
+        pub fn update(&mut self, bytes: &[u8]) {
+            self.0.update(bytes)
+        }
+        /// Finalize the hash and produce a digest.
+        pub fn digest(self) -> Sha256Digest {
+            self.0.finalize().into()
+        }
+    }
+}
+
+#[cfg(feature = "sha2")]
+pub use _sha256::Sha256;
+
+/// A hash implementation for any of the supported [kinds of hashes][git_hash::Kind], which can be used once.
+#[cfg(all(feature = "sha2", any(feature = "rustsha1", feature = "fast-sha1")))]
+#[derive(Clone)]
+pub enum Hasher {
+    /// The Sha1 hash
+    Sha1(Sha1),
+    /// The Sha256 hash
+    Sha256(Sha256),
+}
+
+#[cfg(all(feature = "sha2", any(feature = "rustsha1", feature = "fast-sha1")))]
+impl Hasher {
+    /// Digest the given `bytes`.
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Sha1(hasher) => hasher.update(bytes),
+            Hasher::Sha256(hasher) => hasher.update(bytes),
+        }
+    }
+
+    /// Finalize the hash and produce an object id from the digest.
+    pub fn digest(self) -> git_hash::ObjectId {
+        match self {
+            Hasher::Sha1(hasher) => git_hash::ObjectId::new_sha1(hasher.digest()),
+            Hasher::Sha256(hasher) => git_hash::ObjectId::new_sha256(hasher.digest()),
+        }
+    }
+}
+
 /// Compute a CRC32 hash from the given `bytes`, returning the CRC32 hash.
 ///
 /// When calling this function for the first time, `previous_value` should be `0`. Otherwise it
@@ -75,10 +136,11 @@ pub fn crc32(bytes: &[u8]) -> u32 {
 }
 
 /// Produce a hasher suitable for the given kind of hash.
-#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
-pub fn hasher(kind: git_hash::Kind) -> Sha1 {
+#[cfg(all(feature = "sha2", any(feature = "rustsha1", feature = "fast-sha1")))]
+pub fn hasher(kind: git_hash::Kind) -> Hasher {
     match kind {
-        git_hash::Kind::Sha1 => Sha1::default(),
+        git_hash::Kind::Sha1 => Hasher::Sha1(Sha1::default()),
+        git_hash::Kind::Sha256 => Hasher::Sha256(Sha256::default()),
     }
 }
 
@@ -93,7 +155,11 @@ pub fn hasher(kind: git_hash::Kind) -> Sha1 {
 /// * Only available with the `git-object` feature enabled due to usage of the [`git_hash::Kind`] enum and the
 ///   [`git_hash::ObjectId`] return value.
 /// * [Interrupts][crate::interrupt] are supported.
-#[cfg(all(feature = "progress", any(feature = "rustsha1", feature = "fast-sha1")))]
+#[cfg(all(
+    feature = "progress",
+    feature = "sha2",
+    any(feature = "rustsha1", feature = "fast-sha1")
+))]
 pub fn bytes_of_file(
     path: impl AsRef<std::path::Path>,
     num_bytes_from_start: usize,
@@ -111,7 +177,11 @@ pub fn bytes_of_file(
 }
 
 /// Similar to [`bytes_of_file`], but operates on an already open file.
-#[cfg(all(feature = "progress", any(feature = "rustsha1", feature = "fast-sha1")))]
+#[cfg(all(
+    feature = "progress",
+    feature = "sha2",
+    any(feature = "rustsha1", feature = "fast-sha1")
+))]
 pub fn bytes(
     mut read: impl std::io::Read,
     num_bytes_from_start: usize,
@@ -139,19 +209,19 @@ pub fn bytes(
         }
     }
 
-    let id = git_hash::ObjectId::new_sha1(hasher.digest());
+    let id = hasher.digest();
     progress.show_throughput(start);
     Ok(id)
 }
 
-#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
+#[cfg(all(feature = "sha2", any(feature = "rustsha1", feature = "fast-sha1")))]
 mod write {
-    use crate::hash::Sha1;
+    use crate::hash::Hasher;
 
     /// A utility to automatically generate a hash while writing into an inner writer.
     pub struct Write<T> {
         /// The hash implementation.
-        pub hash: Sha1,
+        pub hash: Hasher,
         /// The inner writer.
         pub inner: T,
     }
@@ -177,14 +247,12 @@ mod write {
     {
         /// Create a new hash writer which hashes all bytes written to `inner` with a hash of `kind`.
         pub fn new(inner: T, kind: git_hash::Kind) -> Self {
-            match kind {
-                git_hash::Kind::Sha1 => Write {
-                    inner,
-                    hash: Sha1::default(),
-                },
+            Write {
+                inner,
+                hash: crate::hash::hasher(kind),
             }
         }
     }
 }
-#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
+#[cfg(all(feature = "sha2", any(feature = "rustsha1", feature = "fast-sha1")))]
 pub use write::Write;
@@ -11,3 +11,64 @@ fn size_of_sha1() {
 fn size_of_sha1() {
     assert_eq!(std::mem::size_of::<Sha1>(), 104)
 }
+
+mod sha256 {
+    use git_features::hash::{hasher, Sha256};
+
+    fn hex(digest: &[u8]) -> String {
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn known_digests() {
+        for (input, expected) in &[
+            (
+                &b""[..],
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                b"abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+            (
+                b"tree 0\0",
+                "6ef19b41225c5369f1c104d45d8d85efa9b057b53b14b4b9b939dd74decc5321",
+            ),
+        ] {
+            let mut hash = Sha256::default();
+            hash.update(input);
+            assert_eq!(hex(&hash.digest()), *expected);
+        }
+    }
+
+    #[test]
+    fn updates_across_block_boundaries_are_the_same_as_one_update() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let mut expected = Sha256::default();
+        expected.update(&data);
+        let expected = expected.digest();
+
+        for chunk_size in &[1, 7, 55, 56, 63, 64, 65, 128, 999] {
+            let mut hash = Sha256::default();
+            for chunk in data.chunks(*chunk_size) {
+                hash.update(chunk);
+            }
+            assert_eq!(hash.digest(), expected, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn hasher_produces_ids_of_the_given_kind() {
+        let mut hash = hasher(git_hash::Kind::Sha256);
+        hash.update(b"tree 0\0");
+        assert_eq!(hash.digest(), git_hash::ObjectId::empty_tree(git_hash::Kind::Sha256));
+
+        let mut hash = hasher(git_hash::Kind::Sha1);
+        hash.update(b"tree 0\0");
+        assert_eq!(hash.digest(), git_hash::ObjectId::empty_tree(git_hash::Kind::Sha1));
+    }
+}
@@ -1,6 +1,6 @@
 use std::{convert::TryInto, fmt};
 
-use crate::{ObjectId, SIZE_OF_SHA1_DIGEST};
+use crate::{ObjectId, SIZE_OF_SHA1_DIGEST, SIZE_OF_SHA256_DIGEST};
 
 /// A borrowed reference to a hash identifying objects.
 ///
@@ -26,6 +26,7 @@ impl std::fmt::Debug for oid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.kind() {
             crate::Kind::Sha1 => f.write_str("Sha1(")?,
+            crate::Kind::Sha256 => f.write_str("Sha256(")?,
         }
         for b in self.as_bytes() {
             write!(f, "{:02x}", b)?;
@@ -50,7 +51,7 @@ impl oid {
     /// Try to create a shared object id from a slice of bytes representing a hash `digest`
     pub fn try_from(digest: &[u8]) -> Result<&Self, Error> {
         match digest.len() {
-            20 => Ok(
+            SIZE_OF_SHA1_DIGEST | SIZE_OF_SHA256_DIGEST => Ok(
                 #[allow(unsafe_code)]
                 unsafe {
                     &*(digest as *const [u8] as *const oid)
@@ -73,10 +74,8 @@ impl oid {
 impl oid {
     /// The kind of hash used for this Digest
     pub fn kind(&self) -> crate::Kind {
-        match self.bytes.len() {
-            20 => crate::Kind::Sha1,
-            _ => unreachable!("creating this instance is checked and fails on unknown lengths"),
-        }
+        crate::Kind::from_len_in_bytes(self.bytes.len())
+            .expect("creating this instance is checked and fails on unknown lengths")
     }
 
     /// The first byte of the hash, commonly used to partition a set of `Id`s
@@ -89,24 +88,30 @@ impl oid {
     pub fn as_bytes(&self) -> &[u8] {
         &self.bytes
     }
+
+    /// Write this id in hexadecimal notation into `buf`, which must be large enough to hold it, and return the written portion.
+    pub fn hex_to_buf<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let len = self.kind().len_in_hex();
+        hex::encode_to_slice(&self.bytes, &mut buf[..len]).expect("to count correctly");
+        &mut buf[..len]
+    }
 }
 
 /// Sha1 specific methods
 impl oid {
-    /// Returns an array with a hexadecimal encoded version of the Sha1 hash this `Id` represents.
-    ///
-    /// **Panics** if this is not a Sha1 hash, as identifiable by [`ObjectId::kind()`].
-    pub fn to_sha1_hex(&self) -> [u8; SIZE_OF_SHA1_DIGEST * 2] {
-        let mut buf = [0u8; SIZE_OF_SHA1_DIGEST * 2];
-        hex::encode_to_slice(&self.bytes, &mut buf).expect("to count correctly");
-        buf
+    /// Returns an array with a hexadecimal encoded version of the Sha1 hash this `Id` represents,
+    /// or `None` if this is not a Sha1 hash, as identifiable by [`ObjectId::kind()`].
+    pub fn to_sha1_hex(&self) -> Option<[u8; SIZE_OF_SHA1_DIGEST * 2]> {
+        self.sha1().map(|bytes| {
+            let mut buf = [0u8; SIZE_OF_SHA1_DIGEST * 2];
+            hex::encode_to_slice(bytes, &mut buf).expect("to count correctly");
+            buf
+        })
     }
 
-    /// Returns the bytes making up the Sha1.
-    ///
-    /// **Panics** if this is not a Sha1 hash, as identifiable by [`ObjectId::kind()`].
-    pub fn sha1(&self) -> &[u8; SIZE_OF_SHA1_DIGEST] {
-        self.bytes.try_into().expect("correctly sized slice")
+    /// Returns the bytes making up the Sha1, or `None` if this is not a Sha1 hash, as identifiable by [`ObjectId::kind()`].
+    pub fn sha1(&self) -> Option<&[u8; SIZE_OF_SHA1_DIGEST]> {
+        self.bytes.try_into().ok()
     }
 
     /// Returns a Sha1 digest with all bytes being initialized to zero.
@@ -127,6 +132,7 @@ impl ToOwned for oid {
     fn to_owned(&self) -> Self::Owned {
         match self.kind() {
             crate::Kind::Sha1 => crate::ObjectId::Sha1(self.bytes.try_into().expect("no bug in hash detection")),
+            crate::Kind::Sha256 => crate::ObjectId::Sha256(self.bytes.try_into().expect("no bug in hash detection")),
         }
     }
 }
@@ -137,6 +143,12 @@ impl<'a> From<&'a [u8; SIZE_OF_SHA1_DIGEST]> for &'a oid {
     }
 }
 
+impl<'a> From<&'a [u8; SIZE_OF_SHA256_DIGEST]> for &'a oid {
+    fn from(v: &'a [u8; SIZE_OF_SHA256_DIGEST]) -> Self {
+        oid::from(v.as_ref())
+    }
+}
+
 impl fmt::Display for &oid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for b in self.as_bytes() {
@@ -181,7 +193,7 @@ impl<'de: 'a, 'a> serde::Deserialize<'de> for &'a oid {
                         return Err(__err);
                     }
                 };
-                Ok(oid::try_from(__field0).expect("exactly 20 bytes"))
+                Ok(oid::try_from(__field0).expect("exactly 20 or 32 bytes"))
             }
             #[inline]
             fn visit_seq<__A>(self, mut __seq: __A) -> std::result::Result<Self::Value, __A::Error>
@@ -202,7 +214,7 @@ impl<'de: 'a, 'a> serde::Deserialize<'de> for &'a oid {
                         ));
                     }
                 };
-                Ok(oid::try_from(__field0).expect("exactly 20 bytes"))
+                Ok(oid::try_from(__field0).expect("exactly 20 or 32 bytes"))
             }
         }
         serde::Deserializer::deserialize_newtype_struct(
@@ -11,6 +11,10 @@ pub use borrowed::oid;
 mod owned;
 pub use owned::ObjectId;
 
+///
+pub mod prefix;
+pub use prefix::Prefix;
+
 #[allow(missing_docs)]
 pub mod decode {
     use std::str::FromStr;
@@ -32,7 +36,8 @@ pub mod decode {
 
     /// Hash decoding
     impl ObjectId {
-        /// Create an instance from a `buffer` of 40 bytes encoded with hexadecimal notation.
+        /// Create an instance from a `buffer` of 40 or 64 bytes encoded with hexadecimal notation, for a Sha1 or Sha256 hash
+        /// respectively.
         ///
         /// Such a buffer can be obtained using [`write_hex_to(buffer)`][ObjectId::write_hex_to()]
         pub fn from_hex(buffer: &[u8]) -> Result<ObjectId, Error> {
@@ -41,6 +46,9 @@ pub mod decode {
                 40 => Ok(ObjectId::Sha1(
                     <[u8; 20]>::from_hex(buffer).expect("our length check is correct thus we can decode hex"),
                 )),
+                64 => Ok(ObjectId::Sha256(
+                    <[u8; 32]>::from_hex(buffer).expect("our length check is correct thus we can decode hex"),
+                )),
                 len => Err(Error::InvalidHexEncodingLength(len)),
             }
         }
@@ -50,19 +58,15 @@ pub mod decode {
         type Err = Error;
 
         fn from_str(s: &str) -> Result<Self, Self::Err> {
-            use hex::FromHex;
-            match s.len() {
-                40 => Ok(ObjectId::Sha1(
-                    <[u8; 20]>::from_hex(s).expect("our length check is correct thus we can decode hex"),
-                )),
-                len => Err(Error::InvalidHexEncodingLength(len)),
-            }
+            Self::from_hex(s.as_bytes())
         }
     }
 }
 
 /// The size of a SHA1 hash digest in bytes
 pub const SIZE_OF_SHA1_DIGEST: usize = 20;
+/// The size of a SHA256 hash digest in bytes
+pub const SIZE_OF_SHA256_DIGEST: usize = 32;
 
 /// Denotes the kind of function to produce a `Id`
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
@@ -70,6 +74,8 @@ pub const SIZE_OF_SHA1_DIGEST: usize = 20;
 pub enum Kind {
     /// The Sha1 hash with 160 bits.
     Sha1,
+    /// The Sha256 hash with 256 bits.
+    Sha256,
 }
 
 impl Default for Kind {
@@ -86,13 +92,91 @@ impl Kind {
 
     /// Returns the longest hash we support
     pub const fn longest() -> Self {
-        Self::Sha1
+        Self::Sha256
     }
 
     /// Returns the amount of ascii-characters needed to encode this has in hex
     pub const fn len_in_hex(&self) -> usize {
+        self.len_in_bytes() * 2
+    }
+
+    /// Returns the amount of bytes taken up by the hash of this kind
+    pub const fn len_in_bytes(&self) -> usize {
         match self {
-            Kind::Sha1 => 40,
+            Kind::Sha1 => SIZE_OF_SHA1_DIGEST,
+            Kind::Sha256 => SIZE_OF_SHA256_DIGEST,
+        }
+    }
+
+    /// Returns the kind of hash whose digest is `len` bytes long, if there is one
+    pub const fn from_len_in_bytes(len: usize) -> Option<Self> {
+        match len {
+            SIZE_OF_SHA1_DIGEST => Some(Kind::Sha1),
+            SIZE_OF_SHA256_DIGEST => Some(Kind::Sha256),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of hash whose hexadecimal representation is `hex_len` characters long, if there is one
+    pub const fn from_hex_len(hex_len: usize) -> Option<Self> {
+        match hex_len {
+            40 => Some(Kind::Sha1),
+            64 => Some(Kind::Sha256),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of hash identified by the `version` number used in the headers of commit-graph and multi-pack-index files
+    pub const fn from_version(version: u8) -> Option<Self> {
+        match version {
+            1 => Some(Kind::Sha1),
+            2 => Some(Kind::Sha256),
+            _ => None,
+        }
+    }
+
+    /// Returns the number identifying this kind of hash in the headers of commit-graph and multi-pack-index files
+    pub const fn version(&self) -> u8 {
+        match self {
+            Kind::Sha1 => 1,
+            Kind::Sha256 => 2,
+        }
+    }
+
+    /// Returns the name of this kind of hash as used in the `extensions.objectFormat` configuration value
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Sha1 => "sha1",
+            Kind::Sha256 => "sha256",
+        }
+    }
+}
+
+impl std::str::FromStr for Kind {
+    type Err = kind::Error;
+
+    /// Parse the name of a hash as used in the `extensions.objectFormat` configuration value.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha1" => Ok(Kind::Sha1),
+            "sha256" => Ok(Kind::Sha256),
+            _ => Err(kind::Error::Unknown { name: s.into() }),
+        }
+    }
+}
+
+///
+pub mod kind {
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned when parsing a [`Kind`][super::Kind] from its name.
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Unknown { name: String } {
+                display("Unknown object format '{}', expected 'sha1' or 'sha256'", name)
+            }
         }
     }
 }
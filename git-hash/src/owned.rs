@@ -1,18 +1,20 @@
 use std::{borrow::Borrow, fmt, io, ops::Deref};
 
-use crate::{borrowed::oid, SIZE_OF_SHA1_DIGEST};
+use crate::{borrowed::oid, SIZE_OF_SHA1_DIGEST, SIZE_OF_SHA256_DIGEST};
 
 /// An owned hash identifying objects, most commonly Sha1
 #[derive(PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Copy)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub enum ObjectId {
     Sha1([u8; SIZE_OF_SHA1_DIGEST]),
+    Sha256([u8; SIZE_OF_SHA256_DIGEST]),
 }
 
 impl std::fmt::Debug for ObjectId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ObjectId::Sha1(_hash) => f.write_str("Sha1(")?,
+            ObjectId::Sha256(_hash) => f.write_str("Sha256(")?,
         }
         for b in self.as_bytes() {
             write!(f, "{:02x}", b)?;
@@ -25,75 +27,106 @@ impl std::fmt::Debug for ObjectId {
 impl ObjectId {
     /// Returns the kind of hash used in this `Id`
     pub fn kind(&self) -> crate::Kind {
-        crate::Kind::Sha1
+        match self {
+            Self::Sha1(_) => crate::Kind::Sha1,
+            Self::Sha256(_) => crate::Kind::Sha256,
+        }
     }
     /// Return the raw byte slice representing this hash
     pub fn as_slice(&self) -> &[u8] {
         match self {
             Self::Sha1(b) => b.as_ref(),
+            Self::Sha256(b) => b.as_ref(),
         }
     }
     /// Return the raw mutable byte slice representing this hash
     pub fn as_mut_slice(&mut self) -> &mut [u8] {
         match self {
             Self::Sha1(b) => b.as_mut(),
+            Self::Sha256(b) => b.as_mut(),
         }
     }
 
     /// Write ourselves to `out` in hexadecimal notation
     pub fn write_hex_to(&self, mut out: impl io::Write) -> io::Result<()> {
-        out.write_all(&self.to_sha1_hex())
+        let mut buf = [0u8; SIZE_OF_SHA256_DIGEST * 2];
+        out.write_all(self.as_ref().hex_to_buf(&mut buf))
     }
 
-    pub const fn empty_tree() -> ObjectId {
-        ObjectId::Sha1(*b"\x4b\x82\x5d\xc6\x42\xcb\x6e\xb9\xa0\x60\xe5\x4b\xf8\xd6\x92\x88\xfb\xee\x49\x04")
+    /// Returns the id of the empty tree for hashes of `kind`.
+    pub const fn empty_tree(kind: crate::Kind) -> ObjectId {
+        match kind {
+            crate::Kind::Sha1 => {
+                ObjectId::Sha1(*b"\x4b\x82\x5d\xc6\x42\xcb\x6e\xb9\xa0\x60\xe5\x4b\xf8\xd6\x92\x88\xfb\xee\x49\x04")
+            }
+            crate::Kind::Sha256 => ObjectId::Sha256(
+                *b"\x6e\xf1\x9b\x41\x22\x5c\x53\x69\xf1\xc1\x04\xd4\x5d\x8d\x85\xef\xa9\xb0\x57\xb5\x3b\x14\xb4\xb9\xb9\x39\xdd\x74\xde\xcc\x53\x21",
+            ),
+        }
     }
 
     /// Returns true if this hash consists of all null bytes
     pub fn is_null(&self) -> bool {
-        match self {
-            ObjectId::Sha1(digest) => &digest[..] == Self::null_sha1().as_bytes(),
-        }
+        self.as_slice().iter().all(|b| *b == 0)
     }
 
     /// Returns an Digest representing a hash with whose memory is zeroed.
     pub const fn null_sha(kind: crate::Kind) -> ObjectId {
         match kind {
             crate::Kind::Sha1 => Self::null_sha1(),
+            crate::Kind::Sha256 => ObjectId::Sha256([0u8; SIZE_OF_SHA256_DIGEST]),
         }
     }
-}
 
-/// Sha1 hash specific methods
-impl ObjectId {
-    /// Returns ourselves as slice of 20 bytes.
+    /// Instantiate an id of `kind` from `b`, which must be exactly as long as hashes of that kind.
     ///
-    /// Panics if this instance is not a sha1 hash.
-    pub fn sha1(&self) -> &[u8; SIZE_OF_SHA1_DIGEST] {
-        match self {
-            Self::Sha1(b) => b,
+    /// Panics if the slice has a different length.
+    pub fn from_bytes(kind: crate::Kind, b: &[u8]) -> ObjectId {
+        match kind {
+            crate::Kind::Sha1 => Self::from_20_bytes(b),
+            crate::Kind::Sha256 => {
+                let mut id = [0; SIZE_OF_SHA256_DIGEST];
+                id.copy_from_slice(b);
+                ObjectId::Sha256(id)
+            }
         }
     }
 
-    /// Return ourselves as array of 40 hexadecimal bytes.
+    /// Instantiate an id of the kind matching the length of `b`, which must be 20 or 32 bytes long.
     ///
-    /// Panics if this instance is not a sha1 hash.
-    pub fn to_sha1_hex(self) -> [u8; SIZE_OF_SHA1_DIGEST * 2] {
+    /// Panics if the slice doesn't have one of these lengths.
+    pub fn from_bytes_or_panic(b: &[u8]) -> ObjectId {
+        match b.len() {
+            SIZE_OF_SHA1_DIGEST => Self::from_bytes(crate::Kind::Sha1, b),
+            SIZE_OF_SHA256_DIGEST => Self::from_bytes(crate::Kind::Sha256, b),
+            len => panic!("A hash of {} bytes is not supported", len),
+        }
+    }
+}
+
+/// Sha1 hash specific methods
+impl ObjectId {
+    /// Returns ourselves as slice of 20 bytes, or `None` if this instance is not a sha1 hash.
+    pub fn sha1(&self) -> Option<&[u8; SIZE_OF_SHA1_DIGEST]> {
         match self {
-            Self::Sha1(b) => {
-                let mut hex_buf = [0u8; 40];
-                hex::encode_to_slice(b, &mut hex_buf).expect("we can count");
-                hex_buf
-            }
+            Self::Sha1(b) => Some(b),
+            Self::Sha256(_) => None,
         }
     }
 
-    /// Return ourselves as hexadecimal string with a length of 40 bytes.
-    ///
-    /// Panics if this instance is not a sha1 hash.
-    pub fn to_sha1_hex_string(self) -> String {
-        let buf = self.to_sha1_hex();
-        std::str::from_utf8(&buf).expect("hex is valid UTF-8").to_string()
+    /// Return ourselves as array of 40 hexadecimal bytes, or `None` if this instance is not a sha1 hash.
+    pub fn to_sha1_hex(self) -> Option<[u8; SIZE_OF_SHA1_DIGEST * 2]> {
+        self.sha1().map(|b| {
+            let mut hex_buf = [0u8; 40];
+            hex::encode_to_slice(b, &mut hex_buf).expect("we can count");
+            hex_buf
+        })
+    }
+
+    /// Return ourselves as hexadecimal string with a length of 40 bytes, or `None` if this instance is not a sha1 hash.
+    pub fn to_sha1_hex_string(self) -> Option<String> {
+        self.to_sha1_hex()
+            .map(|buf| std::str::from_utf8(&buf).expect("hex is valid UTF-8").to_string())
     }
 
     /// Instantiate an Digest from 20 bytes of a Sha1 digest.
@@ -124,17 +157,29 @@ impl ObjectId {
     }
 }
 
+/// Sha256 hash specific methods
+impl ObjectId {
+    /// Instantiate an Digest from 32 bytes of a Sha256 digest.
+    pub fn new_sha256(id: [u8; SIZE_OF_SHA256_DIGEST]) -> Self {
+        ObjectId::Sha256(id)
+    }
+}
+
 impl From<[u8; SIZE_OF_SHA1_DIGEST]> for ObjectId {
     fn from(v: [u8; 20]) -> Self {
         Self::new_sha1(v)
     }
 }
 
+impl From<[u8; SIZE_OF_SHA256_DIGEST]> for ObjectId {
+    fn from(v: [u8; 32]) -> Self {
+        Self::new_sha256(v)
+    }
+}
+
 impl From<&crate::oid> for ObjectId {
     fn from(v: &oid) -> Self {
-        match v.kind() {
-            crate::Kind::Sha1 => ObjectId::from_20_bytes(v.as_bytes()),
-        }
+        Self::from_bytes_or_panic(v.as_bytes())
     }
 }
 
@@ -0,0 +1,142 @@
+use std::{cmp::Ordering, fmt};
+
+use crate::{oid, ObjectId};
+
+/// An abbreviated object id made of the first `hex_len` hexadecimal characters of a full hash, as used by
+/// `git rev-parse` and in the output of `git log --oneline`.
+#[derive(PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Prefix {
+    bytes: ObjectId,
+    hex_len: usize,
+}
+
+///
+pub mod new {
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`Prefix::new()`][super::Prefix::new()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            TooShort { hex_len: usize } {
+                display("A prefix must have at least {} hexadecimal characters, got {}", super::Prefix::MIN_HEX_LEN, hex_len)
+            }
+            TooLong { object_kind: crate::Kind, hex_len: usize } {
+                display("An object of kind {:?} cannot be abbreviated to {} hexadecimal characters", object_kind, hex_len)
+            }
+        }
+    }
+}
+
+///
+pub mod from_hex {
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`Prefix::from_hex()`][super::Prefix::from_hex()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Length(err: super::new::Error) {
+                display("The prefix has an invalid length")
+                from()
+                source(err)
+            }
+            InvalidCharacter { c: char, index: usize } {
+                display("Character '{}' at index {} is not a hexadecimal digit", c, index)
+            }
+        }
+    }
+}
+
+impl Prefix {
+    /// The smallest amount of hexadecimal characters a prefix can have, the same limit git applies.
+    pub const MIN_HEX_LEN: usize = 4;
+
+    /// Create a prefix from the first `hex_len` hexadecimal characters of `id`.
+    pub fn new(id: impl AsRef<oid>, hex_len: usize) -> Result<Self, new::Error> {
+        let id = id.as_ref();
+        if hex_len < Self::MIN_HEX_LEN {
+            return Err(new::Error::TooShort { hex_len });
+        }
+        if hex_len > id.kind().len_in_hex() {
+            return Err(new::Error::TooLong {
+                object_kind: id.kind(),
+                hex_len,
+            });
+        }
+        let mut bytes = ObjectId::null_sha(id.kind());
+        let (full_bytes, has_half_byte) = (hex_len / 2, hex_len % 2 == 1);
+        let out = bytes.as_mut_slice();
+        out[..full_bytes].copy_from_slice(&id.as_bytes()[..full_bytes]);
+        if has_half_byte {
+            out[full_bytes] = id.as_bytes()[full_bytes] & 0xf0;
+        }
+        Ok(Prefix { bytes, hex_len })
+    }
+
+    /// Parse `value` as hexadecimal prefix of an object id, accepting upper and lower case characters.
+    ///
+    /// Prefixes with more characters than a Sha1 object id has are assumed to be prefixes of Sha256 object ids.
+    pub fn from_hex(value: &str) -> Result<Self, from_hex::Error> {
+        let mut bytes = ObjectId::null_sha(if value.len() > crate::Kind::Sha1.len_in_hex() {
+            crate::Kind::Sha256
+        } else {
+            crate::Kind::Sha1
+        });
+        if value.len() > bytes.kind().len_in_hex() {
+            return Err(new::Error::TooLong {
+                object_kind: bytes.kind(),
+                hex_len: value.len(),
+            }
+            .into());
+        }
+        let out = bytes.as_mut_slice();
+        for (index, c) in value.chars().enumerate() {
+            let nibble = c.to_digit(16).ok_or(from_hex::Error::InvalidCharacter { c, index })? as u8;
+            out[index / 2] |= if index % 2 == 0 { nibble << 4 } else { nibble };
+        }
+        Ok(Prefix::new(bytes, value.len())?)
+    }
+
+    /// Return the full object id whose first `hex_len` characters are those of this prefix, with all others being zero.
+    pub fn as_oid(&self) -> &oid {
+        self.bytes.as_ref()
+    }
+
+    /// Return the amount of hexadecimal characters in this prefix.
+    pub fn hex_len(&self) -> usize {
+        self.hex_len
+    }
+
+    /// Compare the first [`hex_len()`][Prefix::hex_len()] characters of `candidate` to this prefix, returning
+    /// `Ordering::Equal` if `candidate` starts with it.
+    ///
+    /// Ordering this way is consistent with the ordering of full object ids, which allows to search for prefixes in sorted lists.
+    ///
+    /// Ids of a different kind are compared by the bytes they have in common, which is why a prefix that is longer than
+    /// `candidate` never matches it.
+    pub fn cmp_oid(&self, candidate: &oid) -> Ordering {
+        let (full_bytes, has_half_byte) = (self.hex_len / 2, self.hex_len % 2 == 1);
+        let (prefix, candidate) = (self.bytes.as_bytes(), candidate.as_bytes());
+        if self.hex_len > candidate.len() * 2 {
+            return prefix[..candidate.len()].cmp(candidate).then(Ordering::Greater);
+        }
+        prefix[..full_bytes]
+            .cmp(&candidate[..full_bytes])
+            .then_with(|| match has_half_byte {
+                true => prefix[full_bytes].cmp(&(candidate[full_bytes] & 0xf0)),
+                false => Ordering::Equal,
+            })
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buf = [0u8; crate::SIZE_OF_SHA256_DIGEST * 2];
+        let hex = self.bytes.hex_to_buf(&mut buf);
+        f.write_str(std::str::from_utf8(&hex[..self.hex_len]).expect("ascii only in hex"))
+    }
+}
@@ -0,0 +1,176 @@
+use std::{cmp::Ordering, time::SystemTime};
+
+use bstr::BStr;
+
+use crate::{extension, Entry, State, Version};
+
+/// Instantiation
+impl State {
+    /// Create an empty state of the given `version`, associated with `timestamp`.
+    pub fn new(version: Version, timestamp: SystemTime) -> Self {
+        State {
+            timestamp,
+            version,
+            entries: Vec::new(),
+            tree: None,
+            resolve_undo: None,
+            untracked: None,
+            fs_monitor: None,
+            unknown_extensions: Vec::new(),
+            is_sparse: false,
+        }
+    }
+}
+
+/// General information and entries
+impl State {
+    /// The version of the index the state was read from or will be written as, see [`State::write_to()`] for details.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Set the version of the index to write to `version`.
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
+    /// The time at which this state was read, see the field documentation of [`State`] for details.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// All entries, sorted by path and stage.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// All entries for modification, which must not affect their path or stage to keep them sorted.
+    ///
+    /// Note that changes to entries made here aren't reflected in the extensions, hence trees in the [cache-tree][State::tree()]
+    /// containing changed entries must be [invalidated][extension::Tree::invalidate_path()] by the caller.
+    pub fn entries_mut(&mut self) -> &mut [Entry] {
+        &mut self.entries
+    }
+
+    /// Find the index of the entry at `path` with the given `stage`.
+    pub fn entry_index_by_path_and_stage(&self, path: &BStr, stage: u32) -> Option<usize> {
+        self.entries
+            .binary_search_by(|e| e.path().cmp(path).then_with(|| e.stage().cmp(&stage)))
+            .ok()
+    }
+
+    /// Find the entry at `path` with the given `stage`.
+    pub fn entry_by_path_and_stage(&self, path: &BStr, stage: u32) -> Option<&Entry> {
+        self.entry_index_by_path_and_stage(path, stage)
+            .map(|idx| &self.entries[idx])
+    }
+}
+
+/// Extensions
+impl State {
+    /// The cache-tree, if present.
+    pub fn tree(&self) -> Option<&extension::Tree> {
+        self.tree.as_ref()
+    }
+
+    /// Set the cache-tree to `tree`, or remove it, and return the previous one.
+    pub fn set_tree(&mut self, tree: Option<extension::Tree>) -> Option<extension::Tree> {
+        std::mem::replace(&mut self.tree, tree)
+    }
+
+    /// The paths of resolved conflicts along with their stages, if present.
+    pub fn resolve_undo(&self) -> Option<&[extension::resolve_undo::Path]> {
+        self.resolve_undo.as_deref()
+    }
+
+    /// Set the resolve-undo information to `paths`, or remove it, and return the previous one.
+    pub fn set_resolve_undo(
+        &mut self,
+        paths: Option<Vec<extension::resolve_undo::Path>>,
+    ) -> Option<Vec<extension::resolve_undo::Path>> {
+        std::mem::replace(&mut self.resolve_undo, paths)
+    }
+
+    /// The untracked cache, if present.
+    pub fn untracked_cache(&self) -> Option<&extension::UntrackedCache> {
+        self.untracked.as_ref()
+    }
+
+    /// Set the untracked cache to `cache`, or remove it, and return the previous one.
+    pub fn set_untracked_cache(
+        &mut self,
+        cache: Option<extension::UntrackedCache>,
+    ) -> Option<extension::UntrackedCache> {
+        std::mem::replace(&mut self.untracked, cache)
+    }
+
+    /// The filesystem monitor information, if present.
+    pub fn fs_monitor(&self) -> Option<&extension::FsMonitor> {
+        self.fs_monitor.as_ref()
+    }
+
+    /// Set the filesystem monitor information to `fs_monitor`, or remove it, and return the previous one.
+    pub fn set_fs_monitor(&mut self, fs_monitor: Option<extension::FsMonitor>) -> Option<extension::FsMonitor> {
+        std::mem::replace(&mut self.fs_monitor, fs_monitor)
+    }
+
+    /// Optional extensions we don't understand, in the order they were read, to be written back unchanged.
+    pub fn unknown_extensions(&self) -> &[extension::Unknown] {
+        &self.unknown_extensions
+    }
+}
+
+/// Mutation
+impl State {
+    /// Insert `entry` at its sorted position, replacing an existing entry with the same path and stage.
+    ///
+    /// Return the replaced entry, if there was one.
+    ///
+    /// Extensions are adjusted to reflect the change, which invalidates the trees leading to the entry in the cache-tree.
+    pub fn upsert_entry(&mut self, entry: Entry) -> Option<Entry> {
+        self.invalidate_extensions_for(entry.path());
+        match self.entries.binary_search_by(|e| e.cmp_by_path_and_stage(&entry)) {
+            Ok(idx) => {
+                if let Some(fs_monitor) = self.fs_monitor.as_mut() {
+                    fs_monitor.mark_dirty(idx);
+                }
+                Some(std::mem::replace(&mut self.entries[idx], entry))
+            }
+            Err(idx) => {
+                if let Some(fs_monitor) = self.fs_monitor.as_mut() {
+                    fs_monitor.entry_inserted_at(idx);
+                }
+                self.entries.insert(idx, entry);
+                None
+            }
+        }
+    }
+
+    /// Remove the entry at `index` as obtained by [`entry_index_by_path_and_stage()`][State::entry_index_by_path_and_stage()].
+    ///
+    /// Extensions are adjusted to reflect the change, similar to [`upsert_entry()`][State::upsert_entry()].
+    pub fn remove_entry_at(&mut self, index: usize) -> Entry {
+        let entry = self.entries.remove(index);
+        self.invalidate_extensions_for(entry.path());
+        if let Some(fs_monitor) = self.fs_monitor.as_mut() {
+            fs_monitor.entry_removed_at(index);
+        }
+        entry
+    }
+
+    fn invalidate_extensions_for(&mut self, path: &BStr) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.invalidate_path(path);
+        }
+        if let Some(untracked) = self.untracked.as_mut() {
+            untracked.invalidate_path(path);
+        }
+    }
+
+    /// Return true if the entries are sorted by path and stage without duplicates, as git requires.
+    pub(crate) fn entries_are_sorted(&self) -> bool {
+        self.entries
+            .windows(2)
+            .all(|w| w[0].cmp_by_path_and_stage(&w[1]) == Ordering::Less)
+    }
+}
@@ -0,0 +1,349 @@
+use std::{convert::TryFrom, time::SystemTime};
+
+use bstr::BString;
+use quick_error::quick_error;
+
+use crate::{
+    entry,
+    extension::{
+        self, end_of_index_entry, fs_monitor, index_entry_offset_table, resolve_undo, sparse, tree, untracked_cache,
+    },
+    util::{read_u16, read_u32, read_varint, split_at_pos},
+    Entry, State, Version, HEADER_LEN, SIGNATURE,
+};
+
+quick_error! {
+    /// The error returned by [`State::from_bytes()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Corrupt(message: &'static str) {
+            display("{}", message)
+        }
+        UnsupportedVersion(version: u32) {
+            display("Index version {} is not supported", version)
+        }
+        Entry(index: u32) {
+            display("Could not parse entry at index {}", index)
+        }
+        UnsupportedEntryMode { index: u32, mode: u32 } {
+            display("Entry at index {} has unsupported mode {:o}", index, mode)
+        }
+        ExtendedFlagsInVersion2 { index: u32 } {
+            display("Entry at index {} has extended flags, which are not allowed in index version 2", index)
+        }
+        UnknownMandatoryExtension { signature: extension::Signature } {
+            display("Encountered mandatory extension '{}' which isn't supported", bstr::ByteSlice::as_bstr(&signature[..]))
+        }
+        Extension { signature: extension::Signature } {
+            display("Could not parse extension '{}'", bstr::ByteSlice::as_bstr(&signature[..]))
+        }
+    }
+}
+
+/// Options to define how to decode an index state [from bytes][State::from_bytes()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+    /// The kind of hash used to identify objects and to produce the checksum of the file.
+    pub hash_kind: git_hash::Kind,
+    /// The amount of threads to use when decoding entries in parallel, or all logical cores if `None`.
+    ///
+    /// Entries can only be decoded in parallel if the index contains an offset table for them,
+    /// and if the `parallel` feature of `git-features` is enabled.
+    pub thread_limit: Option<usize>,
+}
+
+/// The amount of bits reserved for the path length in the on-disk flags. Longer paths have all bits set.
+pub(crate) const PATH_LEN_MASK: u16 = 0x0fff;
+/// The bit signalling that an extended flags field follows the basic flags.
+pub(crate) const EXTENDED_FLAG: u16 = 0x4000;
+
+impl State {
+    /// Decode an index state from `data`, the entire content of an index file, and associate it with `timestamp`,
+    /// typically the modification time of the file the data was read from.
+    ///
+    /// Return the state along with the checksum stored in the trailer of the file, which is _not_ verified here.
+    pub fn from_bytes(
+        data: &[u8],
+        timestamp: SystemTime,
+        Options {
+            hash_kind,
+            thread_limit,
+        }: Options,
+    ) -> Result<(Self, git_hash::ObjectId), Error> {
+        let hash_len = hash_kind.len_in_bytes();
+        if data.len() < HEADER_LEN + hash_len {
+            return Err(Error::Corrupt("File is too small to be a valid index"));
+        }
+        let (data, checksum) = data.split_at(data.len() - hash_len);
+        let checksum = git_hash::ObjectId::from_bytes(hash_kind, checksum);
+
+        let (version, num_entries, post_header_data) = decode_header(data)?;
+        let offset_table = end_of_index_entry::decode(data, hash_kind).and_then(|offset_to_extensions| {
+            find_offset_table(&data[offset_to_extensions..]).map(|offsets| (offsets, offset_to_extensions))
+        });
+
+        let (entries, extension_data) = match offset_table {
+            Some((offsets, offset_to_extensions)) if offsets.len() > 1 => (
+                decode_entries_in_parallel(data, &offsets, num_entries, version, hash_kind, thread_limit)?,
+                &data[offset_to_extensions..],
+            ),
+            _ => decode_entries(post_header_data, num_entries, version, hash_kind)?,
+        };
+
+        let mut state = State {
+            timestamp,
+            version,
+            entries,
+            tree: None,
+            resolve_undo: None,
+            untracked: None,
+            fs_monitor: None,
+            unknown_extensions: Vec::new(),
+            is_sparse: false,
+        };
+        state.decode_extensions(extension_data, hash_kind)?;
+        if !state.entries_are_sorted() {
+            return Err(Error::Corrupt(
+                "Entries are not sorted by path and stage or contain duplicates",
+            ));
+        }
+        Ok((state, checksum))
+    }
+
+    fn decode_extensions(&mut self, mut data: &[u8], hash_kind: git_hash::Kind) -> Result<(), Error> {
+        while !data.is_empty() {
+            let (signature, ext_data, rest) = decode_extension(data)?;
+            let err = || Error::Extension { signature };
+            match signature {
+                tree::SIGNATURE => self.tree = Some(extension::Tree::from_bytes(ext_data, hash_kind).ok_or_else(err)?),
+                resolve_undo::SIGNATURE => {
+                    self.resolve_undo = Some(resolve_undo::decode(ext_data, hash_kind).ok_or_else(err)?)
+                }
+                untracked_cache::SIGNATURE => {
+                    self.untracked = Some(extension::UntrackedCache::from_bytes(ext_data, hash_kind).ok_or_else(err)?)
+                }
+                fs_monitor::SIGNATURE => {
+                    self.fs_monitor = Some(extension::FsMonitor::from_bytes(ext_data).ok_or_else(err)?)
+                }
+                sparse::SIGNATURE => self.is_sparse = true,
+                end_of_index_entry::SIGNATURE | index_entry_offset_table::SIGNATURE => {}
+                signature if is_optional_extension(signature) => self.unknown_extensions.push(extension::Unknown {
+                    signature,
+                    data: ext_data.to_owned(),
+                }),
+                signature => return Err(Error::UnknownMandatoryExtension { signature }),
+            }
+            data = rest;
+        }
+        Ok(())
+    }
+}
+
+fn decode_entries(
+    mut data: &[u8],
+    num_entries: u32,
+    version: Version,
+    hash_kind: git_hash::Kind,
+) -> Result<(Vec<Entry>, &[u8]), Error> {
+    let mut entries = Vec::<Entry>::with_capacity(num_entries as usize);
+    for index in 0..num_entries {
+        let (entry, rest) = decode_entry(data, version, hash_kind, entries.last(), index)?;
+        entries.push(entry);
+        data = rest;
+    }
+    Ok((entries, data))
+}
+
+/// Decode the blocks of entries at `offsets` within `data` using multiple threads.
+fn decode_entries_in_parallel(
+    data: &[u8],
+    offsets: &[index_entry_offset_table::Offset],
+    num_entries: u32,
+    version: Version,
+    hash_kind: git_hash::Kind,
+    thread_limit: Option<usize>,
+) -> Result<Vec<Entry>, Error> {
+    if offsets.iter().map(|o| o.num_entries as u64).sum::<u64>() != num_entries as u64 {
+        return Err(Error::Corrupt("The entry offset table doesn't account for all entries"));
+    }
+    let blocks = offsets.iter().scan(0u32, |first_index, offset| {
+        let block = (*first_index, *offset);
+        *first_index += offset.num_entries;
+        Some(block)
+    });
+    git_features::parallel::in_parallel_if(
+        || true,
+        blocks,
+        thread_limit,
+        |_| (),
+        |(first_index, offset), _state| -> Result<(u32, Vec<Entry>), Error> {
+            let block = data
+                .get(offset.from_beginning_of_file as usize..)
+                .ok_or(Error::Corrupt("Entry offset table points past the end of the file"))?;
+            let mut entries = Vec::<Entry>::with_capacity(offset.num_entries as usize);
+            let mut block = block;
+            for index in first_index..first_index + offset.num_entries {
+                let (entry, rest) = decode_entry(block, version, hash_kind, entries.last(), index)?;
+                entries.push(entry);
+                block = rest;
+            }
+            Ok((first_index, entries))
+        },
+        EntryBlocks::default(),
+    )
+}
+
+/// Collect blocks of entries decoded in any order and put them back into their original order.
+#[derive(Default)]
+struct EntryBlocks {
+    blocks: Vec<(u32, Vec<Entry>)>,
+}
+
+impl git_features::parallel::Reduce for EntryBlocks {
+    type Input = Result<(u32, Vec<Entry>), Error>;
+    type FeedProduce = ();
+    type Output = Vec<Entry>;
+    type Error = Error;
+
+    fn feed(&mut self, item: Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+        self.blocks.push(item?);
+        Ok(())
+    }
+
+    fn finalize(mut self) -> Result<Self::Output, Self::Error> {
+        self.blocks.sort_by_key(|(first_index, _)| *first_index);
+        Ok(self.blocks.into_iter().flat_map(|(_, entries)| entries).collect())
+    }
+}
+
+/// Find the entry offset table among the extensions in `data`, which are validated by the end-of-index-entry extension.
+fn find_offset_table(mut data: &[u8]) -> Option<Vec<index_entry_offset_table::Offset>> {
+    while !data.is_empty() {
+        let (signature, ext_data, rest) = decode_extension(data).ok()?;
+        if signature == index_entry_offset_table::SIGNATURE {
+            return index_entry_offset_table::decode(ext_data);
+        }
+        data = rest;
+    }
+    None
+}
+
+/// Extensions whose signature starts with an uppercase letter may be ignored by implementations that don't know them.
+pub(crate) fn is_optional_extension(signature: extension::Signature) -> bool {
+    signature[0].is_ascii_uppercase()
+}
+
+fn decode_header(data: &[u8]) -> Result<(Version, u32, &[u8]), Error> {
+    let (signature, data) = split_at_pos(data, SIGNATURE.len()).ok_or(Error::Corrupt("File is too small"))?;
+    if signature != SIGNATURE {
+        return Err(Error::Corrupt(
+            "Signature mismatch - this doesn't claim to be an index file",
+        ));
+    }
+    let (version, data) = read_u32(data).ok_or(Error::Corrupt("Could not read index version"))?;
+    let version = match version {
+        2 => Version::V2,
+        3 => Version::V3,
+        4 => Version::V4,
+        unknown => return Err(Error::UnsupportedVersion(unknown)),
+    };
+    let (num_entries, data) = read_u32(data).ok_or(Error::Corrupt("Could not read amount of entries"))?;
+    Ok((version, num_entries, data))
+}
+
+fn decode_entry<'a>(
+    data: &'a [u8],
+    version: Version,
+    hash_kind: git_hash::Kind,
+    previous: Option<&Entry>,
+    index: u32,
+) -> Result<(Entry, &'a [u8]), Error> {
+    let start = data;
+    let (ctime_secs, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (ctime_nsecs, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (mtime_secs, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (mtime_nsecs, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (dev, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (ino, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (mode, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (uid, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (gid, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (size, data) = read_u32(data).ok_or(Error::Entry(index))?;
+    let (id, data) = split_at_pos(data, hash_kind.len_in_bytes()).ok_or(Error::Entry(index))?;
+    let (flags, data) = read_u16(data).ok_or(Error::Entry(index))?;
+    let (extended_flags, data) = if flags & EXTENDED_FLAG == EXTENDED_FLAG {
+        if version == Version::V2 {
+            return Err(Error::ExtendedFlagsInVersion2 { index });
+        }
+        read_u16(data).ok_or(Error::Entry(index))?
+    } else {
+        (0, data)
+    };
+    let mode = entry::Mode::try_from(mode).map_err(|mode| Error::UnsupportedEntryMode { index, mode })?;
+
+    let (path, data) = match version {
+        Version::V2 | Version::V3 => {
+            let path_len = (flags & PATH_LEN_MASK) as usize;
+            let path_len = if path_len == PATH_LEN_MASK as usize {
+                data.iter().position(|b| *b == 0).ok_or(Error::Entry(index))?
+            } else {
+                path_len
+            };
+            let (path, _) = split_at_pos(data, path_len).ok_or(Error::Entry(index))?;
+            let consumed_without_padding = start.len() - data.len() + path_len;
+            let entry_len = (consumed_without_padding + 8) & !7;
+            let (_, data) = split_at_pos(start, entry_len).ok_or(Error::Entry(index))?;
+            (BString::from(path), data)
+        }
+        Version::V4 => {
+            let (strip_len, data) = read_varint(data).ok_or(Error::Entry(index))?;
+            // The first entry of a block has no previous entry and ignores the amount of bytes to strip.
+            let previous_path = previous.map(|e| e.path.as_slice()).unwrap_or(&[]);
+            let prefix_len = match previous {
+                Some(_) => previous_path.len().checked_sub(strip_len).ok_or(Error::Entry(index))?,
+                None => 0,
+            };
+            let suffix_len = data.iter().position(|b| *b == 0).ok_or(Error::Entry(index))?;
+            let mut path = Vec::with_capacity(prefix_len + suffix_len);
+            path.extend_from_slice(&previous_path[..prefix_len]);
+            path.extend_from_slice(&data[..suffix_len]);
+            (BString::from(path), &data[suffix_len + 1..])
+        }
+    };
+
+    Ok((
+        Entry {
+            stat: entry::Stat {
+                ctime: entry::Time {
+                    secs: ctime_secs,
+                    nsecs: ctime_nsecs,
+                },
+                mtime: entry::Time {
+                    secs: mtime_secs,
+                    nsecs: mtime_nsecs,
+                },
+                dev,
+                ino,
+                uid,
+                gid,
+                size,
+            },
+            id: git_hash::ObjectId::from_bytes(hash_kind, id),
+            flags: entry::Flags::from_bits_truncate(flags as u32 | (extended_flags as u32) << 16),
+            mode,
+            path,
+        },
+        data,
+    ))
+}
+
+fn decode_extension(data: &[u8]) -> Result<(extension::Signature, &[u8], &[u8]), Error> {
+    let (signature, data) = split_at_pos(data, 4).ok_or(Error::Corrupt("Extension signature is truncated"))?;
+    let (size, data) = read_u32(data).ok_or(Error::Corrupt("Extension size is truncated"))?;
+    let (ext_data, rest) =
+        split_at_pos(data, size as usize).ok_or(Error::Corrupt("Extension data exceeds the end of the file"))?;
+    let mut sig = [0u8; 4];
+    sig.copy_from_slice(signature);
+    Ok((sig, ext_data, rest))
+}
@@ -0,0 +1,249 @@
+use std::{cmp::Ordering, convert::TryFrom};
+
+use bstr::{BStr, BString};
+
+/// An entry in the index, identifying a non-tree item on disk, or a directory in a sparse index.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// The filesystem stat information of the file on disk at the time it was last added to the index.
+    pub stat: Stat,
+    /// The id of the object this entry refers to, usually a blob.
+    pub id: git_hash::ObjectId,
+    /// Additional flags, like the stage of the entry.
+    pub flags: Flags,
+    /// The kind of item this entry represents.
+    pub mode: Mode,
+    /// The path of the entry relative to the root of the working tree, using forward slashes as separators.
+    pub path: BString,
+}
+
+impl Entry {
+    /// The stage of this entry, with `0` being the normal stage and `1` to `3` being used for the base, ours and theirs
+    /// side of a conflict respectively.
+    pub fn stage(&self) -> u32 {
+        self.flags.stage()
+    }
+
+    /// The path of this entry as borrowed string.
+    pub fn path(&self) -> &BStr {
+        self.path.as_ref()
+    }
+
+    /// Return true if this entry represents an entire directory in a sparse index, pointing to its tree.
+    ///
+    /// Its path ends with a slash.
+    pub fn is_sparse_directory(&self) -> bool {
+        self.mode == Mode::Dir
+    }
+
+    /// Compare this entry to `other` by path and stage, which is the order in which entries are stored in the index.
+    pub fn cmp_by_path_and_stage(&self, other: &Entry) -> Ordering {
+        self.path
+            .cmp(&other.path)
+            .then_with(|| self.stage().cmp(&other.stage()))
+    }
+}
+
+/// A point in time as stored in the index, with a precision of up to nanoseconds.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Time {
+    /// The seconds elapsed since the unix epoch, truncated to 32 bits.
+    pub secs: u32,
+    /// The nanoseconds elapsed within the second denoted by `secs`.
+    pub nsecs: u32,
+}
+
+/// The filesystem stat information git keeps for each entry to detect changes without reading the file.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stat {
+    /// The time at which the file metadata changed last.
+    pub ctime: Time,
+    /// The time at which the file content was modified last.
+    pub mtime: Time,
+    /// The device the file resides on.
+    pub dev: u32,
+    /// The inode of the file.
+    pub ino: u32,
+    /// The id of the user owning the file.
+    pub uid: u32,
+    /// The id of the group owning the file.
+    pub gid: u32,
+    /// The size of the file in bytes, truncated to 32 bits.
+    pub size: u32,
+}
+
+impl Stat {
+    /// Obtain the stat information from `metadata` as git would record it, truncating all values to 32 bits.
+    ///
+    /// On platforms other than unix, only the times and the size are available, with the creation time being used as `ctime`.
+    pub fn from_fs(metadata: &std::fs::Metadata) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            Stat {
+                ctime: Time {
+                    secs: metadata.ctime() as u32,
+                    nsecs: metadata.ctime_nsec() as u32,
+                },
+                mtime: Time {
+                    secs: metadata.mtime() as u32,
+                    nsecs: metadata.mtime_nsec() as u32,
+                },
+                dev: metadata.dev() as u32,
+                ino: metadata.ino() as u32,
+                uid: metadata.uid(),
+                gid: metadata.gid(),
+                size: metadata.size() as u32,
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let time = |time: std::io::Result<std::time::SystemTime>| {
+                time.ok()
+                    .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|since_epoch| Time {
+                        secs: since_epoch.as_secs() as u32,
+                        nsecs: since_epoch.subsec_nanos(),
+                    })
+                    .unwrap_or_default()
+            };
+            Stat {
+                ctime: time(metadata.created()),
+                mtime: time(metadata.modified()),
+                size: metadata.len() as u32,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// The kind of item an entry represents, along with its executable bit if it is a file.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Mode {
+    File = 0o100644,
+    FileExecutable = 0o100755,
+    Symlink = 0o120000,
+    /// A submodule, also known as gitlink.
+    Commit = 0o160000,
+    /// A directory whose entries are not checked out, only present in sparse indices.
+    Dir = 0o040000,
+}
+
+impl Mode {
+    /// Return the mode as it would be stored on disk.
+    pub fn bits(&self) -> u32 {
+        *self as u32
+    }
+}
+
+impl From<git_object::tree::EntryMode> for Mode {
+    fn from(mode: git_object::tree::EntryMode) -> Self {
+        use git_object::tree::EntryMode::*;
+        match mode {
+            Tree => Mode::Dir,
+            Blob => Mode::File,
+            BlobExecutable => Mode::FileExecutable,
+            Link => Mode::Symlink,
+            Commit => Mode::Commit,
+        }
+    }
+}
+
+impl From<Mode> for git_object::tree::EntryMode {
+    fn from(mode: Mode) -> Self {
+        use git_object::tree::EntryMode::*;
+        match mode {
+            Mode::Dir => Tree,
+            Mode::File => Blob,
+            Mode::FileExecutable => BlobExecutable,
+            Mode::Symlink => Link,
+            Mode::Commit => Commit,
+        }
+    }
+}
+
+impl TryFrom<u32> for Mode {
+    type Error = u32;
+
+    /// Convert the on-disk `mode` into a `Mode`, or return it unchanged if it is unknown.
+    fn try_from(mode: u32) -> Result<Self, Self::Error> {
+        Ok(match mode {
+            0o100644 => Mode::File,
+            0o100755 => Mode::FileExecutable,
+            0o120000 => Mode::Symlink,
+            0o160000 => Mode::Commit,
+            0o040000 => Mode::Dir,
+            _ => return Err(mode),
+        })
+    }
+}
+
+/// Flags stored with each entry, combining the basic flags in the lower 16 bits with the extended flags of
+/// index versions 3 and higher in the upper 16 bits.
+///
+/// The length of the path is not stored here as it's derived from the entry path itself.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Flags(u32);
+
+impl Flags {
+    /// The entry is assumed to be unchanged, which is controlled with `git update-index --assume-unchanged`.
+    pub const ASSUME_VALID: Flags = Flags(1 << 15);
+    /// The bits holding the stage of the entry.
+    pub const STAGE_MASK: Flags = Flags(0x3000);
+    /// The entry is not checked out into the working tree, as used by sparse checkouts.
+    pub const SKIP_WORKTREE: Flags = Flags(1 << 30);
+    /// The entry was added with `git add --intent-to-add` and has no content in the index yet.
+    pub const INTENT_TO_ADD: Flags = Flags(1 << 29);
+
+    /// All flags that are stored in the extended flags field, requiring at least index version 3.
+    pub(crate) const EXTENDED_MASK: u32 = 0xffff_0000;
+    /// Flags we understand and store in memory, whereas other bits of the on-disk flags are derived on write.
+    const KNOWN_MASK: u32 = Self::ASSUME_VALID.0 | Self::STAGE_MASK.0 | Self::SKIP_WORKTREE.0 | Self::INTENT_TO_ADD.0;
+
+    /// Create flags from the given `bits`, ignoring all bits we don't know.
+    pub fn from_bits_truncate(bits: u32) -> Self {
+        Flags(bits & Self::KNOWN_MASK)
+    }
+
+    /// Return the raw bits of these flags.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns true if all flags in `other` are set in `self`.
+    pub fn contains(&self, other: Flags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Set all flags in `other`.
+    pub fn insert(&mut self, other: Flags) {
+        self.0 |= other.0;
+    }
+
+    /// Unset all flags in `other`.
+    pub fn remove(&mut self, other: Flags) {
+        self.0 &= !other.0;
+    }
+
+    /// The stage of the entry, see [`Entry::stage()`].
+    pub fn stage(&self) -> u32 {
+        (self.0 & Self::STAGE_MASK.0) >> 12
+    }
+
+    /// Set the stage of the entry to `stage`, which must be a value from 0 to 3.
+    pub fn set_stage(&mut self, stage: u32) {
+        assert!(stage <= 3, "stages are 0 to 3");
+        self.0 = (self.0 & !Self::STAGE_MASK.0) | (stage << 12);
+    }
+
+    /// Returns true if any of the flags requires the extended flags field of index version 3 or higher.
+    pub fn is_extended(&self) -> bool {
+        self.0 & Self::EXTENDED_MASK != 0
+    }
+}
@@ -0,0 +1,73 @@
+//! The end-of-index-entry extension is always the last extension and points to the first byte after the last entry,
+//! which allows to read all extensions before the entries are decoded.
+use std::io;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{
+    extension::{self, HEADER_LEN},
+    util::{read_u32, split_at_pos},
+};
+
+pub const SIGNATURE: extension::Signature = *b"EOIE";
+
+/// The size of the extension without its header, which depends on the `hash_kind` used to hash the extension headers.
+pub const fn size(hash_kind: git_hash::Kind) -> usize {
+    4 /* offset to the end of entries */ + hash_kind.len_in_bytes() /* hash of extension headers */
+}
+
+/// Decode the extension at the end of `data`, which is all data of the index file without its trailing checksum,
+/// and return the offset to the first byte after the last entry if the extension is present and valid.
+pub fn decode(data: &[u8], hash_kind: git_hash::Kind) -> Option<usize> {
+    let size = size(hash_kind);
+    if data.len() < crate::HEADER_LEN + HEADER_LEN + size {
+        return None;
+    }
+    let start_of_eoie = data.len() - HEADER_LEN - size;
+    let ext_data = &data[start_of_eoie..];
+
+    let (signature, ext_data) = split_at_pos(ext_data, 4)?;
+    let (ext_size, ext_data) = read_u32(ext_data)?;
+    if signature != SIGNATURE || ext_size as usize != size {
+        return None;
+    }
+
+    let (offset, checksum) = read_u32(ext_data)?;
+    let offset = offset as usize;
+    if offset < crate::HEADER_LEN || offset > start_of_eoie {
+        return None;
+    }
+
+    let mut hasher = git_features::hash::hasher(hash_kind);
+    let mut extensions = &data[offset..start_of_eoie];
+    while !extensions.is_empty() {
+        let (header, rest) = split_at_pos(extensions, HEADER_LEN)?;
+        let (_, size) = split_at_pos(header, 4)?;
+        let (size, _) = read_u32(size)?;
+        hasher.update(header);
+        let (_, rest) = split_at_pos(rest, size as usize)?;
+        extensions = rest;
+    }
+
+    (hasher.digest().as_bytes() == checksum).then(|| offset)
+}
+
+/// Write the extension for entries ending at `offset_to_extensions` and the extensions identified by their
+/// `signature` and `size`, in order.
+pub fn write_to(
+    mut out: impl io::Write,
+    hash_kind: git_hash::Kind,
+    offset_to_extensions: u32,
+    prior_extensions: impl IntoIterator<Item = (extension::Signature, u32)>,
+) -> io::Result<()> {
+    out.write_all(&SIGNATURE)?;
+    out.write_u32::<BigEndian>(size(hash_kind) as u32)?;
+    out.write_u32::<BigEndian>(offset_to_extensions)?;
+
+    let mut hasher = git_features::hash::hasher(hash_kind);
+    for (signature, size) in prior_extensions {
+        hasher.update(&signature);
+        hasher.update(&size.to_be_bytes());
+    }
+    out.write_all(hasher.digest().as_bytes())
+}
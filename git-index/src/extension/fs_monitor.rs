@@ -0,0 +1,158 @@
+use std::io;
+
+use bstr::{BString, ByteSlice};
+use byteorder::{BigEndian, WriteBytesExt};
+use git_bitmap::ewah;
+
+use crate::{
+    util::{read_u32, read_u64, split_at_pos},
+    Entry,
+};
+
+/// The signature of the filesystem monitor extension.
+pub const SIGNATURE: super::Signature = *b"FSMN";
+
+/// Information about the last query of a filesystem monitor, along with the entries that may have changed since then.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct FsMonitor {
+    /// Identifies the point in time at which the filesystem monitor was queried last.
+    pub token: Token,
+    /// Whether or not the entry at the same index is dirty, meaning it may have changed and has to be checked.
+    ///
+    /// Entries past the end of this list are not dirty.
+    pub entry_dirty: Vec<bool>,
+}
+
+/// The token identifying the last query of a filesystem monitor, depending on the version of its protocol.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Token {
+    /// Version 1 of the protocol, using the time of the last query in nanoseconds since the unix epoch.
+    V1 {
+        /// The time of the last query.
+        nanos_since_1970: u64,
+    },
+    /// Version 2 of the protocol, using an opaque token provided by the filesystem monitor.
+    V2 {
+        /// The token as provided by the filesystem monitor.
+        token: BString,
+    },
+}
+
+impl FsMonitor {
+    /// Return true if the entry at `index` may have changed and needs to be checked.
+    pub fn is_dirty(&self, index: usize) -> bool {
+        self.entry_dirty.get(index).copied().unwrap_or(false)
+    }
+
+    /// Adjust our dirty-information after an entry was inserted at `index`, considering it dirty.
+    pub(crate) fn entry_inserted_at(&mut self, index: usize) {
+        if index < self.entry_dirty.len() {
+            self.entry_dirty.insert(index, true);
+        } else {
+            self.mark_dirty(index);
+        }
+    }
+
+    /// Mark the entry at `index` as dirty, for instance because it was replaced.
+    pub(crate) fn mark_dirty(&mut self, index: usize) {
+        if index >= self.entry_dirty.len() {
+            self.entry_dirty.resize(index + 1, false);
+        }
+        self.entry_dirty[index] = true;
+    }
+
+    /// Adjust our dirty-information after the entry at `index` was removed.
+    pub(crate) fn entry_removed_at(&mut self, index: usize) {
+        if index < self.entry_dirty.len() {
+            self.entry_dirty.remove(index);
+        }
+    }
+
+    /// Return our dirty-information for `collapsed` entries, which are `entries` with some directories collapsed into
+    /// sparse directory entries. A sparse directory entry is dirty if any of the entries it replaces is dirty.
+    pub(crate) fn for_collapsed_entries<'a>(
+        &self,
+        entries: &[Entry],
+        collapsed: impl IntoIterator<Item = &'a Entry>,
+    ) -> FsMonitor {
+        let mut pos = 0;
+        let entry_dirty = collapsed
+            .into_iter()
+            .map(|entry| {
+                let start = pos;
+                pos += 1;
+                if entry.is_sparse_directory() {
+                    pos = start + entries[start..].partition_point(|e| e.path.starts_with(&entry.path));
+                }
+                (start..pos).any(|index| self.is_dirty(index))
+            })
+            .collect();
+        FsMonitor {
+            token: self.token.clone(),
+            entry_dirty,
+        }
+    }
+
+    pub(crate) fn from_bytes(data: &[u8]) -> Option<Self> {
+        let (version, data) = read_u32(data)?;
+        let (token, data) = match version {
+            1 => {
+                let (nanos_since_1970, data) = read_u64(data)?;
+                (Token::V1 { nanos_since_1970 }, data)
+            }
+            2 => {
+                let end = data.find_byte(0)?;
+                (
+                    Token::V2 {
+                        token: data[..end].into(),
+                    },
+                    &data[end + 1..],
+                )
+            }
+            _ => return None,
+        };
+        let (ewah_size, data) = read_u32(data)?;
+        let (bitmap, _) = split_at_pos(data, ewah_size as usize)?;
+        let (bitmap, _) = ewah::decode(bitmap).ok()?;
+
+        let mut entry_dirty = vec![false; bitmap.num_bits()];
+        let mut in_bounds = true;
+        bitmap.for_each_set_bit(|index| match entry_dirty.get_mut(index) {
+            Some(dirty) => {
+                *dirty = true;
+                true
+            }
+            None => {
+                in_bounds = false;
+                false
+            }
+        });
+        in_bounds.then(|| FsMonitor { token, entry_dirty })
+    }
+
+    pub(crate) fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        match &self.token {
+            Token::V1 { nanos_since_1970 } => {
+                out.write_u32::<BigEndian>(1)?;
+                out.write_u64::<BigEndian>(*nanos_since_1970)?;
+            }
+            Token::V2 { token } => {
+                out.write_u32::<BigEndian>(2)?;
+                out.write_all(token)?;
+                out.write_all(&[0])?;
+            }
+        }
+        let bitmap: ewah::Vec = self
+            .entry_dirty
+            .iter()
+            .enumerate()
+            .filter_map(|(index, dirty)| dirty.then(|| index))
+            .collect();
+        let mut buf = Vec::new();
+        bitmap.write_to(&mut buf)?;
+        out.write_u32::<BigEndian>(buf.len() as u32)?;
+        out.write_all(&buf)
+    }
+}
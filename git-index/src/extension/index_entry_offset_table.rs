@@ -0,0 +1,46 @@
+//! The index-entry-offset-table extension splits the entries into blocks that can be decoded independently,
+//! allowing to decode them in parallel.
+use std::io;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::{extension, util::read_u32};
+
+pub const SIGNATURE: extension::Signature = *b"IEOT";
+const VERSION: u32 = 1;
+
+/// A block of entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Offset {
+    /// The offset of the first entry of the block from the beginning of the file.
+    pub from_beginning_of_file: u32,
+    /// The amount of entries in the block.
+    pub num_entries: u32,
+}
+
+pub fn decode(data: &[u8]) -> Option<Vec<Offset>> {
+    let (version, mut data) = read_u32(data)?;
+    if version != VERSION || data.len() % 8 != 0 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len() / 8);
+    while !data.is_empty() {
+        let (from_beginning_of_file, rest) = read_u32(data)?;
+        let (num_entries, rest) = read_u32(rest)?;
+        out.push(Offset {
+            from_beginning_of_file,
+            num_entries,
+        });
+        data = rest;
+    }
+    Some(out)
+}
+
+pub fn write_to(offsets: &[Offset], mut out: impl io::Write) -> io::Result<()> {
+    out.write_u32::<BigEndian>(VERSION)?;
+    for offset in offsets {
+        out.write_u32::<BigEndian>(offset.from_beginning_of_file)?;
+        out.write_u32::<BigEndian>(offset.num_entries)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+//! Extensions store additional information after the entries of an index file.
+//!
+//! Each extension starts with a four byte signature followed by the size of its data. Extensions whose signature starts
+//! with an uppercase letter are optional and may be ignored, all others are mandatory and must be understood to use the index.
+use std::io;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+///
+pub mod tree;
+pub use tree::Tree;
+
+///
+pub mod resolve_undo;
+
+///
+pub mod untracked_cache;
+pub use untracked_cache::UntrackedCache;
+
+///
+pub mod fs_monitor;
+pub use fs_monitor::FsMonitor;
+
+pub(crate) mod end_of_index_entry;
+pub(crate) mod index_entry_offset_table;
+pub(crate) mod sparse;
+
+/// The four bytes identifying an extension.
+pub type Signature = [u8; 4];
+
+/// The size of the header of each extension, consisting of its signature and the size of its data.
+pub(crate) const HEADER_LEN: usize = 4 + 4;
+
+/// An optional extension this implementation doesn't understand, which is kept as is to be written back unchanged.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Unknown {
+    /// The signature of the extension, which always starts with an uppercase letter.
+    pub signature: Signature,
+    /// The data of the extension without its header.
+    pub data: Vec<u8>,
+}
+
+/// Write an extension with `signature` and `data` to `out`.
+pub(crate) fn write(signature: Signature, data: &[u8], mut out: impl io::Write) -> io::Result<()> {
+    out.write_all(&signature)?;
+    out.write_u32::<BigEndian>(data.len() as u32)?;
+    out.write_all(data)
+}
@@ -0,0 +1,78 @@
+use std::{convert::TryFrom, io};
+
+use bstr::{BString, ByteSlice};
+
+use crate::{entry, util::split_at_pos};
+
+/// The signature of the resolve-undo extension.
+pub const SIGNATURE: super::Signature = *b"REUC";
+
+/// A path that had conflicts which were resolved, along with the stages it had before, allowing to recreate the conflict.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Path {
+    /// The path relative to the root of the repository.
+    pub name: BString,
+    /// The base, ours and theirs stages (1, 2 and 3) of the path, if they existed.
+    pub stages: [Option<Stage>; 3],
+}
+
+/// A single stage of a [`Path`] before the conflict was resolved.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stage {
+    /// The mode of the entry at this stage.
+    pub mode: entry::Mode,
+    /// The id of the object at this stage.
+    pub id: git_hash::ObjectId,
+}
+
+pub(crate) fn decode(mut data: &[u8], hash_kind: git_hash::Kind) -> Option<Vec<Path>> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let name_end = data.find_byte(0)?;
+        let name = &data[..name_end];
+        data = &data[name_end + 1..];
+
+        let mut modes = [0u32; 3];
+        for mode in modes.iter_mut() {
+            let mode_end = data.find_byte(0)?;
+            *mode = u32::from_str_radix(data[..mode_end].to_str().ok()?, 8).ok()?;
+            data = &data[mode_end + 1..];
+        }
+
+        let mut stages = [None; 3];
+        for (stage, mode) in stages.iter_mut().zip(modes.iter()) {
+            if *mode == 0 {
+                continue;
+            }
+            let (id, rest) = split_at_pos(data, hash_kind.len_in_bytes())?;
+            data = rest;
+            *stage = Some(Stage {
+                mode: entry::Mode::try_from(*mode).ok()?,
+                id: git_hash::ObjectId::from_bytes(hash_kind, id),
+            });
+        }
+        out.push(Path {
+            name: name.into(),
+            stages,
+        });
+    }
+    Some(out)
+}
+
+pub(crate) fn write_to(paths: &[Path], mut out: impl io::Write) -> io::Result<()> {
+    for path in paths {
+        out.write_all(&path.name)?;
+        out.write_all(&[0])?;
+        for stage in &path.stages {
+            let mode = stage.map(|s| s.mode.bits()).unwrap_or(0);
+            write!(out, "{:o}", mode)?;
+            out.write_all(&[0])?;
+        }
+        for stage in path.stages.iter().flatten() {
+            out.write_all(stage.id.as_slice())?;
+        }
+    }
+    Ok(())
+}
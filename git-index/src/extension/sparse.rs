@@ -0,0 +1,5 @@
+//! The sparse directory extension has no data and marks an index as sparse, which means it may contain directory entries
+//! for trees that are entirely excluded from the working tree, in place of all entries within them.
+use crate::extension;
+
+pub const SIGNATURE: extension::Signature = *b"sdir";
@@ -0,0 +1,119 @@
+use std::io;
+
+use bstr::{BStr, BString, ByteSlice};
+
+use crate::util::split_at_pos;
+
+/// The signature of the cache-tree extension.
+pub const SIGNATURE: super::Signature = *b"TREE";
+
+/// A node of the cache-tree, which caches the ids of trees corresponding to directories in the index to speed up
+/// writing trees and comparing them with the index.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tree {
+    /// The name of the directory this tree represents, without its leading directories. It's empty for the root tree.
+    pub name: BString,
+    /// The id of the tree along with the amount of index entries it covers, or `None` if it was invalidated
+    /// because entries within it changed.
+    pub id: Option<NodeId>,
+    /// The trees of sub-directories, in the order they were stored in.
+    pub children: Vec<Tree>,
+}
+
+/// The id of a valid [`Tree`] along with the amount of entries it represents.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeId {
+    /// The amount of index entries within the tree and all of its sub-trees.
+    pub entry_count: u32,
+    /// The id of the tree object.
+    pub id: git_hash::ObjectId,
+}
+
+impl Tree {
+    /// Find the tree of the directory at `path`, with components separated by slashes, relative to this tree.
+    pub fn find(&self, path: &BStr) -> Option<&Tree> {
+        let mut tree = self;
+        for component in path.split_str("/").filter(|c| !c.is_empty()) {
+            tree = tree.children.iter().find(|t| t.name == component)?;
+        }
+        Some(tree)
+    }
+
+    /// Invalidate this tree and all trees leading to the file at `path`, which is relative to this tree,
+    /// because the entry at `path` was added, removed or changed.
+    pub fn invalidate_path(&mut self, path: &BStr) {
+        self.id = None;
+        if let Some(pos) = path.find_byte(b'/') {
+            let (dir, rest) = (&path[..pos], &path[pos + 1..]);
+            if let Some(child) = self.children.iter_mut().find(|t| t.name == dir) {
+                child.invalidate_path(rest.as_bstr());
+            }
+        }
+    }
+
+    pub(crate) fn from_bytes(data: &[u8], hash_kind: git_hash::Kind) -> Option<Tree> {
+        let (tree, rest) = one_recursive(data, hash_kind)?;
+        rest.is_empty().then(|| tree)
+    }
+
+    pub(crate) fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        self.write_recursive(&mut out)
+    }
+
+    fn write_recursive<W: io::Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(&self.name)?;
+        out.write_all(&[0])?;
+        match &self.id {
+            Some(NodeId { entry_count, id }) => {
+                writeln!(out, "{} {}", entry_count, self.children.len())?;
+                out.write_all(id.as_slice())?;
+            }
+            None => writeln!(out, "-1 {}", self.children.len())?,
+        }
+        for child in &self.children {
+            child.write_recursive(out)?;
+        }
+        Ok(())
+    }
+}
+
+fn one_recursive(data: &[u8], hash_kind: git_hash::Kind) -> Option<(Tree, &[u8])> {
+    let name_end = data.find_byte(0)?;
+    let name = &data[..name_end];
+    let data = &data[name_end + 1..];
+
+    let line_end = data.find_byte(b'\n')?;
+    let (entry_count, subtree_count) = data[..line_end].to_str().ok()?.split_once(' ')?;
+    let entry_count: i32 = entry_count.parse().ok()?;
+    let subtree_count: usize = subtree_count.parse().ok()?;
+    let mut data = &data[line_end + 1..];
+
+    let id = if entry_count >= 0 {
+        let (id, rest) = split_at_pos(data, hash_kind.len_in_bytes())?;
+        data = rest;
+        Some(NodeId {
+            entry_count: entry_count as u32,
+            id: git_hash::ObjectId::from_bytes(hash_kind, id),
+        })
+    } else {
+        None
+    };
+
+    let mut children = Vec::with_capacity(subtree_count);
+    for _ in 0..subtree_count {
+        let (child, rest) = one_recursive(data, hash_kind)?;
+        children.push(child);
+        data = rest;
+    }
+
+    Some((
+        Tree {
+            name: name.into(),
+            id,
+            children,
+        },
+        data,
+    ))
+}
@@ -0,0 +1,337 @@
+use std::io;
+
+use bstr::{BStr, BString, ByteSlice};
+use byteorder::{BigEndian, WriteBytesExt};
+use git_bitmap::ewah;
+
+use crate::{
+    entry,
+    util::{read_u32, read_varint, split_at_pos, write_varint},
+};
+
+/// The signature of the untracked cache extension.
+pub const SIGNATURE: super::Signature = *b"UNTR";
+
+/// A cache of untracked files and directories along with the information needed to know when it has to be refreshed,
+/// speeding up the discovery of untracked files considerably.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct UntrackedCache {
+    /// Identifies the environment the cache was created in, typically the location of the working tree and
+    /// the operating system. Git discards the cache if it doesn't match its own environment.
+    ///
+    /// It's stored exactly as read, including the trailing null byte git adds.
+    pub identifier: BString,
+    /// Stat information and id of the `$GIT_DIR/info/exclude` file.
+    pub info_exclude: OidStat,
+    /// Stat information and id of the file configured in `core.excludesFile`.
+    pub excludes_file: OidStat,
+    /// The flags git used when it collected untracked files, as they affect the cached result.
+    pub dir_flags: u32,
+    /// The name of the file containing per-directory exclude patterns, usually `.gitignore`.
+    pub exclude_filename_per_dir: BString,
+    /// The root directory of the working tree, or `None` if nothing was cached yet.
+    pub root: Option<Directory>,
+}
+
+/// Stat information of a file along with the id of its content, which is null if the file doesn't exist.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct OidStat {
+    /// The stat information of the file.
+    pub stat: entry::Stat,
+    /// The id of the file's content.
+    pub id: git_hash::ObjectId,
+}
+
+/// A directory in the untracked cache.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Directory {
+    /// The name of the directory without its leading directories, empty for the root.
+    pub name: BString,
+    /// The untracked files and directories within, the latter with a trailing slash.
+    pub untracked_entries: Vec<BString>,
+    /// Directories within this one.
+    pub sub_directories: Vec<Directory>,
+    /// The stat information of the directory if its cached data is valid, or `None` if it needs to be refreshed.
+    pub stat: Option<entry::Stat>,
+    /// The id of the per-directory exclude file in this directory, if there was one.
+    pub exclude_file_oid: Option<git_hash::ObjectId>,
+    /// If true, only the existence of untracked files was checked, but they were not listed.
+    pub check_only: bool,
+}
+
+impl UntrackedCache {
+    /// Invalidate all directories leading to the file at `path`, relative to the root of the working tree,
+    /// as the file was added or removed from the index.
+    pub fn invalidate_path(&mut self, path: &BStr) {
+        let mut dir = match self.root.as_mut() {
+            Some(dir) => dir,
+            None => return,
+        };
+        let mut components = path.split_str("/").peekable();
+        loop {
+            dir.invalidate();
+            let component = match components.next() {
+                Some(component) if components.peek().is_some() => component,
+                _ => break,
+            };
+            dir = match dir.sub_directories.iter_mut().find(|d| d.name == component) {
+                Some(dir) => dir,
+                None => break,
+            };
+        }
+    }
+
+    pub(crate) fn from_bytes(data: &[u8], hash_kind: git_hash::Kind) -> Option<Self> {
+        let (identifier_len, data) = read_varint(data)?;
+        let (identifier, data) = split_at_pos(data, identifier_len)?;
+
+        let (info_exclude_stat, data) = decode_stat(data)?;
+        let (excludes_file_stat, data) = decode_stat(data)?;
+        let (dir_flags, data) = read_u32(data)?;
+        let (info_exclude_id, data) = split_at_pos(data, hash_kind.len_in_bytes())?;
+        let (excludes_file_id, data) = split_at_pos(data, hash_kind.len_in_bytes())?;
+        let exclude_filename_end = data.find_byte(0)?;
+        let exclude_filename_per_dir = &data[..exclude_filename_end];
+        let data = &data[exclude_filename_end + 1..];
+
+        let (num_directories, data) = read_varint(data)?;
+        let root = if num_directories == 0 {
+            None
+        } else {
+            let (mut root, data) = decode_directory_recursive(data, num_directories)?;
+            let (valid, data) = ewah::decode(data).ok()?;
+            let (check_only, data) = ewah::decode(data).ok()?;
+            let (oid_valid, mut data) = ewah::decode(data).ok()?;
+
+            let bits = |bitmap: &ewah::Vec| -> Option<Vec<bool>> {
+                let mut bits = vec![false; num_directories];
+                let mut in_bounds = true;
+                bitmap.for_each_set_bit(|index| match bits.get_mut(index) {
+                    Some(bit) => {
+                        *bit = true;
+                        true
+                    }
+                    None => {
+                        in_bounds = false;
+                        false
+                    }
+                });
+                in_bounds.then(|| bits)
+            };
+            let (valid, check_only, oid_valid) = (bits(&valid)?, bits(&check_only)?, bits(&oid_valid)?);
+
+            let mut stats = Vec::with_capacity(num_directories);
+            for is_valid in &valid {
+                stats.push(if *is_valid {
+                    let (stat, rest) = decode_stat(data)?;
+                    data = rest;
+                    Some(stat)
+                } else {
+                    None
+                });
+            }
+            let mut oids = Vec::with_capacity(num_directories);
+            for is_valid in &oid_valid {
+                oids.push(if *is_valid {
+                    let (id, rest) = split_at_pos(data, hash_kind.len_in_bytes())?;
+                    data = rest;
+                    Some(git_hash::ObjectId::from_bytes(hash_kind, id))
+                } else {
+                    None
+                });
+            }
+
+            let mut index = 0;
+            let directories_seen = root.for_each_depth_first_mut(&mut |dir| {
+                if let (Some(stat), Some(check_only), Some(oid)) =
+                    (stats.get(index), check_only.get(index), oids.get(index))
+                {
+                    dir.stat = *stat;
+                    dir.check_only = *check_only;
+                    dir.exclude_file_oid = *oid;
+                }
+                index += 1;
+            });
+            if directories_seen != num_directories {
+                return None;
+            }
+            Some(root)
+        };
+
+        Some(UntrackedCache {
+            identifier: identifier.into(),
+            info_exclude: OidStat {
+                stat: info_exclude_stat,
+                id: git_hash::ObjectId::from_bytes(hash_kind, info_exclude_id),
+            },
+            excludes_file: OidStat {
+                stat: excludes_file_stat,
+                id: git_hash::ObjectId::from_bytes(hash_kind, excludes_file_id),
+            },
+            dir_flags,
+            exclude_filename_per_dir: exclude_filename_per_dir.into(),
+            root,
+        })
+    }
+
+    pub(crate) fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        write_varint(self.identifier.len(), &mut out)?;
+        out.write_all(&self.identifier)?;
+        write_stat(&self.info_exclude.stat, &mut out)?;
+        write_stat(&self.excludes_file.stat, &mut out)?;
+        out.write_u32::<BigEndian>(self.dir_flags)?;
+        out.write_all(self.info_exclude.id.as_slice())?;
+        out.write_all(self.excludes_file.id.as_slice())?;
+        out.write_all(&self.exclude_filename_per_dir)?;
+        out.write_all(&[0])?;
+
+        let root = match &self.root {
+            Some(root) => root,
+            None => return write_varint(0, out),
+        };
+        let mut directories = Vec::new();
+        root.collect_depth_first(&mut directories);
+        write_varint(directories.len(), &mut out)?;
+        for dir in &directories {
+            write_varint(dir.untracked_entries.len(), &mut out)?;
+            write_varint(dir.sub_directories.len(), &mut out)?;
+            out.write_all(&dir.name)?;
+            out.write_all(&[0])?;
+            for entry in &dir.untracked_entries {
+                out.write_all(entry)?;
+                out.write_all(&[0])?;
+            }
+        }
+
+        let indices_where = |f: fn(&Directory) -> bool| -> ewah::Vec {
+            directories
+                .iter()
+                .enumerate()
+                .filter_map(|(index, dir)| f(dir).then(|| index))
+                .collect()
+        };
+        indices_where(|dir| dir.stat.is_some()).write_to(&mut out)?;
+        indices_where(|dir| dir.check_only).write_to(&mut out)?;
+        indices_where(|dir| dir.exclude_file_oid.is_some()).write_to(&mut out)?;
+        for stat in directories.iter().filter_map(|dir| dir.stat.as_ref()) {
+            write_stat(stat, &mut out)?;
+        }
+        for id in directories.iter().filter_map(|dir| dir.exclude_file_oid.as_ref()) {
+            out.write_all(id.as_slice())?;
+        }
+        out.write_all(&[0])
+    }
+}
+
+impl Directory {
+    /// Mark this directory as requiring a refresh, dropping its cached untracked entries.
+    pub fn invalidate(&mut self) {
+        self.stat = None;
+        self.check_only = false;
+        self.untracked_entries.clear();
+    }
+
+    /// Call `f` on this directory and all directories below it depth-first, returning the amount of visited directories.
+    fn for_each_depth_first_mut(&mut self, f: &mut impl FnMut(&mut Directory)) -> usize {
+        f(self);
+        let mut count = 1;
+        for dir in &mut self.sub_directories {
+            count += dir.for_each_depth_first_mut(f);
+        }
+        count
+    }
+
+    fn collect_depth_first<'a>(&'a self, out: &mut Vec<&'a Directory>) {
+        out.push(self);
+        for dir in &self.sub_directories {
+            dir.collect_depth_first(out);
+        }
+    }
+}
+
+fn decode_directory_recursive(data: &[u8], max_directories: usize) -> Option<(Directory, &[u8])> {
+    let (num_untracked, data) = read_varint(data)?;
+    let (num_sub_directories, data) = read_varint(data)?;
+    if num_sub_directories >= max_directories {
+        return None;
+    }
+    let name_end = data.find_byte(0)?;
+    let name = &data[..name_end];
+    let mut data = &data[name_end + 1..];
+
+    let mut untracked_entries = Vec::with_capacity(num_untracked.min(data.len()));
+    for _ in 0..num_untracked {
+        let end = data.find_byte(0)?;
+        untracked_entries.push(data[..end].into());
+        data = &data[end + 1..];
+    }
+
+    let mut sub_directories = Vec::with_capacity(num_sub_directories);
+    for _ in 0..num_sub_directories {
+        let (dir, rest) = decode_directory_recursive(data, max_directories)?;
+        sub_directories.push(dir);
+        data = rest;
+    }
+
+    Some((
+        Directory {
+            name: name.into(),
+            untracked_entries,
+            sub_directories,
+            stat: None,
+            exclude_file_oid: None,
+            check_only: false,
+        },
+        data,
+    ))
+}
+
+fn decode_stat(data: &[u8]) -> Option<(entry::Stat, &[u8])> {
+    let (ctime_secs, data) = read_u32(data)?;
+    let (ctime_nsecs, data) = read_u32(data)?;
+    let (mtime_secs, data) = read_u32(data)?;
+    let (mtime_nsecs, data) = read_u32(data)?;
+    let (dev, data) = read_u32(data)?;
+    let (ino, data) = read_u32(data)?;
+    let (uid, data) = read_u32(data)?;
+    let (gid, data) = read_u32(data)?;
+    let (size, data) = read_u32(data)?;
+    Some((
+        entry::Stat {
+            ctime: entry::Time {
+                secs: ctime_secs,
+                nsecs: ctime_nsecs,
+            },
+            mtime: entry::Time {
+                secs: mtime_secs,
+                nsecs: mtime_nsecs,
+            },
+            dev,
+            ino,
+            uid,
+            gid,
+            size,
+        },
+        data,
+    ))
+}
+
+fn write_stat(stat: &entry::Stat, mut out: impl io::Write) -> io::Result<()> {
+    for field in &[
+        stat.ctime.secs,
+        stat.ctime.nsecs,
+        stat.mtime.secs,
+        stat.mtime.nsecs,
+        stat.dev,
+        stat.ino,
+        stat.uid,
+        stat.gid,
+        stat.size,
+    ] {
+        out.write_u32::<BigEndian>(*field)?;
+    }
+    Ok(())
+}
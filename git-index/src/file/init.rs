@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use quick_error::quick_error;
+
+use crate::{decode, File, State};
+
+quick_error! {
+    /// The error returned by [`File::at()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: std::io::Error) {
+            display("An IO error occurred while opening the index")
+            source(err)
+            from()
+        }
+        Decode(err: decode::Error) {
+            display("The index file could not be decoded")
+            source(err)
+            from()
+        }
+        ChecksumMismatch { expected: git_hash::ObjectId, actual: git_hash::ObjectId } {
+            display("The index checksum should have been {}, but was {}", expected, actual)
+        }
+    }
+}
+
+impl File {
+    /// Open the index file at `path` and decode it using `options`, verifying its checksum in the process.
+    ///
+    /// A trailer consisting of null bytes is accepted without verification, as written by git if `index.skipHash` is set.
+    pub fn at(path: impl AsRef<Path>, options: decode::Options) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let timestamp = std::fs::metadata(path)?.modified()?;
+
+        let (state, checksum) = State::from_bytes(&data, timestamp, options)?;
+        if !checksum.is_null() {
+            let actual = super::verify::checksum_of(&data[..data.len() - checksum.as_slice().len()], options.hash_kind);
+            if actual != checksum {
+                return Err(Error::ChecksumMismatch {
+                    expected: checksum,
+                    actual,
+                });
+            }
+        }
+        Ok(File {
+            state,
+            path: path.to_owned(),
+            checksum,
+        })
+    }
+}
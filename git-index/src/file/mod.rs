@@ -0,0 +1,45 @@
+use std::{
+    ops::{Deref, DerefMut},
+    path::Path,
+};
+
+use crate::{File, State};
+
+///
+pub mod init;
+///
+pub mod verify;
+///
+pub mod write;
+
+/// Access
+impl File {
+    /// The path from which the index was read or to which it is supposed to be written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The checksum over all content of the file as stored in its trailer, or as computed when it was last written.
+    pub fn checksum(&self) -> git_hash::ObjectId {
+        self.checksum
+    }
+
+    /// Consume this instance and return the contained state.
+    pub fn into_state(self) -> State {
+        self.state
+    }
+}
+
+impl Deref for File {
+    type Target = State;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state
+    }
+}
+
+impl DerefMut for File {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.state
+    }
+}
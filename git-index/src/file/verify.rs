@@ -0,0 +1,47 @@
+use quick_error::quick_error;
+
+use crate::File;
+
+quick_error! {
+    /// The error returned by [`File::verify_integrity()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: std::io::Error) {
+            display("Could not read index file to generate hash")
+            source(err)
+            from()
+        }
+        ChecksumMismatch { expected: git_hash::ObjectId, actual: git_hash::ObjectId } {
+            display("Index checksum should have been {}, but was {}", expected, actual)
+        }
+    }
+}
+
+pub(crate) fn checksum_of(data: &[u8], kind: git_hash::Kind) -> git_hash::ObjectId {
+    let mut hasher = git_features::hash::hasher(kind);
+    hasher.update(data);
+    hasher.digest()
+}
+
+impl File {
+    /// Read the file at our path once more and assure its content matches the checksum we know,
+    /// which is the one we read or wrote most recently.
+    pub fn verify_integrity(&self) -> Result<(), Error> {
+        let data = std::fs::read(&self.path)?;
+        let hash_len = self.checksum.as_slice().len();
+        let actual = if data.len() < hash_len {
+            checksum_of(&data, self.checksum.kind())
+        } else {
+            checksum_of(&data[..data.len() - hash_len], self.checksum.kind())
+        };
+        if actual == self.checksum {
+            Ok(())
+        } else {
+            Err(Error::ChecksumMismatch {
+                expected: self.checksum,
+                actual,
+            })
+        }
+    }
+}
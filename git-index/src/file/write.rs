@@ -0,0 +1,43 @@
+use quick_error::quick_error;
+
+use crate::{write, File};
+
+quick_error! {
+    /// The error returned by [`File::write()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: std::io::Error) {
+            display("An IO error occurred while writing the index")
+            source(err)
+            from()
+        }
+        AcquireLock(err: git_lock::acquire::Error) {
+            display("Could not acquire lock for index file")
+            source(err)
+            from()
+        }
+        CommitLock(err: git_lock::commit::Error<git_lock::File>) {
+            display("Could not commit lock for index file")
+            source(err)
+            from()
+        }
+    }
+}
+
+impl File {
+    /// Write the index to our path atomically by writing it into a lock file first, which is then moved into place.
+    ///
+    /// The version and checksum of this instance are updated to reflect what was written.
+    pub fn write(&mut self, options: write::Options) -> Result<(), Error> {
+        let mut lock =
+            git_lock::File::acquire_to_update_resource(&self.path, git_lock::acquire::Fail::Immediately, None)?;
+        let (version, checksum) = self.state.write_to(&mut lock, options)?;
+        lock.commit()?;
+
+        self.state.version = version;
+        self.state.timestamp = std::fs::metadata(&self.path)?.modified()?;
+        self.checksum = checksum;
+        Ok(())
+    }
+}
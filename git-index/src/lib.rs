@@ -1 +1,80 @@
+//! Read and write the git index file, also known as `dircache`.
+//!
+//! The index tracks the content of the next commit along with enough information about each file in the working tree
+//! to quickly determine whether it changed. It is represented by the [`State`], which can be decoded from bytes,
+//! or by the [`File`], which additionally knows where it was loaded from and how to write itself back atomically.
+//!
+//! Index versions 2, 3 and 4 are supported, including the path-prefix compression of version 4, along with
+//! the [extensions][extension] git writes. Unknown optional extensions are kept to be written back unchanged.
 #![forbid(unsafe_code, rust_2018_idioms)]
+#![deny(missing_docs)]
+
+use std::{path::PathBuf, time::SystemTime};
+
+///
+pub mod entry;
+pub use entry::Entry;
+
+///
+pub mod extension;
+
+///
+pub mod decode;
+///
+pub mod write;
+
+///
+pub mod file;
+
+///
+pub mod sparse;
+
+mod access;
+mod util;
+
+/// The version of an index file.
+#[derive(PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Version {
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::V2
+    }
+}
+
+/// An in-memory representation of the index, holding all entries sorted by path and stage.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct State {
+    /// The time at which the state was created, usually the modification time of the index file it was read from.
+    ///
+    /// It's used to detect entries whose stat information can't be trusted as they were changed within the same
+    /// time granularity of the file system, making them _racily clean_.
+    timestamp: SystemTime,
+    version: Version,
+    entries: Vec<Entry>,
+    tree: Option<extension::Tree>,
+    resolve_undo: Option<Vec<extension::resolve_undo::Path>>,
+    untracked: Option<extension::UntrackedCache>,
+    fs_monitor: Option<extension::FsMonitor>,
+    unknown_extensions: Vec<extension::Unknown>,
+    is_sparse: bool,
+}
+
+/// An index file on disk along with its decoded [`State`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct File {
+    state: State,
+    path: PathBuf,
+    /// The checksum of all bytes prior to the trailer, as stored in the file.
+    checksum: git_hash::ObjectId,
+}
+
+/// The size of the header of an index file, consisting of the signature, version and the amount of entries.
+const HEADER_LEN: usize = 4 + 4 + 4;
+const SIGNATURE: &[u8] = b"DIRC";
@@ -0,0 +1,343 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use git_object::immutable;
+use quick_error::quick_error;
+
+use crate::{
+    entry,
+    extension::{tree::NodeId, Tree},
+    Entry, State,
+};
+
+quick_error! {
+    /// The error returned when expanding sparse directories.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        NotASparseDirectory { index: usize } {
+            display("The entry at index {} is not a sparse directory", index)
+        }
+        NotFound { id: git_hash::ObjectId } {
+            display("The tree {} of a sparse directory could not be found", id)
+        }
+        Traverse(err: git_traverse::tree::breadthfirst::Error) {
+            display("The tree of a sparse directory could not be traversed")
+            source(err)
+            from()
+        }
+    }
+}
+
+/// Sparse indices
+impl State {
+    /// Return true if this is a sparse index, which may contain [sparse directories][Entry::is_sparse_directory()]
+    /// in place of all entries within them, with all of them excluded from the working tree.
+    ///
+    /// Expanding sparse directories doesn't change this, and sparse indices are collapsed again when written.
+    pub fn is_sparse(&self) -> bool {
+        self.is_sparse
+    }
+
+    /// Set whether or not this is a sparse index to `sparse`, affecting how it is written, see [`write_to()`][State::write_to()].
+    ///
+    /// Note that sparse directories must be expanded before writing a non-sparse index.
+    pub fn set_sparse(&mut self, sparse: bool) {
+        self.is_sparse = sparse;
+    }
+
+    /// Replace the sparse directory entry at `index` with entries for all files within it, obtaining trees with `find`,
+    /// and return the amount of entries it was expanded into.
+    ///
+    /// `find` writes the data of the tree with the given id into the provided buffer and returns an iterator over its
+    /// entries, or `None` if it couldn't be found.
+    ///
+    /// All new entries are excluded from the working tree. The cache-tree is updated to reflect the new entries.
+    pub fn expand_sparse_directory_at<Find>(&mut self, index: usize, mut find: Find) -> Result<usize, Error>
+    where
+        Find: for<'a> FnMut(&git_hash::oid, &'a mut Vec<u8>) -> Option<immutable::TreeIter<'a>>,
+    {
+        let dir = match self.entries.get(index) {
+            Some(dir) if dir.is_sparse_directory() => dir,
+            _ => return Err(Error::NotASparseDirectory { index }),
+        };
+        let (dir_path, tree_id) = (dir.path.clone(), dir.id);
+
+        let mut buf = Vec::new();
+        let root = find(&tree_id, &mut buf).ok_or(Error::NotFound { id: tree_id })?;
+        let mut recorder = git_traverse::tree::Recorder::default();
+        git_traverse::tree::breadthfirst(
+            root,
+            git_traverse::tree::breadthfirst::State::default(),
+            &mut find,
+            &mut recorder,
+        )?;
+
+        let mut trees = BTreeMap::<BString, NodeId>::new();
+        let mut expanded = Vec::new();
+        for record in recorder.records {
+            if record.mode.is_tree() {
+                trees.insert(
+                    record.filepath,
+                    NodeId {
+                        entry_count: 0,
+                        id: record.oid,
+                    },
+                );
+                continue;
+            }
+            let mut path = dir_path.clone();
+            path.push_str(&record.filepath);
+            expanded.push(Entry {
+                stat: Default::default(),
+                id: record.oid,
+                flags: entry::Flags::SKIP_WORKTREE,
+                mode: record.mode.into(),
+                path,
+            });
+        }
+        expanded.sort_by(|a, b| a.path.cmp(&b.path));
+        for entry in &expanded {
+            let mut path = &entry.path[dir_path.len()..];
+            while let Some(pos) = path.rfind_byte(b'/') {
+                path = &path[..pos];
+                if let Some(node) = trees.get_mut(path.as_bstr()) {
+                    node.entry_count += 1;
+                }
+            }
+        }
+
+        let num_expanded = expanded.len();
+        self.update_tree_for_expansion(dir_path.as_bstr(), tree_id, num_expanded, &trees);
+        if let Some(fs_monitor) = self.fs_monitor.as_mut() {
+            fs_monitor.entry_removed_at(index);
+            for new_index in index..index + num_expanded {
+                fs_monitor.entry_inserted_at(new_index);
+            }
+        }
+        self.entries.splice(index..index + 1, expanded);
+        Ok(num_expanded)
+    }
+
+    /// Expand the sparse directory containing the entry at `path`, if there is one, obtaining trees with `find` as described
+    /// in [`expand_sparse_directory_at()`][State::expand_sparse_directory_at()], and return true if a directory was expanded.
+    ///
+    /// This allows to lazily expand only the portions of a sparse index that are needed to find `path`.
+    pub fn expand_sparse_directory_containing<Find>(&mut self, path: &BStr, find: Find) -> Result<bool, Error>
+    where
+        Find: for<'a> FnMut(&git_hash::oid, &'a mut Vec<u8>) -> Option<immutable::TreeIter<'a>>,
+    {
+        let index = match self.sparse_directory_index_containing(path) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+        self.expand_sparse_directory_at(index, find)?;
+        Ok(true)
+    }
+
+    /// Expand all sparse directories, obtaining trees with `find` as described
+    /// in [`expand_sparse_directory_at()`][State::expand_sparse_directory_at()], to turn this into a full index.
+    pub fn expand_sparse_directories<Find>(&mut self, mut find: Find) -> Result<(), Error>
+    where
+        Find: for<'a> FnMut(&git_hash::oid, &'a mut Vec<u8>) -> Option<immutable::TreeIter<'a>>,
+    {
+        let mut index = 0;
+        while index < self.entries.len() {
+            if self.entries[index].is_sparse_directory() {
+                index += self.expand_sparse_directory_at(index, &mut find)?;
+            } else {
+                index += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the index of the sparse directory whose path is a prefix of `path`.
+    fn sparse_directory_index_containing(&self, path: &BStr) -> Option<usize> {
+        let mut end = path.len();
+        while let Some(pos) = path[..end].rfind_byte(b'/') {
+            let dir = &path[..=pos];
+            if let Some(index) = self.entry_index_by_path_and_stage(dir.as_bstr(), 0) {
+                return self.entries[index].is_sparse_directory().then(|| index);
+            }
+            end = pos;
+        }
+        None
+    }
+
+    /// Adjust the cache-tree after the sparse directory at `dir_path` pointing to `tree_id` was expanded into `num_expanded`
+    /// entries, with `trees` being all trees below it along with the amount of entries within them.
+    fn update_tree_for_expansion(
+        &mut self,
+        dir_path: &BStr,
+        tree_id: git_hash::ObjectId,
+        num_expanded: usize,
+        trees: &BTreeMap<BString, NodeId>,
+    ) {
+        let root = match self.tree.as_mut() {
+            Some(root) => root,
+            None => return,
+        };
+        let components: Vec<_> = match dir_path.split_last() {
+            Some((b'/', dir)) => dir.split_str("/").collect(),
+            _ => {
+                root.invalidate_path(dir_path);
+                return;
+            }
+        };
+        let node_matches = {
+            let mut node = &*root;
+            let mut ancestors_are_valid = node.id.is_some();
+            for component in &components {
+                match node.children.iter().find(|t| t.name == *component) {
+                    Some(child) => node = child,
+                    None => {
+                        ancestors_are_valid = false;
+                        break;
+                    }
+                }
+                ancestors_are_valid &= node.id.is_some();
+            }
+            ancestors_are_valid && node.id.map_or(false, |id| id.id == tree_id && id.entry_count == 1)
+        };
+        if !node_matches {
+            root.invalidate_path(dir_path);
+            return;
+        }
+
+        // The sparse directory entry is replaced by all entries it expanded into, which are none for an empty tree.
+        let mut node = root;
+        for component in &components {
+            let entry_count = &mut node.id.as_mut().expect("checked above").entry_count;
+            *entry_count = (*entry_count + num_expanded as u32).saturating_sub(1);
+            node = node
+                .children
+                .iter_mut()
+                .find(|t| t.name == *component)
+                .expect("checked above");
+        }
+        node.id = Some(NodeId {
+            entry_count: num_expanded as u32,
+            id: tree_id,
+        });
+        node.children = subtrees_of(b"".as_bstr(), trees);
+    }
+
+    /// Return our entries with sparse directories collapsed along with a matching cache-tree, for writing as sparse index,
+    /// or `None` if nothing can be collapsed.
+    ///
+    /// A directory is collapsed if it has a valid tree in the cache-tree and all entries within it are excluded from the
+    /// working tree. Like git, nothing is collapsed if there are conflicts.
+    pub(crate) fn collapsed_sparse_directories(&self) -> Option<(Vec<Cow<'_, Entry>>, Tree)> {
+        let root = self.tree.as_ref()?;
+        if !self.is_sparse || self.entries.iter().any(|e| e.stage() != 0) {
+            return None;
+        }
+        let mut tree = root.clone();
+        let mut collapse = Collapse {
+            entries: &self.entries,
+            pos: 0,
+            out: Vec::with_capacity(self.entries.len()),
+            path: BString::default(),
+            changed: false,
+        };
+        let removed = collapse.children(&mut tree);
+        if let Some(id) = tree.id.as_mut() {
+            id.entry_count -= removed;
+        }
+        if !collapse.changed {
+            return None;
+        }
+        let Collapse {
+            entries, pos, mut out, ..
+        } = collapse;
+        out.extend(entries[pos..].iter().map(Cow::Borrowed));
+        Some((out, tree))
+    }
+}
+
+struct Collapse<'a> {
+    entries: &'a [Entry],
+    /// The position of the first entry in `entries` not yet pushed to `out`.
+    pos: usize,
+    out: Vec<Cow<'a, Entry>>,
+    /// The path of the directory currently being processed, with trailing slash.
+    path: BString,
+    changed: bool,
+}
+
+impl<'a> Collapse<'a> {
+    /// Try to collapse all sub-directories of `node` in the order of their entries,
+    /// and return the amount of entries they shrunk by.
+    fn children(&mut self, node: &mut Tree) -> u32 {
+        let mut children: Vec<_> = node.children.iter_mut().collect();
+        children.sort_by(|a, b| a.name.iter().chain(b"/").cmp(b.name.iter().chain(b"/")));
+        children.into_iter().map(|child| self.directory(child)).sum()
+    }
+
+    /// Collapse the directory represented by `node` if possible, or try its sub-directories otherwise,
+    /// and return the amount of entries it shrunk by.
+    fn directory(&mut self, node: &mut Tree) -> u32 {
+        let previous_len = self.path.len();
+        self.path.push_str(&node.name);
+        self.path.push_byte(b'/');
+
+        let entries = self.entries;
+        let path = self.path.as_slice();
+        let start = self.pos + entries[self.pos..].partition_point(|e| e.path.as_slice() < path);
+        let end = start + entries[start..].partition_point(|e| e.path.starts_with(path));
+        let dir_entries = &entries[start..end];
+        let removed = match node.id {
+            Some(id)
+                if !dir_entries.is_empty()
+                    && id.entry_count as usize == dir_entries.len()
+                    && dir_entries
+                        .iter()
+                        .all(|e| e.flags.contains(entry::Flags::SKIP_WORKTREE)) =>
+            {
+                self.changed |= !(dir_entries.len() == 1 && dir_entries[0].is_sparse_directory());
+                self.out.extend(entries[self.pos..start].iter().map(Cow::Borrowed));
+                self.out.push(Cow::Owned(Entry {
+                    stat: Default::default(),
+                    id: id.id,
+                    flags: entry::Flags::SKIP_WORKTREE,
+                    mode: entry::Mode::Dir,
+                    path: self.path.clone(),
+                }));
+                self.pos = end;
+                node.id = Some(NodeId { entry_count: 1, ..id });
+                node.children.clear();
+                id.entry_count - 1
+            }
+            _ => {
+                let removed = self.children(node);
+                if let Some(id) = node.id.as_mut() {
+                    id.entry_count -= removed;
+                }
+                removed
+            }
+        };
+        self.path.truncate(previous_len);
+        removed
+    }
+}
+
+/// Build the cache-tree nodes of all trees directly below `parent` within `trees`, ordered like git does.
+fn subtrees_of(parent: &BStr, trees: &BTreeMap<BString, NodeId>) -> Vec<Tree> {
+    let mut prefix = BString::from(parent.to_vec());
+    if !prefix.is_empty() {
+        prefix.push_byte(b'/');
+    }
+    let mut children: Vec<_> = trees
+        .range::<BString, _>(prefix.clone()..)
+        .take_while(|(path, _)| path.starts_with(prefix.as_slice()))
+        .filter(|(path, _)| !path[prefix.len()..].contains(&b'/'))
+        .map(|(path, id)| Tree {
+            name: path[prefix.len()..].into(),
+            id: Some(*id),
+            children: subtrees_of(path.as_bstr(), trees),
+        })
+        .collect();
+    children.sort_by(|a, b| a.name.len().cmp(&b.name.len()).then_with(|| a.name.cmp(&b.name)));
+    children
+}
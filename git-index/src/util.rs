@@ -0,0 +1,102 @@
+use std::io;
+
+use byteorder::{BigEndian, ByteOrder};
+
+pub fn split_at_pos(data: &[u8], pos: usize) -> Option<(&[u8], &[u8])> {
+    if data.len() < pos {
+        return None;
+    }
+    Some(data.split_at(pos))
+}
+
+pub fn read_u32(data: &[u8]) -> Option<(u32, &[u8])> {
+    split_at_pos(data, 4).map(|(num, data)| (BigEndian::read_u32(num), data))
+}
+
+pub fn read_u64(data: &[u8]) -> Option<(u64, &[u8])> {
+    split_at_pos(data, 8).map(|(num, data)| (BigEndian::read_u64(num), data))
+}
+
+pub fn read_u16(data: &[u8]) -> Option<(u16, &[u8])> {
+    split_at_pos(data, 2).map(|(num, data)| (BigEndian::read_u16(num), data))
+}
+
+/// Decode the variable length integer used in index version 4 to encode the amount of bytes to strip from the previous path.
+///
+/// Unlike LEB128, each continuation adds one to the accumulated value to avoid redundant encodings, just like git's `varint.c`.
+pub fn read_varint(data: &[u8]) -> Option<(usize, &[u8])> {
+    let mut byte = *data.get(0)?;
+    let mut consumed = 1;
+    let mut value = (byte & 0x7f) as usize;
+    while byte & 0x80 != 0 {
+        byte = *data.get(consumed)?;
+        consumed += 1;
+        value = value.checked_add(1)?.checked_mul(1 << 7)? | (byte & 0x7f) as usize;
+    }
+    Some((value, &data[consumed..]))
+}
+
+/// The inverse of [`read_varint()`].
+pub fn write_varint(mut value: usize, mut out: impl io::Write) -> io::Result<()> {
+    let mut buf = [0u8; 16];
+    let mut pos = buf.len() - 1;
+    buf[pos] = (value & 0x7f) as u8;
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        pos -= 1;
+        buf[pos] = 0x80 | (value & 0x7f) as u8;
+        value >>= 7;
+    }
+    out.write_all(&buf[pos..])
+}
+
+/// A writer which counts the bytes written through it.
+pub struct Count<W> {
+    pub bytes: u64,
+    pub inner: W,
+}
+
+impl<W> Count<W> {
+    pub fn new(inner: W) -> Self {
+        Count { bytes: 0, inner }
+    }
+}
+
+impl<W> io::Write for Count<W>
+where
+    W: io::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_varint, write_varint};
+
+    #[test]
+    fn varint_round_trips() {
+        for value in [0, 1, 0x7f, 0x80, 0x3fff, 0x4000, 0x407f, 12345678, u32::MAX as usize] {
+            let mut buf = Vec::new();
+            write_varint(value, &mut buf).unwrap();
+            let (decoded, rest) = read_varint(&buf).expect("valid varint");
+            assert_eq!(decoded, value);
+            assert!(rest.is_empty(), "all bytes are consumed");
+        }
+    }
+
+    #[test]
+    fn varint_matches_git_encoding() {
+        let mut buf = Vec::new();
+        write_varint(128, &mut buf).unwrap();
+        assert_eq!(buf, [0x80, 0x00], "128 is encoded as (0 + 1) << 7 | 0");
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use byteorder::{BigEndian, WriteBytesExt};
+use git_features::hash;
+
+use crate::{
+    decode::{EXTENDED_FLAG, PATH_LEN_MASK},
+    entry,
+    extension::{
+        self, end_of_index_entry, fs_monitor, index_entry_offset_table, resolve_undo, sparse, tree, untracked_cache,
+    },
+    util, Entry, State, Version, SIGNATURE,
+};
+
+/// Options to define how to [write][State::write_to()] an index state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+    /// The kind of hash used to produce the checksum of the file.
+    pub hash_kind: git_hash::Kind,
+    /// If true, write the end-of-index-entry extension which allows readers to find all other extensions
+    /// without decoding the entries first. It's what git does if `index.recordEndOfIndexEntries` is set.
+    pub end_of_index_entry_extension: bool,
+    /// If larger than one, split the entries into up to this amount of blocks and record their offsets in the
+    /// index-entry-offset-table extension, allowing readers to decode the blocks in parallel.
+    /// It's what git does if `index.recordOffsetTable` is set, using `index.threads` as amount of blocks.
+    ///
+    /// Readers only use the table along with the [end-of-index-entry extension][Options::end_of_index_entry_extension].
+    pub offset_table_blocks: usize,
+}
+
+impl State {
+    /// Serialize this state to `out` and return the version that was actually written along with the checksum
+    /// of the data, which is also written as trailer.
+    ///
+    /// Just like git, the version is chosen automatically unless it is [`Version::V4`]:
+    /// [`Version::V3`] is used only if at least one entry has extended flags, and [`Version::V2`] otherwise.
+    ///
+    /// [Sparse indices][State::is_sparse()] are written with all directories collapsed that have a valid tree in the cache-tree
+    /// and whose entries are all excluded from the working tree, without affecting the entries of this instance.
+    pub fn write_to(
+        &self,
+        out: impl io::Write,
+        Options {
+            hash_kind,
+            end_of_index_entry_extension,
+            offset_table_blocks,
+        }: Options,
+    ) -> io::Result<(Version, git_hash::ObjectId)> {
+        let version = self.detect_required_version();
+        let collapsed = self.collapsed_sparse_directories();
+        let mut out = util::Count::new(io::BufWriter::with_capacity(8 * 4096, hash::Write::new(out, hash_kind)));
+
+        out.write_all(SIGNATURE)?;
+        out.write_u32::<BigEndian>(version as u32)?;
+        out.write_u32::<BigEndian>(match &collapsed {
+            Some((entries, _)) => entries.len(),
+            None => self.entries.len(),
+        } as u32)?;
+
+        let (offsets, tree, has_sparse_directories) = match &collapsed {
+            Some((entries, tree)) => (
+                write_entries(
+                    entries.iter().map(AsRef::as_ref),
+                    version,
+                    offset_table_blocks,
+                    &mut out,
+                )?,
+                Some(tree),
+                true,
+            ),
+            None => (
+                write_entries(self.entries.iter(), version, offset_table_blocks, &mut out)?,
+                self.tree.as_ref(),
+                self.entries.iter().any(Entry::is_sparse_directory),
+            ),
+        };
+
+        let offset_to_extensions = out.bytes as u32;
+        let mut written_extensions = Vec::new();
+        let mut buf = Vec::new();
+        let mut write_extension =
+            |signature: extension::Signature, encode: &dyn Fn(&mut Vec<u8>) -> io::Result<()>| -> io::Result<()> {
+                buf.clear();
+                encode(&mut buf)?;
+                extension::write(signature, &buf, &mut out)?;
+                written_extensions.push((signature, buf.len() as u32));
+                Ok(())
+            };
+        if !offsets.is_empty() {
+            write_extension(index_entry_offset_table::SIGNATURE, &|buf| {
+                index_entry_offset_table::write_to(&offsets, buf)
+            })?;
+        }
+        if let Some(tree) = tree {
+            write_extension(tree::SIGNATURE, &|buf| tree.write_to(buf))?;
+        }
+        if let Some(paths) = &self.resolve_undo {
+            write_extension(resolve_undo::SIGNATURE, &|buf| resolve_undo::write_to(paths, buf))?;
+        }
+        if let Some(untracked) = &self.untracked {
+            write_extension(untracked_cache::SIGNATURE, &|buf| untracked.write_to(buf))?;
+        }
+        let fs_monitor = match (&self.fs_monitor, &collapsed) {
+            (Some(fs_monitor), Some((entries, _))) => Some(Cow::Owned(
+                fs_monitor.for_collapsed_entries(&self.entries, entries.iter().map(AsRef::as_ref)),
+            )),
+            (fs_monitor, _) => fs_monitor.as_ref().map(Cow::Borrowed),
+        };
+        if let Some(fs_monitor) = &fs_monitor {
+            write_extension(fs_monitor::SIGNATURE, &|buf| fs_monitor.write_to(buf))?;
+        }
+        if self.is_sparse || has_sparse_directories {
+            write_extension(sparse::SIGNATURE, &|_buf| Ok(()))?;
+        }
+        for ext in &self.unknown_extensions {
+            write_extension(ext.signature, &|buf| buf.write_all(&ext.data))?;
+        }
+        if end_of_index_entry_extension {
+            end_of_index_entry::write_to(&mut out, hash_kind, offset_to_extensions, written_extensions)?;
+        }
+
+        let mut out = out.inner.into_inner()?;
+        let checksum = out.hash.digest();
+        out.inner.write_all(checksum.as_slice())?;
+        out.inner.flush()?;
+        Ok((version, checksum))
+    }
+
+    fn detect_required_version(&self) -> Version {
+        match self.version {
+            Version::V4 => Version::V4,
+            Version::V2 | Version::V3 => {
+                if self.entries.iter().any(|e| e.flags.is_extended()) {
+                    Version::V3
+                } else {
+                    Version::V2
+                }
+            }
+        }
+    }
+}
+
+/// Write all `entries` to `out`, splitting them into up to `num_blocks` blocks whose offsets are returned.
+fn write_entries<'a>(
+    entries: impl ExactSizeIterator<Item = &'a Entry>,
+    version: Version,
+    num_blocks: usize,
+    out: &mut util::Count<impl io::Write>,
+) -> io::Result<Vec<index_entry_offset_table::Offset>> {
+    let num_entries = entries.len();
+    let num_blocks = num_blocks.min(num_entries);
+    let entries_per_block = (num_blocks > 1).then(|| (num_entries + num_blocks - 1) / num_blocks);
+    let mut offsets = Vec::new();
+    let mut previous_path: &[u8] = &[];
+    for (index, entry) in entries.enumerate() {
+        let starts_block = entries_per_block.map_or(false, |per_block| index % per_block == 0);
+        if starts_block {
+            offsets.push(index_entry_offset_table::Offset {
+                from_beginning_of_file: out.bytes as u32,
+                num_entries: 0,
+            });
+        }
+        write_entry(entry, version, previous_path, starts_block && index != 0, &mut *out)?;
+        if let Some(offset) = offsets.last_mut() {
+            offset.num_entries += 1;
+        }
+        previous_path = entry.path.as_slice();
+    }
+    Ok(offsets)
+}
+
+/// Write `entry` to `out`, compressing its path relative to `previous_path` in version 4 unless `starts_new_block` is true.
+/// In that case, the path shares no prefix with the previous one to allow decoding it on its own, just like git does.
+fn write_entry(
+    entry: &Entry,
+    version: Version,
+    previous_path: &[u8],
+    starts_new_block: bool,
+    mut out: impl io::Write,
+) -> io::Result<()> {
+    let entry::Stat {
+        ctime,
+        mtime,
+        dev,
+        ino,
+        uid,
+        gid,
+        size,
+    } = entry.stat;
+    for field in &[
+        ctime.secs,
+        ctime.nsecs,
+        mtime.secs,
+        mtime.nsecs,
+        dev,
+        ino,
+        entry.mode.bits(),
+        uid,
+        gid,
+        size,
+    ] {
+        out.write_u32::<BigEndian>(*field)?;
+    }
+    out.write_all(entry.id.as_slice())?;
+
+    let path = entry.path.as_slice();
+    let is_extended = entry.flags.is_extended();
+    let flags = entry.flags.bits();
+    let path_len = path.len().min(PATH_LEN_MASK as usize) as u16;
+    out.write_u16::<BigEndian>((flags & 0xffff) as u16 | if is_extended { EXTENDED_FLAG } else { 0 } | path_len)?;
+    let mut path_offset = 10 * 4 + entry.id.as_slice().len() + 2;
+    if is_extended {
+        out.write_u16::<BigEndian>((flags >> 16) as u16)?;
+        path_offset += 2;
+    }
+
+    match version {
+        Version::V2 | Version::V3 => {
+            out.write_all(path)?;
+            let entry_len = (path_offset + path.len() + 8) & !7;
+            const PADDING: [u8; 8] = [0; 8];
+            out.write_all(&PADDING[..entry_len - path_offset - path.len()])?;
+        }
+        Version::V4 => {
+            let common_prefix_len = if starts_new_block {
+                0
+            } else {
+                previous_path
+                    .iter()
+                    .zip(path.iter())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            };
+            util::write_varint(previous_path.len() - common_prefix_len, &mut out)?;
+            out.write_all(&path[common_prefix_len..])?;
+            out.write_all(&[0])?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,261 @@
+use git_index::{
+    entry,
+    extension::{fs_monitor, resolve_undo, tree},
+};
+use git_testtools::bstr::ByteSlice;
+
+use crate::{fixture_repo_with_extensions, fixture_repo_with_sha256_extensions, index_path};
+
+fn rev_parse(repo: &std::path::Path, spec: &str) -> git_hash::ObjectId {
+    let output = std::process::Command::new("git")
+        .args(&["rev-parse", spec])
+        .current_dir(repo)
+        .output()
+        .expect("git can be executed");
+    assert!(output.status.success(), "{}", output.stderr.as_bstr());
+    git_testtools::hex_to_id(output.stdout.trim().to_str().expect("valid UTF-8"))
+}
+
+fn file(repo: &std::path::Path) -> git_index::File {
+    git_index::File::at(index_path(repo), Default::default()).expect("valid index")
+}
+
+#[test]
+fn tree() {
+    let repo = fixture_repo_with_extensions("2");
+    let file = file(&repo);
+    let root = file.tree().expect("present");
+    assert_eq!(root.name, "", "the root has no name");
+    assert_eq!(root.id, None, "the root was invalidated when the conflict was resolved");
+
+    let dir = root.find("dir".into()).expect("present");
+    assert_eq!(
+        dir.id,
+        Some(tree::NodeId {
+            entry_count: 2,
+            id: rev_parse(&repo, "HEAD:dir")
+        })
+    );
+    let sub = root.find("dir/sub".into()).expect("present");
+    assert_eq!(
+        sub.id,
+        Some(tree::NodeId {
+            entry_count: 1,
+            id: rev_parse(&repo, "HEAD:dir/sub")
+        })
+    );
+    assert!(sub.children.is_empty());
+    assert!(root.find("dir/missing".into()).is_none());
+}
+
+#[test]
+fn resolve_undo() {
+    let repo = fixture_repo_with_extensions("2");
+    let file = file(&repo);
+    let stage = |spec: &str| {
+        Some(resolve_undo::Stage {
+            mode: entry::Mode::File,
+            id: rev_parse(&repo, spec),
+        })
+    };
+    assert_eq!(
+        file.resolve_undo().expect("present"),
+        &[resolve_undo::Path {
+            name: "a".into(),
+            stages: [stage("HEAD~1:a"), stage("HEAD:a"), stage("other:a")]
+        }]
+    );
+}
+
+#[test]
+fn untracked_cache() {
+    let repo = fixture_repo_with_extensions("2");
+    let file = file(&repo);
+    let cache = file.untracked_cache().expect("present");
+    assert!(cache.identifier.starts_with(b"Location "));
+    assert_eq!(cache.exclude_filename_per_dir, ".gitignore");
+
+    let root = cache.root.as_ref().expect("untracked files were collected");
+    assert_eq!(root.untracked_entries, vec!["udir/"]);
+    assert_eq!(
+        root.sub_directories
+            .iter()
+            .map(|d| d.name.as_bstr())
+            .collect::<Vec<_>>(),
+        vec!["dir", "udir"]
+    );
+    assert!(root.stat.is_some(), "the root was checked and is valid");
+    let udir = &root.sub_directories[1];
+    assert!(
+        udir.check_only,
+        "only the presence of untracked files in untracked directories is checked"
+    );
+    assert_eq!(root.sub_directories[0].sub_directories[0].name, "sub");
+}
+
+#[test]
+fn fs_monitor() {
+    let repo = fixture_repo_with_extensions("2");
+    let file = file(&repo);
+    let fs_monitor = file.fs_monitor().expect("present");
+    assert_eq!(fs_monitor.token, fs_monitor::Token::V2 { token: "token".into() });
+    assert!(
+        (0..file.entries().len()).all(|index| fs_monitor.is_dirty(index)),
+        "the hook claims everything changed"
+    );
+    assert!(!fs_monitor.is_dirty(file.entries().len()));
+}
+
+#[test]
+fn entries_are_decoded_in_blocks_using_the_offset_table() -> crate::Result {
+    for version in &["2", "4"] {
+        let path = index_path(&fixture_repo_with_extensions(version));
+        let single_threaded = git_index::File::at(
+            &path,
+            git_index::decode::Options {
+                thread_limit: Some(1),
+                ..Default::default()
+            },
+        )?;
+        let multi_threaded = git_index::File::at(
+            &path,
+            git_index::decode::Options {
+                thread_limit: Some(2),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(single_threaded, multi_threaded);
+        assert_eq!(
+            multi_threaded
+                .entries()
+                .iter()
+                .map(|e| e.path.as_bstr())
+                .collect::<Vec<_>>(),
+            vec!["a", "dir/b", "dir/sub/c"]
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn sha256_ids_are_decoded_and_written_like_git_does() -> crate::Result {
+    let repo = fixture_repo_with_sha256_extensions();
+    let path = index_path(&repo);
+    let file = git_index::File::at(
+        &path,
+        git_index::decode::Options {
+            hash_kind: git_hash::Kind::Sha256,
+            thread_limit: Some(2),
+        },
+    )?;
+    assert_eq!(file.checksum().kind(), git_hash::Kind::Sha256);
+    assert_eq!(
+        file.entries().iter().map(|e| e.id).collect::<Vec<_>>(),
+        crate::git_ls_files(&repo)
+            .into_iter()
+            .map(|(_, id, _, _)| id)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        file.tree()
+            .and_then(|root| root.find("dir".into()))
+            .and_then(|dir| dir.id),
+        Some(tree::NodeId {
+            entry_count: 2,
+            id: rev_parse(&repo, "HEAD:dir")
+        })
+    );
+    assert_eq!(
+        file.resolve_undo().expect("present")[0].stages[2].map(|stage| stage.id),
+        Some(rev_parse(&repo, "other:a"))
+    );
+    assert!(file.untracked_cache().is_some());
+
+    let mut actual = Vec::new();
+    file.write_to(
+        &mut actual,
+        git_index::write::Options {
+            hash_kind: git_hash::Kind::Sha256,
+            end_of_index_entry_extension: true,
+            offset_table_blocks: 2,
+        },
+    )?;
+    assert_eq!(actual, std::fs::read(&path)?, "it's written exactly as git wrote it");
+    Ok(())
+}
+
+#[test]
+fn mutations_invalidate_extensions() {
+    let mut file = file(&fixture_repo_with_extensions("2"));
+    let mut entry = file
+        .entry_by_path_and_stage("dir/b".into(), 0)
+        .expect("present")
+        .clone();
+    entry.path = "dir/sub/new".into();
+    assert!(file.upsert_entry(entry).is_none());
+
+    let tree = file.tree().expect("still present");
+    assert_eq!(tree.find("dir".into()).expect("present").id, None);
+    assert_eq!(tree.find("dir/sub".into()).expect("present").id, None);
+
+    let untracked = file
+        .untracked_cache()
+        .expect("still present")
+        .root
+        .as_ref()
+        .expect("present");
+    assert!(untracked.stat.is_none());
+    assert!(untracked.untracked_entries.is_empty());
+    assert!(untracked.sub_directories[0].stat.is_none(), "dir was invalidated");
+    assert!(
+        untracked.sub_directories[0].sub_directories[0].stat.is_none(),
+        "dir/sub too"
+    );
+    assert!(untracked.sub_directories[1].stat.is_some(), "udir is unaffected");
+
+    assert_eq!(
+        file.fs_monitor().expect("present").entry_dirty.len(),
+        file.entries().len(),
+        "the new entry is tracked as dirty"
+    );
+    let index = file.entry_index_by_path_and_stage("a".into(), 0).expect("present");
+    file.remove_entry_at(index);
+    assert_eq!(
+        file.fs_monitor().expect("present").entry_dirty.len(),
+        file.entries().len()
+    );
+}
+
+#[test]
+fn unknown_optional_extensions_are_kept_and_mandatory_ones_are_rejected() -> crate::Result {
+    let data = std::fs::read(index_path(&crate::fixture_repo(&["2"])))?;
+    let with_extension = |signature: &[u8; 4]| {
+        let mut data = data[..data.len() - 20].to_vec();
+        data.extend_from_slice(signature);
+        data.extend_from_slice(&3u32.to_be_bytes());
+        data.extend_from_slice(b"foo");
+        let mut hasher = git_features::hash::hasher(git_hash::Kind::Sha1);
+        hasher.update(&data);
+        data.extend_from_slice(hasher.digest().as_slice());
+        data
+    };
+
+    let data = with_extension(b"ZZZZ");
+    let (state, _checksum) = git_index::State::from_bytes(&data, std::time::SystemTime::now(), Default::default())?;
+    assert_eq!(
+        state.unknown_extensions(),
+        &[git_index::extension::Unknown {
+            signature: *b"ZZZZ",
+            data: b"foo".to_vec()
+        }]
+    );
+    let mut written = Vec::new();
+    state.write_to(&mut written, Default::default())?;
+    assert_eq!(written, data, "unknown extensions are written back unchanged");
+
+    assert!(matches!(
+        git_index::State::from_bytes(&with_extension(b"zzzz"), std::time::SystemTime::now(), Default::default()),
+        Err(git_index::decode::Error::UnknownMandatoryExtension { signature }) if &signature == b"zzzz"
+    ));
+    Ok(())
+}
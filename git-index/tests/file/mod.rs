@@ -0,0 +1,155 @@
+mod init {
+    use git_index::{entry, Version};
+
+    use crate::{fixture_repo, git_ls_files, index_path};
+
+    fn assert_entries_match_git(repo: &std::path::Path, file: &git_index::File) {
+        let expected = git_ls_files(repo);
+        assert_eq!(file.entries().len(), expected.len());
+        for (entry, (mode, id, stage, path)) in file.entries().iter().zip(expected) {
+            assert_eq!(entry.mode.bits(), mode);
+            assert_eq!(entry.id, id);
+            assert_eq!(entry.stage(), stage);
+            assert_eq!(entry.path, path);
+        }
+    }
+
+    #[test]
+    fn read_v2_without_extended_flags() -> crate::Result {
+        let repo = fixture_repo(&["2"]);
+        let file = git_index::File::at(index_path(&repo), Default::default())?;
+        assert_eq!(file.version(), Version::V2);
+        assert_entries_match_git(&repo, &file);
+
+        let exe = file.entry_by_path_and_stage("dir/exe".into(), 0).expect("present");
+        assert_eq!(exe.mode, entry::Mode::FileExecutable);
+        assert_eq!(exe.stat.size, 4);
+        assert_eq!(
+            file.entry_by_path_and_stage("link".into(), 0).expect("present").mode,
+            entry::Mode::Symlink
+        );
+        assert!(file.entries().iter().all(|e| e.flags.bits() == 0));
+        Ok(())
+    }
+
+    #[test]
+    fn read_v3_with_extended_flags() -> crate::Result {
+        let repo = fixture_repo(&["3", "extended"]);
+        let file = git_index::File::at(index_path(&repo), Default::default())?;
+        assert_eq!(file.version(), Version::V3);
+        assert_entries_match_git(&repo, &file);
+
+        let skipped = file.entry_by_path_and_stage("dir/b".into(), 0).expect("present");
+        assert!(skipped.flags.contains(entry::Flags::SKIP_WORKTREE));
+        let intended = file.entry_by_path_and_stage("intended".into(), 0).expect("present");
+        assert!(intended.flags.contains(entry::Flags::INTENT_TO_ADD));
+        assert!(!intended.flags.contains(entry::Flags::SKIP_WORKTREE));
+        Ok(())
+    }
+
+    #[test]
+    fn read_v4_with_path_compression() -> crate::Result {
+        for args in &[&["4"][..], &["4", "extended"]] {
+            let repo = fixture_repo(args);
+            let file = git_index::File::at(index_path(&repo), Default::default())?;
+            assert_eq!(file.version(), Version::V4);
+            assert_entries_match_git(&repo, &file);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_checksum_is_detected() -> crate::Result {
+        let repo = fixture_repo(&["2"]);
+        let mut data = std::fs::read(index_path(&repo))?;
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        let tmp = git_testtools::tempfile::tempdir()?;
+        let path = tmp.path().join("index");
+        std::fs::write(&path, data)?;
+        assert!(matches!(
+            git_index::File::at(&path, Default::default()),
+            Err(git_index::file::init::Error::ChecksumMismatch { .. })
+        ));
+        Ok(())
+    }
+}
+
+mod write {
+    use git_index::{entry, Version};
+
+    use crate::{fixture_repo, fixture_repo_with_extensions, git_ls_files, index_path};
+
+    #[test]
+    fn round_trips_byte_for_byte() -> crate::Result {
+        for args in &[&["2"][..], &["3", "extended"], &["4"], &["4", "extended"]] {
+            let path = index_path(&fixture_repo(args));
+            let expected = std::fs::read(&path)?;
+            let file = git_index::File::at(&path, Default::default())?;
+
+            let mut actual = Vec::new();
+            let (version, checksum) = file.write_to(&mut actual, Default::default())?;
+            assert_eq!(version, file.version());
+            assert_eq!(checksum, file.checksum());
+            assert_eq!(actual, expected, "{:?} must be written exactly as git wrote it", args);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn round_trips_extensions_byte_for_byte() -> crate::Result {
+        for version in &["2", "4"] {
+            let path = index_path(&fixture_repo_with_extensions(version));
+            let expected = std::fs::read(&path)?;
+            let file = git_index::File::at(&path, Default::default())?;
+
+            let mut actual = Vec::new();
+            let (_, checksum) = file.write_to(
+                &mut actual,
+                git_index::write::Options {
+                    end_of_index_entry_extension: true,
+                    offset_table_blocks: 2,
+                    ..Default::default()
+                },
+            )?;
+            assert_eq!(checksum, file.checksum());
+            assert_eq!(actual, expected, "v{} must be written exactly as git wrote it", version);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn v3_is_downgraded_to_v2_without_extended_flags() -> crate::Result {
+        let file = git_index::File::at(index_path(&fixture_repo(&["3", "extended"])), Default::default())?;
+        let mut state = file.into_state();
+        for entry in state.entries_mut() {
+            entry.flags.remove(entry::Flags::SKIP_WORKTREE);
+            entry.flags.remove(entry::Flags::INTENT_TO_ADD);
+        }
+        let (version, _) = state.write_to(std::io::sink(), Default::default())?;
+        assert_eq!(version, Version::V2);
+        Ok(())
+    }
+
+    #[test]
+    fn in_place_with_lock_file_is_readable_by_git() -> crate::Result {
+        let repo = git_testtools::scripted_fixture_repo_writable_with_args("make_index.sh", Some("2"))?;
+        let expected = git_ls_files(repo.path());
+        let mut file = git_index::File::at(index_path(repo.path()), Default::default())?;
+        file.set_version(Version::V4);
+        let index = file.entry_index_by_path_and_stage("a".into(), 0).expect("present");
+        let removed = file.remove_entry_at(index);
+        assert!(file.upsert_entry(removed).is_none(), "the entry was re-added");
+        file.write(Default::default())?;
+
+        assert_eq!(file.version(), Version::V4);
+        file.verify_integrity()?;
+        assert_eq!(git_ls_files(repo.path()), expected);
+        assert_eq!(
+            git_index::File::at(index_path(repo.path()), Default::default())?,
+            file,
+            "reading what we wrote yields the same state"
+        );
+        Ok(())
+    }
+}
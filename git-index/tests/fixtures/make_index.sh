@@ -0,0 +1,28 @@
+#!/bin/bash
+set -eu -o pipefail
+
+version=${1:?need index version}
+extended_flags=${2:-}
+
+git init -q
+git config commit.gpgsign false
+git config index.recordEndOfIndexEntries false
+git config index.recordOffsetTable false
+
+mkdir -p dir/sub
+echo a > a
+echo b > dir/b
+echo c > dir/sub/c
+echo d > dir/sub/d
+echo exe > dir/exe && chmod +x dir/exe
+ln -s a link
+touch empty
+
+git add .
+git update-index --index-version "$version"
+
+if [ -n "$extended_flags" ]; then
+  git update-index --skip-worktree dir/b
+  echo intended > intended
+  git add --intent-to-add intended
+fi
@@ -0,0 +1,42 @@
+#!/bin/bash
+set -eu -o pipefail
+
+version=${1:-2}
+object_format=${2:-sha1}
+
+git init -q --object-format="$object_format"
+git config commit.gpgsign false
+git config core.untrackedCache true
+git config index.threads 2
+git config index.recordEndOfIndexEntries true
+git config index.recordOffsetTable true
+
+cat > .git/fsmonitor-hook <<'HOOK'
+#!/bin/bash
+printf 'token\0/\0'
+HOOK
+chmod +x .git/fsmonitor-hook
+git config core.fsmonitor .git/fsmonitor-hook
+git config core.fsmonitorHookVersion 2
+
+mkdir -p dir/sub udir
+echo a > a
+echo b > dir/b
+echo c > dir/sub/c
+echo untracked > udir/untracked
+git add a dir
+git commit -q -m "base"
+
+git checkout -q -b other
+echo other > a
+git commit -q -am "other"
+git checkout -q -
+echo main > a
+git commit -q -am "main"
+git merge -q other || true
+echo resolved > a
+git add a
+
+git status --porcelain >/dev/null
+git update-index --index-version "$version"
+git update-index --force-write-index
@@ -0,0 +1,21 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config index.recordEndOfIndexEntries false
+git config index.recordOffsetTable false
+
+mkdir -p in/deep out/sub out2
+echo a > a
+echo b > in/b
+echo c > in/deep/c
+echo d > out/d
+echo e > out/sub/e
+echo f > out2/f
+
+git add .
+git commit -q -m "initial"
+
+git sparse-checkout init --cone --sparse-index
+git sparse-checkout set in
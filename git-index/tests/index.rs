@@ -0,0 +1,64 @@
+use std::path::{Path, PathBuf};
+
+use git_testtools::bstr::ByteSlice;
+
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+mod extension;
+mod file;
+mod sparse;
+
+pub fn fixture_repo(args: &[&'static str]) -> PathBuf {
+    git_testtools::scripted_fixture_repo_read_only_with_args("make_index.sh", args.iter().copied())
+        .expect("fixture script succeeds")
+}
+
+pub fn fixture_repo_with_extensions(version: &'static str) -> PathBuf {
+    git_testtools::scripted_fixture_repo_read_only_with_args("make_index_with_extensions.sh", Some(version))
+        .expect("fixture script succeeds")
+}
+
+pub fn fixture_repo_with_sha256_extensions() -> PathBuf {
+    git_testtools::scripted_fixture_repo_read_only_with_args(
+        "make_index_with_extensions.sh",
+        ["2", "sha256"].iter().copied(),
+    )
+    .expect("fixture script succeeds")
+}
+
+pub fn fixture_repo_with_sparse_index() -> PathBuf {
+    git_testtools::scripted_fixture_repo_read_only("make_sparse_index.sh").expect("fixture script succeeds")
+}
+
+pub fn index_path(repo: &Path) -> PathBuf {
+    repo.join(".git").join("index")
+}
+
+/// Return `(mode, id, stage, path)` for each entry in the index of `repo` as listed by `git ls-files --stage`.
+pub fn git_ls_files(repo: &Path) -> Vec<(u32, git_hash::ObjectId, u32, String)> {
+    git_ls_files_with_args(repo, &[])
+}
+
+/// Like [`git_ls_files()`], but passes `args` to `git ls-files` as well.
+pub fn git_ls_files_with_args(repo: &Path, args: &[&str]) -> Vec<(u32, git_hash::ObjectId, u32, String)> {
+    let output = std::process::Command::new("git")
+        .args(&["ls-files", "--stage"])
+        .args(args)
+        .current_dir(repo)
+        .output()
+        .expect("git can be executed");
+    assert!(output.status.success(), "{}", output.stderr.as_bstr());
+    output
+        .stdout
+        .lines()
+        .map(|line| {
+            let line = line.to_str().expect("valid UTF-8");
+            let (info, path) = line.split_at(line.find('\t').expect("tab separator"));
+            let mut tokens = info.split(' ');
+            let mode = u32::from_str_radix(tokens.next().expect("mode"), 8).expect("octal mode");
+            let id = git_testtools::hex_to_id(tokens.next().expect("id"));
+            let stage = tokens.next().expect("stage").parse().expect("numeric stage");
+            (mode, id, stage, path[1..].to_owned())
+        })
+        .collect()
+}
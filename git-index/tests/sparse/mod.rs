@@ -0,0 +1,145 @@
+use git_index::{entry, extension};
+use git_odb::{linked::Store, pack, FindExt};
+
+use crate::{fixture_repo_with_sparse_index, git_ls_files, git_ls_files_with_args, index_path};
+
+fn db(repo: &std::path::Path) -> Store {
+    Store::at(repo.join(".git").join("objects"), git_hash::Kind::Sha1).expect("valid object database")
+}
+
+fn assert_entries_match(file: &git_index::File, expected: Vec<(u32, git_hash::ObjectId, u32, String)>) {
+    assert_eq!(
+        file.entries()
+            .iter()
+            .map(|e| (e.mode.bits(), e.id, e.stage(), e.path.to_string()))
+            .collect::<Vec<_>>(),
+        expected
+    );
+}
+
+#[test]
+fn read_sparse_directories() -> crate::Result {
+    let repo = fixture_repo_with_sparse_index();
+    let file = git_index::File::at(index_path(&repo), Default::default())?;
+    assert!(file.is_sparse());
+    assert_entries_match(&file, git_ls_files_with_args(&repo, &["--sparse"]));
+
+    let out = file.entry_by_path_and_stage("out/".into(), 0).expect("present");
+    assert!(out.is_sparse_directory());
+    assert_eq!(out.mode, entry::Mode::Dir);
+    assert!(out.flags.contains(entry::Flags::SKIP_WORKTREE));
+    assert_eq!(
+        file.entries().iter().filter(|e| e.is_sparse_directory()).count(),
+        2,
+        "out/ and out2/"
+    );
+    Ok(())
+}
+
+#[test]
+fn expand_lazily_and_collapse_on_write() -> crate::Result {
+    let repo = fixture_repo_with_sparse_index();
+    let db = db(&repo);
+    let path = index_path(&repo);
+    let original = std::fs::read(&path)?;
+    let mut file = git_index::File::at(&path, Default::default())?;
+
+    assert!(!file.expand_sparse_directory_containing("in/b".into(), |oid, buf| db
+        .find_existing_tree_iter(oid, buf, &mut pack::cache::Never)
+        .ok())?);
+    assert!(
+        file.expand_sparse_directory_containing("out/sub/e".into(), |oid, buf| db
+            .find_existing_tree_iter(oid, buf, &mut pack::cache::Never)
+            .ok())?
+    );
+    assert!(file.entry_by_path_and_stage("out/".into(), 0).is_none());
+    let e = file.entry_by_path_and_stage("out/sub/e".into(), 0).expect("expanded");
+    assert_eq!(e.mode, entry::Mode::File);
+    assert!(e.flags.contains(entry::Flags::SKIP_WORKTREE));
+    assert!(
+        file.entry_by_path_and_stage("out2/".into(), 0)
+            .expect("present")
+            .is_sparse_directory(),
+        "other directories stay collapsed"
+    );
+    assert!(file.is_sparse(), "the index remains sparse");
+    assert_eq!(
+        file.tree()
+            .expect("present")
+            .find("out/sub".into())
+            .expect("expanded")
+            .id
+            .map(|id| id.entry_count),
+        Some(1),
+        "the cache-tree learns about the expanded trees"
+    );
+
+    let mut written = Vec::new();
+    file.write_to(&mut written, Default::default())?;
+    assert_eq!(
+        written, original,
+        "expanded directories are collapsed again when writing"
+    );
+    Ok(())
+}
+
+#[test]
+fn fs_monitor_dirty_bits_follow_collapsed_entries() -> crate::Result {
+    let repo = fixture_repo_with_sparse_index();
+    let db = db(&repo);
+    let mut file = git_index::File::at(index_path(&repo), Default::default())?;
+    assert!(
+        file.expand_sparse_directory_containing("out/sub/e".into(), |oid, buf| db
+            .find_existing_tree_iter(oid, buf, &mut pack::cache::Never)
+            .ok())?
+    );
+    let dirty_path = "out/sub/e";
+    let entry_dirty = file.entries().iter().map(|e| e.path == dirty_path).collect();
+    file.set_fs_monitor(Some(extension::FsMonitor {
+        token: extension::fs_monitor::Token::V1 { nanos_since_1970: 42 },
+        entry_dirty,
+    }));
+
+    let mut written = Vec::new();
+    file.write_to(&mut written, Default::default())?;
+    let (state, _checksum) = git_index::State::from_bytes(&written, std::time::SystemTime::now(), Default::default())?;
+    let fs_monitor = state.fs_monitor().expect("present");
+    assert_eq!(
+        state
+            .entries()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| fs_monitor.is_dirty(*index))
+            .map(|(_, e)| e.path.to_string())
+            .collect::<Vec<_>>(),
+        vec!["out/"],
+        "the collapsed directory is dirty as it contains a dirty entry"
+    );
+    Ok(())
+}
+
+#[test]
+fn expand_all() -> crate::Result {
+    let repo = fixture_repo_with_sparse_index();
+    let db = db(&repo);
+    let mut file = git_index::File::at(index_path(&repo), Default::default())?;
+    file.expand_sparse_directories(|oid, buf| db.find_existing_tree_iter(oid, buf, &mut pack::cache::Never).ok())?;
+    assert!(file.entries().iter().all(|e| !e.is_sparse_directory()));
+    assert_entries_match(&file, git_ls_files(&repo));
+
+    let mut written = Vec::new();
+    file.write_to(&mut written, Default::default())?;
+    assert_eq!(written, std::fs::read(index_path(&repo))?);
+
+    file.set_sparse(false);
+    let mut written = Vec::new();
+    file.write_to(&mut written, Default::default())?;
+    let (state, _checksum) = git_index::State::from_bytes(&written, std::time::SystemTime::now(), Default::default())?;
+    assert!(
+        !state.is_sparse(),
+        "full indices have no sparse directories and no sdir extension"
+    );
+    assert_entries_match(&file, git_ls_files(&repo));
+    assert_eq!(state.entries(), file.entries());
+    Ok(())
+}
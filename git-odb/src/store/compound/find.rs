@@ -1,3 +1,5 @@
+use std::sync::atomic::Ordering;
+
 use git_pack::data;
 
 use crate::{
@@ -13,6 +15,10 @@ pub enum Error {
     Loose(#[from] loose::find::Error),
     #[error("An error occurred while obtaining an object from the packed object store")]
     Pack(#[from] pack::data::decode_entry::Error),
+    #[error("The multi-pack index refers to pack {pack_index}, but only {num_packs} packs are associated with it")]
+    CorruptMultiIndex { pack_index: u32, num_packs: usize },
+    #[error("The packs could not be refreshed after the object wasn't found")]
+    Refresh(#[from] compound::init::Error),
 }
 
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
@@ -25,6 +31,9 @@ impl compound::Store {
     /// Find an object as identified by [`ObjectId`][git_hash::ObjectId] and store its data in full in the provided `buffer`.
     /// This will search the object in all contained object databases.
     /// Use a `pack_cache` to accelerate pack access by reducing the amount of work duplication, or [`pack::cache::Never`] to disable any caching.
+    ///
+    /// If the object can't be found, the packs are [refreshed][compound::Store::refresh()] once to pick up packs written
+    /// by other processes.
     pub fn find<'a>(
         &self,
         id: impl AsRef<git_hash::oid>,
@@ -32,16 +41,23 @@ impl compound::Store {
         pack_cache: &mut impl pack::cache::DecodeEntry,
     ) -> Result<Option<data::Object<'a>>, Error> {
         let id = id.as_ref();
-        for bundle in &self.bundles {
-            if let Some(idx) = find_pack_index(bundle, id) {
-                let object = bundle.get_object_by_index(idx, buffer, pack_cache)?;
-                return Ok(Some(object));
+        let mut refreshed = false;
+        loop {
+            let packs = self.packs();
+            if let Some(location) = self.internal_find_packed(&packs, id)? {
+                return packs.bundles[location.bundle_index]
+                    .get_object_by_index(location.entry_index, buffer, pack_cache)
+                    .map(Some)
+                    .map_err(Into::into);
             }
+            if self.loose.contains(id) {
+                return self.loose.find(id, buffer).map_err(Into::into);
+            }
+            if refreshed || !self.refresh()? {
+                return Ok(None);
+            }
+            refreshed = true;
         }
-        if self.loose.contains(id) {
-            return self.loose.find(id, buffer).map_err(Into::into);
-        }
-        Ok(None)
     }
 
     /// Internal-use function to look up a packed object index or loose object.
@@ -49,34 +65,60 @@ impl compound::Store {
     /// (The polonius borrow-checker would support this via the locate
     /// function, so this can be [simplified](https://github.com/Byron/gitoxide/blob/0c5f4043da4615820cb180804a81c2d4fe75fe5e/git-odb/src/compound/locate.rs#L47)
     /// once polonius is stable.)
-    pub(crate) fn internal_find_packed(&self, id: impl AsRef<git_hash::oid>) -> Option<PackLocation> {
+    pub(crate) fn internal_find_packed(
+        &self,
+        packs: &compound::Packs,
+        id: impl AsRef<git_hash::oid>,
+    ) -> Result<Option<PackLocation>, Error> {
         let id = id.as_ref();
-        for (bundle_index, bundle) in self.bundles.iter().enumerate() {
-            if let Some(idx) = find_pack_index(bundle, id) {
-                return Some(PackLocation {
+        let find_in_bundle = |bundle_index: usize| {
+            packs.bundles[bundle_index]
+                .index
+                .lookup(id)
+                .map(|entry_index| PackLocation {
                     bundle_index,
-                    entry_index: idx,
-                });
+                    entry_index,
+                })
+        };
+        Ok(match &packs.multi_index {
+            Some(multi_index) => {
+                let location =
+                    match multi_index.file.lookup(id) {
+                        Some(index) => {
+                            let (pack_index, _pack_offset) = multi_index.file.pack_index_and_offset_at_index(index);
+                            let bundle_index = *multi_index.bundle_indices.get(pack_index as usize).ok_or(
+                                Error::CorruptMultiIndex {
+                                    pack_index,
+                                    num_packs: multi_index.bundle_indices.len(),
+                                },
+                            )?;
+                            find_in_bundle(bundle_index)
+                        }
+                        None => None,
+                    };
+                location.or_else(|| {
+                    multi_index
+                        .uncovered_bundle_indices
+                        .iter()
+                        .find_map(|bundle_index| find_in_bundle(*bundle_index))
+                })
             }
-        }
-        None
-    }
-
-    pub(crate) fn internal_get_packed_object_by_index<'a>(
-        &self,
-        pack_index: usize,
-        object_index: u32,
-        buffer: &'a mut Vec<u8>,
-        pack_cache: &mut impl pack::cache::DecodeEntry,
-    ) -> Result<data::Object<'a>, pack::data::decode_entry::Error> {
-        self.bundles[pack_index].get_object_by_index(object_index, buffer, pack_cache)
+            None if self.pack_order == compound::PackOrder::MostRecentlyHit => {
+                let last_pack_hit = self.last_pack_hit.load(Ordering::Relaxed);
+                if last_pack_hit < packs.bundles.len() {
+                    if let Some(location) = find_in_bundle(last_pack_hit) {
+                        return Ok(Some(location));
+                    }
+                }
+                let location = (0..packs.bundles.len())
+                    .filter(|bundle_index| *bundle_index != last_pack_hit)
+                    .find_map(find_in_bundle);
+                if let Some(location) = &location {
+                    self.last_pack_hit.store(location.bundle_index, Ordering::Relaxed);
+                }
+                location
+            }
+            None => (0..packs.bundles.len()).find_map(find_in_bundle),
+        })
     }
 }
-
-/// Special-use function to look up an object index. Used to avoid double-lookups in
-/// [compound::Store::find()][crate::store::compound::Store::find()]. (The polonius borrow-checker would support this via the 'find'
-/// function, so this can be [simplified](https://github.com/Byron/gitoxide/blob/0c5f4043da4615820cb180804a81c2d4fe75fe5e/git-odb/src/compound/locate.rs#L47)
-/// once polonius is stable.)
-fn find_pack_index(bundle: &git_pack::Bundle, id: &git_hash::oid) -> Option<u32> {
-    bundle.index.lookup(id)
-}
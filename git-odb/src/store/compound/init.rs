@@ -1,4 +1,12 @@
-use std::path::PathBuf;
+use std::{
+    cmp::Reverse,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
 
 use crate::{
     pack,
@@ -19,38 +27,210 @@ pub enum Error {
 
 /// Instantiation
 impl compound::Store {
-    /// Returns a compound database as initialized from the given git `objects_directory`, commonly `.git/objects`.
+    /// Returns a compound database as initialized from the given git `objects_directory`, commonly `.git/objects`,
+    /// whose packs use object ids of `hash_kind`, usually as configured by `extensions.objectFormat` in the repository.
     ///
     /// Only loose and packed objects will be considered. See the [linked Db][crate::store::linked::Store] for a database with
     /// support for _git alternates_, i.e. linking to other repositories.
-    pub fn at(objects_directory: impl Into<PathBuf>) -> Result<compound::Store, Error> {
+    ///
+    /// Packs are ordered by [size][compound::PackOrder::Size], use [`at_with_pack_order()`][compound::Store::at_with_pack_order()]
+    /// to choose a different order.
+    pub fn at(objects_directory: impl Into<PathBuf>, hash_kind: git_hash::Kind) -> Result<compound::Store, Error> {
+        Self::at_with_pack_order(objects_directory, hash_kind, compound::PackOrder::default())
+    }
+
+    /// Like [`at()`][compound::Store::at()], but with packs looked up in the given `pack_order`.
+    pub fn at_with_pack_order(
+        objects_directory: impl Into<PathBuf>,
+        hash_kind: git_hash::Kind,
+        pack_order: compound::PackOrder,
+    ) -> Result<compound::Store, Error> {
         let loose_objects = objects_directory.into();
         if !loose_objects.is_dir() {
             return Err(Error::Inaccessible(loose_objects));
         }
-        let packs = match std::fs::read_dir(loose_objects.join("pack")) {
-            Ok(entries) => {
-                let mut packs_and_sizes = entries
-                    .filter_map(Result::ok)
-                    .filter_map(|e| e.metadata().map(|md| (e.path(), md)).ok())
-                    .filter(|(_, md)| md.file_type().is_file())
-                    .filter(|(p, _)| {
-                        p.extension().unwrap_or_default() == "idx"
-                            && p.file_name().unwrap_or_default().to_string_lossy().starts_with("pack-")
+        let packs = load_packs(
+            &loose_objects,
+            sorted_pack_index_paths(&loose_objects.join("pack"), pack_order),
+            &[],
+            hash_kind,
+        )?;
+        Ok(compound::Store {
+            loose: loose::Store::at(loose_objects),
+            packs: parking_lot::RwLock::new(Arc::new(packs)),
+            pack_order,
+            hash_kind,
+            last_pack_hit: AtomicUsize::new(compound::NO_PACK_HIT),
+        })
+    }
+}
+
+/// Access to packs
+impl compound::Store {
+    /// Return the packs currently in use, which remain usable even if the store is [refreshed][compound::Store::refresh()]
+    /// meanwhile, but won't contain the packs it picked up.
+    pub fn packs(&self) -> Arc<compound::Packs> {
+        Arc::clone(&self.packs.read())
+    }
+
+    /// The kind of hash used by the object ids in all packs of this store.
+    pub fn hash_kind(&self) -> git_hash::Kind {
+        self.hash_kind
+    }
+
+    /// The order in which packs are looked up, which is also the order of their bundles.
+    pub fn pack_order(&self) -> compound::PackOrder {
+        self.pack_order
+    }
+
+    /// Return the packs currently in use for modification, or `None` if [`packs()`][compound::Store::packs()] obtained
+    /// earlier are still in use.
+    pub fn packs_mut(&mut self) -> Option<&mut compound::Packs> {
+        Arc::get_mut(self.packs.get_mut())
+    }
+
+    /// Drop all packs to release their memory maps, for instance to be able to delete them on all platforms.
+    pub(crate) fn clear_packs(&mut self) {
+        *self.packs.get_mut() = Default::default();
+        *self.last_pack_hit.get_mut() = compound::NO_PACK_HIT;
+    }
+}
+
+/// Refreshing
+impl compound::Store {
+    /// Rescan the pack directory to add bundles for packs that were written since and to drop those whose pack index
+    /// was deleted, returning true if the packs changed. Bundles for packs that are still present are kept as they are.
+    ///
+    /// This is useful for long-running processes that need to see packs written by other processes, for instance
+    /// during a fetch or `git gc`. Loose objects don't need a refresh as they are looked up on disk each time.
+    /// [`find()`][compound::Store::find()] refreshes the packs automatically if an object can't be found.
+    ///
+    /// Readers that obtained [`packs()`][compound::Store::packs()] before keep using the previous packs.
+    pub fn refresh(&self) -> Result<bool, Error> {
+        let index_paths = sorted_pack_index_paths(&self.loose.path.join("pack"), self.pack_order);
+        let packs = self.packs();
+        if index_paths.len() == packs.bundles.len()
+            && index_paths
+                .iter()
+                .all(|path| packs.bundles.iter().any(|bundle| bundle.index.path() == path))
+        {
+            return Ok(false);
+        }
+        let packs = load_packs(&self.loose.path, index_paths, &packs.bundles, self.hash_kind)?;
+        *self.packs.write() = Arc::new(packs);
+        self.last_pack_hit.store(compound::NO_PACK_HIT, Ordering::Relaxed);
+        Ok(true)
+    }
+
+    /// Change the order in which packs are looked up to `pack_order` and sort the existing bundles accordingly,
+    /// which also [refreshes][compound::Store::refresh()] them.
+    pub fn set_pack_order(&mut self, pack_order: compound::PackOrder) -> Result<(), Error> {
+        self.pack_order = pack_order;
+        let packs = load_packs(
+            &self.loose.path,
+            sorted_pack_index_paths(&self.loose.path.join("pack"), pack_order),
+            &self.packs().bundles,
+            self.hash_kind,
+        )?;
+        *self.packs.get_mut() = Arc::new(packs);
+        *self.last_pack_hit.get_mut() = compound::NO_PACK_HIT;
+        Ok(())
+    }
+
+    /// Return true if the given object `id` is contained in the store, [refreshing][compound::Store::refresh()] the packs
+    /// once if it can't be found at first.
+    pub fn contains_or_refresh(&self, id: impl AsRef<git_hash::oid>) -> Result<bool, Error> {
+        let id = id.as_ref();
+        let contains = |db: &compound::Store| {
+            matches!(db.internal_find_packed(&db.packs(), id), Ok(Some(_))) || db.loose.contains(id)
+        };
+        Ok(contains(self) || (self.refresh()? && contains(self)))
+    }
+}
+
+/// Load the bundles at `index_paths` in order, reusing those in `existing`, along with the multi-pack index
+/// in `objects_directory`, all of which use object ids of `hash_kind`.
+fn load_packs(
+    objects_directory: &Path,
+    index_paths: Vec<PathBuf>,
+    existing: &[Arc<pack::Bundle>],
+    hash_kind: git_hash::Kind,
+) -> Result<compound::Packs, Error> {
+    let bundles = index_paths
+        .into_iter()
+        .map(
+            |path| match existing.iter().find(|bundle| bundle.index.path() == path) {
+                Some(bundle) => Ok(Arc::clone(bundle)),
+                None => pack::Bundle::at(path, hash_kind).map(Arc::new),
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    let multi_index = compound::MultiIndex::at(
+        objects_directory.join("pack").join("multi-pack-index"),
+        &bundles,
+        hash_kind,
+    );
+    Ok(compound::Packs { bundles, multi_index })
+}
+
+/// Return the paths to all pack indices in `pack_directory` in the given `order`, or nothing if it can't be read.
+fn sorted_pack_index_paths(pack_directory: &Path, order: compound::PackOrder) -> Vec<PathBuf> {
+    match std::fs::read_dir(pack_directory) {
+        Ok(entries) => {
+            let mut paths_and_metadata: Vec<_> = entries
+                .filter_map(Result::ok)
+                .filter_map(|e| e.metadata().map(|md| (e.path(), md)).ok())
+                .filter(|(_, md)| md.file_type().is_file())
+                .filter(|(p, _)| {
+                    p.extension().unwrap_or_default() == "idx"
+                        && p.file_name().unwrap_or_default().to_string_lossy().starts_with("pack-")
+                })
+                .collect();
+            match order {
+                compound::PackOrder::Size => paths_and_metadata.sort_by_key(|(_, md)| Reverse(md.len())),
+                compound::PackOrder::ModificationTime | compound::PackOrder::MostRecentlyHit => {
+                    // Like git, use the modification time of the pack data file.
+                    paths_and_metadata.sort_by_cached_key(|(p, md)| {
+                        Reverse(
+                            std::fs::metadata(p.with_extension("pack"))
+                                .and_then(|md| md.modified())
+                                .or_else(|_| md.modified())
+                                .unwrap_or(SystemTime::UNIX_EPOCH),
+                        )
                     })
-                    // TODO: make this configurable, git for instance sorts by modification date
-                    //       https://github.com/libgit2/libgit2/blob/main/src/odb_pack.c#L41-L158
-                    .map(|(p, md)| pack::Bundle::at(p).map(|b| (b, md.len())))
-                    .collect::<Result<Vec<_>, _>>()?;
-                packs_and_sizes.sort_by_key(|e| e.1);
-                packs_and_sizes.into_iter().rev().map(|(b, _)| b).collect()
+                }
             }
-            Err(_) => Vec::new(),
-        };
+            paths_and_metadata.into_iter().map(|(p, _)| p).collect()
+        }
+        Err(_) => Vec::new(),
+    }
+}
 
-        Ok(compound::Store {
-            loose: loose::Store::at(loose_objects),
-            bundles: packs,
+impl compound::MultiIndex {
+    /// Open the multi-pack index at `path` and associate its indices with `bundles`.
+    ///
+    /// Like git, we ignore multi-pack indices that can't be read. The same is done if one of its packs is missing
+    /// as the multi-pack index is clearly outdated then, or if it doesn't use object ids of `hash_kind`.
+    fn at(path: PathBuf, bundles: &[Arc<pack::Bundle>], hash_kind: git_hash::Kind) -> Option<Self> {
+        let file = pack::multi_index::File::at(path)
+            .ok()
+            .filter(|file| file.hash_kind() == hash_kind)?;
+        let bundle_indices = file
+            .index_names()
+            .iter()
+            .map(|name| {
+                bundles
+                    .iter()
+                    .position(|bundle| bundle.index.path().file_name() == Some(name.as_os_str()))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let uncovered_bundle_indices = (0..bundles.len())
+            .filter(|bundle_index| !bundle_indices.contains(bundle_index))
+            .collect();
+        Some(compound::MultiIndex {
+            file,
+            bundle_indices,
+            uncovered_bundle_indices,
         })
     }
 }
@@ -1,10 +1,14 @@
 //! An object database delegating object access to multiple contained object databases with loose and packed objects.
+use std::sync::{atomic::AtomicUsize, Arc};
+
 use crate::{pack, store::loose};
 
 ///
 pub mod find;
 ///
 pub mod init;
+///
+pub mod prefix;
 mod write;
 
 /// An object database with tiered lookup packs and loose objects.
@@ -12,6 +16,52 @@ mod write;
 pub struct Store {
     /// A loose object database into which new objects are written
     pub loose: loose::Store,
+    /// The packs currently in use, replaced as a whole when [refreshing][Store::refresh()].
+    packs: parking_lot::RwLock<Arc<Packs>>,
+    /// The order of the bundles, which is the order in which they are searched for objects.
+    pack_order: PackOrder,
+    /// The kind of hash used by the object ids in all packs.
+    hash_kind: git_hash::Kind,
+    /// The index into `bundles` of the pack that contained the last object found, or [`NO_PACK_HIT`].
+    last_pack_hit: AtomicUsize,
+}
+
+const NO_PACK_HIT: usize = usize::MAX;
+
+/// The order in which the packs of a [compound store][Store] are searched for objects.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum PackOrder {
+    /// Search the packs with the largest index first.
+    Size,
+    /// Search the most recently modified packs first, like git does, as objects in new packs tend to be accessed more often.
+    ModificationTime,
+    /// Like [`ModificationTime`][PackOrder::ModificationTime], but start each search with the pack that contained the
+    /// last object found, like libgit2 does. This avoids probing cold pack indices while traversing objects that are
+    /// stored together.
+    MostRecentlyHit,
+}
+
+impl Default for PackOrder {
+    fn default() -> Self {
+        PackOrder::Size
+    }
+}
+
+/// The packs of a [compound store][Store] at one point in time, which remain usable even if the store is refreshed meanwhile.
+#[derive(Default)]
+pub struct Packs {
     /// All packs in the `objects/packs` directory
-    pub bundles: Vec<pack::Bundle>,
+    pub bundles: Vec<Arc<pack::Bundle>>,
+    /// The multi-pack index in the `objects/packs` directory, if present and if all packs it refers to are available.
+    pub multi_index: Option<MultiIndex>,
+}
+
+/// A multi-pack index along with the bundles of the [compound store][Store] it refers to.
+pub struct MultiIndex {
+    /// The multi-pack index file
+    pub file: pack::multi_index::File,
+    /// For each of the indices in the multi-pack index, the position of its bundle in [`Packs::bundles`].
+    pub bundle_indices: Vec<usize>,
+    /// The positions of all bundles in [`Packs::bundles`] that are not covered by the multi-pack index, in order.
+    pub uncovered_bundle_indices: Vec<usize>,
 }
@@ -0,0 +1,124 @@
+use git_hash::{oid, ObjectId, Prefix};
+
+use crate::store::compound;
+
+///
+pub mod lookup {
+    use git_hash::{ObjectId, Prefix};
+
+    /// Returned by [`compound::Store::lookup_prefix()`][crate::store::compound::Store::lookup_prefix()]
+    #[derive(thiserror::Error, Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The loose objects matching the prefix could not be listed")]
+        Io(#[from] std::io::Error),
+        #[error("The abbreviated object id {prefix} is ambiguous as it matches {} objects", .candidates.len())]
+        Ambiguous { prefix: Prefix, candidates: Vec<ObjectId> },
+    }
+}
+
+///
+pub mod shortest_unique {
+    /// Returned by [`compound::Store::shortest_unique_prefix()`][crate::store::compound::Store::shortest_unique_prefix()]
+    #[derive(thiserror::Error, Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The loose objects sharing a prefix with the object could not be listed")]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        Prefix(#[from] git_hash::prefix::new::Error),
+    }
+}
+
+/// Abbreviated object ids
+impl compound::Store {
+    /// Return the id of the only object starting with `prefix`, or `None` if there is no such object.
+    ///
+    /// If more than one object matches, all of them are returned as candidates in the [ambiguity error][lookup::Error::Ambiguous].
+    pub fn lookup_prefix(&self, prefix: Prefix) -> Result<Option<ObjectId>, lookup::Error> {
+        let mut candidates = Vec::new();
+        self.collect_prefix_candidates(prefix, &mut candidates)?;
+        unique_candidate(prefix, candidates)
+    }
+
+    /// Return the shortest prefix of `id` with at least `min_hex_len` characters that no other object in this store starts with.
+    ///
+    /// `id` itself doesn't have to be contained in the store. Use [`auto_hex_len()`][compound::Store::auto_hex_len()] as
+    /// `min_hex_len` to obtain abbreviations like git does with `core.abbrev=auto`.
+    pub fn shortest_unique_prefix(&self, id: &oid, min_hex_len: usize) -> Result<Prefix, shortest_unique::Error> {
+        let mut candidates = Vec::new();
+        self.collect_prefix_candidates(Prefix::new(id, min_hex_len)?, &mut candidates)?;
+        Ok(shortest_unique_prefix(id, min_hex_len, &candidates)?)
+    }
+
+    /// Return the amount of hexadecimal characters to abbreviate object ids to by default, which grows with the amount of
+    /// packed objects just like git computes it for `core.abbrev=auto`.
+    pub fn auto_hex_len(&self) -> usize {
+        auto_hex_len(self.packed_object_count())
+    }
+
+    pub(crate) fn packed_object_count(&self) -> u64 {
+        self.packs()
+            .bundles
+            .iter()
+            .map(|bundle| bundle.index.num_objects() as u64)
+            .sum()
+    }
+
+    /// Add all ids starting with `prefix` to `out`, which may then contain duplicates if the object is stored more than once.
+    pub(crate) fn collect_prefix_candidates(&self, prefix: Prefix, out: &mut Vec<ObjectId>) -> std::io::Result<()> {
+        for bundle in &self.packs().bundles {
+            let range = bundle.index.lookup_prefix(prefix);
+            out.extend(range.map(|index| bundle.index.oid_at_index(index).to_owned()));
+        }
+        out.extend(self.loose.lookup_prefix(prefix)?);
+        Ok(())
+    }
+}
+
+pub(crate) fn unique_candidate(
+    prefix: Prefix,
+    mut candidates: Vec<ObjectId>,
+) -> Result<Option<ObjectId>, lookup::Error> {
+    candidates.sort();
+    candidates.dedup();
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(candidates.pop()),
+        _ => Err(lookup::Error::Ambiguous { prefix, candidates }),
+    }
+}
+
+/// Return the shortest prefix of `id` that sets it apart from all `candidates`, which share its first `min_hex_len` characters.
+pub(crate) fn shortest_unique_prefix(
+    id: &oid,
+    min_hex_len: usize,
+    candidates: &[ObjectId],
+) -> Result<Prefix, git_hash::prefix::new::Error> {
+    let hex_len = candidates
+        .iter()
+        .filter(|candidate| candidate.as_ref() != id)
+        .map(|candidate| common_hex_len(id, candidate) + 1)
+        .fold(min_hex_len, usize::max)
+        .min(id.kind().len_in_hex());
+    Prefix::new(id, hex_len)
+}
+
+fn common_hex_len(lhs: &oid, rhs: &oid) -> usize {
+    lhs.as_bytes()
+        .iter()
+        .zip(rhs.as_bytes())
+        .position(|(lhs, rhs)| lhs != rhs)
+        .map(|byte| {
+            let (lhs, rhs) = (lhs.as_bytes()[byte], rhs.as_bytes()[byte]);
+            byte * 2 + usize::from(lhs >> 4 == rhs >> 4)
+        })
+        .unwrap_or_else(|| lhs.kind().len_in_hex())
+}
+
+/// Like git, use enough characters to expect no collision among `object_count` objects, but no less than 7.
+pub(crate) fn auto_hex_len(object_count: u64) -> usize {
+    const FALLBACK_HEX_LEN: usize = 7;
+    let bits = (64 - object_count.leading_zeros()) as usize;
+    ((bits + 1) / 2).max(FALLBACK_HEX_LEN)
+}
@@ -1,4 +1,4 @@
-use std::convert::TryInto;
+use std::{convert::TryInto, sync::Arc};
 
 use git_hash::oid;
 use git_pack::{data::Object, find::Entry};
@@ -14,12 +14,19 @@ impl linked::Store {
     pub fn contains(&self, id: impl AsRef<oid>) -> bool {
         let id = id.as_ref();
         for db in self.dbs.iter() {
-            if db.internal_find_packed(id).is_some() || db.loose.contains(id) {
+            if matches!(db.internal_find_packed(&db.packs(), id), Ok(Some(_))) || db.loose.contains(id) {
                 return true;
             }
         }
         false
     }
+
+    /// Return true if the given object `id` is contained in the store, [refreshing][linked::Store::refresh()] the packs
+    /// of all databases once if it can't be found at first.
+    pub fn contains_or_refresh(&self, id: impl AsRef<oid>) -> Result<bool, compound::init::Error> {
+        let id = id.as_ref();
+        Ok(self.contains(id) || (self.refresh()? && self.contains(id)))
+    }
 }
 
 impl crate::Find for linked::Store {
@@ -32,36 +39,44 @@ impl crate::Find for linked::Store {
         pack_cache: &mut impl pack::cache::DecodeEntry,
     ) -> Result<Option<Object<'a>>, Self::Error> {
         let id = id.as_ref();
-        for db in self.dbs.iter() {
-            match db.internal_find_packed(id) {
-                Some(compound::find::PackLocation {
-                    bundle_index: pack_id,
-                    entry_index,
-                }) => {
-                    return db
-                        .internal_get_packed_object_by_index(pack_id, entry_index, buffer, pack_cache)
-                        .map(Some)
-                        .map_err(Into::into)
-                }
-                None => {
-                    if db.loose.contains(id) {
-                        return db.loose.find(id, buffer).map_err(Into::into);
+        let mut refreshed = false;
+        loop {
+            for db in self.dbs.iter() {
+                let packs = db.packs();
+                match db.internal_find_packed(&packs, id)? {
+                    Some(compound::find::PackLocation {
+                        bundle_index,
+                        entry_index,
+                    }) => {
+                        return packs.bundles[bundle_index]
+                            .get_object_by_index(entry_index, buffer, pack_cache)
+                            .map(Some)
+                            .map_err(Into::into)
+                    }
+                    None => {
+                        if db.loose.contains(id) {
+                            return db.loose.find(id, buffer).map_err(Into::into);
+                        }
                     }
                 }
             }
+            if refreshed || !self.refresh()? {
+                return Ok(None);
+            }
+            refreshed = true;
         }
-        Ok(None)
     }
 
     fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Option<pack::bundle::Location> {
         let id = id.as_ref();
         for db in self.dbs.iter() {
+            let packs = db.packs();
             if let Some(compound::find::PackLocation {
                 bundle_index,
                 entry_index,
-            }) = db.internal_find_packed(id)
+            }) = db.internal_find_packed(&packs, id).ok().flatten()
             {
-                let bundle = &db.bundles[bundle_index];
+                let bundle = &packs.bundles[bundle_index];
                 let pack_offset = bundle.index.pack_offset_at_index(entry_index);
                 let entry = bundle.pack.entry(pack_offset);
 
@@ -81,26 +96,25 @@ impl crate::Find for linked::Store {
         None
     }
 
-    fn bundle_by_pack_id(&self, pack_id: u32) -> Option<&Bundle> {
+    fn bundle_by_pack_id(&self, pack_id: u32) -> Option<Arc<Bundle>> {
         self.dbs
             .iter()
-            .find_map(|db| db.bundles.iter().find(|b| b.pack.id == pack_id))
+            .find_map(|db| db.packs().bundles.iter().find(|b| b.pack.id == pack_id).cloned())
     }
 
-    fn entry_by_location(&self, location: &pack::bundle::Location) -> Option<Entry<'_>> {
-        self.dbs
-            .iter()
-            .find_map(|db| db.bundles.iter().find(|p| p.pack.id == location.pack_id))
-            .map(|b| (b, location))
-            .and_then(|(bundle, l)| {
-                let crc32 = bundle.index.crc32_at_index(l.index_file_id);
-                let pack_offset = bundle.index.pack_offset_at_index(l.index_file_id);
-                bundle.pack.entry_slice(l.entry_range(pack_offset)).map(|data| Entry {
-                    data,
+    fn entry_by_location(&self, location: &pack::bundle::Location) -> Option<Entry> {
+        self.bundle_by_pack_id(location.pack_id).and_then(|bundle| {
+            let crc32 = bundle.index.crc32_at_index(location.index_file_id);
+            let pack_offset = bundle.index.pack_offset_at_index(location.index_file_id);
+            bundle
+                .pack
+                .entry_slice(location.entry_range(pack_offset))
+                .map(|data| Entry {
+                    data: data.to_owned(),
                     crc32,
                     version: bundle.pack.version(),
                 })
-            })
+        })
     }
 }
 
@@ -120,11 +134,11 @@ impl crate::Find for &linked::Store {
         (*self).location_by_oid(id, buf)
     }
 
-    fn bundle_by_pack_id(&self, pack_id: u32) -> Option<&Bundle> {
+    fn bundle_by_pack_id(&self, pack_id: u32) -> Option<Arc<Bundle>> {
         (*self).bundle_by_pack_id(pack_id)
     }
 
-    fn entry_by_location(&self, location: &pack::bundle::Location) -> Option<Entry<'_>> {
+    fn entry_by_location(&self, location: &pack::bundle::Location) -> Option<Entry> {
         (*self).entry_by_location(location)
     }
 }
@@ -16,13 +16,15 @@ pub enum Error {
 }
 
 impl linked::Store {
-    /// Instantiate an instance at the given `objects_directory`, commonly `.git/objects`.
+    /// Instantiate an instance at the given `objects_directory`, commonly `.git/objects`, whose packs use object ids
+    /// of `hash_kind`.
     ///
-    /// _git alternate_ files will be traversed to build a chain of [`compound::Store`] instances.
-    pub fn at(objects_directory: impl Into<PathBuf>) -> Result<Self, Error> {
-        let mut dbs = vec![compound::Store::at(objects_directory.into())?];
+    /// _git alternate_ files will be traversed to build a chain of [`compound::Store`] instances, all of which are expected
+    /// to use the same kind of hash.
+    pub fn at(objects_directory: impl Into<PathBuf>, hash_kind: git_hash::Kind) -> Result<Self, Error> {
+        let mut dbs = vec![compound::Store::at(objects_directory.into(), hash_kind)?];
         for object_path in alternate::resolve(dbs[0].loose.path.clone())?.into_iter() {
-            dbs.push(compound::Store::at(object_path)?);
+            dbs.push(compound::Store::at(object_path, hash_kind)?);
         }
         assert!(
             !dbs.is_empty(),
@@ -30,12 +32,15 @@ impl linked::Store {
         );
         Ok(linked::Store { dbs })
     }
-}
-
-impl std::convert::TryFrom<PathBuf> for linked::Store {
-    type Error = Error;
 
-    fn try_from(value: PathBuf) -> Result<Self, Self::Error> {
-        linked::Store::at(value)
+    /// [Refresh][compound::Store::refresh()] the packs of all compound databases, returning true if any of them changed.
+    ///
+    /// Alternates are not resolved again.
+    pub fn refresh(&self) -> Result<bool, compound::init::Error> {
+        let mut changed = false;
+        for db in self.dbs.iter() {
+            changed |= db.refresh()?;
+        }
+        Ok(changed)
     }
 }
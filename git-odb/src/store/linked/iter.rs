@@ -2,19 +2,31 @@ use std::{borrow::Borrow, option::Option::None, sync::Arc};
 
 use git_hash::ObjectId;
 
-use crate::store::{linked, loose};
+use crate::store::{compound, linked, loose};
 
 #[allow(clippy::large_enum_variant)]
 enum DbState {
-    Pack { pack_index: usize, entry_index: u32 },
-    Loose { iter: loose::iter::Iter },
+    Pack {
+        packs: Arc<compound::Packs>,
+        pack_index: usize,
+        entry_index: u32,
+    },
+    Loose {
+        iter: loose::iter::Iter,
+    },
 }
 
-impl Default for DbState {
-    fn default() -> Self {
-        DbState::Pack {
-            pack_index: 0,
-            entry_index: 0,
+impl DbState {
+    fn new(db: &compound::Store) -> Self {
+        let packs = db.packs();
+        if packs.bundles.is_empty() {
+            DbState::Loose { iter: db.loose.iter() }
+        } else {
+            DbState::Pack {
+                packs,
+                pack_index: 0,
+                entry_index: 0,
+            }
         }
     }
 }
@@ -33,18 +45,12 @@ where
     /// Create a new iterator from a linked database
     pub fn new(db: Db) -> Self {
         let db_index = 0;
-        let db_state = {
-            let db = &db
-                .borrow()
+        let db_state = DbState::new(
+            db.borrow()
                 .dbs
                 .get(db_index)
-                .expect("at least one db or no linked::Store at all");
-            if db.bundles.is_empty() {
-                DbState::Loose { iter: db.loose.iter() }
-            } else {
-                DbState::default()
-            }
-        };
+                .expect("at least one db or no linked::Store at all"),
+        );
         AllObjects { db, db_index, db_state }
     }
 }
@@ -63,11 +69,12 @@ where
 
         match &mut self.db_state {
             DbState::Pack {
+                packs,
                 pack_index,
                 entry_index,
             } => {
                 let db = &db.dbs[self.db_index];
-                match db.bundles.get(*pack_index) {
+                match packs.bundles.get(*pack_index) {
                     Some(bundle) => {
                         if *entry_index < bundle.index.num_objects() {
                             let oid = bundle.index.oid_at_index(*entry_index).to_owned();
@@ -89,7 +96,9 @@ where
                 Some(id) => Some(id),
                 None => {
                     self.db_index += 1;
-                    self.db_state = Default::default();
+                    if let Some(db) = db.dbs.get(self.db_index) {
+                        self.db_state = DbState::new(db);
+                    }
                     self.next()
                 }
             },
@@ -99,7 +108,8 @@ where
     fn size_hint(&self) -> (usize, Option<usize>) {
         let packed_objects = self.db.borrow().dbs.iter().fold(0usize, |dbc, db| {
             dbc.saturating_add(
-                db.bundles
+                db.packs()
+                    .bundles
                     .iter()
                     .fold(0, |pc, pack| pc.saturating_add(pack.index.num_objects() as usize)),
             )
@@ -14,6 +14,11 @@ pub mod init;
 
 mod find;
 
+mod prefix;
+
+///
+pub mod repack;
+
 ///
 mod write;
 
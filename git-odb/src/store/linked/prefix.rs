@@ -0,0 +1,44 @@
+use git_hash::{oid, ObjectId, Prefix};
+
+use crate::store::{
+    compound::{
+        self,
+        prefix::{lookup, shortest_unique},
+    },
+    linked,
+};
+
+/// Abbreviated object ids
+impl linked::Store {
+    /// Return the id of the only object in any of the linked databases starting with `prefix`, or `None` if there is no such object.
+    ///
+    /// If more than one object matches, all of them are returned as candidates in the [ambiguity error][lookup::Error::Ambiguous].
+    pub fn lookup_prefix(&self, prefix: Prefix) -> Result<Option<ObjectId>, lookup::Error> {
+        let mut candidates = Vec::new();
+        for db in &self.dbs {
+            db.collect_prefix_candidates(prefix, &mut candidates)?;
+        }
+        compound::prefix::unique_candidate(prefix, candidates)
+    }
+
+    /// Return the shortest prefix of `id` with at least `min_hex_len` characters that no other object in any of the linked
+    /// databases starts with.
+    ///
+    /// See [`compound::Store::shortest_unique_prefix()`] for details.
+    pub fn shortest_unique_prefix(&self, id: &oid, min_hex_len: usize) -> Result<Prefix, shortest_unique::Error> {
+        let prefix = Prefix::new(id, min_hex_len)?;
+        let mut candidates = Vec::new();
+        for db in &self.dbs {
+            db.collect_prefix_candidates(prefix, &mut candidates)?;
+        }
+        Ok(compound::prefix::shortest_unique_prefix(id, min_hex_len, &candidates)?)
+    }
+
+    /// Return the amount of hexadecimal characters to abbreviate object ids to by default, based on the amount of packed objects
+    /// in all linked databases.
+    ///
+    /// See [`compound::Store::auto_hex_len()`] for details.
+    pub fn auto_hex_len(&self) -> usize {
+        compound::prefix::auto_hex_len(self.dbs.iter().map(|db| db.packed_object_count()).sum())
+    }
+}
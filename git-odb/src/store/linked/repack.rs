@@ -0,0 +1,305 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
+use git_features::{
+    parallel::reduce::Finalize,
+    progress::{self, Progress},
+};
+use git_hash::ObjectId;
+use git_object::immutable;
+use git_pack::{
+    data::output::{self, count, entry::iter_from_counts},
+    FindExt,
+};
+
+use crate::{
+    pack,
+    store::{compound, linked},
+};
+
+type FindError = pack::find::existing::Error<compound::find::Error>;
+type EntriesError = iter_from_counts::Error<compound::find::Error>;
+
+/// Returned by [`linked::Store::repack()`]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("A tip could not be looked up")]
+    FindExisting(#[from] FindError),
+    #[error("The reachable objects could not be counted")]
+    Count(#[from] count::objects::Error<FindError, git_traverse::commit::ancestors::Error>),
+    #[error("The new pack could not be written")]
+    WritePack(#[from] output::bytes::Error<EntriesError>),
+    #[error("The pack entries could not be created")]
+    Entries(#[from] EntriesError),
+    #[error("The new pack could not be indexed and moved into place")]
+    Bundle(#[from] pack::bundle::write::Error),
+    #[error("The new pack could not be opened")]
+    OpenBundle(#[from] pack::bundle::Error),
+    #[error("The object database could not be reloaded after repacking")]
+    Reload(#[from] linked::init::Error),
+    #[error("A loose object could not be listed")]
+    LooseIter(#[from] crate::store::loose::iter::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+/// Configuration for [`linked::Store::repack()`]
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The amount of threads to use at most when creating pack entries and indexing the new pack.
+    /// If `None`, all logical cores are used.
+    pub thread_limit: Option<usize>,
+    /// How to obtain the entries of the new pack, where [delta compression][iter_from_counts::Mode::PackCopyAndDeltaCompression]
+    /// produces the smallest packs from loose objects.
+    pub mode: iter_from_counts::Mode,
+    /// Unreachable loose objects and packs containing unreachable objects are only deleted if they were
+    /// last modified longer ago than this, which protects objects that were just written but aren't referenced yet.
+    ///
+    /// Defaults to two weeks, just like git's `gc.pruneExpire`.
+    pub prune_grace_period: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            thread_limit: None,
+            mode: iter_from_counts::Mode::PackCopyAndDeltaCompression,
+            prune_grace_period: Duration::from_secs(60 * 60 * 24 * 14),
+        }
+    }
+}
+
+/// Information about a run of [`linked::Store::repack()`]
+#[derive(Debug)]
+pub struct Outcome {
+    /// Statistics about counting the reachable objects.
+    pub counts: count::objects::Outcome,
+    /// Statistics about creating the entries of the new pack.
+    pub entries: iter_from_counts::Outcome,
+    /// Information about the newly written pack, or `None` if there were no reachable objects to pack.
+    pub pack: Option<pack::bundle::write::Outcome>,
+    /// The paths to the indices of all packs that were deleted.
+    pub removed_packs: Vec<PathBuf>,
+    /// The amount of loose objects that were deleted as they are packed now or are unreachable.
+    pub removed_loose_objects: usize,
+}
+
+impl linked::Store {
+    /// Write all objects reachable from `tips` into a single new pack in the first of our
+    /// [compound databases][compound::Store], along with its index, and delete objects made redundant by it.
+    ///
+    /// Reachability is determined by `tips` alone, which is why they must include everything that is to be kept:
+    /// the targets of all references, but also the objects mentioned in reflogs and in the index. Objects not reachable
+    /// from `tips` are pruned once they are older than the [grace period][Options::prune_grace_period].
+    ///
+    /// * Objects only present in alternates are not packed.
+    /// * Existing packs are deleted if all of their objects are in the new pack, or if they are older than the
+    ///   grace period, in which case all of their objects reachable from `tips` are in the new pack.
+    ///   Packs with a `.keep` file are never deleted.
+    /// * Loose objects are deleted if they are in the new pack, or if they are older than the grace period.
+    ///
+    /// Readers will always see all reachable objects as the new pack is moved into place before anything is deleted.
+    /// This instance is reloaded to see the new pack once the operation is done, even if deleting objects failed.
+    pub fn repack(
+        &mut self,
+        tips: impl IntoIterator<Item = ObjectId>,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+        options: Options,
+    ) -> Result<Outcome, Error> {
+        let objects_directory = self.dbs[0].loose.path.clone();
+        let pack_directory = objects_directory.join("pack");
+        let hash_kind = self.dbs[0].hash_kind();
+        let db = Arc::new(linked::Store::at(objects_directory.clone(), hash_kind)?);
+
+        let (counts, counts_outcome) = {
+            let mut progress = progress.add_child("counting");
+            progress.init(None, progress::count("objects"));
+            let mut buf = Vec::new();
+            let mut input = Vec::new();
+            let mut commits = Vec::new();
+            for tip in tips {
+                let mut id = tip;
+                loop {
+                    let obj = db.find_existing(id, &mut buf, &mut pack::cache::Never)?;
+                    match obj.kind {
+                        git_object::Kind::Commit => commits.push(id),
+                        git_object::Kind::Tag => {
+                            if id == tip {
+                                input.push(tip);
+                            }
+                            if let Some(target) = immutable::TagIter::from_bytes(obj.data).target_id() {
+                                id = target;
+                                continue;
+                            }
+                        }
+                        git_object::Kind::Tree | git_object::Kind::Blob => input.push(id),
+                    }
+                    break;
+                }
+            }
+            let commits =
+                git_traverse::commit::Ancestors::new(commits, git_traverse::commit::ancestors::State::default(), {
+                    let db = Arc::clone(&db);
+                    move |oid, buf| db.find_existing_commit_iter(oid, buf, &mut pack::cache::Never).ok()
+                });
+            let (mut counts, outcome) = count::objects_unthreaded(
+                Arc::clone(&db),
+                &mut pack::cache::Never,
+                input.into_iter().map(Ok).chain(commits),
+                progress::ThroughputOnDrop::new(progress),
+                should_interrupt,
+                count::objects::ObjectExpansion::TreeContents,
+                true,
+            )?;
+            let local = &db.dbs[0];
+            let local_packs = local.packs();
+            counts.retain(|count| {
+                matches!(local.internal_find_packed(&local_packs, count.id), Ok(Some(_)))
+                    || local.loose.contains(count.id)
+            });
+            (counts, outcome)
+        };
+
+        let (bundle, entries_outcome) = if counts.is_empty() {
+            (None, iter_from_counts::Outcome::default())
+        } else {
+            let num_objects = counts.len();
+            let mut entries = output::InOrderIter::from(output::entry::iter_from_counts(
+                counts,
+                Arc::clone(&db),
+                || pack::cache::Never,
+                progress.add_child("creating entries"),
+                iter_from_counts::Options {
+                    thread_limit: options.thread_limit,
+                    mode: options.mode,
+                    ..Default::default()
+                },
+            ));
+            std::fs::create_dir_all(&pack_directory)?;
+            let mut pack_file = tempfile::NamedTempFile::new_in(&pack_directory)?;
+            {
+                let mut write_progress = progress.add_child("writing");
+                write_progress.init(None, progress::bytes());
+                let mut bytes = output::bytes::FromEntriesIter::new(
+                    entries.by_ref(),
+                    &mut pack_file,
+                    num_objects as u32,
+                    pack::data::Version::V2,
+                    hash_kind,
+                );
+                for written in bytes.by_ref() {
+                    if should_interrupt.load(Ordering::Relaxed) {
+                        return Err(Error::Interrupted);
+                    }
+                    write_progress.inc_by(written? as usize);
+                }
+            }
+            let entries_outcome = entries.inner.finalize()?;
+            let mut outcome = pack::Bundle::write_to_directory(
+                io::BufReader::new(pack_file.reopen()?),
+                Some(&pack_directory),
+                progress.add_child("indexing"),
+                should_interrupt,
+                None,
+                pack::bundle::write::Options {
+                    thread_limit: options.thread_limit,
+                    hash_kind,
+                    ..Default::default()
+                },
+            )?;
+            // Packs are only picked up by git and by us if their names start with `pack-`, so the index is renamed last.
+            for path in outcome.data_path.iter_mut().chain(outcome.index_path.iter_mut()) {
+                let mut file_name = std::ffi::OsString::from("pack-");
+                file_name.push(path.file_name().expect("file name"));
+                let new_path = path.with_file_name(file_name);
+                std::fs::rename(&path, &new_path)?;
+                *path = new_path;
+            }
+            (Some(outcome), entries_outcome)
+        };
+
+        let new_bundle = bundle
+            .as_ref()
+            .and_then(pack::bundle::write::Outcome::to_bundle)
+            .transpose()?;
+        let is_packed = |id: &git_hash::oid| new_bundle.as_ref().map_or(false, |b| b.index.lookup(id).is_some());
+
+        let redundant_packs: Vec<_> = db.dbs[0]
+            .packs()
+            .bundles
+            .iter()
+            .filter(|old| {
+                let index_path = old.index.path();
+                bundle.as_ref().and_then(|b| b.index_path.as_deref()) != Some(index_path)
+                    && !index_path.with_extension("keep").is_file()
+                    && (old.index.iter().all(|entry| is_packed(&entry.oid))
+                        || is_older_than(index_path, options.prune_grace_period))
+            })
+            .map(|old| old.index.path().to_owned())
+            .collect();
+
+        // Release all memory maps so that pack files can be deleted on all platforms.
+        drop(db);
+        self.dbs[0].clear_packs();
+
+        let removed_loose_objects = (|| -> Result<usize, Error> {
+            for index_path in &redundant_packs {
+                for extension in &["idx", "pack", "bitmap"] {
+                    remove_file_if_present(&index_path.with_extension(extension))?;
+                }
+            }
+            if !redundant_packs.is_empty() {
+                remove_file_if_present(&pack_directory.join("multi-pack-index"))?;
+            }
+
+            let mut removed_loose_objects = 0;
+            let loose = &self.dbs[0].loose;
+            for id in loose.iter().collect::<Result<Vec<_>, _>>()? {
+                let path = crate::store::loose::hash_path(&id, loose.path.clone());
+                if is_packed(&id) || is_older_than(&path, options.prune_grace_period) {
+                    remove_file_if_present(&path)?;
+                    std::fs::remove_dir(path.parent().expect("fan-out directory")).ok();
+                    removed_loose_objects += 1;
+                }
+            }
+            Ok(removed_loose_objects)
+        })();
+
+        // Reload even if deletion failed as our packs were unloaded, and some of them may be gone.
+        *self = linked::Store::at(objects_directory, hash_kind)?;
+        let removed_loose_objects = removed_loose_objects?;
+        Ok(Outcome {
+            counts: counts_outcome,
+            entries: entries_outcome,
+            pack: bundle,
+            removed_packs: redundant_packs,
+            removed_loose_objects,
+        })
+    }
+}
+
+fn is_older_than(path: &Path, grace_period: Duration) -> bool {
+    std::fs::metadata(path)
+        .and_then(|md| md.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map_or(false, |age| age > grace_period)
+}
+
+fn remove_file_if_present(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
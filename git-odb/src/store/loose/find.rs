@@ -3,7 +3,7 @@ use std::{convert::TryInto, fs, io::Read, path::PathBuf};
 use git_features::zlib;
 use git_pack::{data, loose::object::header};
 
-use crate::store::loose::{sha1_path, Store, HEADER_READ_UNCOMPRESSED_BYTES};
+use crate::store::loose::{hash_path, Store, HEADER_READ_UNCOMPRESSED_BYTES};
 
 /// Returned by [`Store::find()`]
 #[derive(thiserror::Error, Debug)]
@@ -30,7 +30,7 @@ impl Store {
 
     /// Returns true if the given id is contained in our repository.
     pub fn contains(&self, id: impl AsRef<git_hash::oid>) -> bool {
-        sha1_path(id.as_ref(), self.path.clone()).is_file()
+        hash_path(id.as_ref(), self.path.clone()).is_file()
     }
 
     /// Return the object identified by the given [`ObjectId`][git_hash::ObjectId] if present in this database,
@@ -67,7 +67,7 @@ impl Store {
     }
 
     fn find_inner<'a>(&self, id: &git_hash::oid, buf: &'a mut Vec<u8>) -> Result<data::Object<'a>, Error> {
-        let path = sha1_path(id, self.path.clone());
+        let path = hash_path(id, self.path.clone());
 
         let mut inflate = zlib::Inflate::default();
         let ((status, consumed_in, consumed_out), bytes_read) = {
@@ -1,6 +1,6 @@
 use git_features::fs;
 
-use crate::store::loose::Store;
+use crate::store::loose::{prefix::is_loose_object_file_name, Store};
 
 /// Returned by [`Store::iter()`]
 #[derive(thiserror::Error, Debug)]
@@ -29,15 +29,17 @@ impl Store {
             let mut ci = p.components();
             let (c2, c1) = (ci.next_back(), ci.next_back());
             if let (Some(Normal(c1)), Some(Normal(c2))) = (c1, c2) {
-                if c1.len() == 2 && c2.len() == 38 {
-                    if let (Some(c1), Some(c2)) = (c1.to_str(), c2.to_str()) {
-                        let mut buf = [0u8; 40];
+                if c1.len() == 2 {
+                    if let (Some(c1), Some(c2)) = (c1.to_str(), c2.to_str().filter(|c2| is_loose_object_file_name(c2)))
+                    {
+                        let mut buf = [0u8; git_hash::Kind::longest().len_in_hex()];
+                        let buf = &mut buf[..2 + c2.len()];
                         {
                             let (first_byte, rest) = buf.split_at_mut(2);
                             first_byte.copy_from_slice(c1.as_bytes());
                             rest.copy_from_slice(c2.as_bytes());
                         }
-                        if let Ok(b) = git_hash::ObjectId::from_hex(&buf[..]) {
+                        if let Ok(b) = git_hash::ObjectId::from_hex(buf) {
                             is_valid_path = true;
                             return b;
                         }
@@ -21,16 +21,13 @@ impl Store {
     }
 }
 
-fn sha1_path(id: &git_hash::oid, mut root: PathBuf) -> PathBuf {
-    match id.kind() {
-        git_hash::Kind::Sha1 => {
-            let hex = id.to_sha1_hex();
-            let buf = std::str::from_utf8(&hex).expect("ascii only in hex");
-            root.push(&buf[..2]);
-            root.push(&buf[2..]);
-            root
-        }
-    }
+pub(crate) fn hash_path(id: &git_hash::oid, mut root: PathBuf) -> PathBuf {
+    let mut hex = [0u8; git_hash::Kind::longest().len_in_hex()];
+    let hex = id.hex_to_buf(&mut hex);
+    let buf = std::str::from_utf8(hex).expect("ascii only in hex");
+    root.push(&buf[..2]);
+    root.push(&buf[2..]);
+    root
 }
 
 ///
@@ -39,5 +36,6 @@ pub mod find;
 pub mod iter;
 #[doc(inline)]
 pub use iter::Iter;
+mod prefix;
 ///
 pub mod write;
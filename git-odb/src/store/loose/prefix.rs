@@ -0,0 +1,46 @@
+use std::{cmp::Ordering, io};
+
+use git_hash::ObjectId;
+
+use crate::store::loose::Store;
+
+/// Abbreviated object ids
+impl Store {
+    /// Return the sorted ids of all loose objects starting with `prefix`, reading only the directory of the hash's first byte.
+    pub fn lookup_prefix(&self, prefix: git_hash::Prefix) -> io::Result<Vec<ObjectId>> {
+        let mut hex = [0u8; git_hash::Kind::longest().len_in_hex()];
+        prefix.as_oid().hex_to_buf(&mut hex);
+        let dir = self
+            .path
+            .join(std::str::from_utf8(&hex[..2]).expect("ascii only in hex"));
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut out = Vec::new();
+        for entry in entries {
+            let name = entry?.file_name();
+            let name = match name.to_str() {
+                Some(name) if is_loose_object_file_name(name) => name,
+                _ => continue,
+            };
+            let hex = &mut hex[..2 + name.len()];
+            hex[2..].copy_from_slice(name.as_bytes());
+            if let Ok(id) = ObjectId::from_hex(hex) {
+                if prefix.cmp_oid(&id) == Ordering::Equal {
+                    out.push(id);
+                }
+            }
+        }
+        out.sort();
+        Ok(out)
+    }
+}
+
+/// Return true if `name` is the name of a loose object file within the directory of its hash's first byte.
+pub(crate) fn is_loose_object_file_name(name: &str) -> bool {
+    [git_hash::Kind::Sha1, git_hash::Kind::Sha256]
+        .iter()
+        .any(|kind| name.len() == kind.len_in_hex() - 2)
+}
@@ -37,18 +37,14 @@ impl crate::write::Write for Store {
         from: &[u8],
         hash: git_hash::Kind,
     ) -> Result<git_hash::ObjectId, Self::Error> {
-        match hash {
-            git_hash::Kind::Sha1 => {
-                let mut to = self.write_header(kind, from.len() as u64, hash)?;
-                to.write_all(from).map_err(|err| Error::Io {
-                    source: err,
-                    message: "stream all data into tempfile in",
-                    path: self.path.to_owned(),
-                })?;
-                to.flush()?;
-                self.finalize_object(to)
-            }
-        }
+        let mut to = self.write_header(kind, from.len() as u64, hash)?;
+        to.write_all(from).map_err(|err| Error::Io {
+            source: err,
+            message: "stream all data into tempfile in",
+            path: self.path.to_owned(),
+        })?;
+        to.flush()?;
+        self.finalize_object(to)
     }
 
     /// Write the given stream in `from` to disk with at least one syscall.
@@ -61,18 +57,14 @@ impl crate::write::Write for Store {
         mut from: impl io::Read,
         hash: git_hash::Kind,
     ) -> Result<git_hash::ObjectId, Self::Error> {
-        match hash {
-            git_hash::Kind::Sha1 => {
-                let mut to = self.write_header(kind, size, hash)?;
-                io::copy(&mut from, &mut to).map_err(|err| Error::Io {
-                    source: err,
-                    message: "stream all data into tempfile in",
-                    path: self.path.to_owned(),
-                })?;
-                to.flush()?;
-                self.finalize_object(to)
-            }
-        }
+        let mut to = self.write_header(kind, size, hash)?;
+        io::copy(&mut from, &mut to).map_err(|err| Error::Io {
+            source: err,
+            message: "stream all data into tempfile in",
+            path: self.path.to_owned(),
+        })?;
+        to.flush()?;
+        self.finalize_object(to)
     }
 }
 
@@ -106,8 +98,8 @@ impl Store {
         &self,
         hash::Write { hash, inner: file }: hash::Write<CompressedTempfile>,
     ) -> Result<git_hash::ObjectId, Error> {
-        let id = git_hash::ObjectId::from(hash.digest());
-        let object_path = loose::sha1_path(&id, self.path.clone());
+        let id = hash.digest();
+        let object_path = loose::hash_path(&id, self.path.clone());
         let object_dir = object_path
             .parent()
             .expect("each object path has a 1 hex-bytes directory");
@@ -0,0 +1,36 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.logAllRefUpdates false
+
+function commit() {
+  echo "$1" >> file
+  mkdir -p "dir/$1"
+  echo "$1" > "dir/$1/file"
+  git add .
+  git commit -qm "$1"
+}
+
+git checkout -q -b main
+commit 1
+commit 2
+git repack -dq
+
+commit 3
+commit 4
+git repack -dq
+
+# a pack with objects that become unreachable once the branch is gone
+git checkout -q -b temporary
+commit temporary
+git repack -dq
+git checkout -q main
+git branch -qD temporary
+
+commit 5
+git tag -m "a tag object" v1
+commit 6
+
+git hash-object -w --stdin <<<"unreachable" >/dev/null
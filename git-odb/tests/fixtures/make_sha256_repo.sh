@@ -0,0 +1,15 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q --object-format=sha256
+git config commit.gpgsign false
+
+git checkout -q -b main
+mkdir dir
+echo content > dir/file
+git add .
+git commit -qm first
+echo more >> dir/file
+git add .
+git commit -qm second
+git tag -m "a tag object" v1
@@ -1,9 +1,24 @@
+use std::path::Path;
+
 use git_odb::compound::Store;
 
 use crate::fixture_path;
 
 fn db() -> Store {
-    Store::at(fixture_path("objects")).expect("valid object path")
+    Store::at(fixture_path("objects"), git_hash::Kind::Sha1).expect("valid object path")
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
 }
 
 mod init {
@@ -11,7 +26,7 @@ mod init {
 
     #[test]
     fn has_packs() {
-        assert_eq!(db().bundles.len(), 3)
+        assert_eq!(db().packs().bundles.len(), 3)
     }
 }
 
@@ -40,3 +55,249 @@ mod locate {
         can_locate(&db(), "dd25c539efbb0ab018caa4cda2d133285634e9b5"); // pack c043
     }
 }
+
+mod multi_index {
+    use std::sync::atomic::AtomicBool;
+
+    use git_features::progress;
+    use git_odb::compound::Store;
+
+    use crate::{fixture_path, odb::store::compound::copy_dir};
+
+    fn objects_with_multi_index() -> crate::Result<(tempfile::TempDir, Store)> {
+        let dir = tempfile::tempdir()?;
+        copy_dir(&fixture_path("objects"), dir.path())?;
+        let packs = Store::at(dir.path(), git_hash::Kind::Sha1)?.packs();
+        assert!(packs.multi_index.is_none(), "there is no multi-pack index yet");
+        git_pack::multi_index::File::write_from_indices(
+            packs.bundles.iter().map(|bundle| &bundle.index),
+            std::fs::File::create(dir.path().join("pack").join("multi-pack-index"))?,
+            progress::Discard,
+            &AtomicBool::new(false),
+        )?;
+        let db = Store::at(dir.path(), git_hash::Kind::Sha1)?;
+        Ok((dir, db))
+    }
+
+    #[test]
+    fn is_used_to_find_packed_objects() -> crate::Result {
+        let (_keep, db) = objects_with_multi_index()?;
+        let packs = db.packs();
+        let multi_index = packs.multi_index.as_ref().expect("multi-pack index is loaded");
+        assert_eq!(multi_index.bundle_indices.len(), packs.bundles.len());
+        assert!(multi_index.uncovered_bundle_indices.is_empty());
+        for (name, bundle_index) in multi_index.file.index_names().iter().zip(&multi_index.bundle_indices) {
+            assert_eq!(
+                packs.bundles[*bundle_index].index.path().file_name(),
+                Some(name.as_os_str())
+            );
+        }
+
+        let mut buf = Vec::new();
+        for entry in multi_index.file.iter() {
+            let object = db
+                .find(entry.oid, &mut buf, &mut git_pack::cache::Never)?
+                .expect("object present");
+            let location = object.pack_location.expect("a packed object");
+            let bundle = &packs.bundles[multi_index.bundle_indices[entry.pack_index as usize]];
+            assert_eq!(
+                location.pack_id, bundle.pack.id,
+                "the pack chosen by the multi-pack index is used"
+            );
+            assert_eq!(location.pack_offset, entry.pack_offset);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn referring_to_unknown_packs_is_a_corruption_error() -> crate::Result {
+        let (_keep, mut db) = objects_with_multi_index()?;
+        let multi_index = db
+            .packs_mut()
+            .expect("no other packs in use")
+            .multi_index
+            .as_mut()
+            .expect("multi-pack index is loaded");
+        let id = multi_index.file.oid_at_index(0).to_owned();
+        multi_index.bundle_indices.clear();
+        assert!(matches!(
+            db.find(id, &mut Vec::new(), &mut git_pack::cache::Never),
+            Err(git_odb::compound::find::Error::CorruptMultiIndex { num_packs: 0, .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn is_ignored_if_one_of_its_packs_is_missing() -> crate::Result {
+        let (dir, db) = objects_with_multi_index()?;
+        let removed = db.packs().bundles[0].index.path().to_owned();
+        drop(db);
+        std::fs::remove_file(&removed)?;
+        std::fs::remove_file(removed.with_extension("pack"))?;
+
+        let packs = Store::at(dir.path(), git_hash::Kind::Sha1)?.packs();
+        assert!(packs.multi_index.is_none());
+        assert_eq!(packs.bundles.len(), 2);
+        Ok(())
+    }
+}
+
+mod refresh {
+    use git_odb::compound::Store;
+
+    use crate::{fixture_path, hex_to_id, odb::store::compound::copy_dir};
+
+    #[test]
+    fn packs_written_later_are_added_and_deleted_ones_are_dropped() -> crate::Result {
+        let dir = tempfile::tempdir()?;
+        copy_dir(&fixture_path("objects"), dir.path())?;
+        let pack_directory = dir.path().join("pack");
+        let hidden_directory = dir.path().join("hidden");
+        std::fs::create_dir(&hidden_directory)?;
+        let pack_name = "pack-11fdfa9e156ab73caae3b6da867192221f2089c2";
+        for extension in &["idx", "pack"] {
+            let file_name = format!("{}.{}", pack_name, extension);
+            std::fs::rename(pack_directory.join(&file_name), hidden_directory.join(&file_name))?;
+        }
+
+        let db = Store::at(dir.path(), git_hash::Kind::Sha1)?;
+        let old_packs = db.packs();
+        assert_eq!(old_packs.bundles.len(), 2);
+        assert!(!db.refresh()?, "nothing changed yet");
+
+        let id_in_hidden_pack = hex_to_id("501b297447a8255d3533c6858bb692575cdefaa0");
+        for extension in &["pack", "idx"] {
+            let file_name = format!("{}.{}", pack_name, extension);
+            std::fs::rename(hidden_directory.join(&file_name), pack_directory.join(&file_name))?;
+        }
+        assert!(
+            db.find(id_in_hidden_pack, &mut Vec::new(), &mut git_pack::cache::Never)?
+                .is_some(),
+            "the new pack is picked up when the object isn't found at first"
+        );
+        let packs = db.packs();
+        assert_eq!(packs.bundles.len(), 3);
+        assert!(
+            old_packs
+                .bundles
+                .iter()
+                .all(|old| packs.bundles.iter().any(|bundle| std::sync::Arc::ptr_eq(bundle, old))),
+            "previously loaded packs are kept"
+        );
+        assert_eq!(old_packs.bundles.len(), 2, "earlier packs remain usable as they were");
+        assert!(db.contains_or_refresh(id_in_hidden_pack)?);
+
+        let removed = packs.bundles[0].index.path().to_owned();
+        std::fs::remove_file(&removed)?;
+        std::fs::remove_file(removed.with_extension("pack"))?;
+        assert!(db.refresh()?);
+        let packs = db.packs();
+        assert_eq!(packs.bundles.len(), 2);
+        assert!(packs.bundles.iter().all(|bundle| bundle.index.path() != removed));
+        assert!(
+            !db.contains_or_refresh(hex_to_id("0000000000000000000000000000000000000000"))?,
+            "refreshing doesn't make missing objects appear"
+        );
+        Ok(())
+    }
+}
+
+mod pack_order {
+    use std::time::{Duration, SystemTime};
+
+    use git_odb::compound::{PackOrder, Store};
+
+    use crate::{fixture_path, odb::store::compound::copy_dir};
+
+    fn index_file_names(db: &Store) -> Vec<String> {
+        db.packs()
+            .bundles
+            .iter()
+            .map(|bundle| {
+                bundle
+                    .index
+                    .path()
+                    .file_name()
+                    .expect("file name")
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    fn objects_with_packs_modified_in_order(names: &[&str]) -> crate::Result<tempfile::TempDir> {
+        let dir = tempfile::tempdir()?;
+        copy_dir(&fixture_path("objects"), dir.path())?;
+        let oldest = SystemTime::now() - Duration::from_secs(60 * 60);
+        for (age, name) in names.iter().enumerate() {
+            std::fs::File::options()
+                .write(true)
+                .open(dir.path().join("pack").join(format!("{}.pack", name)))?
+                .set_modified(oldest + Duration::from_secs(age as u64 * 60))?;
+        }
+        Ok(dir)
+    }
+
+    #[test]
+    fn packs_are_sorted_by_size_or_modification_time() -> crate::Result {
+        let dir = objects_with_packs_modified_in_order(&[
+            "pack-c0438c19fb16422b6bbcce24387b3264416d485b",
+            "pack-11fdfa9e156ab73caae3b6da867192221f2089c2",
+            "pack-a2bf8e71d8c18879e499335762dd95119d93d9f1",
+        ])?;
+        let mut db = Store::at(dir.path(), git_hash::Kind::Sha1)?;
+        assert_eq!(db.pack_order(), PackOrder::Size);
+        let sizes: Vec<_> = db
+            .packs()
+            .bundles
+            .iter()
+            .map(|bundle| std::fs::metadata(bundle.index.path()).map(|md| md.len()))
+            .collect::<Result<_, _>>()?;
+        assert!(sizes.windows(2).all(|w| w[0] >= w[1]), "largest index first");
+
+        let newest_first = vec![
+            "pack-a2bf8e71d8c18879e499335762dd95119d93d9f1.idx",
+            "pack-11fdfa9e156ab73caae3b6da867192221f2089c2.idx",
+            "pack-c0438c19fb16422b6bbcce24387b3264416d485b.idx",
+        ];
+        for order in &[PackOrder::ModificationTime, PackOrder::MostRecentlyHit] {
+            assert_eq!(
+                index_file_names(&Store::at_with_pack_order(dir.path(), git_hash::Kind::Sha1, *order)?),
+                newest_first
+            );
+        }
+
+        let pack_ids: Vec<_> = db.packs().bundles.iter().map(|bundle| bundle.pack.id).collect();
+        db.set_pack_order(PackOrder::ModificationTime)?;
+        assert_eq!(index_file_names(&db), newest_first, "existing bundles are reordered");
+        assert!(
+            pack_ids
+                .iter()
+                .all(|id| db.packs().bundles.iter().any(|bundle| bundle.pack.id == *id)),
+            "and kept"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn starting_with_the_most_recently_hit_pack_finds_all_objects() -> crate::Result {
+        let db = Store::at_with_pack_order(
+            fixture_path("objects"),
+            git_hash::Kind::Sha1,
+            PackOrder::MostRecentlyHit,
+        )?;
+        let (mut buf, mut expected_buf) = (Vec::new(), Vec::new());
+        let linked = git_odb::linked::Store::at(fixture_path("objects"), git_hash::Kind::Sha1)?;
+        for id in linked.iter() {
+            let id = id?;
+            let expected = git_odb::Find::find(&linked, id, &mut expected_buf, &mut git_pack::cache::Never)?
+                .expect("object exists");
+            let object = db
+                .find(id, &mut buf, &mut git_pack::cache::Never)?
+                .expect("object exists");
+            assert_eq!(object.kind, expected.kind);
+            assert_eq!(object.data, expected.data);
+        }
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@ use git_odb::linked::Store;
 use crate::fixture_path;
 
 fn db() -> Store {
-    Store::at(fixture_path("objects")).expect("valid object path")
+    Store::at(fixture_path("objects"), git_hash::Kind::Sha1).expect("valid object path")
 }
 
 mod iter {
@@ -59,8 +59,6 @@ mod locate {
 }
 
 mod init {
-    use std::convert::TryFrom;
-
     use git_odb::linked;
 
     use crate::odb::{alternate::alternate, store::linked::db};
@@ -69,7 +67,7 @@ mod init {
     fn multiple_linked_repositories_via_alternates() -> crate::Result {
         let tmp = git_testtools::tempfile::TempDir::new()?;
         let (object_path, linked_object_path) = alternate(tmp.path().join("a"), tmp.path().join("b"))?;
-        let db = linked::Store::try_from(object_path.clone())?;
+        let db = linked::Store::at(object_path.clone(), git_hash::Kind::Sha1)?;
         assert_eq!(db.dbs.len(), 2);
         assert_eq!(db.dbs[0].loose.path, object_path);
         assert_eq!(db.dbs[1].loose.path, linked_object_path);
@@ -79,7 +77,7 @@ mod init {
     #[test]
     fn a_linked_db_without_alternates() -> crate::Result {
         let tmp = git_testtools::tempfile::TempDir::new()?;
-        let db = linked::Store::at(tmp.path())?;
+        let db = linked::Store::at(tmp.path(), git_hash::Kind::Sha1)?;
         assert_eq!(db.dbs.len(), 1);
         assert_eq!(db.dbs[0].loose.path, tmp.path());
         Ok(())
@@ -89,6 +87,180 @@ mod init {
     fn has_packs() {
         let db = db();
         assert_eq!(db.dbs.len(), 1);
-        assert_eq!(db.dbs[0].bundles.len(), 3)
+        assert_eq!(db.dbs[0].packs().bundles.len(), 3)
+    }
+}
+
+mod prefix {
+    use git_hash::Prefix;
+    use git_odb::compound::prefix::lookup;
+
+    use crate::{hex_to_id, odb::store::linked::db};
+
+    #[test]
+    fn ambiguous_prefixes_list_loose_and_packed_candidates() -> crate::Result {
+        let db = db();
+        let prefix = Prefix::from_hex("a706")?;
+        match db.lookup_prefix(prefix) {
+            Err(lookup::Error::Ambiguous {
+                prefix: actual,
+                candidates,
+            }) => {
+                assert_eq!(actual, prefix);
+                assert_eq!(
+                    candidates,
+                    vec![
+                        hex_to_id("a7065b5e971a6d8b55875d8cf634a3a37202ab23"), // pack a2bf
+                        hex_to_id("a706d7cd20fc8ce71489f34b50cf01011c104193"), // loose
+                    ]
+                );
+            }
+            res => panic!("expected ambiguity, got {:?}", res),
+        }
+        assert_eq!(
+            db.lookup_prefix(Prefix::from_hex("a706D")?)?,
+            Some(hex_to_id("a706d7cd20fc8ce71489f34b50cf01011c104193")),
+            "upper case is fine too"
+        );
+        assert_eq!(db.lookup_prefix(Prefix::from_hex("a7060")?)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn shortest_unique_prefix_of_every_object_resolves_to_it() -> crate::Result {
+        let db = db();
+        for id in db.iter() {
+            let id = id?;
+            let prefix = db.shortest_unique_prefix(&id, Prefix::MIN_HEX_LEN)?;
+            assert_eq!(db.lookup_prefix(prefix)?, Some(id));
+            if prefix.hex_len() > Prefix::MIN_HEX_LEN {
+                assert!(
+                    db.lookup_prefix(Prefix::new(id, prefix.hex_len() - 1)?).is_err(),
+                    "one less character is ambiguous"
+                );
+            }
+        }
+        assert_eq!(
+            db.shortest_unique_prefix(&hex_to_id("a706d7cd20fc8ce71489f34b50cf01011c104193"), 4)?
+                .to_string(),
+            "a706d"
+        );
+        assert_eq!(
+            db.shortest_unique_prefix(
+                &hex_to_id("a706d7cd20fc8ce71489f34b50cf01011c104193"),
+                db.auto_hex_len()
+            )?
+            .to_string(),
+            "a706d7c",
+            "small repositories use at least 7 characters, like git"
+        );
+        Ok(())
+    }
+}
+
+mod repack {
+    use std::{path::Path, process::Command, sync::atomic::AtomicBool, time::Duration};
+
+    use git_features::progress;
+    use git_hash::ObjectId;
+    use git_odb::{linked::Store, pack, Find};
+
+    fn git(repo: &Path, args: &[&str]) -> crate::Result<String> {
+        let output = Command::new("git").args(args).current_dir(repo).output()?;
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    fn ids(lines: String) -> crate::Result<Vec<ObjectId>> {
+        Ok(lines
+            .lines()
+            .map(|line| ObjectId::from_hex(&line.as_bytes()[..40]))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn repack(repo: &Path, prune_grace_period: Duration) -> crate::Result<(Store, git_odb::linked::repack::Outcome)> {
+        let mut db = Store::at(repo.join(".git").join("objects"), git_hash::Kind::Sha1)?;
+        let tips = ids(git(repo, &["for-each-ref", "--format=%(objectname)"])?)?;
+        let outcome = db.repack(
+            tips,
+            progress::Discard,
+            &AtomicBool::new(false),
+            git_odb::linked::repack::Options {
+                prune_grace_period,
+                ..Default::default()
+            },
+        )?;
+        Ok((db, outcome))
+    }
+
+    fn assert_reachable_objects_are_in_a_single_pack(repo: &Path, db: &Store) -> crate::Result {
+        let reachable = ids(git(repo, &["rev-list", "--objects", "--all"])?)?;
+        let packs_with_all_reachable_objects = db.dbs[0]
+            .packs()
+            .bundles
+            .iter()
+            .filter(|bundle| reachable.iter().all(|id| bundle.index.lookup(id).is_some()))
+            .count();
+        assert_eq!(packs_with_all_reachable_objects, 1);
+        let mut buf = Vec::new();
+        for id in reachable {
+            assert!(db.find(id, &mut buf, &mut pack::cache::Never)?.is_some());
+        }
+        git(repo, &["fsck", "--full", "--strict"])?;
+        Ok(())
+    }
+
+    #[test]
+    fn recently_written_unreachable_objects_are_kept() -> crate::Result {
+        let repo = git_testtools::scripted_fixture_repo_writable("make_repo_for_repack.sh")?;
+        let (db, outcome) = repack(repo.path(), Duration::from_secs(60 * 60))?;
+
+        let new_pack = outcome.pack.expect("reachable objects were packed");
+        assert_eq!(
+            new_pack.index.num_objects, 36,
+            "all reachable objects, including the tag"
+        );
+        assert!(
+            outcome.entries.delta_compressed_objects > 0,
+            "loose objects are delta compressed"
+        );
+        assert_eq!(
+            outcome.removed_packs.len(),
+            2,
+            "the pack with unreachable objects is too young to be removed"
+        );
+        assert_eq!(db.dbs[0].packs().bundles.len(), 2);
+        assert_eq!(outcome.removed_loose_objects, 13, "all loose objects are packed now");
+        assert_eq!(
+            db.dbs[0].loose.iter().count(),
+            1,
+            "the unreachable loose object is too young to be removed"
+        );
+        assert_reachable_objects_are_in_a_single_pack(repo.path(), &db)
+    }
+
+    #[test]
+    fn unreachable_objects_are_pruned_once_older_than_grace_period() -> crate::Result {
+        let repo = git_testtools::scripted_fixture_repo_writable("make_repo_for_repack.sh")?;
+        let (db, outcome) = repack(repo.path(), Duration::from_secs(0))?;
+        assert_eq!(outcome.removed_packs.len(), 3);
+        assert_eq!(outcome.removed_loose_objects, 14);
+        assert_eq!(db.dbs[0].packs().bundles.len(), 1);
+        assert_eq!(db.dbs[0].loose.iter().count(), 0);
+        assert_reachable_objects_are_in_a_single_pack(repo.path(), &db)?;
+
+        let (db, outcome) = repack(repo.path(), Duration::from_secs(0))?;
+        assert_eq!(
+            outcome.removed_packs.len(),
+            0,
+            "the new pack has the same name as the existing one, which is kept"
+        );
+        assert_eq!(db.dbs[0].packs().bundles.len(), 1);
+        assert_reachable_objects_are_in_a_single_pack(repo.path(), &db)
     }
 }
@@ -54,6 +54,50 @@ mod write {
     }
 }
 
+mod sha256 {
+    use std::{path::Path, process::Command};
+
+    use git_hash::ObjectId;
+    use git_odb::{loose, Write};
+
+    use crate::scripted_fixture_repo_read_only;
+
+    fn object_ids_according_to_git(repo: &Path) -> crate::Result<Vec<ObjectId>> {
+        let output = Command::new("git")
+            .args(&["rev-list", "--objects", "--all"])
+            .current_dir(repo)
+            .output()?;
+        assert!(output.status.success(), "git rev-list failed");
+        let mut ids = String::from_utf8(output.stdout)?
+            .lines()
+            .map(|line| ObjectId::from_hex(line.split(' ').next().expect("id").as_bytes()))
+            .collect::<Result<Vec<_>, _>>()?;
+        ids.sort();
+        Ok(ids)
+    }
+
+    #[test]
+    fn iter_find_lookup_prefix_and_write() -> crate::Result {
+        let repo = scripted_fixture_repo_read_only("make_sha256_repo.sh")?;
+        let db = loose::Store::at(repo.join(".git").join("objects"));
+        let mut ids = db.iter().collect::<Result<Vec<_>, _>>()?;
+        ids.sort();
+        assert_eq!(ids, object_ids_according_to_git(&repo)?);
+
+        let dir = tempfile::tempdir()?;
+        let out = loose::Store::at(dir.path());
+        let mut buf = Vec::new();
+        for id in ids {
+            assert_eq!(id.kind(), git_hash::Kind::Sha256);
+            let obj = db.find(id, &mut buf)?.expect("id present");
+            obj.verify_checksum(id)?;
+            assert_eq!(out.write_buf(obj.kind, obj.data, git_hash::Kind::Sha256)?, id);
+            assert_eq!(db.lookup_prefix(git_hash::Prefix::new(id, 60)?)?, vec![id]);
+        }
+        Ok(())
+    }
+}
+
 mod locate {
     use git_object::{bstr::ByteSlice, immutable, immutable::tree, tree::EntryMode, Kind};
 
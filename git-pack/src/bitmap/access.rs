@@ -0,0 +1,46 @@
+use git_object::Kind;
+
+use crate::bitmap::{Bits, File};
+
+/// Access to bitmaps
+impl File {
+    /// Return the set of all objects reachable from the commit at `index_position` in the pack index, or `None` if there is
+    /// no bitmap for it.
+    ///
+    /// Use [`index_position_at()`][File::index_position_at()] to learn where each object in the result is located
+    /// in the pack index.
+    pub fn reachable_from(&self, index_position: u32) -> Option<Bits> {
+        let mut entry_index = *self.entry_by_index_position.get(&index_position)?;
+        let mut chain = vec![entry_index];
+        while self.entries[entry_index].xor_offset != 0 {
+            entry_index -= self.entries[entry_index].xor_offset as usize;
+            chain.push(entry_index);
+        }
+        let mut bits = Bits::default();
+        for entry_index in chain.into_iter().rev() {
+            bits.xor_with(&Bits::from_ewah(&self.entries[entry_index].bitmap));
+        }
+        Some(bits)
+    }
+
+    /// Return the set of all objects in the pack of the given `kind`.
+    pub fn objects_of_kind(&self, kind: Kind) -> Bits {
+        Bits::from_ewah(match kind {
+            Kind::Commit => &self.commits,
+            Kind::Tree => &self.trees,
+            Kind::Blob => &self.blobs,
+            Kind::Tag => &self.tags,
+        })
+    }
+
+    /// Return the positions in the pack index of all commits that have a bitmap, in the order they are stored.
+    pub fn commit_index_positions(&self) -> impl ExactSizeIterator<Item = u32> + '_ {
+        self.entries.iter().map(|entry| entry.index_position)
+    }
+
+    /// Return the position in the pack index of the object represented by the bit at `pack_position`, or `None` if
+    /// there is no such object in the pack.
+    pub fn index_position_at(&self, pack_position: usize) -> Option<u32> {
+        self.pack_order.get(pack_position).copied()
+    }
+}
@@ -0,0 +1,92 @@
+use crate::bitmap::{ewah, Bits};
+
+const WORD_BITS: usize = 64;
+
+impl Bits {
+    /// Create a new instance from the given `words`, with bit `n` being bit `n % 64` of word `n / 64`.
+    pub fn from_words(words: Vec<u64>) -> Self {
+        Bits { words }
+    }
+
+    /// Decompress `bitmap` into its bits.
+    pub fn from_ewah(bitmap: &ewah::Vec) -> Self {
+        let mut bits = Bits {
+            words: Vec::with_capacity((bitmap.num_bits() + WORD_BITS - 1) / WORD_BITS),
+        };
+        bitmap.for_each_set_bit(|position| {
+            bits.set(position);
+            true
+        });
+        bits
+    }
+
+    /// Compress these bits into an EWAH bitmap.
+    pub fn to_ewah(&self) -> ewah::Vec {
+        self.iter_ones().collect()
+    }
+
+    /// The words holding all bits, as described in [`from_words()`][Bits::from_words()].
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Set the bit at `position`.
+    pub fn set(&mut self, position: usize) {
+        let word = position / WORD_BITS;
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (position % WORD_BITS);
+    }
+
+    /// Return true if the bit at `position` is set.
+    pub fn contains(&self, position: usize) -> bool {
+        self.words
+            .get(position / WORD_BITS)
+            .map_or(false, |word| word & (1 << (position % WORD_BITS)) != 0)
+    }
+
+    /// Return the amount of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Return true if no bit is set.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Iterate over the positions of all set bits in ascending order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_index, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(word_index * WORD_BITS + bit)
+            })
+        })
+    }
+
+    /// Set all bits that are set in `other`.
+    pub fn union_with(&mut self, other: &Bits) {
+        self.combine_with(other, |lhs, rhs| lhs | rhs)
+    }
+
+    /// Flip all bits that are set in `other`.
+    pub fn xor_with(&mut self, other: &Bits) {
+        self.combine_with(other, |lhs, rhs| lhs ^ rhs)
+    }
+
+    fn combine_with(&mut self, other: &Bits, op: impl Fn(u64, u64) -> u64) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (lhs, rhs) in self.words.iter_mut().zip(other.words.iter()) {
+            *lhs = op(*lhs, *rhs);
+        }
+    }
+}
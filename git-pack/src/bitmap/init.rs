@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{
+    bitmap::{
+        ewah, flags, pack_order, Entry, File, Version, ENTRY_HEADER_LEN, HASH_CACHE_ENTRY_SIZE,
+        HEADER_LEN_WITHOUT_CHECKSUM, LOOKUP_TABLE_ENTRY_SIZE, MAX_XOR_OFFSET, SIGNATURE,
+    },
+    index,
+};
+
+/// Returned by [`bitmap::File::at()`][crate::bitmap::File::at()].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read bitmap file at '{path}'")]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{message}")]
+    Corrupt { message: String },
+    #[error("Unsupported bitmap file version: {version}")]
+    UnsupportedVersion { version: u16 },
+    #[error("Bitmap file uses unsupported options: {flags:#x}")]
+    UnsupportedFlags { flags: u16 },
+    #[error("The bitmap file belongs to pack {actual}, but the index is for pack {expected}")]
+    PackMismatch {
+        expected: git_hash::ObjectId,
+        actual: git_hash::ObjectId,
+    },
+    #[error(transparent)]
+    Ewah(#[from] ewah::decode::Error),
+}
+
+/// Instantiation
+impl File {
+    /// Open the bitmap file at the given `path` which belongs to the pack of `index`.
+    pub fn at(path: impl AsRef<Path>, index: &index::File) -> Result<File, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|source| Error::Io {
+            source,
+            path: path.to_owned(),
+        })?;
+        Self::from_bytes(&data, path.to_owned(), index)
+    }
+
+    fn from_bytes(data: &[u8], path: PathBuf, index: &index::File) -> Result<File, Error> {
+        let corrupt = |message: String| Error::Corrupt { message };
+        let hash_len = index.hash_kind().len_in_bytes();
+        let header_len = HEADER_LEN_WITHOUT_CHECKSUM + hash_len;
+        if data.len() < header_len + hash_len {
+            return Err(corrupt(format!(
+                "Bitmap file of size {} is too small for even an empty bitmap file",
+                data.len()
+            )));
+        }
+        if &data[..SIGNATURE.len()] != SIGNATURE {
+            return Err(corrupt("Bitmap file does not start with expected signature".into()));
+        }
+        let version = match BigEndian::read_u16(&data[4..6]) {
+            1 => Version::V1,
+            version => return Err(Error::UnsupportedVersion { version }),
+        };
+        let file_flags = BigEndian::read_u16(&data[6..8]);
+        if file_flags & flags::FULL_DAG == 0
+            || file_flags & !(flags::FULL_DAG | flags::HASH_CACHE | flags::LOOKUP_TABLE) != 0
+        {
+            return Err(Error::UnsupportedFlags { flags: file_flags });
+        }
+        let num_entries = BigEndian::read_u32(&data[8..12]) as usize;
+        let pack_checksum = git_hash::ObjectId::from_bytes_or_panic(&data[HEADER_LEN_WITHOUT_CHECKSUM..header_len]);
+        if pack_checksum != index.pack_checksum() {
+            return Err(Error::PackMismatch {
+                expected: index.pack_checksum(),
+                actual: pack_checksum,
+            });
+        }
+
+        let num_objects = index.num_objects() as usize;
+        let mut trailing_bytes = hash_len;
+        if file_flags & flags::HASH_CACHE != 0 {
+            trailing_bytes += num_objects * HASH_CACHE_ENTRY_SIZE;
+        }
+        if file_flags & flags::LOOKUP_TABLE != 0 {
+            trailing_bytes += num_entries * LOOKUP_TABLE_ENTRY_SIZE;
+        }
+        let data = data
+            .len()
+            .checked_sub(trailing_bytes)
+            .filter(|end| *end >= header_len)
+            .map(|end| &data[header_len..end])
+            .ok_or_else(|| corrupt("Bitmap file is too small to hold its hash cache or lookup table".into()))?;
+
+        let (commits, data) = ewah::decode(data)?;
+        let (trees, data) = ewah::decode(data)?;
+        let (blobs, data) = ewah::decode(data)?;
+        let (tags, mut data) = ewah::decode(data)?;
+
+        let mut entries = Vec::with_capacity(num_entries);
+        let mut entry_by_index_position = HashMap::with_capacity(num_entries);
+        for entry_index in 0..num_entries {
+            if data.len() < ENTRY_HEADER_LEN {
+                return Err(corrupt(format!("Bitmap entry {} is truncated", entry_index)));
+            }
+            let index_position = BigEndian::read_u32(&data[..4]);
+            let xor_offset = data[4];
+            if index_position as usize >= num_objects {
+                return Err(corrupt(format!(
+                    "Bitmap entry {} refers to object {} in a pack of {} objects",
+                    entry_index, index_position, num_objects
+                )));
+            }
+            if xor_offset as usize > entry_index || xor_offset as usize > MAX_XOR_OFFSET {
+                return Err(corrupt(format!(
+                    "Bitmap entry {} has invalid xor offset {}",
+                    entry_index, xor_offset
+                )));
+            }
+            let (bitmap, rest) = ewah::decode(&data[ENTRY_HEADER_LEN..])?;
+            data = rest;
+            entry_by_index_position.insert(index_position, entries.len());
+            entries.push(Entry {
+                index_position,
+                xor_offset,
+                bitmap,
+            });
+        }
+        if !data.is_empty() {
+            return Err(corrupt(format!(
+                "Bitmap file has {} unexpected bytes after its last entry",
+                data.len()
+            )));
+        }
+
+        Ok(File {
+            path,
+            version,
+            pack_checksum,
+            commits,
+            trees,
+            blobs,
+            tags,
+            entries,
+            entry_by_index_position,
+            pack_order: pack_order(index),
+        })
+    }
+}
@@ -0,0 +1,106 @@
+//! Reachability bitmaps are stored next to a pack and its index as `.bitmap` file, and associate selected commits with
+//! the set of all objects reachable from them, which greatly accelerates counting objects for clones and fetches.
+//!
+//! Each bit represents one object of the pack, in the order objects appear in the pack data file.
+use std::{collections::HashMap, path::PathBuf};
+
+/// The version of a bitmap file
+#[derive(PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Version {
+    V1 = 1,
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::V1
+    }
+}
+
+/// An uncompressed set of bits, with each set bit representing an object by its position in a pack data file.
+#[derive(Default, PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bits {
+    words: Vec<u64>,
+}
+
+/// A representation of a bitmap file, conventionally located next to a pack with the `.bitmap` extension.
+pub struct File {
+    path: PathBuf,
+    version: Version,
+    pack_checksum: git_hash::ObjectId,
+    commits: ewah::Vec,
+    trees: ewah::Vec,
+    blobs: ewah::Vec,
+    tags: ewah::Vec,
+    entries: Vec<Entry>,
+    entry_by_index_position: HashMap<u32, usize>,
+    /// The position of each object in the pack index, in the order of objects in the pack.
+    pack_order: Vec<u32>,
+}
+
+struct Entry {
+    index_position: u32,
+    xor_offset: u8,
+    bitmap: ewah::Vec,
+}
+
+/// Basic file information
+impl File {
+    /// The version of the bitmap file
+    pub fn version(&self) -> Version {
+        self.version
+    }
+    /// The path of the opened bitmap file
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+    /// The checksum of the pack this bitmap file belongs to.
+    pub fn pack_checksum(&self) -> git_hash::ObjectId {
+        self.pack_checksum
+    }
+    /// The amount of commits with a reachability bitmap.
+    pub fn num_commits(&self) -> u32 {
+        self.entries.len() as u32
+    }
+    /// The amount of objects in the pack, which is also the amount of bits available in each bitmap.
+    pub fn num_objects(&self) -> u32 {
+        self.pack_order.len() as u32
+    }
+}
+
+const SIGNATURE: &[u8] = b"BITM";
+/// The length of the header without the trailing pack checksum, whose length depends on the kind of hash.
+const HEADER_LEN_WITHOUT_CHECKSUM: usize = 4 + 2 + 2 + 4;
+const ENTRY_HEADER_LEN: usize = 4 + 1 + 1;
+const LOOKUP_TABLE_ENTRY_SIZE: usize = 4 + 8 + 4;
+const HASH_CACHE_ENTRY_SIZE: usize = 4;
+const MAX_XOR_OFFSET: usize = 160;
+
+mod flags {
+    /// Every bitmap contains the full closure of objects reachable from its commit.
+    pub const FULL_DAG: u16 = 0x1;
+    /// A name-hash for each object in pack order is stored at the end of the file.
+    pub const HASH_CACHE: u16 = 0x4;
+    /// A table to look up bitmaps by commit without reading all of them is stored at the end of the file.
+    pub const LOOKUP_TABLE: u16 = 0x10;
+}
+
+fn pack_order(index: &crate::index::File) -> Vec<u32> {
+    let mut order: Vec<u32> = (0..index.num_objects()).collect();
+    order.sort_by_key(|index_position| index.pack_offset_at_index(*index_position));
+    order
+}
+
+pub use git_bitmap::ewah;
+
+mod bits;
+
+///
+pub mod init;
+
+mod access;
+
+///
+pub mod write;
@@ -0,0 +1,199 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use byteorder::{BigEndian, WriteBytesExt};
+use git_features::{
+    hash,
+    progress::{self, Progress},
+};
+use git_hash::ObjectId;
+use git_object::{immutable, tree::EntryMode, Kind};
+
+use crate::{
+    bitmap::{flags, pack_order, Bits, File, Version, SIGNATURE},
+    data, Bundle,
+};
+
+/// Returned by [`bitmap::File::write_for_commits()`][crate::bitmap::File::write_for_commits()]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Object {id} is reachable from the selected commits but not contained in the pack")]
+    ObjectNotInPack { id: ObjectId },
+    #[error("Object {id} was expected to be a commit, but is a {kind}")]
+    NotACommit { id: ObjectId, kind: Kind },
+    #[error("Could not decode pack entry of object {id}")]
+    DecodeEntry {
+        id: ObjectId,
+        source: data::decode_entry::Error,
+    },
+    #[error(transparent)]
+    Decode(#[from] immutable::object::decode::Error),
+    #[error("Interrupted by user")]
+    Interrupted,
+}
+
+/// The outcome of [`bitmap::File::write_for_commits()`][crate::bitmap::File::write_for_commits()]
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The checksum over all bytes of the written bitmap file, as stored in its trailer.
+    pub bitmap_checksum: ObjectId,
+    /// The amount of commits for which a bitmap was written.
+    pub num_commits: u32,
+}
+
+/// Writing
+impl File {
+    /// Write a bitmap file for the pack in `bundle` to `out`, with a reachability bitmap for each of the given `commits`,
+    /// which should be placed next to the pack with the `.bitmap` extension.
+    ///
+    /// All objects reachable from `commits` must be contained in the pack, as it's the case after a full repack.
+    /// Bitmaps for all branch tips and some of their ancestors make for the fastest object counting, while each bitmap adds
+    /// a few bytes per object in the pack to the file.
+    ///
+    /// `pack_cache` is used to accelerate decoding objects while traversing from each commit. Commits are processed from
+    /// oldest to newest to allow reusing already computed bitmaps of ancestors.
+    pub fn write_for_commits(
+        bundle: &Bundle,
+        commits: impl IntoIterator<Item = ObjectId>,
+        out: impl io::Write,
+        pack_cache: &mut impl crate::cache::DecodeEntry,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        let index = &bundle.index;
+        let pack_order = pack_order(index);
+        let mut pack_position_of = vec![0u32; pack_order.len()];
+        for (pack_position, index_position) in pack_order.iter().enumerate() {
+            pack_position_of[*index_position as usize] = pack_position as u32;
+        }
+
+        let mut objects_by_kind = [Bits::default(), Bits::default(), Bits::default(), Bits::default()];
+        for (pack_position, index_position) in pack_order.iter().enumerate() {
+            let kind = object_kind_at(bundle, index.pack_offset_at_index(*index_position))?;
+            objects_by_kind[match kind {
+                Kind::Commit => 0,
+                Kind::Tree => 1,
+                Kind::Blob => 2,
+                Kind::Tag => 3,
+            }]
+            .set(pack_position);
+        }
+
+        let mut buf = Vec::new();
+        let mut selected = Vec::new();
+        let mut seen = HashSet::new();
+        for id in commits {
+            if !seen.insert(id) {
+                continue;
+            }
+            let index_position = index.lookup(id).ok_or(Error::ObjectNotInPack { id })?;
+            let object = bundle
+                .get_object_by_index(index_position, &mut buf, pack_cache)
+                .map_err(|source| Error::DecodeEntry { id, source })?;
+            if object.kind != Kind::Commit {
+                return Err(Error::NotACommit { id, kind: object.kind });
+            }
+            let commit_time = immutable::Commit::from_bytes(object.data)?.committer.time.time;
+            selected.push((commit_time, id, index_position));
+        }
+        selected.sort();
+
+        progress.init(Some(selected.len()), progress::count("commits"));
+        let start = std::time::Instant::now();
+        let mut reachable_by_commit = HashMap::<ObjectId, Bits>::new();
+        let mut stack = Vec::new();
+        for (_, commit_id, _) in &selected {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            let mut reachable = Bits::default();
+            stack.clear();
+            stack.push(*commit_id);
+            while let Some(id) = stack.pop() {
+                let index_position = index.lookup(id).ok_or(Error::ObjectNotInPack { id })?;
+                let pack_position = pack_position_of[index_position as usize] as usize;
+                if reachable.contains(pack_position) {
+                    continue;
+                }
+                if let Some(ancestor_reachable) = reachable_by_commit.get(&id) {
+                    reachable.union_with(ancestor_reachable);
+                    continue;
+                }
+                reachable.set(pack_position);
+                let object = bundle
+                    .get_object_by_index(index_position, &mut buf, pack_cache)
+                    .map_err(|source| Error::DecodeEntry { id, source })?;
+                match object.kind {
+                    Kind::Commit => {
+                        let commit = immutable::Commit::from_bytes(object.data)?;
+                        stack.push(commit.tree());
+                        stack.extend(commit.parents());
+                    }
+                    Kind::Tree => {
+                        for entry in immutable::TreeIter::from_bytes(object.data) {
+                            let entry = entry?;
+                            // Submodule commits are not part of this repository.
+                            if entry.mode != EntryMode::Commit {
+                                stack.push(entry.oid.to_owned());
+                            }
+                        }
+                    }
+                    Kind::Tag => stack.push(immutable::Tag::from_bytes(object.data)?.target()),
+                    Kind::Blob => {}
+                }
+            }
+            reachable_by_commit.insert(*commit_id, reachable);
+            progress.inc();
+        }
+        progress.show_throughput(start);
+
+        let mut out = hash::Write::new(io::BufWriter::with_capacity(8 * 4096, out), index.hash_kind());
+        out.write_all(SIGNATURE)?;
+        out.write_u16::<BigEndian>(Version::V1 as u16)?;
+        out.write_u16::<BigEndian>(flags::FULL_DAG)?;
+        out.write_u32::<BigEndian>(selected.len() as u32)?;
+        out.write_all(index.pack_checksum().as_slice())?;
+        for objects in &objects_by_kind {
+            objects.to_ewah().write_to(&mut out)?;
+        }
+        for (_, commit_id, index_position) in &selected {
+            out.write_u32::<BigEndian>(*index_position)?;
+            out.write_u8(0 /* xor offset */)?;
+            out.write_u8(0 /* flags */)?;
+            reachable_by_commit[commit_id].to_ewah().write_to(&mut out)?;
+        }
+
+        let bitmap_checksum = out.hash.digest();
+        let mut out = out.inner;
+        out.write_all(bitmap_checksum.as_slice())?;
+        out.flush()?;
+
+        Ok(Outcome {
+            bitmap_checksum,
+            num_commits: selected.len() as u32,
+        })
+    }
+}
+
+/// Return the kind of object stored at `pack_offset`, following delta chains to their base object as needed.
+fn object_kind_at(bundle: &Bundle, mut pack_offset: u64) -> Result<Kind, Error> {
+    loop {
+        let entry = bundle.pack.entry(pack_offset);
+        pack_offset = match entry.header {
+            data::entry::Header::OfsDelta { base_distance } => entry.base_pack_offset(base_distance),
+            data::entry::Header::RefDelta { base_id } => bundle
+                .index
+                .lookup(base_id)
+                .map(|index_position| bundle.index.pack_offset_at_index(index_position))
+                .ok_or(Error::ObjectNotInPack { id: base_id })?,
+            header => return Ok(header.as_kind().expect("base objects to have a kind")),
+        }
+    }
+}
@@ -1,7 +1,4 @@
-use std::{
-    convert::TryFrom,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 mod find;
 ///
@@ -80,37 +77,36 @@ pub struct Bundle {
     pub pack: crate::data::File,
     /// The index file corresponding to `pack`
     pub index: crate::index::File,
+    /// The reachability bitmaps for some commits in `pack`, if there is a `.bitmap` file for it.
+    pub bitmap: Option<crate::bitmap::File>,
 }
 
 /// Initialization
 impl Bundle {
-    /// Create a `Bundle` from `path`, which is either a pack file _(*.pack)_ or an index file _(*.idx)_.
+    /// Create a `Bundle` from `path`, which is either a pack file _(*.pack)_ or an index file _(*.idx)_, whose object ids
+    /// and checksums are hashes of `hash_kind`.
     ///
     /// The corresponding complementary file is expected to be present.
-    /// Also available via [`Bundle::try_from()`].
-    pub fn at(path: impl AsRef<Path>) -> Result<Self, Error> {
-        Self::try_from(path.as_ref())
-    }
-}
-
-impl TryFrom<&Path> for Bundle {
-    type Error = Error;
-
-    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+    /// A `.bitmap` file is loaded as well if present. Like git does, it's ignored if it can't be read or belongs to another pack.
+    pub fn at(path: impl AsRef<Path>, hash_kind: git_hash::Kind) -> Result<Self, Error> {
+        let path = path.as_ref();
         let ext = path
             .extension()
             .and_then(|e| e.to_str())
             .ok_or_else(|| Error::InvalidPath(path.to_owned()))?;
-        Ok(match ext {
-            "idx" => Self {
-                index: crate::index::File::at(path)?,
-                pack: crate::data::File::at(path.with_extension("pack"))?,
-            },
-            "pack" => Self {
-                pack: crate::data::File::at(path)?,
-                index: crate::index::File::at(path.with_extension("idx"))?,
-            },
+        let index_path = match ext {
+            "idx" => path.to_owned(),
+            "pack" => path.with_extension("idx"),
             _ => return Err(Error::InvalidPath(path.to_owned())),
-        })
+        };
+        let index = crate::index::File::at(&index_path, hash_kind)?;
+        let pack = crate::data::File::at(index_path.with_extension("pack"), hash_kind)?;
+        let bitmap_path = path.with_extension("bitmap");
+        let bitmap = if bitmap_path.is_file() {
+            crate::bitmap::File::at(bitmap_path, &index).ok()
+        } else {
+            None
+        };
+        Ok(Bundle { pack, index, bitmap })
     }
 }
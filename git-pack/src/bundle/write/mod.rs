@@ -11,7 +11,7 @@ use git_tempfile::{handle::Writable, AutoRemove, ContainingDirectory};
 use crate::data;
 
 mod error;
-use error::Error;
+pub use error::Error;
 
 mod types;
 use types::{LockWriter, PassThrough};
@@ -65,6 +65,7 @@ impl crate::Bundle {
                         buffered_pack,
                         options.iteration_mode,
                         data::input::EntryDataMode::KeepAndCrc32,
+                        options.hash_kind,
                     )?,
                     thin_pack_lookup_fn,
                 );
@@ -75,7 +76,7 @@ impl crate::Bundle {
                         writer: data_file.clone(),
                     },
                     pack_kind,
-                    git_hash::Kind::Sha1,
+                    options.hash_kind,
                 );
                 (Box::new(pack_entries_iter), pack_kind)
             }
@@ -96,6 +97,7 @@ impl crate::Bundle {
                     buffered_pack,
                     options.iteration_mode,
                     data::input::EntryDataMode::Crc32,
+                    options.hash_kind,
                 )?;
                 let pack_kind = pack_entries_iter.kind();
                 (Box::new(pack_entries_iter), pack_kind)
@@ -161,6 +163,7 @@ impl crate::Bundle {
                         buffered_pack,
                         options.iteration_mode,
                         data::input::EntryDataMode::KeepAndCrc32,
+                        options.hash_kind,
                     )?,
                     thin_pack_lookup_fn,
                 );
@@ -180,6 +183,7 @@ impl crate::Bundle {
                     buffered_pack,
                     options.iteration_mode,
                     data::input::EntryDataMode::Crc32,
+                    options.hash_kind,
                 )?;
                 let pack_kind = pack_entries_iter.kind();
                 (Box::new(pack_entries_iter), pack_kind)
@@ -213,6 +217,7 @@ impl crate::Bundle {
             thread_limit,
             iteration_mode: _,
             index_kind,
+            hash_kind: _,
         }: Options,
         data_file: Arc<parking_lot::Mutex<git_tempfile::Handle<Writable>>>,
         pack_entries_iter: impl Iterator<Item = Result<data::input::Entry, data::input::Error>>,
@@ -237,7 +242,7 @@ impl crate::Bundle {
                     should_interrupt,
                 )?;
 
-                let data_path = directory.join(format!("{}.pack", outcome.data_hash.to_sha1_hex_string()));
+                let data_path = directory.join(format!("{}.pack", outcome.data_hash));
                 let index_path = data_path.with_extension("idx");
 
                 Arc::try_unwrap(data_file)
@@ -12,6 +12,8 @@ pub struct Options {
     pub iteration_mode: crate::data::input::Mode,
     /// The version of pack index to write, should be [`crate::index::Version::default()`]
     pub index_kind: crate::index::Version,
+    /// The kind of hash used by the pack for object ids and its checksum, which has to be known as packs don't record it.
+    pub hash_kind: git_hash::Kind,
 }
 
 impl Default for Options {
@@ -21,6 +23,7 @@ impl Default for Options {
             thread_limit: None,
             iteration_mode: crate::data::input::Mode::Verify,
             index_kind: Default::default(),
+            hash_kind: Default::default(),
         }
     }
 }
@@ -44,7 +47,9 @@ pub struct Outcome {
 impl Outcome {
     /// Instantiate a bundle from the newly written index and data file that are represented by this `Outcome`
     pub fn to_bundle(&self) -> Option<Result<crate::Bundle, crate::bundle::Error>> {
-        self.index_path.as_ref().map(crate::Bundle::at)
+        self.index_path
+            .as_ref()
+            .map(|path| crate::Bundle::at(path, self.index.index_hash.kind()))
     }
 }
 
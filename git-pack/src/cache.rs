@@ -36,16 +36,47 @@ impl<T: DecodeEntry + ?Sized> DecodeEntry for Box<T> {
     }
 }
 
+/// A trait to model putting entire objects identified by their id into a cache, and fetching them.
+///
+/// It is used to speed up workloads that access the same objects over and over, like [diffing consecutive trees][crate::FindExt::find_existing_tree_iter_cached()].
+pub trait Object {
+    /// Store the object `id` of `kind` with `data` in the cache.
+    ///
+    /// It is up to the cache implementation whether that actually happens or not.
+    fn put(&mut self, id: git_hash::ObjectId, kind: git_object::Kind, data: &[u8]);
+    /// Attempt to fetch the object `id` and store its data in `out`, as previously stored with [`Object::put()`], and return its `kind`.
+    fn get(&mut self, id: &git_hash::oid, out: &mut Vec<u8>) -> Option<git_object::Kind>;
+}
+
+impl Object for Never {
+    fn put(&mut self, _id: git_hash::ObjectId, _kind: git_object::Kind, _data: &[u8]) {}
+    fn get(&mut self, _id: &git_hash::oid, _out: &mut Vec<u8>) -> Option<git_object::Kind> {
+        None
+    }
+}
+
+impl<T: Object + ?Sized> Object for Box<T> {
+    fn put(&mut self, id: git_hash::ObjectId, kind: Kind, data: &[u8]) {
+        self.deref_mut().put(id, kind, data)
+    }
+
+    fn get(&mut self, id: &git_hash::oid, out: &mut Vec<u8>) -> Option<Kind> {
+        self.deref_mut().get(id, out)
+    }
+}
+
 /// Various implementations of [`DecodeEntry`] using least-recently-used algorithms.
-#[cfg(any(feature = "pack-cache-lru-dynamic", feature = "pack-cache-lru-static"))]
+#[cfg(any(
+    feature = "pack-cache-lru-dynamic",
+    feature = "pack-cache-lru-static",
+    feature = "object-cache-dynamic"
+))]
 pub mod lru {
-    use super::DecodeEntry;
+    #[cfg(any(feature = "pack-cache-lru-dynamic", feature = "object-cache-dynamic"))]
+    pub(crate) mod memory {
+        use std::{collections::hash_map::RandomState, hash::Hash, num::NonZeroUsize};
 
-    #[cfg(feature = "pack-cache-lru-dynamic")]
-    mod memory {
-        use super::DecodeEntry;
         use clru::WeightScale;
-        use std::num::NonZeroUsize;
 
         struct Entry {
             data: Vec<u8>,
@@ -53,42 +84,41 @@ pub mod lru {
             compressed_size: usize,
         }
 
-        type Key = (u32, u64);
         struct CustomScale;
 
-        impl WeightScale<Key, Entry> for CustomScale {
-            fn weight(&self, _key: &Key, value: &Entry) -> usize {
+        impl<K> WeightScale<K, Entry> for CustomScale {
+            fn weight(&self, _key: &K, value: &Entry) -> usize {
                 value.data.len()
             }
         }
 
-        /// An LRU cache with hash map backing and an eviction rule based on the memory usage for object data in bytes.
-        pub struct MemoryCappedHashmap {
-            inner: clru::CLruCache<Key, Entry, std::collections::hash_map::RandomState, CustomScale>,
+        /// An LRU cache of object data by `K` which evicts entries once they use more than a given amount of memory.
+        pub(crate) struct MemoryCapped<K: Clone + Eq + Hash> {
+            /// `None` if the memory cap is 0, which caches nothing.
+            inner: Option<clru::CLruCache<K, Entry, RandomState, CustomScale>>,
             free_list: Vec<Vec<u8>>,
             debug: git_features::cache::Debug,
         }
 
-        impl MemoryCappedHashmap {
-            /// Return a new instance which evicts least recently used items if it uses more than `memory_cap_in_bytes`
-            /// object data.
-            pub fn new(memory_cap_in_bytes: usize) -> MemoryCappedHashmap {
-                MemoryCappedHashmap {
-                    inner: clru::CLruCache::with_config(
-                        clru::CLruCacheConfig::new(NonZeroUsize::new(memory_cap_in_bytes).expect("non zero"))
-                            .with_scale(CustomScale),
-                    ),
+        impl<K: Clone + Eq + Hash> MemoryCapped<K> {
+            pub(crate) fn new(memory_cap_in_bytes: usize, debug_name: &str) -> Self {
+                MemoryCapped {
+                    inner: NonZeroUsize::new(memory_cap_in_bytes).map(|cap| {
+                        clru::CLruCache::with_config(clru::CLruCacheConfig::new(cap).with_scale(CustomScale))
+                    }),
                     free_list: Vec::new(),
-                    debug: git_features::cache::Debug::new(format!("MemoryCappedHashmap({}B)", memory_cap_in_bytes)),
+                    debug: git_features::cache::Debug::new(format!("{}({}B)", debug_name, memory_cap_in_bytes)),
                 }
             }
-        }
 
-        impl DecodeEntry for MemoryCappedHashmap {
-            fn put(&mut self, pack_id: u32, offset: u64, data: &[u8], kind: git_object::Kind, compressed_size: usize) {
+            pub(crate) fn put(&mut self, key: K, data: &[u8], kind: git_object::Kind, compressed_size: usize) {
                 self.debug.put();
-                if let Ok(Some(previous_entry)) = self.inner.put_with_weight(
-                    (pack_id, offset),
+                let inner = match self.inner.as_mut() {
+                    Some(inner) => inner,
+                    None => return,
+                };
+                if let Ok(Some(previous_entry)) = inner.put_with_weight(
+                    key,
                     Entry {
                         data: self
                             .free_list
@@ -108,8 +138,8 @@ pub mod lru {
                 }
             }
 
-            fn get(&mut self, pack_id: u32, offset: u64, out: &mut Vec<u8>) -> Option<(git_object::Kind, usize)> {
-                let res = self.inner.get(&(pack_id, offset)).map(|e| {
+            pub(crate) fn get(&mut self, key: &K, out: &mut Vec<u8>) -> Option<(git_object::Kind, usize)> {
+                let res = self.inner.as_mut().and_then(|inner| inner.get(key)).map(|e| {
                     out.resize(e.data.len(), 0);
                     out.copy_from_slice(&e.data);
                     (e.kind, e.compressed_size)
@@ -122,13 +152,37 @@ pub mod lru {
                 res
             }
         }
+
+        /// An LRU cache with hash map backing and an eviction rule based on the memory usage for object data in bytes.
+        #[cfg(feature = "pack-cache-lru-dynamic")]
+        pub struct MemoryCappedHashmap(MemoryCapped<(u32, u64)>);
+
+        #[cfg(feature = "pack-cache-lru-dynamic")]
+        impl MemoryCappedHashmap {
+            /// Return a new instance which evicts least recently used items if it uses more than `memory_cap_in_bytes`
+            /// object data, or which caches nothing if it is 0.
+            pub fn new(memory_cap_in_bytes: usize) -> MemoryCappedHashmap {
+                MemoryCappedHashmap(MemoryCapped::new(memory_cap_in_bytes, "MemoryCappedHashmap"))
+            }
+        }
+
+        #[cfg(feature = "pack-cache-lru-dynamic")]
+        impl crate::cache::DecodeEntry for MemoryCappedHashmap {
+            fn put(&mut self, pack_id: u32, offset: u64, data: &[u8], kind: git_object::Kind, compressed_size: usize) {
+                self.0.put((pack_id, offset), data, kind, compressed_size)
+            }
+
+            fn get(&mut self, pack_id: u32, offset: u64, out: &mut Vec<u8>) -> Option<(git_object::Kind, usize)> {
+                self.0.get(&(pack_id, offset), out)
+            }
+        }
     }
     #[cfg(feature = "pack-cache-lru-dynamic")]
     pub use memory::MemoryCappedHashmap;
 
     #[cfg(feature = "pack-cache-lru-static")]
     mod _static {
-        use super::DecodeEntry;
+        use crate::cache::DecodeEntry;
         struct Entry {
             pack_id: u32,
             offset: u64,
@@ -201,3 +255,32 @@ pub mod lru {
     #[cfg(feature = "pack-cache-lru-static")]
     pub use _static::StaticLinkedList;
 }
+
+/// Implementations of [`Object`] using least-recently-used algorithms.
+#[cfg(feature = "object-cache-dynamic")]
+pub mod object {
+    use git_hash::{oid, ObjectId};
+
+    use super::{lru::memory::MemoryCapped, Object};
+
+    /// An LRU cache with hash map backing and an eviction rule based on the memory usage for object data in bytes.
+    pub struct MemoryCappedHashmap(MemoryCapped<ObjectId>);
+
+    impl MemoryCappedHashmap {
+        /// Return a new instance which evicts least recently used items if it uses more than `memory_cap_in_bytes`
+        /// object data, or which caches nothing if it is 0.
+        pub fn new(memory_cap_in_bytes: usize) -> MemoryCappedHashmap {
+            MemoryCappedHashmap(MemoryCapped::new(memory_cap_in_bytes, "MemoryCappedObjectHashmap"))
+        }
+    }
+
+    impl Object for MemoryCappedHashmap {
+        fn put(&mut self, id: ObjectId, kind: git_object::Kind, data: &[u8]) {
+            self.0.put(id, data, kind, 0)
+        }
+
+        fn get(&mut self, id: &oid, out: &mut Vec<u8>) -> Option<git_object::Kind> {
+            self.0.get(&id.to_owned(), out).map(|(kind, _)| kind)
+        }
+    }
+}
@@ -15,6 +15,14 @@ pub fn decode_header_size(d: &[u8]) -> (u64, usize) {
     (size, consumed)
 }
 
+/// Apply the delta instructions in `data`, which follow the two header sizes of a delta, to `base` to produce `target`.
+/// Equivalent to [this canonical git function](https://github.com/git/git/blob/311531c9de557d25ac087c1637818bd2aad6eb3a/patch-delta.c)
+///
+/// `target` must be exactly as large as the result size in the delta header.
+///
+/// # Panics
+///
+/// If the instructions are invalid or don't fit `base` or `target`.
 pub fn apply(base: &[u8], mut target: &mut [u8], data: &[u8]) {
     let mut i = 0;
     while let Some(cmd) = data.get(i) {
@@ -68,3 +76,192 @@ pub fn apply(base: &[u8], mut target: &mut [u8], data: &[u8]) {
     assert_eq!(i, data.len());
     assert_eq!(target.len(), 0);
 }
+
+/// The amount of bytes hashed at a time to find copies from the base, equivalent to git's `RABIN_WINDOW`.
+const BLOCK_SIZE: usize = 16;
+/// The maximum amount of base positions to remember for a single hash, to bound the time spent on repetitive data.
+const MAX_POSITIONS_PER_HASH: usize = 64;
+/// The maximum amount of bytes a single insert instruction can carry.
+const MAX_INSERT_SIZE: usize = 0x7f;
+/// The maximum amount of bytes a single copy instruction copies, which all versions of git can decode.
+const MAX_COPY_SIZE: usize = 0x10000;
+const HASH_MULTIPLIER: u32 = 0x0100_0193;
+
+/// Write the delta instructions to produce `target` from `base` into `out`, which is cleared beforehand.
+///
+/// Use an [`Index`] instead to produce deltas for multiple targets against the same base, or to bound their size.
+pub fn encode(base: &[u8], target: &[u8], out: &mut Vec<u8>) {
+    let fits = Index::new(base).encode(target, usize::MAX, out);
+    debug_assert!(fits, "there is no size limit");
+}
+
+/// An index over a base object to find the blocks it shares with target objects quickly, similar to git's `delta_index`.
+///
+/// The deltas it produces start with the size of the base and of the target as read by [`decode_header_size()`],
+/// followed by the instructions to pass to [`apply()`]. The instructions copy ranges of the base where the target
+/// shares at least 16 bytes with it, found with a rolling hash, and insert all other bytes.
+///
+/// The base is borrowed or owned through `T`, which allows keeping an index around together with its base.
+pub struct Index<T> {
+    base: T,
+    /// `(hash, position)` pairs of blocks in `base`, sorted by hash.
+    blocks: Vec<(u32, u32)>,
+    /// The factor to remove the leading byte of a block from a rolling hash.
+    leading_byte_factor: u32,
+}
+
+impl<T: AsRef<[u8]>> Index<T> {
+    /// Create a new instance to produce deltas against `base`.
+    pub fn new(base: T) -> Self {
+        let mut blocks: Vec<_> = base
+            .as_ref()
+            .chunks_exact(BLOCK_SIZE)
+            .enumerate()
+            .map(|(block_index, block)| (hash(block), (block_index * BLOCK_SIZE) as u32))
+            .collect();
+        // Runs of identical blocks would only yield the same copies.
+        blocks.dedup_by_key(|(hash, _)| *hash);
+        blocks.sort_by_key(|(hash, _)| *hash);
+
+        let mut kept = 0;
+        let mut run_start = 0;
+        for index in 0..blocks.len() {
+            if blocks[index].0 != blocks[run_start].0 {
+                run_start = index;
+            }
+            if index - run_start < MAX_POSITIONS_PER_HASH {
+                blocks[kept] = blocks[index];
+                kept += 1;
+            }
+        }
+        blocks.truncate(kept);
+
+        Index {
+            base,
+            blocks,
+            leading_byte_factor: (1..BLOCK_SIZE).fold(1u32, |f, _| f.wrapping_mul(HASH_MULTIPLIER)),
+        }
+    }
+
+    /// Return the base object we produce deltas against.
+    pub fn base(&self) -> &[u8] {
+        self.base.as_ref()
+    }
+
+    /// Write the delta to produce `target` from our base into `out`, which is cleared beforehand.
+    ///
+    /// Return false if the delta would be larger than `max_size` bytes, in which case `out` contains an incomplete delta.
+    pub fn encode(&self, target: &[u8], max_size: usize, out: &mut Vec<u8>) -> bool {
+        let base = self.base();
+        out.clear();
+        encode_header_size(base.len() as u64, out);
+        encode_header_size(target.len() as u64, out);
+
+        let mut insert_start = 0;
+        let mut pos = 0;
+        let mut rolling_hash = target.get(..BLOCK_SIZE).map(hash).unwrap_or(0);
+        while pos + BLOCK_SIZE <= target.len() {
+            match self.longest_copy_at(rolling_hash, &target[pos..]) {
+                Some((mut base_offset, mut copy_len)) => {
+                    while pos > insert_start && base_offset > 0 && base[base_offset - 1] == target[pos - 1] {
+                        base_offset -= 1;
+                        pos -= 1;
+                        copy_len += 1;
+                    }
+                    encode_inserts(&target[insert_start..pos], out);
+                    encode_copies(base_offset, copy_len, out);
+                    pos += copy_len;
+                    insert_start = pos;
+                    if let Some(block) = target.get(pos..pos + BLOCK_SIZE) {
+                        rolling_hash = hash(block);
+                    }
+                }
+                None => {
+                    if let Some(&incoming) = target.get(pos + BLOCK_SIZE) {
+                        rolling_hash = rolling_hash
+                            .wrapping_sub((target[pos] as u32).wrapping_mul(self.leading_byte_factor))
+                            .wrapping_mul(HASH_MULTIPLIER)
+                            .wrapping_add(incoming as u32);
+                    }
+                    pos += 1;
+                }
+            }
+            if out.len() + (pos - insert_start) > max_size {
+                return false;
+            }
+        }
+        encode_inserts(&target[insert_start..], out);
+        out.len() <= max_size
+    }
+
+    /// Return the base offset and length of the longest match of `target`'s beginning, whose first block has `block_hash`.
+    fn longest_copy_at(&self, block_hash: u32, target: &[u8]) -> Option<(usize, usize)> {
+        let first = self.blocks.partition_point(|(hash, _)| *hash < block_hash);
+        self.blocks[first..]
+            .iter()
+            .take_while(|(hash, _)| *hash == block_hash)
+            .map(|(_, base_offset)| {
+                let base_offset = *base_offset as usize;
+                let len = self.base()[base_offset..]
+                    .iter()
+                    .zip(target)
+                    .take_while(|(base, target)| base == target)
+                    .count();
+                (base_offset, len)
+            })
+            .filter(|(_, len)| *len >= BLOCK_SIZE)
+            .max_by_key(|(_, len)| *len)
+    }
+}
+
+fn hash(block: &[u8]) -> u32 {
+    block
+        .iter()
+        .fold(0u32, |h, b| h.wrapping_mul(HASH_MULTIPLIER).wrapping_add(*b as u32))
+}
+
+fn encode_header_size(mut size: u64, out: &mut Vec<u8>) {
+    loop {
+        let byte = (size & 0x7f) as u8;
+        size >>= 7;
+        if size == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn encode_inserts(data: &[u8], out: &mut Vec<u8>) {
+    for chunk in data.chunks(MAX_INSERT_SIZE) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+fn encode_copies(mut base_offset: usize, mut len: usize, out: &mut Vec<u8>) {
+    while len != 0 {
+        let size = len.min(MAX_COPY_SIZE);
+        let cmd_pos = out.len();
+        let mut cmd = 0b1000_0000;
+        out.push(cmd);
+        for (byte_index, flag) in [0b0000_0001, 0b0000_0010, 0b0000_0100, 0b0000_1000].iter().enumerate() {
+            let byte = (base_offset >> (byte_index * 8)) as u8;
+            if byte != 0 {
+                cmd |= flag;
+                out.push(byte);
+            }
+        }
+        // A size of 0x10000 is encoded by leaving out all size bytes.
+        for (byte_index, flag) in [0b0001_0000, 0b0010_0000, 0b0100_0000].iter().enumerate() {
+            let byte = ((size & 0xffff) >> (byte_index * 8)) as u8;
+            if byte != 0 {
+                cmd |= flag;
+                out.push(byte);
+            }
+        }
+        out[cmd_pos] = cmd;
+        base_offset += size;
+        len -= size;
+    }
+}
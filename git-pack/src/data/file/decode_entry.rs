@@ -108,7 +108,7 @@ impl File {
         assert!(pack_offset <= self.data.len(), "offset out of bounds");
 
         let object_data = &self.data[pack_offset..];
-        crate::data::Entry::from_bytes(object_data, offset)
+        crate::data::Entry::from_bytes(object_data, offset, self.hash_kind.len_in_bytes())
     }
 
     /// Decompress the object expected at the given data offset, sans pack header. This information is only
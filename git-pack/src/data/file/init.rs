@@ -1,34 +1,23 @@
-use std::{
-    convert::{TryFrom, TryInto},
-    path::Path,
-};
+use std::{convert::TryInto, path::Path};
 
 use filebuffer::FileBuffer;
-use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
 
-use crate::data;
+use crate::{data, data::header::N32_SIZE};
 
 /// Instantiation
 impl data::File {
-    /// Try opening a data file at the given `path`.
-    pub fn at(path: impl AsRef<Path>) -> Result<data::File, data::header::decode::Error> {
-        data::File::try_from(path.as_ref())
-    }
-}
-
-impl TryFrom<&Path> for data::File {
-    type Error = data::header::decode::Error;
-
-    /// Try opening a data file at the given `path`.
-    fn try_from(path: &Path) -> Result<Self, Self::Error> {
-        use crate::data::header::N32_SIZE;
-
+    /// Try opening a data file at the given `path` whose object ids and checksum are hashes of `hash_kind`.
+    ///
+    /// Pack data files don't record the kind of hash they use, which is why it must be known beforehand, for instance
+    /// from the [index file][crate::index::File::hash_kind()] belonging to the pack.
+    pub fn at(path: impl AsRef<Path>, hash_kind: git_hash::Kind) -> Result<data::File, data::header::decode::Error> {
+        let path = path.as_ref();
         let data = FileBuffer::open(path).map_err(|e| data::header::decode::Error::Io {
             source: e,
             path: path.to_owned(),
         })?;
         let pack_len = data.len();
-        if pack_len < N32_SIZE * 3 + SHA1_SIZE {
+        if pack_len < N32_SIZE * 3 + hash_kind.len_in_bytes() {
             return Err(data::header::decode::Error::Corrupt(format!(
                 "Pack data of size {} is too small for even an empty pack",
                 pack_len
@@ -42,6 +31,7 @@ impl TryFrom<&Path> for data::File {
             id: git_features::hash::crc32(path.as_os_str().to_string_lossy().as_bytes()),
             version: kind,
             num_objects,
+            hash_kind,
         })
     }
 }
@@ -2,7 +2,7 @@ use std::{fs, io};
 
 use git_features::{
     hash,
-    hash::Sha1,
+    hash::Hasher,
     zlib::{stream::inflate::ReadBoxed, Decompress},
 };
 
@@ -18,7 +18,8 @@ pub struct BytesToEntriesIter<BR> {
     had_error: bool,
     kind: crate::data::Version,
     objects_left: u32,
-    hash: Option<Sha1>,
+    hash: Option<Hasher>,
+    hash_kind: git_hash::Kind,
     mode: input::Mode,
     compressed: input::EntryDataMode,
     compressed_buf: Option<Vec<u8>>,
@@ -39,12 +40,14 @@ where
     }
 
     /// Obtain an iterator from a `read` stream to a pack data file and configure it using `mode` and `compressed`.
+    /// `hash_kind` is the kind of hash used for object ids and the trailing checksum of the pack.
     ///
     /// Note that `read` is expected at the beginning of a valid pack data file with a header, entries and a trailer.
     pub fn new_from_header(
         mut read: BR,
         mode: input::Mode,
         compressed: input::EntryDataMode,
+        hash_kind: git_hash::Kind,
     ) -> Result<BytesToEntriesIter<BR>, input::Error> {
         let mut header_data = [0u8; 12];
         read.read_exact(&mut header_data)?;
@@ -64,10 +67,11 @@ where
             kind,
             objects_left: num_objects,
             hash: (mode != input::Mode::AsIs).then(|| {
-                let mut hash = Sha1::default();
+                let mut hash = hash::hasher(hash_kind);
                 hash.update(&header_data);
                 hash
             }),
+            hash_kind,
             mode,
             compressed_buf: None,
         })
@@ -86,11 +90,11 @@ where
                         hash,
                     },
                 );
-                let res = crate::data::Entry::from_read(&mut read, self.offset);
+                let res = crate::data::Entry::from_read(&mut read, self.offset, self.hash_kind.len_in_bytes());
                 self.hash = Some(read.write.hash);
                 res
             }
-            None => crate::data::Entry::from_read(&mut self.read, self.offset),
+            None => crate::data::Entry::from_read(&mut self.read, self.offset, self.hash_kind.len_in_bytes()),
         }
         .map_err(input::Error::from)?;
 
@@ -137,7 +141,7 @@ where
         }
 
         let crc32 = if self.compressed.crc32() {
-            let mut header_buf = [0u8; 32];
+            let mut header_buf = [0u8; 64];
             let header_len = entry.header.write_to(bytes_copied, header_buf.as_mut())?;
             let state = git_features::hash::crc32_update(0, &header_buf[..header_len]);
             Some(git_features::hash::crc32_update(state, &compressed))
@@ -155,7 +159,7 @@ where
 
         // Last objects gets trailer (which is potentially verified)
         let trailer = if self.objects_left == 0 {
-            let mut id = git_hash::ObjectId::from([0; 20]);
+            let mut id = git_hash::ObjectId::null_sha(self.hash_kind);
             if let Err(err) = self.read.read_exact(id.as_mut_slice()) {
                 if self.mode != input::Mode::Restore {
                     return Err(err.into());
@@ -163,7 +167,7 @@ where
             }
 
             if let Some(hash) = self.hash.take() {
-                let actual_id = git_hash::ObjectId::from(hash.digest());
+                let actual_id = hash.digest();
                 if self.mode == input::Mode::Restore {
                     id = actual_id;
                 }
@@ -177,7 +181,7 @@ where
             Some(id)
         } else if self.mode == input::Mode::Restore {
             let hash = self.hash.clone().expect("in restore mode a hash is set");
-            Some(git_hash::ObjectId::from(hash.digest()))
+            Some(hash.digest())
         } else {
             None
         };
@@ -271,6 +275,11 @@ impl crate::data::File {
     /// Returns an iterator over [`Entries`][crate::data::input::Entry], without making use of the memory mapping.
     pub fn streaming_iter(&self) -> Result<BytesToEntriesIter<impl io::BufRead>, input::Error> {
         let reader = io::BufReader::with_capacity(4096 * 8, fs::File::open(&self.path)?);
-        BytesToEntriesIter::new_from_header(reader, input::Mode::Verify, input::EntryDataMode::KeepAndCrc32)
+        BytesToEntriesIter::new_from_header(
+            reader,
+            input::Mode::Verify,
+            input::EntryDataMode::KeepAndCrc32,
+            self.hash_kind,
+        )
     }
 }
@@ -39,16 +39,12 @@ where
     ///
     /// # Panics
     ///
-    /// Not all combinations of `hash_kind` and `version` are supported currently triggering assertion errors.
+    /// Not all pack versions are supported currently, triggering assertion errors.
     pub fn new(input: I, output: W, version: crate::data::Version, hash_kind: git_hash::Kind) -> Self {
         assert!(
             matches!(version, crate::data::Version::V2),
             "currently only pack version 2 can be written",
         );
-        assert!(
-            matches!(hash_kind, git_hash::Kind::Sha1),
-            "currently only Sha1 is supported, right now we don't know how other hashes are encoded",
-        );
         EntriesToBytesIter {
             input: input.peekable(),
             output,
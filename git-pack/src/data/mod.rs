@@ -2,7 +2,6 @@
 use std::{convert::TryInto, path::Path};
 
 use filebuffer::FileBuffer;
-use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
 
 mod file;
 pub use file::{decode_entry, verify, ResolvedBase};
@@ -54,6 +53,8 @@ pub struct File {
     pub id: u32,
     version: Version,
     num_objects: u32,
+    /// The kind of hash used for the object ids within the pack and its trailing checksum.
+    hash_kind: git_hash::Kind,
 }
 
 /// Information about the pack data file itself
@@ -66,6 +67,10 @@ impl File {
     pub fn num_objects(&self) -> u32 {
         self.num_objects
     }
+    /// The kind of hash used for the object ids of ref-deltas and the trailing checksum of this pack
+    pub fn hash_kind(&self) -> git_hash::Kind {
+        self.hash_kind
+    }
     /// The length of all mapped data, including the pack header and the pack trailer
     pub fn data_len(&self) -> usize {
         self.data.len()
@@ -73,7 +78,7 @@ impl File {
 
     /// The position of the byte one past the last pack entry, or in other terms, the first byte of the trailing hash.
     pub fn pack_end(&self) -> usize {
-        self.data.len() - SHA1_SIZE
+        self.data.len() - self.hash_kind.len_in_bytes()
     }
 
     /// The path to the pack data file on disk
@@ -102,4 +107,5 @@ impl File {
     }
 }
 
-pub(crate) mod delta;
+/// Decode and encode delta instructions, which describe how to produce an object from a base object.
+pub mod delta;
@@ -94,7 +94,7 @@ pub mod verify {
             loose::object::header::encode(self.kind, self.data.len() as u64, &mut sink).expect("hash to always work");
             sink.hash.update(self.data);
 
-            let actual_id = git_hash::ObjectId::from(sink.hash.digest());
+            let actual_id = sink.hash.digest();
             if desired != actual_id {
                 return Err(Error::ChecksumMismatch {
                     desired: desired.into(),
@@ -56,7 +56,7 @@ where
     ///
     /// # Panics
     ///
-    /// Not all combinations of `hash_kind` and `version` are supported currently triggering assertion errors.
+    /// Not all pack versions are supported currently, triggering assertion errors.
     pub fn new(
         input: I,
         output: W,
@@ -68,10 +68,6 @@ where
             matches!(version, crate::data::Version::V2),
             "currently only pack version 2 can be written",
         );
-        assert!(
-            matches!(hash_kind, git_hash::Kind::Sha1),
-            "currently only Sha1 is supported, right now we don't know how other hashes are encoded",
-        );
         FromEntriesIter {
             input,
             output: hash::Write::new(output, hash_kind),
@@ -125,11 +121,11 @@ where
             }
             None => {
                 let digest = self.output.hash.clone().digest();
-                self.output.inner.write_all(&digest[..])?;
-                self.written += digest.len() as u64;
+                self.output.inner.write_all(digest.as_slice())?;
+                self.written += digest.as_slice().len() as u64;
                 self.output.inner.flush()?;
                 self.is_done = true;
-                self.trailer = Some(git_hash::ObjectId::from(digest));
+                self.trailer = Some(digest);
             }
         };
         Ok(self.written - previous_written)
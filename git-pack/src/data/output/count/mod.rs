@@ -13,6 +13,11 @@ pub struct Count {
     pub id: ObjectId,
     /// A way to locate a pack entry in the object database, only available if the object is in a pack.
     pub entry_pack_location: PackLocation,
+    /// A hash of the name of the tree entry the object was found at, or 0 if it is unknown.
+    ///
+    /// Objects with equal name hashes are likely to be versions of the same file, which makes them good candidates for
+    /// delta compression.
+    pub name_hash: u32,
 }
 
 /// Specifies how the pack location was handled during counting
@@ -48,10 +53,21 @@ impl Count {
         Count {
             id: oid.into(),
             entry_pack_location: PackLocation::LookedUp(obj.pack_location.clone()),
+            name_hash: 0,
         }
     }
 }
 
+/// Compute the [`name_hash`][Count::name_hash] of an object found at a tree entry with the given file `name`.
+///
+/// Equivalent to [this canonical git function](https://github.com/git/git/blob/v2.33.0/pack-objects.h), which
+/// weighs the last characters of a name the most.
+pub fn name_hash(name: &[u8]) -> u32 {
+    name.iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .fold(0, |hash, b| (hash >> 2).wrapping_add((*b as u32) << 24))
+}
+
 ///
 pub mod objects;
 pub use objects::{objects, objects_unthreaded};
@@ -37,6 +37,7 @@ pub fn objects<Find, Iter, IterErr, Oid, Cache>(
         thread_limit,
         input_object_expansion,
         chunk_size,
+        use_reachability_bitmaps,
     }: Options,
 ) -> Result<find::existing::Error<Find::Error>, IterErr>
 where
@@ -84,6 +85,7 @@ where
                 expand_inner(
                     &db,
                     input_object_expansion,
+                    use_reachability_bitmaps,
                     &seen_objs,
                     oids,
                     buf1,
@@ -100,6 +102,8 @@ where
 }
 
 /// Like [`objects()`] but using a single thread only to mostly save on the otherwise required overhead.
+///
+/// See [`Options::use_reachability_bitmaps`] for the meaning of `use_reachability_bitmaps`.
 pub fn objects_unthreaded<Find, IterErr, Oid>(
     db: Find,
     pack_cache: &mut impl crate::cache::DecodeEntry,
@@ -107,6 +111,7 @@ pub fn objects_unthreaded<Find, IterErr, Oid>(
     mut progress: impl Progress,
     should_interrupt: &AtomicBool,
     input_object_expansion: ObjectExpansion,
+    use_reachability_bitmaps: bool,
 ) -> Result<find::existing::Error<Find::Error>, IterErr>
 where
     Find: crate::Find + Send + Sync,
@@ -119,6 +124,7 @@ where
     expand_inner(
         &db,
         input_object_expansion,
+        use_reachability_bitmaps,
         &seen_objs,
         object_ids,
         &mut buf1,
@@ -134,6 +140,7 @@ where
 fn expand_inner<Find, IterErr, Oid>(
     db: &Find,
     input_object_expansion: ObjectExpansion,
+    use_reachability_bitmaps: bool,
     seen_objs: &impl util::InsertImmutable<ObjectId>,
     oids: impl IntoIterator<Item = std::result::Result<Oid, IterErr>>,
     buf1: &mut Vec<u8>,
@@ -164,6 +171,11 @@ where
         }
 
         let id = id.map(|oid| oid.into()).map_err(Error::InputIteration)?;
+        if use_reachability_bitmaps && input_object_expansion == TreeContents && seen_objs.contains(&id) {
+            // Seen objects were expanded already, and with bitmaps it's common that all remaining input commits have been.
+            stats.input_objects += 1;
+            continue;
+        }
         let obj = db.find_existing(id, buf1, cache)?;
         stats.input_objects += 1;
         match input_object_expansion {
@@ -269,8 +281,16 @@ where
                                 }
                                 &changes_delegate.objects
                             };
-                            for id in objects.iter() {
-                                out.push(id_to_count(db, buf2, id, progress, stats, allow_pack_lookups));
+                            for (id, name_hash) in objects.iter() {
+                                out.push(id_to_count(
+                                    db,
+                                    buf2,
+                                    id,
+                                    *name_hash,
+                                    progress,
+                                    stats,
+                                    allow_pack_lookups,
+                                ));
                             }
                             break;
                         }
@@ -304,12 +324,27 @@ where
                                 &mut traverse_delegate,
                             )
                             .map_err(Error::TreeTraverse)?;
-                            for id in traverse_delegate.non_trees.iter() {
-                                out.push(id_to_count(db, buf1, id, progress, stats, allow_pack_lookups));
+                            for (id, name_hash) in traverse_delegate.non_trees.iter() {
+                                out.push(id_to_count(
+                                    db,
+                                    buf1,
+                                    id,
+                                    *name_hash,
+                                    progress,
+                                    stats,
+                                    allow_pack_lookups,
+                                ));
                             }
                             break;
                         }
                         Commit => {
+                            if use_reachability_bitmaps {
+                                if let Some(location) = obj.pack_location.clone() {
+                                    if push_reachable_from_bitmap(&mut out, db, seen_objs, &location, progress, stats) {
+                                        break;
+                                    }
+                                }
+                            }
                             id = immutable::CommitIter::from_bytes(obj.data)
                                 .tree_id()
                                 .expect("every commit has a tree");
@@ -337,7 +372,7 @@ where
 
 mod tree {
     pub mod changes {
-        use crate::data::output::count::objects::util::InsertImmutable;
+        use crate::data::output::count::{name_hash, objects::util::InsertImmutable};
         use git_diff::tree::{
             visit::{Action, Change},
             Visit,
@@ -346,7 +381,9 @@ mod tree {
         use git_object::bstr::BStr;
 
         pub struct AllNew<'a, H> {
-            pub objects: Vec<ObjectId>,
+            /// The ids of new objects along with the hash of their name.
+            pub objects: Vec<(ObjectId, u32)>,
+            name_hash: u32,
             all_seen: &'a H,
         }
 
@@ -357,6 +394,7 @@ mod tree {
             pub fn new(all_seen: &'a H) -> Self {
                 AllNew {
                     objects: Default::default(),
+                    name_hash: 0,
                     all_seen,
                 }
             }
@@ -371,18 +409,22 @@ mod tree {
         {
             fn pop_front_tracked_path_and_set_current(&mut self) {}
 
-            fn push_back_tracked_path_component(&mut self, _component: &BStr) {}
+            fn push_back_tracked_path_component(&mut self, component: &BStr) {
+                self.name_hash = name_hash(component);
+            }
 
-            fn push_path_component(&mut self, _component: &BStr) {}
+            fn push_path_component(&mut self, component: &BStr) {
+                self.name_hash = name_hash(component);
+            }
 
             fn pop_path_component(&mut self) {}
 
             fn visit(&mut self, change: Change) -> Action {
                 match change {
-                    Change::Addition { oid, .. } | Change::Modification { oid, .. } => {
+                    Change::Addition { oid, .. } | Change::Modification { oid, .. } | Change::Rename { oid, .. } => {
                         let inserted = self.all_seen.insert(oid);
                         if inserted {
-                            self.objects.push(oid);
+                            self.objects.push((oid, self.name_hash));
                         }
                     }
                     Change::Deletion { .. } => {}
@@ -393,13 +435,14 @@ mod tree {
     }
 
     pub mod traverse {
-        use crate::data::output::count::objects::util::InsertImmutable;
+        use crate::data::output::count::{name_hash, objects::util::InsertImmutable};
         use git_hash::ObjectId;
         use git_object::{bstr::BStr, immutable::tree::Entry};
         use git_traverse::tree::visit::{Action, Visit};
 
         pub struct AllUnseen<'a, H> {
-            pub non_trees: Vec<ObjectId>,
+            /// The ids of unseen objects that aren't trees along with the hash of their name.
+            pub non_trees: Vec<(ObjectId, u32)>,
             all_seen: &'a H,
         }
 
@@ -442,7 +485,7 @@ mod tree {
             fn visit_nontree(&mut self, entry: &Entry<'_>) -> Action {
                 let inserted = self.all_seen.insert(entry.oid.to_owned());
                 if inserted {
-                    self.non_trees.push(entry.oid.to_owned());
+                    self.non_trees.push((entry.oid.to_owned(), name_hash(entry.filename)));
                 }
                 Action::Continue
             }
@@ -471,11 +514,67 @@ fn push_obj_count_unique(
     }
 }
 
+/// Add all objects reachable from the commit at `location` to `out` if there is a reachability bitmap for it, and
+/// return true in that case.
+fn push_reachable_from_bitmap<Find: crate::Find>(
+    out: &mut Vec<output::Count>,
+    db: &Find,
+    all_seen: &impl util::InsertImmutable<ObjectId>,
+    location: &crate::bundle::Location,
+    progress: &mut impl Progress,
+    statistics: &mut Outcome,
+) -> bool {
+    let bundle = match db.bundle_by_pack_id(location.pack_id) {
+        Some(bundle) => bundle,
+        None => return false,
+    };
+    let (bitmap, reachable) = match bundle
+        .bitmap
+        .as_ref()
+        .and_then(|bitmap| bitmap.reachable_from(location.index_file_id).map(|r| (bitmap, r)))
+    {
+        Some(bitmap_and_reachable) => bitmap_and_reachable,
+        None => return false,
+    };
+    let pack_offset_at = |pack_position: usize| {
+        bitmap
+            .index_position_at(pack_position)
+            .map(|index_position| (index_position, bundle.index.pack_offset_at_index(index_position)))
+    };
+    for pack_position in reachable.iter_ones() {
+        let (index_position, pack_offset) = match pack_offset_at(pack_position) {
+            Some(position_and_offset) => position_and_offset,
+            None => break,
+        };
+        let id = bundle.index.oid_at_index(index_position);
+        if !all_seen.insert(id.to_owned()) {
+            continue;
+        }
+        let next_pack_offset = pack_offset_at(pack_position + 1)
+            .map(|(_, offset)| offset)
+            .unwrap_or(bundle.pack.pack_end() as u64);
+        progress.inc();
+        statistics.expanded_objects += 1;
+        out.push(output::Count {
+            id: id.to_owned(),
+            entry_pack_location: PackLocation::LookedUp(Some(crate::bundle::Location {
+                pack_id: location.pack_id,
+                index_file_id: index_position,
+                entry_size: (next_pack_offset - pack_offset) as usize,
+                pack_offset,
+            })),
+            name_hash: 0,
+        });
+    }
+    true
+}
+
 #[inline]
 fn id_to_count<Find: crate::Find>(
     db: &Find,
     buf: &mut Vec<u8>,
     id: &oid,
+    name_hash: u32,
     progress: &mut impl Progress,
     statistics: &mut Outcome,
     allow_pack_lookups: bool,
@@ -489,12 +588,14 @@ fn id_to_count<Find: crate::Find>(
         } else {
             PackLocation::NotLookedUp
         },
+        name_hash,
     }
 }
 
 mod util {
     pub trait InsertImmutable<Item: Eq + std::hash::Hash> {
         fn insert(&self, item: Item) -> bool;
+        fn contains(&self, item: &Item) -> bool;
     }
 
     mod trait_impls {
@@ -508,12 +609,20 @@ mod util {
             fn insert(&self, item: T) -> bool {
                 self.insert(item)
             }
+
+            fn contains(&self, item: &T) -> bool {
+                DashSet::contains(self, item)
+            }
         }
 
         impl<T: Eq + Hash> InsertImmutable<T> for RefCell<HashSet<T>> {
             fn insert(&self, item: T) -> bool {
                 self.borrow_mut().insert(item)
             }
+
+            fn contains(&self, item: &T) -> bool {
+                self.borrow().contains(item)
+            }
         }
     }
 
@@ -588,6 +697,9 @@ mod types {
         /// Otherwise, the same as [`AsIs`][ObjectExpansion::AsIs].
         ///
         /// This mode is useful if all reachable objects should be added, as in cloning a repository.
+        ///
+        /// If [reachability bitmaps are used][Options::use_reachability_bitmaps], commits with a bitmap expand into all
+        /// objects reachable from them instead.
         TreeContents,
         /// If the input is a commit, obtain its ancestors and turn them into pack entries. Obtain the ancestor trees along with the commits
         /// tree and turn them into pack entries. Finally obtain the added/changed objects when comparing the ancestor trees with the
@@ -616,6 +728,13 @@ mod types {
         pub chunk_size: usize,
         /// The way input objects are handled
         pub input_object_expansion: ObjectExpansion,
+        /// If true and the expansion mode is [`TreeContents`][ObjectExpansion::TreeContents], input commits with a
+        /// [reachability bitmap][crate::bitmap] in their pack are expanded into all objects reachable from them, which
+        /// avoids traversing trees entirely. Input objects that were already added this way are skipped.
+        ///
+        /// As these include the objects of all ancestors, this is only equivalent to not using bitmaps if all ancestors of
+        /// input commits are also part of the input, as is the case when counting the objects to clone a repository.
+        pub use_reachability_bitmaps: bool,
     }
 
     impl Default for Options {
@@ -624,6 +743,7 @@ mod types {
                 thread_limit: None,
                 chunk_size: 10,
                 input_object_expansion: Default::default(),
+                use_reachability_bitmaps: false,
             }
         }
     }
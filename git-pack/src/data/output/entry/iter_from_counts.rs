@@ -2,7 +2,7 @@ use std::{cmp::Ordering, sync::Arc};
 
 use git_features::{parallel, progress::Progress};
 
-use crate::data::{output, output::ChunkId};
+use crate::data::{delta, output, output::ChunkId};
 
 /// Given a known list of object `counts`, calculate entries ready to be put into a data pack.
 ///
@@ -45,6 +45,8 @@ pub fn iter_from_counts<Find, Cache>(
         allow_thin_pack,
         thread_limit,
         chunk_size,
+        delta_window,
+        delta_depth,
     }: Options,
 ) -> impl Iterator<Item = Result<(ChunkId, Vec<output::Entry>), Error<Find::Error>>>
        + parallel::reduce::Finalize<Reduce = reduce::Statistics<Error<Find::Error>>>
@@ -57,7 +59,7 @@ where
         matches!(version, crate::data::Version::V2),
         "currently we can only write version 2"
     );
-    let (chunk_size, thread_limit, _) =
+    let (chunk_size, thread_limit, num_threads) =
         parallel::optimize_chunk_size_and_thread_limit(chunk_size, Some(counts.len()), thread_limit, None);
     let chunks = util::ChunkRanges::new(chunk_size, counts.len());
     {
@@ -104,7 +106,7 @@ where
         progress.lock().show_throughput(start);
     }
     let counts_range_by_pack_id = match mode {
-        Mode::PackCopyAndBaseObjects => {
+        Mode::PackCopyAndBaseObjects | Mode::PackCopyAndDeltaCompression => {
             let mut progress = progress.add_child("sorting");
             progress.init(Some(counts.len()), git_features::progress::count("counts"));
             let start = std::time::Instant::now();
@@ -140,7 +142,24 @@ where
             index
         }
     };
+    let deltas = match mode {
+        Mode::PackCopyAndBaseObjects => Vec::new(),
+        Mode::PackCopyAndDeltaCompression => {
+            let num_unpacked = counts.partition_point(|e| e.entry_pack_location.is_none());
+            find_deltas(
+                &mut counts[..num_unpacked],
+                &db,
+                make_cache.clone(),
+                thread_limit,
+                num_threads,
+                delta_window,
+                delta_depth,
+                &mut progress,
+            )
+        }
+    };
     let counts = Arc::new(counts);
+    let deltas = Arc::new(deltas);
     let progress = Arc::new(parking_lot::Mutex::new(progress));
 
     parallel::reduce::Stepwise::new(
@@ -158,14 +177,22 @@ where
         },
         {
             let counts = Arc::clone(&counts);
+            let deltas = Arc::clone(&deltas);
             move |(chunk_id, chunk_range): (ChunkId, std::ops::Range<usize>), (buf, cache, progress)| {
                 let mut out = Vec::new();
+                let first_count_index = chunk_range.start;
                 let chunk = &counts[chunk_range];
                 let mut stats = Outcome::default();
                 let mut pack_offsets_to_id = None;
                 progress.init(Some(chunk.len()), git_features::progress::count("objects"));
 
-                for count in chunk.iter() {
+                for (count_index, count) in (first_count_index..).zip(chunk.iter()) {
+                    if let Some(delta) = deltas.get(count_index).and_then(Option::as_ref) {
+                        stats.delta_compressed_objects += 1;
+                        out.push(output::Entry::from_delta(count, delta.base_index, &delta.data)?);
+                        progress.inc();
+                        continue;
+                    }
                     out.push(match count
                         .entry_pack_location
                         .as_ref()
@@ -249,7 +276,166 @@ where
     )
 }
 
+/// Find deltas for the objects of `counts`, which are expected to be those not in any pack, and return a possible delta
+/// for each of them.
+///
+/// `counts` are sorted so that objects of the same kind and name are next to each other with the largest objects first,
+/// to let objects be deltified against the ones before them in a sliding window of `window_size` objects, while
+/// producing delta chains of no more than `max_depth` deltas.
+/// To compute deltas in parallel, each thread processes a consecutive portion of `counts` with its own window.
+#[allow(clippy::too_many_arguments)]
+fn find_deltas<Find, Cache>(
+    counts: &mut [output::Count],
+    db: &Find,
+    make_cache: impl Fn() -> Cache + Send + Sync,
+    thread_limit: Option<usize>,
+    num_threads: usize,
+    window_size: usize,
+    max_depth: usize,
+    progress: &mut impl Progress,
+) -> Vec<Option<util::Delta>>
+where
+    Find: crate::Find + Send + Sync,
+    Cache: crate::cache::DecodeEntry,
+{
+    if counts.is_empty() || window_size == 0 || max_depth == 0 {
+        return Vec::new();
+    }
+    let kinds_and_sizes = {
+        let mut progress = progress.add_child("sizing");
+        progress.init(Some(counts.len()), git_features::progress::count("objects"));
+        let start = std::time::Instant::now();
+        let counts = &*counts;
+        let kinds_and_sizes = util::unwrap_infallible(parallel::in_parallel(
+            util::ChunkRanges::new(50, counts.len()),
+            thread_limit,
+            |_n| (Vec::new(), make_cache()),
+            |chunk_range, (buf, cache)| {
+                let kinds_and_sizes: Vec<_> = counts[chunk_range.clone()]
+                    .iter()
+                    .map(|count| {
+                        db.find(count.id, buf, cache)
+                            .ok()
+                            .flatten()
+                            .map(|obj| (obj.kind, obj.data.len()))
+                    })
+                    .collect();
+                (chunk_range.start, kinds_and_sizes)
+            },
+            reduce::InOrder::default(),
+        ));
+        progress.set(counts.len());
+        progress.show_throughput(start);
+        kinds_and_sizes
+    };
+
+    let kinds_and_sizes = {
+        let mut order: Vec<_> = (0..counts.len()).collect();
+        order.sort_by(|&lhs, &rhs| match (kinds_and_sizes[lhs], kinds_and_sizes[rhs]) {
+            (Some((lhs_kind, lhs_size)), Some((rhs_kind, rhs_size))) => lhs_kind
+                .cmp(&rhs_kind)
+                .then(counts[lhs].name_hash.cmp(&counts[rhs].name_hash))
+                .then(rhs_size.cmp(&lhs_size)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        let sorted_counts: Vec<_> = order.iter().map(|&index| counts[index].clone()).collect();
+        counts.clone_from_slice(&sorted_counts);
+        order
+            .into_iter()
+            .map(|index| kinds_and_sizes[index])
+            .collect::<Vec<_>>()
+    };
+
+    let mut progress = progress.add_child("deltifying");
+    progress.init(Some(counts.len()), git_features::progress::count("objects"));
+    let start = std::time::Instant::now();
+    let progress = parking_lot::Mutex::new(progress);
+    let counts = &*counts;
+    let deltas = util::unwrap_infallible(parallel::in_parallel(
+        util::ChunkRanges::new((counts.len() + num_threads - 1) / num_threads, counts.len()),
+        thread_limit,
+        |_n| (Vec::new(), make_cache()),
+        |chunk_range, (buf, cache)| {
+            let mut window = std::collections::VecDeque::<util::WindowEntry>::with_capacity(window_size + 1);
+            let mut deltas = Vec::with_capacity(chunk_range.len());
+            let mut delta_buf = Vec::new();
+            for index in chunk_range.clone() {
+                let (kind, data) =
+                    match kinds_and_sizes[index].and_then(|_| db.find(counts[index].id, buf, cache).ok().flatten()) {
+                        Some(obj) => (obj.kind, obj.data.to_owned()),
+                        None => {
+                            deltas.push(None);
+                            continue;
+                        }
+                    };
+
+                // Like git, only accept deltas that are at most half as large as the object to outweigh the cost of
+                // applying them, don't try bases that are more than 32 times larger than the object,
+                // and try more recent objects first as they are more similar.
+                let mut best: Option<(usize, usize, Vec<u8>)> = None;
+                for base in window.iter().rev() {
+                    let base_data = base.delta_index.base();
+                    if base.kind != kind || base.depth >= max_depth || data.len() < base_data.len() / 32 {
+                        continue;
+                    }
+                    let max_size = match &best {
+                        Some((_, _, delta)) => delta.len() - 1,
+                        None => (data.len() / 2).saturating_sub(counts[index].id.kind().len_in_bytes()),
+                    };
+                    if data.len().saturating_sub(base_data.len()) >= max_size {
+                        continue;
+                    }
+                    if base.delta_index.encode(&data, max_size, &mut delta_buf) {
+                        best = Some((base.index, base.depth, std::mem::take(&mut delta_buf)));
+                    }
+                }
+
+                window.push_back(util::WindowEntry {
+                    index,
+                    kind,
+                    depth: best.as_ref().map_or(0, |(_, base_depth, _)| base_depth + 1),
+                    delta_index: delta::Index::new(data),
+                });
+                if window.len() > window_size {
+                    window.pop_front();
+                }
+                deltas.push(best.map(|(base_index, _, data)| util::Delta { base_index, data }));
+            }
+            progress.lock().inc_by(chunk_range.len());
+            (chunk_range.start, deltas)
+        },
+        reduce::InOrder::default(),
+    ));
+    progress.lock().show_throughput(start);
+    deltas
+}
+
 mod util {
+    pub fn unwrap_infallible<T>(res: Result<T, std::convert::Infallible>) -> T {
+        match res {
+            Ok(v) => v,
+            Err(err) => match err {},
+        }
+    }
+
+    /// A delta to produce an object from the one at `base_index` in the list of counts.
+    pub struct Delta {
+        pub base_index: usize,
+        pub data: Vec<u8>,
+    }
+
+    /// An object in the sliding window of delta bases.
+    pub struct WindowEntry {
+        pub index: usize,
+        pub kind: git_object::Kind,
+        /// The length of the delta chain leading to this object, or 0 if it is a base object.
+        pub depth: usize,
+        /// The index to produce deltas against this object, which owns its data.
+        pub delta_index: crate::data::delta::Index<Vec<u8>>,
+    }
+
     #[derive(Clone)]
     pub struct ChunkRanges {
         cursor: usize,
@@ -284,7 +470,7 @@ mod util {
 }
 
 mod reduce {
-    use std::marker::PhantomData;
+    use std::{convert::Infallible, marker::PhantomData};
 
     use git_features::parallel;
 
@@ -322,6 +508,36 @@ mod reduce {
             Ok(self.total)
         }
     }
+
+    /// Collect the items produced for consecutive ranges of input into a single list in input order.
+    pub struct InOrder<T> {
+        items_by_start: Vec<(usize, Vec<T>)>,
+    }
+
+    impl<T> Default for InOrder<T> {
+        fn default() -> Self {
+            InOrder {
+                items_by_start: Vec::new(),
+            }
+        }
+    }
+
+    impl<T> parallel::Reduce for InOrder<T> {
+        type Input = (usize, Vec<T>);
+        type FeedProduce = ();
+        type Output = Vec<T>;
+        type Error = Infallible;
+
+        fn feed(&mut self, item: Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+            self.items_by_start.push(item);
+            Ok(())
+        }
+
+        fn finalize(mut self) -> Result<Self::Output, Self::Error> {
+            self.items_by_start.sort_by_key(|(start, _)| *start);
+            Ok(self.items_by_start.into_iter().flat_map(|(_, items)| items).collect())
+        }
+    }
 }
 
 mod types {
@@ -338,6 +554,9 @@ mod types {
         /// The amount of base or delta objects that could be copied directly from the pack. These are cheapest as they
         /// only cost a memory copy for the most part.
         pub objects_copied_from_pack: usize,
+        /// The amount of objects that were delta-compressed against another object in the pack, which is only done
+        /// in [`Mode::PackCopyAndDeltaCompression`].
+        pub delta_compressed_objects: usize,
     }
 
     impl Outcome {
@@ -347,11 +566,13 @@ mod types {
                 decoded_and_recompressed_objects: decoded_objects,
                 missing_objects,
                 objects_copied_from_pack,
+                delta_compressed_objects,
             }: Self,
         ) {
             self.decoded_and_recompressed_objects += decoded_objects;
             self.missing_objects += missing_objects;
             self.objects_copied_from_pack += objects_copied_from_pack;
+            self.delta_compressed_objects += delta_compressed_objects;
         }
     }
 
@@ -364,6 +585,14 @@ mod types {
         /// from existing pack compression and spending the smallest possible time on compressing unpacked objects at
         /// the cost of bandwidth.
         PackCopyAndBaseObjects,
+        /// Copy base objects and deltas from packs like [`PackCopyAndBaseObjects`][Mode::PackCopyAndBaseObjects], but
+        /// delta-compress non-packed objects against each other, similar to what `git pack-objects` does.
+        ///
+        /// Delta bases are searched for in a sliding window over the non-packed objects sorted by kind, name hash and size,
+        /// which costs a lot more time than writing them as base objects but results in much smaller packs.
+        /// All deltas found that way are offset deltas, whereas copied deltas may also refer to their base by id
+        /// if thin packs are allowed.
+        PackCopyAndDeltaCompression,
     }
 
     /// Configuration options for the pack generation functions provied in [this module][crate::data::output].
@@ -387,6 +616,14 @@ mod types {
         pub chunk_size: usize,
         /// The pack data version to produce for each entry
         pub version: crate::data::Version,
+        /// The amount of objects to try as delta base for each object, similar to git's `pack.window`.
+        ///
+        /// Only used in [`Mode::PackCopyAndDeltaCompression`].
+        pub delta_window: usize,
+        /// The maximum length of delta chains, similar to git's `pack.depth`.
+        ///
+        /// Only used in [`Mode::PackCopyAndDeltaCompression`].
+        pub delta_depth: usize,
     }
 
     impl Default for Options {
@@ -397,6 +634,8 @@ mod types {
                 allow_thin_pack: false,
                 chunk_size: 10,
                 version: Default::default(),
+                delta_window: 10,
+                delta_depth: 50,
             }
         }
     }
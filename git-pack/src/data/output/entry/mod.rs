@@ -79,7 +79,7 @@ impl output::Entry {
     /// Create an Entry from a previously counted object which is located in a pack. It's `entry` is provided here.
     /// The `version` specifies what kind of target `Entry` version the caller desires.
     pub fn from_pack_entry(
-        entry: find::Entry<'_>,
+        entry: find::Entry,
         count: &output::Count,
         potential_bases: &[output::Count],
         bases_index_offset: usize,
@@ -91,9 +91,10 @@ impl output::Entry {
         };
 
         let pack_offset_must_be_zero = 0;
-        let pack_entry = crate::data::Entry::from_bytes(entry.data, pack_offset_must_be_zero);
+        let pack_entry =
+            crate::data::Entry::from_bytes(&entry.data, pack_offset_must_be_zero, count.id.kind().len_in_bytes());
         if let Some(expected) = entry.crc32 {
-            let actual = hash::crc32(entry.data);
+            let actual = hash::crc32(&entry.data);
             if actual != expected {
                 return Some(Err(Error::PackToPackCopyCrc32Mismatch { actual, expected }));
             }
@@ -146,17 +147,20 @@ impl output::Entry {
             id: count.id.to_owned(),
             kind: Kind::Base(obj.kind),
             decompressed_size: obj.data.len(),
-            compressed_data: {
-                let mut out = git_features::zlib::stream::deflate::Write::new(Vec::new());
-                if let Err(err) = std::io::copy(&mut &*obj.data, &mut out) {
-                    match err.kind() {
-                        std::io::ErrorKind::Other => return Err(Error::ZlibDeflate(err)),
-                        err => unreachable!("Should never see other errors than zlib, but got {:?}", err,),
-                    }
-                };
-                out.flush()?;
-                out.into_inner()
+            compressed_data: compress(obj.data)?,
+        })
+    }
+
+    /// Create a new instance for the object of `count` from `delta` instructions to apply to the object at
+    /// `base_index`, which must have been written before.
+    pub(crate) fn from_delta(count: &output::Count, base_index: usize, delta: &[u8]) -> Result<Self, Error> {
+        Ok(output::Entry {
+            id: count.id.to_owned(),
+            kind: Kind::DeltaRef {
+                object_index: base_index,
             },
+            decompressed_size: delta.len(),
+            compressed_data: compress(delta)?,
         })
     }
 
@@ -193,3 +197,15 @@ impl output::Entry {
         }
     }
 }
+
+fn compress(mut data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = git_features::zlib::stream::deflate::Write::new(Vec::new());
+    if let Err(err) = std::io::copy(&mut data, &mut out) {
+        match err.kind() {
+            std::io::ErrorKind::Other => return Err(Error::ZlibDeflate(err)),
+            err => unreachable!("Should never see other errors than zlib, but got {:?}", err,),
+        }
+    };
+    out.flush()?;
+    Ok(out.into_inner())
+}
@@ -33,10 +33,11 @@ pub trait Find {
 
     /// Find the bundle matching `pack_id`, or `None` if there is no such pack.
     ///
+    /// The bundle is shared to remain usable even if the database replaces its packs meanwhile.
     /// _Note_ that the object database may have no notion of packs and thus always returns `None`.
-    fn bundle_by_pack_id(&self, pack_id: u32) -> Option<&crate::Bundle>;
+    fn bundle_by_pack_id(&self, pack_id: u32) -> Option<std::sync::Arc<crate::Bundle>>;
 
-    /// Return the [`Entry`] for `location` if it is backed by a pack.
+    /// Return the [`Entry`] for `location` if it is backed by a pack, with its data copied out of the pack.
     ///
     /// Note that this is only in the interest of avoiding duplicate work during pack generation.
     /// Pack locations can be obtained from a [`data::Object`].
@@ -45,7 +46,7 @@ pub trait Find {
     ///
     /// Custom implementations might be interested in providing their own meta-data with `object`,
     /// which currently isn't possible as the `Locate` trait requires GATs to work like that.
-    fn entry_by_location(&self, location: &crate::bundle::Location) -> Option<Entry<'_>>;
+    fn entry_by_location(&self, location: &crate::bundle::Location) -> Option<Entry>;
 }
 
 mod ext {
@@ -154,6 +155,36 @@ mod ext {
             immutable::TreeIter<'a>,
             into_tree_iter
         );
+
+        /// Like [`find_existing_tree_iter(…)`][Self::find_existing_tree_iter()], but try to obtain the tree from `object_cache` first,
+        /// and put it there if it had to be looked up in the database.
+        ///
+        /// This is useful as `find` function for [tree diffs][git_diff::tree::Changes] with `needed_to_obtain()` of many consecutive
+        /// trees, which share most of their subtrees.
+        fn find_existing_tree_iter_cached<'a>(
+            &self,
+            id: impl AsRef<git_hash::oid>,
+            buffer: &'a mut Vec<u8>,
+            pack_cache: &mut impl crate::cache::DecodeEntry,
+            object_cache: &mut impl crate::cache::Object,
+        ) -> Result<immutable::TreeIter<'a>, find::existing_iter::Error<Self::Error>> {
+            let id = id.as_ref();
+            let obj = match object_cache.get(id, buffer) {
+                Some(kind) => data::Object::new(kind, buffer),
+                None => {
+                    let obj = self.find_existing(id, buffer, pack_cache).map_err(|err| match err {
+                        find::existing::Error::Find(err) => find::existing_iter::Error::Find(err),
+                        find::existing::Error::NotFound { oid } => find::existing_iter::Error::NotFound { oid },
+                    })?;
+                    if obj.kind == Kind::Tree {
+                        object_cache.put(id.to_owned(), obj.kind, obj.data);
+                    }
+                    obj
+                }
+            };
+            obj.into_tree_iter()
+                .ok_or(find::existing_iter::Error::ObjectKind { expected: Kind::Tree })
+        }
     }
 
     impl<T: super::Find> FindExt for T {}
@@ -215,12 +246,12 @@ pub mod existing_iter {
 /// An Entry in a pack providing access to its data.
 ///
 /// Its commonly retrieved by reading from a pack index file followed by a read from a pack data file.
-#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
-pub struct Entry<'a> {
+pub struct Entry {
     /// The pack-data encoded bytes of the pack data entry as present in the pack file, including the header followed by compressed data.
-    pub data: &'a [u8],
+    pub data: Vec<u8>,
     /// The crc32 hash over the entirety of `data`, or None if the pack file format doesn't support it yet.
     pub crc32: Option<u32>,
     /// The version of the pack file containing `data`
@@ -253,11 +284,11 @@ mod find_impls {
             self.deref().location_by_oid(id, buf)
         }
 
-        fn bundle_by_pack_id(&self, pack_id: u32) -> Option<&Bundle> {
+        fn bundle_by_pack_id(&self, pack_id: u32) -> Option<std::sync::Arc<Bundle>> {
             self.deref().bundle_by_pack_id(pack_id)
         }
 
-        fn entry_by_location(&self, object: &crate::bundle::Location) -> Option<Entry<'_>> {
+        fn entry_by_location(&self, object: &crate::bundle::Location) -> Option<Entry> {
             self.deref().entry_by_location(object)
         }
     }
@@ -281,11 +312,11 @@ mod find_impls {
             self.deref().location_by_oid(id, buf)
         }
 
-        fn bundle_by_pack_id(&self, pack_id: u32) -> Option<&Bundle> {
+        fn bundle_by_pack_id(&self, pack_id: u32) -> Option<std::sync::Arc<Bundle>> {
             self.deref().bundle_by_pack_id(pack_id)
         }
 
-        fn entry_by_location(&self, location: &crate::bundle::Location) -> Option<Entry<'_>> {
+        fn entry_by_location(&self, location: &crate::bundle::Location) -> Option<Entry> {
             self.deref().entry_by_location(location)
         }
     }
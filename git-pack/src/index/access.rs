@@ -1,7 +1,6 @@
-use std::{convert::TryInto, mem::size_of};
+use std::{cmp::Ordering, convert::TryInto, mem::size_of, ops::Range};
 
 use byteorder::{BigEndian, ByteOrder};
-use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
 
 use crate::index::{self, FAN_LEN};
 
@@ -33,12 +32,12 @@ impl index::File {
     fn iter_v1(&self) -> impl Iterator<Item = Entry> + '_ {
         match self.version {
             index::Version::V1 => self.data[V1_HEADER_SIZE..]
-                .chunks(N32_SIZE + SHA1_SIZE)
+                .chunks(N32_SIZE + self.hash_len())
                 .take(self.num_objects as usize)
                 .map(|c| {
                     let (ofs, oid) = c.split_at(N32_SIZE);
                     Entry {
-                        oid: git_hash::ObjectId::from_20_bytes(oid),
+                        oid: git_hash::ObjectId::from_bytes_or_panic(oid),
                         pack_offset: BigEndian::read_u32(ofs) as u64,
                         crc32: None,
                     }
@@ -51,13 +50,13 @@ impl index::File {
         let pack64_offset = self.offset_pack_offset64_v2();
         match self.version {
             index::Version::V2 => izip!(
-                self.data[V2_HEADER_SIZE..].chunks(SHA1_SIZE),
+                self.data[V2_HEADER_SIZE..].chunks(self.hash_len()),
                 self.data[self.offset_crc32_v2()..].chunks(N32_SIZE),
                 self.data[self.offset_pack_offset_v2()..].chunks(N32_SIZE)
             )
             .take(self.num_objects as usize)
             .map(move |(oid, crc32, ofs32)| Entry {
-                oid: git_hash::ObjectId::from_20_bytes(oid),
+                oid: git_hash::ObjectId::from_bytes_or_panic(oid),
                 pack_offset: self.pack_offset_from_offset_v2(ofs32, pack64_offset),
                 crc32: Some(BigEndian::read_u32(crc32)),
             }),
@@ -65,7 +64,15 @@ impl index::File {
         }
     }
 
-    /// Returns 20 bytes sha1 at the given index in our list of (sorted) sha1 hashes.
+    fn iter_v3(&self) -> impl Iterator<Item = Entry> + '_ {
+        (0..self.num_objects).map(move |index| Entry {
+            oid: self.oid_at_index(index).to_owned(),
+            pack_offset: self.pack_offset_at_index(index),
+            crc32: self.crc32_at_index(index),
+        })
+    }
+
+    /// Returns the object id at the given index in our list of (sorted) object ids.
     /// The index ranges from 0 to self.num_objects()
     ///
     /// # Panics
@@ -75,11 +82,13 @@ impl index::File {
         let index: usize = index
             .try_into()
             .expect("an architecture able to hold 32 bits of integer");
+        let hash_len = self.hash_len();
         let start = match self.version {
-            index::Version::V2 => V2_HEADER_SIZE + index * SHA1_SIZE,
-            index::Version::V1 => V1_HEADER_SIZE + index * (N32_SIZE + SHA1_SIZE) + N32_SIZE,
+            index::Version::V2 => V2_HEADER_SIZE + index * hash_len,
+            index::Version::V1 => V1_HEADER_SIZE + index * (N32_SIZE + hash_len) + N32_SIZE,
+            index::Version::V3 => self.v3_tables().ids + self.pack_order_v3(index) * hash_len,
         };
-        git_hash::oid::try_from(&self.data[start..start + SHA1_SIZE]).expect("20 bytes SHA1 to be alright")
+        git_hash::oid::try_from(&self.data[start..start + hash_len]).expect("hash of known length to be alright")
     }
 
     /// Returns the offset into our pack data file at which to start reading the object at `index`.
@@ -97,9 +106,14 @@ impl index::File {
                 self.pack_offset_from_offset_v2(&self.data[start..start + N32_SIZE], self.offset_pack_offset64_v2())
             }
             index::Version::V1 => {
-                let start = V1_HEADER_SIZE + index * (N32_SIZE + SHA1_SIZE);
+                let start = V1_HEADER_SIZE + index * (N32_SIZE + self.hash_len());
                 BigEndian::read_u32(&self.data[start..start + N32_SIZE]) as u64
             }
+            index::Version::V3 => {
+                let tables = self.v3_tables();
+                let start = tables.pack_offsets + index * N32_SIZE;
+                self.pack_offset_from_offset_v2(&self.data[start..start + N32_SIZE], tables.pack_offsets64)
+            }
         }
     }
 
@@ -119,6 +133,10 @@ impl index::File {
                 Some(BigEndian::read_u32(&self.data[start..start + N32_SIZE]))
             }
             index::Version::V1 => None,
+            index::Version::V3 => {
+                let start = self.v3_tables().crc32 + self.pack_order_v3(index) * N32_SIZE;
+                Some(BigEndian::read_u32(&self.data[start..start + N32_SIZE]))
+            }
         }
     }
 
@@ -132,7 +150,7 @@ impl index::File {
 
         // Bisect using indices
         // TODO: Performance of V2 could possibly be better if we would be able to do a binary search
-        // on 20 byte chunks directly, but doing so requires transmuting and that is not safe, even though
+        // on hash-sized chunks directly, but doing so requires transmuting and that is not safe, even though
         // it should not be if the bytes match up and the type has no destructor.
         while lower_bound < upper_bound {
             let mid = (lower_bound + upper_bound) / 2;
@@ -148,11 +166,37 @@ impl index::File {
         None
     }
 
+    /// Returns the range of indices of all objects whose id starts with `prefix`, for use with
+    /// [`oid_at_index()`][index::File::oid_at_index()] and friends.
+    ///
+    /// If the range is empty, its start is the index at which an object with the given prefix would have to be inserted.
+    pub fn lookup_prefix(&self, prefix: git_hash::Prefix) -> Range<u32> {
+        let first_byte = prefix.as_oid().first_byte() as usize;
+        let upper_bound = self.fan[first_byte];
+        let lower_bound = if first_byte != 0 { self.fan[first_byte - 1] } else { 0 };
+
+        let bisect = |mut lower_bound: u32, mut upper_bound: u32, is_before: &dyn Fn(Ordering) -> bool| {
+            while lower_bound < upper_bound {
+                let mid = (lower_bound + upper_bound) / 2;
+                if is_before(prefix.cmp_oid(self.oid_at_index(mid))) {
+                    lower_bound = mid + 1;
+                } else {
+                    upper_bound = mid;
+                }
+            }
+            lower_bound
+        };
+        let start = bisect(lower_bound, upper_bound, &|ord| ord == Ordering::Greater);
+        let end = bisect(start, upper_bound, &|ord| ord != Ordering::Less);
+        start..end
+    }
+
     /// An iterator over all [`Entries`][Entry] of this index file.
     pub fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = Entry> + 'a> {
         match self.version {
             index::Version::V2 => Box::new(self.iter_v2()),
             index::Version::V1 => Box::new(self.iter_v1()),
+            index::Version::V3 => Box::new(self.iter_v3()),
         }
     }
 
@@ -161,7 +205,7 @@ impl index::File {
     /// Useful to control an iteration over all pack entries in a cache-friendly way.
     pub fn sorted_offsets(&self) -> Vec<PackOffset> {
         let mut ofs: Vec<_> = match self.version {
-            index::Version::V1 => self.iter().map(|e| e.pack_offset).collect(),
+            index::Version::V1 | index::Version::V3 => self.iter().map(|e| e.pack_offset).collect(),
             index::Version::V2 => {
                 let mut v = Vec::with_capacity(self.num_objects as usize);
                 let mut ofs32 = &self.data[self.offset_pack_offset_v2()..];
@@ -177,8 +221,12 @@ impl index::File {
         ofs
     }
 
+    fn hash_len(&self) -> usize {
+        self.hash_kind.len_in_bytes()
+    }
+
     fn offset_crc32_v2(&self) -> usize {
-        V2_HEADER_SIZE + self.num_objects as usize * SHA1_SIZE
+        V2_HEADER_SIZE + self.num_objects as usize * self.hash_len()
     }
 
     fn offset_pack_offset_v2(&self) -> usize {
@@ -189,8 +237,18 @@ impl index::File {
         self.offset_pack_offset_v2() + self.num_objects as usize * N32_SIZE
     }
 
+    fn v3_tables(&self) -> index::V3Tables {
+        self.v3_tables.expect("V3 indices always have tables")
+    }
+
+    /// The position in pack order of the object at `index` in the order of object ids.
+    fn pack_order_v3(&self, index: usize) -> usize {
+        let start = self.v3_tables().pack_order + index * N32_SIZE;
+        BigEndian::read_u32(&self.data[start..start + N32_SIZE]) as usize
+    }
+
     fn pack_offset_from_offset_v2(&self, offset: &[u8], pack64_offset: usize) -> PackOffset {
-        debug_assert_eq!(self.version, index::Version::V2);
+        debug_assert_ne!(self.version, index::Version::V1);
         let ofs32 = BigEndian::read_u32(offset);
         if (ofs32 & N32_HIGH_BIT) == N32_HIGH_BIT {
             let from = pack64_offset + (ofs32 ^ N32_HIGH_BIT) as usize * N64_SIZE;
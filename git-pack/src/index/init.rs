@@ -1,10 +1,9 @@
-use std::{convert::TryFrom, mem::size_of, path::Path};
+use std::{mem::size_of, path::Path};
 
 use byteorder::{BigEndian, ByteOrder};
 use filebuffer::FileBuffer;
-use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
 
-use crate::index::{self, Version, FAN_LEN, V2_SIGNATURE};
+use crate::index::{self, V3Tables, Version, FAN_LEN, V2_SIGNATURE};
 
 /// Returned by [`index::File::at()`].
 #[derive(thiserror::Error, Debug)]
@@ -19,74 +18,203 @@ pub enum Error {
     Corrupt { message: String },
     #[error("Unsupported index version: {version})")]
     UnsupportedVersion { version: u32 },
+    #[error("The index stores {actual} object ids first, but {expected} ones were requested")]
+    UnsupportedHashKind { expected: &'static str, actual: String },
 }
 
 const N32_SIZE: usize = size_of::<u32>();
-const FOOTER_SIZE: usize = SHA1_SIZE * 2;
+/// The size of the fixed part of a V3 header: signature, version, header length, amount of objects and amount of object formats.
+const V3_HEADER_FIXED_SIZE: usize = N32_SIZE * 5;
+/// The size of the description of an object format in a V3 header: its identifier, length of shortened ids and table offset.
+const V3_FORMAT_SIZE: usize = N32_SIZE * 3;
 
 /// Instantiation
 impl index::File {
-    /// Open the pack index file at the given `path`.
-    pub fn at(path: impl AsRef<Path>) -> Result<index::File, Error> {
-        Self::try_from(path.as_ref())
-    }
-}
-
-impl TryFrom<&Path> for index::File {
-    type Error = Error;
-
-    fn try_from(path: &Path) -> Result<Self, Self::Error> {
-        let data = FileBuffer::open(&path).map_err(|e| Error::Io {
+    /// Open the pack index file at the given `path` whose object ids and checksums are hashes of `hash_kind`.
+    ///
+    /// V1 and V2 index files don't record the kind of hash they use, which is why it must be known beforehand,
+    /// usually from the `extensions.objectFormat` configuration of the repository. V3 index files must list `hash_kind`
+    /// as their first object format.
+    pub fn at(path: impl AsRef<Path>, hash_kind: git_hash::Kind) -> Result<index::File, Error> {
+        let path = path.as_ref();
+        let data = FileBuffer::open(path).map_err(|e| Error::Io {
             source: e,
             path: path.to_owned(),
         })?;
+        let corrupt = |message: String| Error::Corrupt { message };
+        let hash_len = hash_kind.len_in_bytes();
         let idx_len = data.len();
-        if idx_len < FAN_LEN * N32_SIZE + FOOTER_SIZE {
-            return Err(Error::Corrupt {
-                message: format!("Pack index of size {} is too small for even an empty index", idx_len),
-            });
+        if idx_len < FAN_LEN * N32_SIZE + hash_len * 2 {
+            return Err(corrupt(format!(
+                "Pack index of size {} is too small for even an empty index",
+                idx_len
+            )));
         }
-        let (kind, fan, num_objects) = {
-            let (kind, d) = {
-                let (sig, d) = data.split_at(V2_SIGNATURE.len());
-                if sig == V2_SIGNATURE {
-                    (Version::V2, d)
-                } else {
-                    (Version::V1, &data[..])
-                }
-            };
-            let d = {
-                if let Version::V2 = kind {
-                    let (vd, dr) = d.split_at(N32_SIZE);
-                    let version = BigEndian::read_u32(vd);
-                    if version != Version::V2 as u32 {
-                        return Err(Error::UnsupportedVersion { version });
+        let version = if data.starts_with(V2_SIGNATURE) {
+            match BigEndian::read_u32(&data[V2_SIGNATURE.len()..]) {
+                2 => Version::V2,
+                3 => Version::V3,
+                version => return Err(Error::UnsupportedVersion { version }),
+            }
+        } else {
+            Version::V1
+        };
+
+        let (num_objects, fan, v3_tables) = match version {
+            Version::V1 | Version::V2 => {
+                let fan_offset = match version {
+                    Version::V1 => 0,
+                    _ => V2_SIGNATURE.len() + N32_SIZE,
+                };
+                let fan = read_fan(&data[fan_offset..]);
+                let num_objects = fan[FAN_LEN - 1];
+                let n = num_objects as usize;
+                let fits = match version {
+                    Version::V1 => idx_len == FAN_LEN * N32_SIZE + n * (N32_SIZE + hash_len) + hash_len * 2,
+                    _ => {
+                        let min_len = fan_offset + FAN_LEN * N32_SIZE + n * (hash_len + N32_SIZE * 2) + hash_len * 2;
+                        idx_len >= min_len && (idx_len - min_len) % 8 == 0 && (idx_len - min_len) / 8 <= n
                     }
-                    dr
-                } else {
-                    d
+                };
+                if !fits {
+                    return Err(corrupt(format!(
+                        "Pack index of size {} does not fit {} objects with {} hashes",
+                        idx_len,
+                        num_objects,
+                        hash_kind.as_str()
+                    )));
                 }
-            };
-            let (fan, bytes_read) = read_fan(d);
-            let (_, _d) = d.split_at(bytes_read);
-            let num_objects = fan[FAN_LEN - 1];
-
-            (kind, fan, num_objects)
+                (num_objects, fan, None)
+            }
+            Version::V3 => {
+                let (num_objects, tables) = read_v3_tables(&data, hash_kind)?;
+                (num_objects, v3_fan(&data, num_objects, hash_len, tables), Some(tables))
+            }
         };
         Ok(index::File {
             data,
             path: path.to_owned(),
-            version: kind,
+            version,
             num_objects,
             fan,
+            hash_kind,
+            v3_tables,
         })
     }
 }
 
-fn read_fan(d: &[u8]) -> ([u32; FAN_LEN], usize) {
+/// Read the header of a V3 index and return the amount of objects along with the tables of the first object format,
+/// which must be the one of `hash_kind`.
+fn read_v3_tables(data: &[u8], hash_kind: git_hash::Kind) -> Result<(u32, V3Tables), Error> {
+    let corrupt = |message: String| Error::Corrupt { message };
+    let read_u32 = |ofs: usize| -> Result<usize, Error> {
+        data.get(ofs..ofs + N32_SIZE)
+            .map(|bytes| BigEndian::read_u32(bytes) as usize)
+            .ok_or_else(|| corrupt(format!("Pack index is truncated at offset {}", ofs)))
+    };
+    let hash_len = hash_kind.len_in_bytes();
+    let trailer_start = data.len() - hash_len * 2;
+    let header_len = read_u32(N32_SIZE * 2)?;
+    let num_objects = read_u32(N32_SIZE * 3)?;
+    let num_formats = read_u32(N32_SIZE * 4)?;
+    if num_formats == 0
+        || header_len < V3_HEADER_FIXED_SIZE + num_formats * V3_FORMAT_SIZE + N32_SIZE
+        || header_len > trailer_start
+    {
+        return Err(corrupt(format!(
+            "Pack index header of {} bytes can't describe {} object formats",
+            header_len, num_formats
+        )));
+    }
+
+    let format_id = &data[V3_HEADER_FIXED_SIZE..V3_HEADER_FIXED_SIZE + N32_SIZE];
+    if format_id != v3_format_id(hash_kind) {
+        return Err(Error::UnsupportedHashKind {
+            expected: hash_kind.as_str(),
+            actual: String::from_utf8_lossy(format_id).into_owned(),
+        });
+    }
+    let short_id_len = read_u32(V3_HEADER_FIXED_SIZE + N32_SIZE)?;
+    let tables_start = read_u32(V3_HEADER_FIXED_SIZE + N32_SIZE * 2)?;
+    if short_id_len == 0 || short_id_len > hash_len {
+        return Err(corrupt(format!(
+            "Shortened object ids can't be {} bytes long",
+            short_id_len
+        )));
+    }
+
+    let ids = tables_start + num_objects * short_id_len;
+    let pack_order = ids + num_objects * hash_len;
+    // The tables shared by all object formats follow those of the last one.
+    let crc32 = if num_formats == 1 {
+        pack_order + num_objects * N32_SIZE
+    } else {
+        let last_format = V3_HEADER_FIXED_SIZE + (num_formats - 1) * V3_FORMAT_SIZE;
+        let last_hash_len = match &data[last_format..last_format + N32_SIZE] {
+            b"sha1" => git_hash::Kind::Sha1.len_in_bytes(),
+            b"s256" => git_hash::Kind::Sha256.len_in_bytes(),
+            id => {
+                return Err(corrupt(format!(
+                    "Unknown object format '{}'",
+                    String::from_utf8_lossy(id)
+                )))
+            }
+        };
+        let last_short_id_len = read_u32(last_format + N32_SIZE)?;
+        let last_tables_start = read_u32(last_format + N32_SIZE * 2)?;
+        last_tables_start + num_objects * (last_short_id_len + last_hash_len + N32_SIZE)
+    };
+    let pack_offsets = crc32 + num_objects * N32_SIZE;
+    let pack_offsets64 = pack_offsets + num_objects * N32_SIZE;
+    if tables_start < header_len || crc32 < pack_order + num_objects * N32_SIZE || pack_offsets64 > trailer_start {
+        return Err(corrupt(format!(
+            "The tables of {} objects don't fit between the header and the trailer",
+            num_objects
+        )));
+    }
+    if (0..num_objects).any(|index| BigEndian::read_u32(&data[pack_order + index * N32_SIZE..]) as usize >= num_objects)
+    {
+        return Err(corrupt("Pack index refers to objects it doesn't contain".into()));
+    }
+    Ok((
+        num_objects as u32,
+        V3Tables {
+            ids,
+            pack_order,
+            crc32,
+            pack_offsets,
+            pack_offsets64,
+        },
+    ))
+}
+
+/// The identifier of the object format using `hash_kind` in the header of V3 index files.
+fn v3_format_id(hash_kind: git_hash::Kind) -> &'static [u8] {
+    match hash_kind {
+        git_hash::Kind::Sha1 => b"sha1",
+        git_hash::Kind::Sha256 => b"s256",
+    }
+}
+
+/// V3 indices have no fan-out table, so compute it from the object ids in `tables` instead.
+fn v3_fan(data: &[u8], num_objects: u32, hash_len: usize, tables: V3Tables) -> [u32; FAN_LEN] {
+    let mut fan = [0; FAN_LEN];
+    for index in 0..num_objects as usize {
+        let pos = BigEndian::read_u32(&data[tables.pack_order + index * N32_SIZE..]) as usize;
+        fan[data[tables.ids + pos * hash_len] as usize] += 1;
+    }
+    let mut total = 0;
+    for count in fan.iter_mut() {
+        total += *count;
+        *count = total;
+    }
+    fan
+}
+
+fn read_fan(d: &[u8]) -> [u32; FAN_LEN] {
     let mut fan = [0; FAN_LEN];
     for (c, f) in d.chunks(N32_SIZE).zip(fan.iter_mut()) {
         *f = BigEndian::read_u32(c);
     }
-    (fan, FAN_LEN * N32_SIZE)
+    fan
 }
@@ -82,6 +82,7 @@ use filebuffer::FileBuffer;
 pub enum Version {
     V1 = 1,
     V2 = 2,
+    V3 = 3,
 }
 
 impl Default for Version {
@@ -91,10 +92,14 @@ impl Default for Version {
 }
 
 impl Version {
-    /// The kind of hash to produce to be compatible to this kind of index
+    /// The kind of hash to produce by default to be compatible to this kind of index.
+    ///
+    /// Note that V2 indices may also hold `Sha256` hashes and V3 indices `Sha1` hashes,
+    /// see [`File::hash_kind()`][crate::index::File::hash_kind()].
     pub fn hash(&self) -> git_hash::Kind {
         match self {
             Version::V1 | Version::V2 => git_hash::Kind::Sha1,
+            Version::V3 => git_hash::Kind::Sha256,
         }
     }
 }
@@ -108,6 +113,23 @@ pub struct File {
     version: Version,
     num_objects: u32,
     fan: [u32; FAN_LEN],
+    hash_kind: git_hash::Kind,
+    v3_tables: Option<V3Tables>,
+}
+
+/// The offsets from the beginning of a V3 index file to the tables of its first object format.
+#[derive(Clone, Copy)]
+struct V3Tables {
+    /// Full object ids in pack order.
+    ids: usize,
+    /// The position in pack order of each object, in the order of their ids.
+    pack_order: usize,
+    /// CRC32 values in pack order.
+    crc32: usize,
+    /// 32 bit pack offsets in the order of the object ids.
+    pack_offsets: usize,
+    /// 64 bit pack offsets referred to by 32 bit offsets with their high bit set.
+    pack_offsets64: usize,
 }
 
 /// Basic file information
@@ -124,6 +146,10 @@ impl File {
     pub fn num_objects(&self) -> u32 {
         self.num_objects
     }
+    /// The kind of hash used for the object ids and checksums in this index, as passed when [opening][File::at()] it.
+    pub fn hash_kind(&self) -> git_hash::Kind {
+        self.hash_kind
+    }
 }
 
 const V2_SIGNATURE: &[u8] = b"\xfftOc";
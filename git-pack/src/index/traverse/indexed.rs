@@ -65,6 +65,7 @@ impl index::File {
                     sorted_entries.into_iter().map(EntryWithDefault::from),
                     |e| e.index_entry.pack_offset,
                     pack.path(),
+                    pack.hash_kind(),
                     progress.add_child("indexing"),
                     &should_interrupt,
                     |id| self.lookup(id).map(|idx| self.pack_offset_at_index(idx)),
@@ -78,6 +79,7 @@ impl index::File {
                     thread_limit,
                     &should_interrupt,
                     pack.pack_end() as u64,
+                    pack.hash_kind(),
                     || (new_processor(), [0u8; 64]),
                     |data,
                      progress,
@@ -222,11 +222,11 @@ where
         let header_size =
             crate::loose::object::header::encode(object_kind, decompressed.len() as u64, &mut header_buf[..])
                 .expect("header buffer to be big enough");
-        let mut hasher = git_features::hash::Sha1::default();
+        let mut hasher = git_features::hash::hasher(index_entry.oid.kind());
         hasher.update(&header_buf[..header_size]);
         hasher.update(decompressed);
 
-        let actual_oid = git_hash::ObjectId::new_sha1(hasher.digest());
+        let actual_oid = hasher.digest();
         if actual_oid != index_entry.oid {
             return Err(Error::PackObjectMismatch {
                 actual: actual_oid,
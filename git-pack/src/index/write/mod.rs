@@ -47,6 +47,7 @@ impl crate::index::File {
     /// `entries` iterator.
     ///
     /// `kind` is the version of pack index to produce, use [`crate::index::Version::default()`] if in doubt.
+    /// The kind of hash used for object ids and the index checksum is the one of the pack checksum seen in the `entries`.
     /// `tread_limit` is used for a parallel tree traversal for obtaining object hashes with optimal performance.
     /// `root_progress` is the top-level progress to stay informed about the progress of this potentially long-running
     /// computation.
@@ -162,6 +163,8 @@ impl crate::index::File {
 
         root_progress.inc();
 
+        let pack_hash = last_seen_trailer.ok_or(Error::IteratorInvariantTrailer)?;
+        let hash_kind = pack_hash.kind();
         let resolver = make_resolver()?;
         let sorted_pack_offsets_by_oid = {
             let in_parallel_if_pack_is_big_enough = || bytes_to_process > 5_000_000;
@@ -173,6 +176,7 @@ impl crate::index::File {
                 thread_limit,
                 should_interrupt,
                 pack_entries_end,
+                hash_kind,
                 || (),
                 |data,
                  _progress,
@@ -181,7 +185,7 @@ impl crate::index::File {
                      decompressed: bytes,
                      ..
                  }| {
-                    modify_base(data, entry, bytes, kind.hash());
+                    modify_base(data, entry, bytes, hash_kind);
                     Ok::<_, Error>(())
                 },
             )?;
@@ -196,7 +200,6 @@ impl crate::index::File {
             items
         };
 
-        let pack_hash = last_seen_trailer.ok_or(Error::IteratorInvariantTrailer)?;
         let index_hash = encode::write_to(
             out,
             sorted_pack_offsets_by_oid,
@@ -229,7 +232,7 @@ fn modify_base(
         loose::object::header::encode(kind, bytes.len() as u64, &mut write)
             .expect("write to sink and hash cannot fail");
         write.hash.update(bytes);
-        git_hash::ObjectId::from(write.hash.digest())
+        write.hash.digest()
     }
 
     let object_kind = pack_entry.header.as_kind().expect("base object as source of iteration");
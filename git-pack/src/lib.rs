@@ -22,6 +22,8 @@ pub mod find;
 #[doc(inline)]
 pub use find::{Find, FindExt};
 
+///
+pub mod bitmap;
 ///
 pub mod cache;
 ///
@@ -29,6 +31,8 @@ pub mod data;
 ///
 pub mod index;
 ///
+pub mod multi_index;
+///
 pub mod tree;
 
 ///
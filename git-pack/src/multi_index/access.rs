@@ -0,0 +1,103 @@
+use std::{cmp::Ordering, convert::TryInto, ops::Range};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::multi_index::{Entry, File, LARGE_OFFSET_BIT, LARGE_OFFSET_ENTRY_SIZE, OBJECT_OFFSET_ENTRY_SIZE};
+
+/// Iteration and access
+impl File {
+    /// Returns the object id at the given `index` in our list of sorted object ids.
+    /// The index ranges from 0 to self.num_objects()
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn oid_at_index(&self, index: u32) -> &git_hash::oid {
+        let hash_len = self.hash_kind.len_in_bytes();
+        let start = self.lookup_ofs + usize_of(index) * hash_len;
+        git_hash::oid::try_from(&self.data[start..start + hash_len]).expect("hash of known length to be alright")
+    }
+
+    /// Returns the position of the pack containing the object at `index` in the [`index_names()`][File::index_names()]
+    /// along with the offset of the object in its pack data file.
+    ///
+    /// The pack index and large offsets of all objects are validated when the file is [opened][File::at()].
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn pack_index_and_offset_at_index(&self, index: u32) -> (u32, u64) {
+        let start = self.offsets_ofs + usize_of(index) * OBJECT_OFFSET_ENTRY_SIZE;
+        let pack_index = BigEndian::read_u32(&self.data[start..start + 4]);
+        let offset = BigEndian::read_u32(&self.data[start + 4..start + 8]);
+        let offset = match self.large_offsets_ofs {
+            Some(large_offsets_ofs) if offset & LARGE_OFFSET_BIT == LARGE_OFFSET_BIT => {
+                let from = large_offsets_ofs + usize_of(offset ^ LARGE_OFFSET_BIT) * LARGE_OFFSET_ENTRY_SIZE;
+                BigEndian::read_u64(&self.data[from..from + LARGE_OFFSET_ENTRY_SIZE])
+            }
+            _ => offset as u64,
+        };
+        (pack_index, offset)
+    }
+
+    /// Returns the `index` of the given object id for use with [`oid_at_index()`][File::oid_at_index()] or
+    /// [`pack_index_and_offset_at_index()`][File::pack_index_and_offset_at_index()].
+    pub fn lookup(&self, id: impl AsRef<git_hash::oid>) -> Option<u32> {
+        let id = id.as_ref();
+        let first_byte = id.first_byte() as usize;
+        let mut upper_bound = self.fan[first_byte];
+        let mut lower_bound = if first_byte != 0 { self.fan[first_byte - 1] } else { 0 };
+
+        while lower_bound < upper_bound {
+            let mid = (lower_bound + upper_bound) / 2;
+            match id.cmp(self.oid_at_index(mid)) {
+                Ordering::Less => upper_bound = mid,
+                Ordering::Equal => return Some(mid),
+                Ordering::Greater => lower_bound = mid + 1,
+            }
+        }
+        None
+    }
+
+    /// Returns the range of indices of all objects whose id starts with `prefix`.
+    ///
+    /// See [`index::File::lookup_prefix()`][crate::index::File::lookup_prefix()] for details.
+    pub fn lookup_prefix(&self, prefix: git_hash::Prefix) -> Range<u32> {
+        let first_byte = prefix.as_oid().first_byte() as usize;
+        let upper_bound = self.fan[first_byte];
+        let lower_bound = if first_byte != 0 { self.fan[first_byte - 1] } else { 0 };
+
+        let bisect = |mut lower_bound: u32, mut upper_bound: u32, is_before: &dyn Fn(Ordering) -> bool| {
+            while lower_bound < upper_bound {
+                let mid = (lower_bound + upper_bound) / 2;
+                if is_before(prefix.cmp_oid(self.oid_at_index(mid))) {
+                    lower_bound = mid + 1;
+                } else {
+                    upper_bound = mid;
+                }
+            }
+            lower_bound
+        };
+        let start = bisect(lower_bound, upper_bound, &|ord| ord == Ordering::Greater);
+        let end = bisect(start, upper_bound, &|ord| ord != Ordering::Less);
+        start..end
+    }
+
+    /// An iterator over all [`Entries`][Entry] of this multi-pack index, sorted by object id.
+    pub fn iter(&self) -> impl Iterator<Item = Entry> + '_ {
+        (0..self.num_objects()).map(move |index| {
+            let (pack_index, pack_offset) = self.pack_index_and_offset_at_index(index);
+            Entry {
+                oid: self.oid_at_index(index).to_owned(),
+                pack_index,
+                pack_offset,
+            }
+        })
+    }
+}
+
+fn usize_of(index: u32) -> usize {
+    index
+        .try_into()
+        .expect("an architecture able to hold 32 bits of integer")
+}
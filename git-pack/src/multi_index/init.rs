@@ -0,0 +1,229 @@
+use std::{
+    convert::{TryFrom, TryInto},
+    path::{Path, PathBuf},
+};
+
+use byteorder::{BigEndian, ByteOrder};
+use filebuffer::FileBuffer;
+use git_object::bstr::ByteSlice;
+
+use crate::multi_index::{
+    ChunkId, File, Version, CHUNK_LOOKUP_SIZE, FAN_LEN, HEADER_LEN, LARGE_OFFSETS_CHUNK_ID, LARGE_OFFSET_BIT,
+    LARGE_OFFSET_ENTRY_SIZE, OBJECT_OFFSETS_CHUNK_ID, OBJECT_OFFSET_ENTRY_SIZE, OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID,
+    PACK_NAMES_CHUNK_ID, SENTINEL_CHUNK_ID, SIGNATURE,
+};
+
+/// Returned by [`multi_index::File::at()`][crate::multi_index::File::at()].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open multi-pack index file at '{path}'")]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{message}")]
+    Corrupt { message: String },
+    #[error("Unsupported multi-pack index version: {version}")]
+    UnsupportedVersion { version: u8 },
+    #[error("Multi-pack index uses unsupported hash version: {version}")]
+    UnsupportedHashVersion { version: u8 },
+    #[error("Multi-pack index files with {count} base files are not supported")]
+    UnsupportedBaseFiles { count: u8 },
+    #[error("Multi-pack index file contains multiple {:?} chunks", .0.as_bstr())]
+    DuplicateChunk(ChunkId),
+    #[error("Multi-pack index chunk {:?} has invalid size: {message}", .id.as_bstr())]
+    InvalidChunkSize { id: ChunkId, message: String },
+    #[error("Missing required chunk {:?}", .0.as_bstr())]
+    MissingChunk(ChunkId),
+}
+
+/// Instantiation
+impl File {
+    /// Open the multi-pack index file at the given `path`.
+    pub fn at(path: impl AsRef<Path>) -> Result<File, Error> {
+        Self::try_from(path.as_ref())
+    }
+}
+
+impl TryFrom<&Path> for File {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let data = FileBuffer::open(path).map_err(|source| Error::Io {
+            source,
+            path: path.to_owned(),
+        })?;
+        let corrupt = |message: String| Error::Corrupt { message };
+        let data_size = data.len();
+        if data_size < HEADER_LEN + CHUNK_LOOKUP_SIZE + git_hash::Kind::shortest().len_in_bytes() {
+            return Err(corrupt(format!(
+                "Multi-pack index of size {} is too small for even an empty index",
+                data_size
+            )));
+        }
+        if &data[..SIGNATURE.len()] != SIGNATURE {
+            return Err(corrupt(
+                "Multi-pack index does not start with expected signature".into(),
+            ));
+        }
+        let version = match data[4] {
+            1 => Version::V1,
+            version => return Err(Error::UnsupportedVersion { version }),
+        };
+        let hash_kind =
+            git_hash::Kind::from_version(data[5]).ok_or(Error::UnsupportedHashVersion { version: data[5] })?;
+        let hash_len = hash_kind.len_in_bytes();
+        let chunk_count = usize::from(data[6]);
+        if data[7] != 0 {
+            return Err(Error::UnsupportedBaseFiles { count: data[7] });
+        }
+        let num_indices = BigEndian::read_u32(&data[8..12]);
+
+        let chunk_lookup_end = HEADER_LEN + (chunk_count + 1) * CHUNK_LOOKUP_SIZE;
+        if chunk_lookup_end + hash_len > data_size {
+            return Err(corrupt(format!(
+                "Multi-pack index is too small to hold {} chunks",
+                chunk_count
+            )));
+        }
+        let chunk_at = |index: usize| -> (ChunkId, usize) {
+            let ofs = HEADER_LEN + index * CHUNK_LOOKUP_SIZE;
+            (
+                data[ofs..ofs + 4].try_into().expect("ChunkId to accept 4 bytes"),
+                BigEndian::read_u64(&data[ofs + 4..ofs + 12])
+                    .try_into()
+                    .expect("an offset small enough to fit a usize"),
+            )
+        };
+
+        let (mut pack_names, mut fan_ofs, mut lookup, mut offsets, mut large_offsets) = (None, None, None, None, None);
+        for index in 0..chunk_count {
+            let ((id, start), (_, end)) = (chunk_at(index), chunk_at(index + 1));
+            if start < chunk_lookup_end || end < start || end > data_size - hash_len {
+                return Err(Error::InvalidChunkSize {
+                    id,
+                    message: format!("chunk range {}..{} is out of bounds", start, end),
+                });
+            }
+            let slot = match id {
+                PACK_NAMES_CHUNK_ID => &mut pack_names,
+                OID_FAN_CHUNK_ID => &mut fan_ofs,
+                OID_LOOKUP_CHUNK_ID => &mut lookup,
+                OBJECT_OFFSETS_CHUNK_ID => &mut offsets,
+                LARGE_OFFSETS_CHUNK_ID => &mut large_offsets,
+                _ => continue,
+            };
+            if slot.replace(start..end).is_some() {
+                return Err(Error::DuplicateChunk(id));
+            }
+        }
+        if chunk_at(chunk_count).0 != SENTINEL_CHUNK_ID {
+            return Err(corrupt("Multi-pack index chunk table is not terminated".into()));
+        }
+
+        let pack_names = pack_names.ok_or(Error::MissingChunk(PACK_NAMES_CHUNK_ID))?;
+        let fan_range = fan_ofs.ok_or(Error::MissingChunk(OID_FAN_CHUNK_ID))?;
+        let lookup = lookup.ok_or(Error::MissingChunk(OID_LOOKUP_CHUNK_ID))?;
+        let offsets = offsets.ok_or(Error::MissingChunk(OBJECT_OFFSETS_CHUNK_ID))?;
+
+        if fan_range.len() != FAN_LEN * 4 {
+            return Err(Error::InvalidChunkSize {
+                id: OID_FAN_CHUNK_ID,
+                message: format!("expected chunk length {}, got {}", FAN_LEN * 4, fan_range.len()),
+            });
+        }
+        let mut fan = [0; FAN_LEN];
+        for (c, f) in data[fan_range].chunks(4).zip(fan.iter_mut()) {
+            *f = BigEndian::read_u32(c);
+        }
+        let num_objects = fan[FAN_LEN - 1] as usize;
+        for (id, range, entry_size) in &[
+            (OID_LOOKUP_CHUNK_ID, &lookup, hash_len),
+            (OBJECT_OFFSETS_CHUNK_ID, &offsets, OBJECT_OFFSET_ENTRY_SIZE),
+        ] {
+            if range.len() != num_objects * entry_size {
+                return Err(Error::InvalidChunkSize {
+                    id: *id,
+                    message: format!(
+                        "expected {} entries of size {}, got {} bytes",
+                        num_objects,
+                        entry_size,
+                        range.len()
+                    ),
+                });
+            }
+        }
+        if let Some(range) = &large_offsets {
+            if range.len() % LARGE_OFFSET_ENTRY_SIZE != 0 {
+                return Err(Error::InvalidChunkSize {
+                    id: LARGE_OFFSETS_CHUNK_ID,
+                    message: format!(
+                        "chunk size {} is not a multiple of {}",
+                        range.len(),
+                        LARGE_OFFSET_ENTRY_SIZE
+                    ),
+                });
+            }
+        }
+
+        let num_large_offsets = large_offsets
+            .as_ref()
+            .map_or(0, |range| range.len() / LARGE_OFFSET_ENTRY_SIZE);
+        for (index, entry) in data[offsets.clone()].chunks(OBJECT_OFFSET_ENTRY_SIZE).enumerate() {
+            let pack_index = BigEndian::read_u32(&entry[..4]);
+            if pack_index >= num_indices {
+                return Err(corrupt(format!(
+                    "Object {} is in pack {}, but there are only {} packs",
+                    index, pack_index, num_indices
+                )));
+            }
+            let offset = BigEndian::read_u32(&entry[4..]);
+            if offset & LARGE_OFFSET_BIT == LARGE_OFFSET_BIT {
+                let large_offset_index = (offset ^ LARGE_OFFSET_BIT) as usize;
+                if large_offsets.is_none() {
+                    return Err(corrupt(format!(
+                        "Object {} has a large offset, but there is no {:?} chunk",
+                        index,
+                        LARGE_OFFSETS_CHUNK_ID.as_bstr()
+                    )));
+                }
+                if large_offset_index >= num_large_offsets {
+                    return Err(corrupt(format!(
+                        "Object {} refers to large offset {}, but there are only {}",
+                        index, large_offset_index, num_large_offsets
+                    )));
+                }
+            }
+        }
+
+        let index_names: Vec<PathBuf> = data[pack_names]
+            .split(|b| *b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                name.to_path()
+                    .map(ToOwned::to_owned)
+                    .map_err(|_| corrupt("Multi-pack index contains a pack name that isn't a valid path".into()))
+            })
+            .collect::<Result<_, _>>()?;
+        if index_names.len() != num_indices as usize {
+            return Err(corrupt(format!(
+                "Multi-pack index header claims {} packs, but {} pack names are present",
+                num_indices,
+                index_names.len()
+            )));
+        }
+
+        Ok(File {
+            lookup_ofs: lookup.start,
+            offsets_ofs: offsets.start,
+            large_offsets_ofs: large_offsets.map(|range| range.start),
+            data,
+            path: path.to_owned(),
+            version,
+            hash_kind,
+            fan,
+            index_names,
+        })
+    }
+}
@@ -0,0 +1,101 @@
+//! A multi-pack index maps objects of many packs in the same directory to their pack and offset, avoiding a search
+//! through each pack index in turn.
+use std::path::PathBuf;
+
+use filebuffer::FileBuffer;
+
+/// The version of a multi-pack index file
+#[derive(PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Version {
+    V1 = 1,
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::V1
+    }
+}
+
+/// An entry of a multi-pack index, identifying an object and its location in one of the indexed packs.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// The ID of the object
+    pub oid: git_hash::ObjectId,
+    /// The position of the pack containing the object in the [`index_names()`][File::index_names()].
+    pub pack_index: u32,
+    /// The offset to the object's header in the pack data file
+    pub pack_offset: u64,
+}
+
+/// A representation of a multi-pack index file, conventionally located at `objects/pack/multi-pack-index`.
+pub struct File {
+    data: FileBuffer,
+    path: PathBuf,
+    version: Version,
+    hash_kind: git_hash::Kind,
+    fan: [u32; FAN_LEN],
+    index_names: Vec<PathBuf>,
+    lookup_ofs: usize,
+    offsets_ofs: usize,
+    large_offsets_ofs: Option<usize>,
+}
+
+/// Basic file information
+impl File {
+    /// The version of the multi-pack index
+    pub fn version(&self) -> Version {
+        self.version
+    }
+    /// The path of the opened multi-pack index file
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+    /// The kind of hash used for all object ids in this file.
+    pub fn hash_kind(&self) -> git_hash::Kind {
+        self.hash_kind
+    }
+    /// The amount of objects stored in all indexed packs, with each object counted only once.
+    pub fn num_objects(&self) -> u32 {
+        self.fan[FAN_LEN - 1]
+    }
+    /// The amount of indexed packs.
+    pub fn num_indices(&self) -> u32 {
+        self.index_names.len() as u32
+    }
+    /// The file names of the pack index files of all indexed packs, relative to the directory containing this file and
+    /// sorted by name. The position of a name is the pack index used in [`Entry`].
+    pub fn index_names(&self) -> &[PathBuf] {
+        &self.index_names
+    }
+}
+
+const FAN_LEN: usize = 256;
+const SIGNATURE: &[u8] = b"MIDX";
+const HEADER_LEN: usize = 12;
+const CHUNK_LOOKUP_SIZE: usize = 12;
+const CHUNK_ALIGNMENT: usize = 4;
+const OBJECT_OFFSET_ENTRY_SIZE: usize = 8;
+const LARGE_OFFSET_ENTRY_SIZE: usize = 8;
+const LARGE_OFFSET_BIT: u32 = 0x8000_0000;
+
+type ChunkId = [u8; 4];
+const PACK_NAMES_CHUNK_ID: ChunkId = *b"PNAM";
+const OID_FAN_CHUNK_ID: ChunkId = *b"OIDF";
+const OID_LOOKUP_CHUNK_ID: ChunkId = *b"OIDL";
+const OBJECT_OFFSETS_CHUNK_ID: ChunkId = *b"OOFF";
+const LARGE_OFFSETS_CHUNK_ID: ChunkId = *b"LOFF";
+const SENTINEL_CHUNK_ID: ChunkId = [0u8; 4];
+
+///
+pub mod init;
+
+mod access;
+
+///
+pub mod verify;
+
+///
+pub mod write;
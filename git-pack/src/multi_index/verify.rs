@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use git_features::progress::{self, Progress};
+
+use crate::multi_index::File;
+
+/// Returned by [`multi_index::File::verify_checksum()`][crate::multi_index::File::verify_checksum()]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("multi-pack index checksum mismatch: expected {expected}, got {actual}")]
+    Mismatch {
+        expected: git_hash::ObjectId,
+        actual: git_hash::ObjectId,
+    },
+    #[error("Interrupted by user")]
+    Interrupted,
+}
+
+/// Verify the content of the multi-pack index file
+impl File {
+    /// Returns the trailing hash stored at the end of this file, which is a hash over all of its other bytes.
+    pub fn checksum(&self) -> git_hash::ObjectId {
+        git_hash::ObjectId::from_bytes_or_panic(&self.data[self.data.len() - self.hash_kind.len_in_bytes()..])
+    }
+
+    /// Validate that our [`checksum()`][File::checksum()] matches the actual contents of this file, and return it if it does.
+    pub fn verify_checksum(
+        &self,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+    ) -> Result<git_hash::ObjectId, Error> {
+        const CHUNK_SIZE: usize = 1024 * 1024;
+        let data_len_without_trailer = self.data.len() - self.hash_kind.len_in_bytes();
+        progress.init(Some(data_len_without_trailer), progress::bytes());
+        let start = std::time::Instant::now();
+        let mut hasher = git_features::hash::hasher(self.hash_kind);
+        for chunk in self.data[..data_len_without_trailer].chunks(CHUNK_SIZE) {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            hasher.update(chunk);
+            progress.inc_by(chunk.len());
+        }
+        progress.show_throughput(start);
+        let actual = hasher.digest();
+        let expected = self.checksum();
+        if actual == expected {
+            Ok(actual)
+        } else {
+            Err(Error::Mismatch { actual, expected })
+        }
+    }
+}
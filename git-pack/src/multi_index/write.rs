@@ -0,0 +1,212 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    time::SystemTime,
+};
+
+use byteorder::{BigEndian, WriteBytesExt};
+use git_features::{
+    hash,
+    progress::{self, Progress},
+};
+
+use crate::{
+    index,
+    multi_index::{
+        ChunkId, File, Version, CHUNK_ALIGNMENT, CHUNK_LOOKUP_SIZE, FAN_LEN, HEADER_LEN, LARGE_OFFSETS_CHUNK_ID,
+        LARGE_OFFSET_BIT, LARGE_OFFSET_ENTRY_SIZE, OBJECT_OFFSETS_CHUNK_ID, OBJECT_OFFSET_ENTRY_SIZE, OID_FAN_CHUNK_ID,
+        OID_LOOKUP_CHUNK_ID, PACK_NAMES_CHUNK_ID, SENTINEL_CHUNK_ID, SIGNATURE,
+    },
+};
+
+/// Returned by [`multi_index::File::write_from_indices()`][crate::multi_index::File::write_from_indices()]
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Pack index files must be named like 'pack-<checksum>.idx', got '{}'", .0.display())]
+    InvalidIndexName(PathBuf),
+    #[error("A multi-pack index can reference at most {} objects", u32::MAX)]
+    TooManyObjects,
+    #[error("Interrupted by user")]
+    Interrupted,
+    #[error("All pack indices must use the same kind of hash, but both {0:?} and {1:?} were found")]
+    MixedHashKinds(git_hash::Kind, git_hash::Kind),
+}
+
+/// The outcome of [`multi_index::File::write_from_indices()`][crate::multi_index::File::write_from_indices()]
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The checksum over all bytes of the written multi-pack index, as stored in its trailer.
+    pub multi_index_checksum: git_hash::ObjectId,
+    /// The amount of distinct objects in all indexed packs.
+    pub num_objects: u32,
+}
+
+struct Entry {
+    id: git_hash::ObjectId,
+    pack_index: u32,
+    pack_offset: u64,
+    pack_mtime: Option<SystemTime>,
+}
+
+/// Writing
+impl File {
+    /// Write a multi-pack index of version 1 covering all objects in all `indices` to `out`, which should be placed into the
+    /// directory containing the packs as `multi-pack-index`.
+    ///
+    /// Objects contained in more than one pack are attributed to the pack that was modified most recently, just like git does.
+    pub fn write_from_indices<'a>(
+        indices: impl IntoIterator<Item = &'a index::File>,
+        out: impl io::Write,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        let mut indices = indices
+            .into_iter()
+            .map(|index| {
+                let name = index
+                    .path()
+                    .file_name()
+                    .filter(|name| {
+                        let name = name.to_string_lossy();
+                        name.starts_with("pack-") && name.ends_with(".idx")
+                    })
+                    .ok_or_else(|| Error::InvalidIndexName(index.path().to_owned()))?;
+                Ok((PathBuf::from(name), index))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        indices.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+        indices.dedup_by(|(lhs, _), (rhs, _)| lhs == rhs);
+        let hash_kind = indices
+            .first()
+            .map_or(git_hash::Kind::Sha1, |(_, index)| index.hash_kind());
+        if let Some((_, index)) = indices.iter().find(|(_, index)| index.hash_kind() != hash_kind) {
+            return Err(Error::MixedHashKinds(hash_kind, index.hash_kind()));
+        }
+
+        let num_entries: usize = indices.iter().map(|(_, index)| index.num_objects() as usize).sum();
+        progress.init(Some(num_entries), progress::count("entries"));
+        let start = std::time::Instant::now();
+        let mut entries = Vec::with_capacity(num_entries);
+        for (pack_index, (_, index)) in indices.iter().enumerate() {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            let pack_mtime = std::fs::metadata(index.path().with_extension("pack"))
+                .and_then(|md| md.modified())
+                .ok();
+            entries.extend(index.iter().map(|entry| Entry {
+                id: entry.oid,
+                pack_index: pack_index as u32,
+                pack_offset: entry.pack_offset,
+                pack_mtime,
+            }));
+            progress.inc_by(index.num_objects() as usize);
+        }
+        entries.sort_by(|lhs, rhs| {
+            lhs.id
+                .cmp(&rhs.id)
+                .then_with(|| rhs.pack_mtime.cmp(&lhs.pack_mtime))
+                .then_with(|| lhs.pack_index.cmp(&rhs.pack_index))
+        });
+        entries.dedup_by_key(|entry| entry.id);
+        if entries.len() > u32::MAX as usize {
+            return Err(Error::TooManyObjects);
+        }
+        progress.show_throughput(start);
+
+        let mut pack_names = Vec::new();
+        for (name, _) in &indices {
+            pack_names.extend_from_slice(name.to_string_lossy().as_bytes());
+            pack_names.push(0);
+        }
+        pack_names.resize(
+            (pack_names.len() + CHUNK_ALIGNMENT - 1) / CHUNK_ALIGNMENT * CHUNK_ALIGNMENT,
+            0,
+        );
+
+        // Like git, only use large offsets if some offsets don't fit into 32 bits, and then only for those that would
+        // have the high bit set.
+        let large_offsets_needed = entries.iter().any(|e| e.pack_offset > u32::MAX as u64);
+        let num_large_offsets = if large_offsets_needed {
+            entries
+                .iter()
+                .filter(|e| e.pack_offset >= LARGE_OFFSET_BIT as u64)
+                .count()
+        } else {
+            0
+        };
+
+        let num_objects = entries.len();
+        let mut chunks: Vec<(ChunkId, usize)> = vec![
+            (PACK_NAMES_CHUNK_ID, pack_names.len()),
+            (OID_FAN_CHUNK_ID, FAN_LEN * 4),
+            (OID_LOOKUP_CHUNK_ID, num_objects * hash_kind.len_in_bytes()),
+            (OBJECT_OFFSETS_CHUNK_ID, num_objects * OBJECT_OFFSET_ENTRY_SIZE),
+        ];
+        if large_offsets_needed {
+            chunks.push((LARGE_OFFSETS_CHUNK_ID, num_large_offsets * LARGE_OFFSET_ENTRY_SIZE));
+        }
+
+        let mut out = hash::Write::new(io::BufWriter::with_capacity(8 * 4096, out), hash_kind);
+        out.write_all(SIGNATURE)?;
+        out.write_u8(Version::V1 as u8)?;
+        out.write_u8(hash_kind.version())?;
+        out.write_u8(chunks.len() as u8)?;
+        out.write_u8(0 /* base multi-pack index files */)?;
+        out.write_u32::<BigEndian>(indices.len() as u32)?;
+
+        let mut chunk_offset = (HEADER_LEN + (chunks.len() + 1) * CHUNK_LOOKUP_SIZE) as u64;
+        for (id, len) in chunks.iter().chain(std::iter::once(&(SENTINEL_CHUNK_ID, 0))) {
+            out.write_all(id)?;
+            out.write_u64::<BigEndian>(chunk_offset)?;
+            chunk_offset += *len as u64;
+        }
+
+        out.write_all(&pack_names)?;
+
+        let mut fan = [0u32; FAN_LEN];
+        for entry in &entries {
+            fan[entry.id.first_byte() as usize] += 1;
+        }
+        let mut count = 0;
+        for value in fan.iter() {
+            count += value;
+            out.write_u32::<BigEndian>(count)?;
+        }
+
+        for entry in &entries {
+            out.write_all(entry.id.as_slice())?;
+        }
+
+        let mut large_offsets = Vec::with_capacity(num_large_offsets);
+        for entry in &entries {
+            out.write_u32::<BigEndian>(entry.pack_index)?;
+            out.write_u32::<BigEndian>(
+                if large_offsets_needed && entry.pack_offset >= LARGE_OFFSET_BIT as u64 {
+                    large_offsets.push(entry.pack_offset);
+                    (large_offsets.len() - 1) as u32 | LARGE_OFFSET_BIT
+                } else {
+                    entry.pack_offset as u32
+                },
+            )?;
+        }
+        for offset in large_offsets {
+            out.write_u64::<BigEndian>(offset)?;
+        }
+
+        let multi_index_checksum = out.hash.digest();
+        let mut out = out.inner;
+        out.write_all(multi_index_checksum.as_slice())?;
+        out.flush()?;
+
+        Ok(Outcome {
+            multi_index_checksum,
+            num_objects: num_objects as u32,
+        })
+    }
+}
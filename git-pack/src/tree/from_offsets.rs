@@ -35,6 +35,7 @@ impl<T> Tree<T> {
     /// for obtaining the objects entry within the pack.
     /// * `pack_path` is the path to the pack file itself and from which to read the entry data, which is a pack file matching the offsets
     /// returned by `get_pack_offset(…)`.
+    /// * `hash_kind` is the kind of hash used for object ids in the pack.
     /// * `progress` is used to track progress when creating the tree.
     /// * `resolve_in_pack_id(git_hash::oid) -> Option<PackOffset>` takes an object ID and tries to resolve it to an object within this pack if
     /// possible. Failing to do so aborts the operation, and this function is not expected to be called in usual packs. It's a theoretical
@@ -45,6 +46,7 @@ impl<T> Tree<T> {
         data_sorted_by_offsets: impl Iterator<Item = T>,
         get_pack_offset: impl Fn(&T) -> PackOffset,
         pack_path: impl AsRef<std::path::Path>,
+        hash_kind: git_hash::Kind,
         mut progress: impl Progress,
         should_interrupt: &AtomicBool,
         resolve_in_pack_id: impl Fn(&git_hash::oid) -> Option<PackOffset>,
@@ -84,10 +86,13 @@ impl<T> Tree<T> {
             if let Some(previous_offset) = previous_cursor_position {
                 Self::advance_cursor_to_pack_offset(&mut r, pack_offset, previous_offset)?;
             };
-            let entry = crate::data::Entry::from_read(&mut r, pack_offset).map_err(|err| Error::Io {
-                source: err,
-                message: "EOF while parsing header",
-            })?;
+            let entry =
+                crate::data::Entry::from_read(&mut r, pack_offset, hash_kind.len_in_bytes()).map_err(|err| {
+                    Error::Io {
+                        source: err,
+                        message: "EOF while parsing header",
+                    }
+                })?;
             previous_cursor_position = Some(pack_offset + entry.header_size() as u64);
 
             use crate::data::entry::Header::*;
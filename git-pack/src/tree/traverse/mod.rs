@@ -63,6 +63,7 @@ where
     /// * `tread_limit` is limits the amount of threads used if `Some` or otherwise defaults to all available logical cores.
     /// * `pack_entries_end` marks one-past-the-last byte of the last entry in the pack, as the last entries size would otherwise
     ///   be unknown as it's not part of the index file.
+    /// * `hash_kind` is the kind of hash used for object ids in the pack, as needed to decode the headers of ref-deltas.
     /// * `new_thread_state() -> State` is a function to create state to be used in each thread, invoked once per thread.
     /// * `inspect_object(node_data: &mut T, progress: Progress, context: Context<ThreadLocal State>) -> Result<(), CustomError>` is a function
     ///   running for each thread receiving fully decoded objects along with contextual information, which either succceeds with `Ok(())`
@@ -82,6 +83,7 @@ where
         thread_limit: Option<usize>,
         should_interrupt: &AtomicBool,
         pack_entries_end: u64,
+        hash_kind: git_hash::Kind,
         new_thread_state: impl Fn() -> S + Send + Sync,
         inspect_object: MBFN,
     ) -> Result<VecDeque<Item<T>>, Error>
@@ -107,7 +109,7 @@ where
                     new_thread_state(),
                 )
             },
-            |root_nodes, state| resolve::deltas(root_nodes, state, &resolve, &inspect_object),
+            |root_nodes, state| resolve::deltas(root_nodes, state, &resolve, hash_kind.len_in_bytes(), &inspect_object),
             Reducer::new(num_objects, &object_progress, size_progress, should_interrupt),
         )?;
         Ok(self.into_items())
@@ -14,6 +14,7 @@ pub(crate) fn deltas<T, F, P, MBFN, S, E>(
     nodes: crate::tree::Chunk<'_, T>,
     (bytes_buf, ref mut progress, state): &mut (Vec<u8>, P, S),
     resolve: F,
+    hash_len: usize,
     modify_base: MBFN,
 ) -> Result<(usize, u64), Error>
 where
@@ -33,7 +34,7 @@ where
         resolve(slice.clone(), &mut bytes_buf).ok_or(Error::ResolveFailed {
             pack_offset: slice.start,
         })?;
-        let entry = crate::data::Entry::from_bytes(&bytes_buf, slice.start);
+        let entry = crate::data::Entry::from_bytes(&bytes_buf, slice.start, hash_len);
         let compressed = &bytes_buf[entry.header_size() as usize..];
         let decompressed_len = entry.decompressed_size as usize;
         Ok((entry, slice.end, decompress_all_at_once(compressed, decompressed_len)?))
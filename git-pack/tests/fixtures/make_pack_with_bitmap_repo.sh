@@ -0,0 +1,25 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+for round in $(seq 12); do
+  mkdir -p "dir/$round"
+  echo "$round" > "dir/$round/file"
+  echo "$round" >> file
+  git add .
+  git commit -qm "$round"
+done
+
+git checkout -q -b other HEAD~6
+echo other > other
+git add other
+git commit -qm other
+git checkout -q main
+git merge -q --no-ff other -m "merge other"
+git tag -m "a tag object" v1
+
+# a single pack with a bitmap for some of the commits
+git repack -adbq
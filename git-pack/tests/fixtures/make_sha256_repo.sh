@@ -0,0 +1,19 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q --object-format=sha256
+git config commit.gpgsign false
+
+git checkout -q -b main
+for round in $(seq 6); do
+  mkdir -p "dir/$round"
+  echo "$round" > "dir/$round/file"
+  echo "$round" >> file
+  git add .
+  git commit -qm "$round"
+done
+git tag -m "a tag object" v1
+
+# a single pack with a bitmap, along with a multi-pack-index
+git repack -adbq
+git multi-pack-index write
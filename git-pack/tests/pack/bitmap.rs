@@ -0,0 +1,310 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use git_hash::ObjectId;
+use git_pack::{bitmap, Bundle};
+
+use crate::scripted_fixture_repo_read_only;
+
+pub fn pack_index_path(repo: &Path) -> crate::Result<PathBuf> {
+    let pack_dir = repo.join(".git").join("objects").join("pack");
+    for entry in std::fs::read_dir(&pack_dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == "idx") {
+            return Ok(path);
+        }
+    }
+    Err("the fixture has a pack".into())
+}
+
+fn git(repo: &Path, args: &[&str]) -> crate::Result<String> {
+    let output = Command::new("git").args(args).current_dir(repo).output()?;
+    assert!(output.status.success(), "git {:?} failed", args);
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+fn reachable_according_to_git(repo: &Path, commit: &ObjectId) -> crate::Result<BTreeSet<ObjectId>> {
+    git(repo, &["rev-list", "--objects", &commit.to_string()])?
+        .lines()
+        .map(|line| Ok(ObjectId::from_hex(line.split(' ').next().expect("id").as_bytes())?))
+        .collect()
+}
+
+fn reachable_according_to(bundle: &Bundle, bitmap: &bitmap::File, index_position: u32) -> BTreeSet<ObjectId> {
+    bitmap
+        .reachable_from(index_position)
+        .expect("bitmap present")
+        .iter_ones()
+        .map(|pack_position| {
+            let index_position = bitmap.index_position_at(pack_position).expect("object in pack");
+            bundle.index.oid_at_index(index_position).to_owned()
+        })
+        .collect()
+}
+
+mod ewah {
+    use git_pack::bitmap::{ewah, Bits};
+
+    fn round_trip(bits: &Bits) -> Bits {
+        let mut buf = Vec::new();
+        bits.to_ewah().write_to(&mut buf).expect("write to memory");
+        let (decoded, rest) = ewah::decode(&buf).expect("valid encoding");
+        assert!(rest.is_empty(), "everything was consumed");
+        Bits::from_ewah(&decoded)
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut sparse = Bits::default();
+        for position in &[0, 1, 63, 64, 200, 5000] {
+            sparse.set(*position);
+        }
+        let mut dense = Bits::from_words(vec![u64::MAX; 100]);
+        dense.xor_with(&Bits::from_words(vec![0, 0, 0, 0b101]));
+        let mixed = Bits::from_words(vec![0, 0, u64::MAX, 42, 0, u64::MAX, u64::MAX, 1, 0]);
+
+        for bits in &[Bits::default(), sparse, dense, mixed] {
+            let decoded = round_trip(bits);
+            assert_eq!(
+                decoded.iter_ones().collect::<Vec<_>>(),
+                bits.iter_ones().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn num_bits_is_one_past_the_highest_set_bit_like_in_git() {
+        let mut bits = Bits::default();
+        bits.set(70);
+        assert_eq!(bits.to_ewah().num_bits(), 71);
+        assert_eq!(Bits::default().to_ewah().num_bits(), 0);
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let mut buf = Vec::new();
+        Bits::from_words(vec![42, 43])
+            .to_ewah()
+            .write_to(&mut buf)
+            .expect("write to memory");
+        for len in 0..buf.len() {
+            assert!(ewah::decode(&buf[..len]).is_err(), "{} bytes aren't enough", len);
+        }
+    }
+}
+
+mod file {
+    use git_object::Kind;
+    use git_pack::Bundle;
+
+    use crate::{
+        pack::bitmap::{pack_index_path, reachable_according_to, reachable_according_to_git},
+        scripted_fixture_repo_read_only,
+    };
+
+    #[test]
+    fn reachability_matches_git() -> crate::Result {
+        let repo = scripted_fixture_repo_read_only("make_pack_with_bitmap_repo.sh")?;
+        let bundle = Bundle::at(pack_index_path(&repo)?, git_hash::Kind::Sha1)?;
+        let bitmap = bundle.bitmap.as_ref().expect("git wrote a bitmap");
+        assert_eq!(bitmap.version(), git_pack::bitmap::Version::V1);
+        assert_eq!(bitmap.pack_checksum(), bundle.index.pack_checksum());
+        assert_eq!(bitmap.num_objects(), bundle.index.num_objects());
+        assert!(bitmap.num_commits() > 1);
+
+        for index_position in bitmap.commit_index_positions() {
+            let commit = bundle.index.oid_at_index(index_position).to_owned();
+            assert_eq!(
+                reachable_according_to(&bundle, bitmap, index_position),
+                reachable_according_to_git(&repo, &commit)?,
+                "commit {}",
+                commit
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn reachability_matches_git_in_sha256_repositories() -> crate::Result {
+        let repo = scripted_fixture_repo_read_only("make_sha256_repo.sh")?;
+        let bundle = Bundle::at(pack_index_path(&repo)?, git_hash::Kind::Sha256)?;
+        assert_eq!(bundle.index.hash_kind(), git_hash::Kind::Sha256);
+        let bitmap = bundle.bitmap.as_ref().expect("git wrote a bitmap");
+        assert_eq!(bitmap.pack_checksum(), bundle.index.pack_checksum());
+        assert_eq!(bitmap.num_objects(), bundle.index.num_objects());
+
+        for index_position in bitmap.commit_index_positions() {
+            let commit = bundle.index.oid_at_index(index_position).to_owned();
+            assert_eq!(
+                reachable_according_to(&bundle, bitmap, index_position),
+                reachable_according_to_git(&repo, &commit)?,
+                "commit {}",
+                commit
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn objects_of_kind_cover_all_objects() -> crate::Result {
+        let repo = scripted_fixture_repo_read_only("make_pack_with_bitmap_repo.sh")?;
+        let bundle = Bundle::at(pack_index_path(&repo)?, git_hash::Kind::Sha1)?;
+        let bitmap = bundle.bitmap.as_ref().expect("git wrote a bitmap");
+
+        let mut buf = Vec::new();
+        let mut num_objects = 0;
+        for kind in &[Kind::Commit, Kind::Tree, Kind::Blob, Kind::Tag] {
+            for pack_position in bitmap.objects_of_kind(*kind).iter_ones() {
+                let index_position = bitmap.index_position_at(pack_position).expect("object in pack");
+                let object = bundle.get_object_by_index(index_position, &mut buf, &mut git_pack::cache::Never)?;
+                assert_eq!(object.kind, *kind);
+                num_objects += 1;
+            }
+        }
+        assert_eq!(num_objects, bundle.index.num_objects());
+        Ok(())
+    }
+
+    #[test]
+    fn bitmaps_of_other_packs_are_rejected() -> crate::Result {
+        let repo = scripted_fixture_repo_read_only("make_pack_with_bitmap_repo.sh")?;
+        let index_path = pack_index_path(&repo)?;
+        let other_index =
+            git_pack::index::File::at(crate::fixture_path(crate::pack::SMALL_PACK_INDEX), git_hash::Kind::Sha1)?;
+        assert!(matches!(
+            git_pack::bitmap::File::at(index_path.with_extension("bitmap"), &other_index),
+            Err(git_pack::bitmap::init::Error::PackMismatch { .. })
+        ));
+
+        let bundle = Bundle::at(crate::fixture_path(crate::pack::SMALL_PACK_INDEX), git_hash::Kind::Sha1)?;
+        assert!(bundle.bitmap.is_none(), "there is no bitmap for this pack");
+        Ok(())
+    }
+}
+
+mod write {
+    use std::sync::atomic::AtomicBool;
+
+    use git_features::progress;
+    use git_pack::{bitmap, Bundle};
+
+    use crate::pack::bitmap::{git, pack_index_path, reachable_according_to, reachable_according_to_git};
+
+    #[test]
+    fn reachability_matches_git_and_git_accepts_the_file() -> crate::Result {
+        let repo = git_testtools::scripted_fixture_repo_writable("make_pack_with_bitmap_repo.sh")?;
+        let repo = repo.path();
+        let index_path = pack_index_path(repo)?;
+        let bitmap_path = index_path.with_extension("bitmap");
+        std::fs::remove_file(&bitmap_path)?;
+        let bundle = Bundle::at(&index_path, git_hash::Kind::Sha1)?;
+        assert!(bundle.bitmap.is_none());
+
+        let commits: Vec<_> = git(repo, &["rev-list", "--all"])?
+            .lines()
+            .map(|line| git_hash::ObjectId::from_hex(line.as_bytes()))
+            .collect::<Result<_, _>>()?;
+        let outcome = bitmap::File::write_for_commits(
+            &bundle,
+            commits.iter().cloned().chain(commits.first().cloned()),
+            std::fs::File::create(&bitmap_path)?,
+            &mut git_pack::cache::Never,
+            progress::Discard,
+            &AtomicBool::new(false),
+        )?;
+        assert_eq!(outcome.num_commits as usize, commits.len(), "duplicates are ignored");
+
+        let bundle = Bundle::at(&index_path, git_hash::Kind::Sha1)?;
+        let bitmap = bundle.bitmap.as_ref().expect("our bitmap can be read");
+        assert_eq!(bitmap.num_commits() as usize, commits.len());
+        for commit in &commits {
+            let index_position = bundle.index.lookup(commit).expect("commit in pack");
+            assert_eq!(
+                reachable_according_to(&bundle, bitmap, index_position),
+                reachable_according_to_git(repo, commit)?
+            );
+        }
+
+        git(repo, &["rev-list", "--test-bitmap", "main"])?;
+        Ok(())
+    }
+
+    #[test]
+    fn non_commits_are_rejected() -> crate::Result {
+        let repo = crate::scripted_fixture_repo_read_only("make_pack_with_bitmap_repo.sh")?;
+        let bundle = Bundle::at(pack_index_path(&repo)?, git_hash::Kind::Sha1)?;
+        let tree = git_hash::ObjectId::from_hex(git(&repo, &["rev-parse", "main^{tree}"])?.trim().as_bytes())?;
+        let err = bitmap::File::write_for_commits(
+            &bundle,
+            Some(tree),
+            std::io::sink(),
+            &mut git_pack::cache::Never,
+            progress::Discard,
+            &AtomicBool::new(false),
+        )
+        .unwrap_err();
+        assert!(matches!(err, bitmap::write::Error::NotACommit { id, .. } if id == tree));
+        Ok(())
+    }
+}
+
+mod count {
+    use std::{collections::BTreeSet, convert::Infallible, sync::atomic::AtomicBool};
+
+    use git_features::progress;
+    use git_odb::{linked, pack::Find, FindExt};
+    use git_pack::data::output::count;
+
+    use super::{pack_index_path, scripted_fixture_repo_read_only};
+
+    #[test]
+    fn tree_contents_with_bitmaps_yields_the_same_objects_with_less_decoding() -> crate::Result {
+        let repo = scripted_fixture_repo_read_only("make_pack_with_bitmap_repo.sh")?;
+        assert!(pack_index_path(&repo)?.with_extension("bitmap").is_file());
+        let db = linked::Store::at(repo.join(".git").join("objects"), git_hash::Kind::Sha1)?;
+        let head = git_hash::ObjectId::from_hex(super::git(&repo, &["rev-parse", "main"])?.trim().as_bytes())?;
+        let commits = git_traverse::commit::Ancestors::new(
+            Some(head),
+            git_traverse::commit::ancestors::State::default(),
+            |oid, buf| db.find_existing_commit_iter(oid, buf, &mut git_pack::cache::Never).ok(),
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+
+        let count = |use_reachability_bitmaps| {
+            count::objects_unthreaded(
+                &db,
+                &mut git_pack::cache::Never,
+                commits.iter().cloned().map(Ok::<_, Infallible>),
+                progress::Discard,
+                &AtomicBool::new(false),
+                count::objects::ObjectExpansion::TreeContents,
+                use_reachability_bitmaps,
+            )
+        };
+        let (counts, outcome) = count(false)?;
+        let (bitmap_counts, bitmap_outcome) = count(true)?;
+
+        let ids = |counts: &[count::Count]| counts.iter().map(|c| c.id).collect::<BTreeSet<_>>();
+        assert_eq!(ids(&bitmap_counts), ids(&counts));
+        assert_eq!(bitmap_counts.len(), counts.len(), "no object is added twice");
+        let mut buf = Vec::new();
+        for count in &bitmap_counts {
+            assert_eq!(
+                count.entry_pack_location.as_ref(),
+                db.location_by_oid(count.id, &mut buf).as_ref(),
+                "entry locations computed from bitmaps match the actual ones"
+            );
+        }
+        assert_eq!(bitmap_outcome.input_objects, outcome.input_objects);
+        assert!(
+            bitmap_outcome.decoded_objects < outcome.decoded_objects / 4,
+            "{} decoded objects with bitmaps isn't much less than {}",
+            bitmap_outcome.decoded_objects,
+            outcome.decoded_objects
+        );
+        Ok(())
+    }
+}
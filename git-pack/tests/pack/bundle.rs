@@ -6,7 +6,7 @@ mod locate {
     use crate::{fixture_path, hex_to_id, pack::SMALL_PACK_INDEX};
 
     fn locate<'a>(hex_id: &str, out: &'a mut Vec<u8>) -> git_pack::data::Object<'a> {
-        let bundle = pack::Bundle::at(fixture_path(SMALL_PACK_INDEX)).expect("pack and idx");
+        let bundle = pack::Bundle::at(fixture_path(SMALL_PACK_INDEX), git_hash::Kind::Sha1).expect("pack and idx");
         bundle
             .find(hex_to_id(hex_id), out, &mut pack::cache::Never)
             .expect("read success")
@@ -14,16 +14,21 @@ mod locate {
     }
 
     mod locate_and_verify {
+        use git_features::progress;
         use git_odb::pack;
 
-        use crate::{fixture_path, pack::PACKS_AND_INDICES};
+        use crate::{
+            fixture_path,
+            pack::{bitmap::pack_index_path, PACKS_AND_INDICES},
+            scripted_fixture_repo_read_only,
+        };
 
         #[test]
         fn all() -> Result<(), Box<dyn std::error::Error>> {
             for (index_path, data_path) in PACKS_AND_INDICES {
                 // both paths are equivalent
-                pack::Bundle::at(fixture_path(index_path))?;
-                let bundle = pack::Bundle::at(fixture_path(data_path))?;
+                pack::Bundle::at(fixture_path(index_path), git_hash::Kind::Sha1)?;
+                let bundle = pack::Bundle::at(fixture_path(data_path), git_hash::Kind::Sha1)?;
 
                 let mut buf = Vec::new();
                 for entry in bundle.index.iter() {
@@ -35,6 +40,40 @@ mod locate {
             }
             Ok(())
         }
+
+        #[test]
+        fn sha256() -> Result<(), Box<dyn std::error::Error>> {
+            let repo = scripted_fixture_repo_read_only("make_sha256_repo.sh")?;
+            let bundle = pack::Bundle::at(pack_index_path(&repo)?, git_hash::Kind::Sha256)?;
+            assert_eq!(bundle.index.hash_kind(), git_hash::Kind::Sha256);
+            assert_eq!(bundle.pack.hash_kind(), git_hash::Kind::Sha256);
+            assert_eq!(bundle.pack.checksum(), bundle.index.pack_checksum());
+
+            let mut buf = Vec::new();
+            for entry in bundle.index.iter() {
+                assert_eq!(entry.oid.kind(), git_hash::Kind::Sha256);
+                let obj = bundle
+                    .find(entry.oid, &mut buf, &mut pack::cache::Never)?
+                    .expect("id present");
+                obj.verify_checksum(entry.oid)?;
+            }
+
+            for algorithm in &[
+                pack::index::traverse::Algorithm::Lookup,
+                pack::index::traverse::Algorithm::DeltaTreeLookup,
+            ] {
+                let (index_checksum, _, _) = bundle.verify_integrity(
+                    pack::index::verify::Mode::Sha1Crc32,
+                    *algorithm,
+                    || pack::cache::Never,
+                    None,
+                    progress::Discard.into(),
+                    Default::default(),
+                )?;
+                assert_eq!(index_checksum, bundle.index.index_checksum());
+            }
+            Ok(())
+        }
     }
 
     #[test]
@@ -83,7 +122,8 @@ mod write_to_directory {
 
     use crate::{
         fixture_path,
-        pack::{SMALL_PACK, SMALL_PACK_INDEX},
+        pack::{bitmap::pack_index_path, SMALL_PACK, SMALL_PACK_INDEX},
+        scripted_fixture_repo_read_only,
     };
 
     fn expected_outcome() -> Result<pack::bundle::write::Outcome, Box<dyn std::error::Error>> {
@@ -106,7 +146,7 @@ mod write_to_directory {
         assert_eq!(res, expected_outcome()?);
         assert_eq!(
             res.index.index_hash,
-            pack::index::File::at(fixture_path(SMALL_PACK_INDEX))?.index_checksum()
+            pack::index::File::at(fixture_path(SMALL_PACK_INDEX), git_hash::Kind::Sha1)?.index_checksum()
         );
         assert!(res.to_bundle().is_none());
         Ok(())
@@ -122,7 +162,7 @@ mod write_to_directory {
         sorted_entries.sort_by_key(|e| e.file_name());
         assert_eq!(sorted_entries.len(), 2, "we want a pack and the corresponding index");
 
-        let pack_hash = res.index.data_hash.to_sha1_hex_string();
+        let pack_hash = res.index.data_hash.to_string();
         assert_eq!(file_name(&sorted_entries[0]), format!("{}.idx", pack_hash));
         assert_eq!(Some(sorted_entries[0].path()), index_path);
 
@@ -134,6 +174,43 @@ mod write_to_directory {
         Ok(())
     }
 
+    #[test]
+    fn sha256_pack_yields_the_same_index_as_git() -> Result<(), Box<dyn std::error::Error>> {
+        let repo = scripted_fixture_repo_read_only("make_sha256_repo.sh")?;
+        let index_path = pack_index_path(&repo)?;
+        let dir = TempDir::new()?;
+        static SHOULD_INTERRUPT: AtomicBool = AtomicBool::new(false);
+        let res = pack::Bundle::write_to_directory_eagerly(
+            fs::File::open(index_path.with_extension("pack"))?,
+            None,
+            Some(&dir),
+            progress::Discard,
+            &SHOULD_INTERRUPT,
+            None,
+            pack::bundle::write::Options {
+                thread_limit: None,
+                iteration_mode: pack::data::input::Mode::Verify,
+                index_kind: pack::index::Version::V2,
+                hash_kind: git_hash::Kind::Sha256,
+            },
+        )?;
+
+        let git_index = pack::index::File::at(&index_path, git_hash::Kind::Sha256)?;
+        assert_eq!(res.index.data_hash, git_index.pack_checksum());
+        assert_eq!(res.index.index_hash, git_index.index_checksum());
+        assert_eq!(res.index.num_objects, git_index.num_objects());
+        assert_eq!(
+            fs::read(res.index_path.as_ref().expect("written to directory"))?,
+            fs::read(&index_path)?,
+            "the index is byte-for-byte identical to the one written by git"
+        );
+        assert_eq!(
+            res.data_path,
+            Some(dir.path().join(format!("{}.pack", res.index.data_hash)))
+        );
+        Ok(())
+    }
+
     fn file_name(entry: &fs::DirEntry) -> String {
         entry.path().file_name().unwrap().to_str().unwrap().to_owned()
     }
@@ -155,6 +232,7 @@ mod write_to_directory {
                 thread_limit: None,
                 iteration_mode: pack::data::input::Mode::Verify,
                 index_kind: pack::index::Version::V2,
+                hash_kind: git_hash::Kind::Sha1,
             },
         )
         .map_err(Into::into)
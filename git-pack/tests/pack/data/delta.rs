@@ -0,0 +1,184 @@
+use git_pack::data::delta;
+
+fn apply(base: &[u8], delta: &[u8]) -> Vec<u8> {
+    let (base_size, base_size_len) = delta::decode_header_size(delta);
+    assert_eq!(base_size as usize, base.len(), "the base size is recorded");
+    let (target_size, target_size_len) = delta::decode_header_size(&delta[base_size_len..]);
+    let mut target = vec![0; target_size as usize];
+    delta::apply(base, &mut target, &delta[base_size_len + target_size_len..]);
+    target
+}
+
+fn round_trip(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    delta::encode(base, target, &mut out);
+    assert_eq!(apply(base, &out), target);
+    out
+}
+
+/// Deterministic bytes which don't repeat within the sizes used here.
+fn pseudo_random_bytes(len: usize, seed: u32) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn round_trips_for_edge_cases() {
+    let data = pseudo_random_bytes(1000, 1);
+    round_trip(b"", b"");
+    round_trip(b"", &data);
+    round_trip(&data, b"");
+    round_trip(b"short", b"shorter");
+    round_trip(&data[..15], &data[..15]);
+    round_trip(&[0; 5000], &[0; 6000]);
+    round_trip(&[0; 5000], &data);
+}
+
+#[test]
+fn round_trips_for_edits() {
+    let base = pseudo_random_bytes(10_000, 2);
+    let insertion = pseudo_random_bytes(300, 3);
+
+    let mut inserted_in_middle = base.clone();
+    inserted_in_middle.splice(5000..5000, insertion.iter().cloned());
+    let mut replaced_in_middle = base.clone();
+    replaced_in_middle.splice(5000..5100, insertion.iter().cloned());
+    let mut moved_blocks = base[5000..].to_vec();
+    moved_blocks.extend_from_slice(&base[..5000]);
+    let mut prefixed_and_suffixed = insertion.clone();
+    prefixed_and_suffixed.extend_from_slice(&base);
+    prefixed_and_suffixed.extend_from_slice(&insertion);
+
+    for target in &[
+        base.clone(),
+        inserted_in_middle,
+        replaced_in_middle,
+        moved_blocks,
+        prefixed_and_suffixed,
+        base[1..base.len() - 1].to_vec(),
+    ] {
+        let delta = round_trip(&base, target);
+        assert!(
+            delta.len() < 2 * insertion.len() + 100,
+            "a delta of {} bytes only contains what's new and the copy instructions",
+            delta.len()
+        );
+    }
+}
+
+#[test]
+fn copies_larger_than_a_single_copy_instruction_are_split() {
+    let base = pseudo_random_bytes(200_000, 4);
+    let mut target = base.clone();
+    target[150_000] ^= 0xff;
+    let delta = round_trip(&base, &target);
+    assert!(
+        delta.len() < 50,
+        "only a few copy instructions are needed, got {}",
+        delta.len()
+    );
+}
+
+#[test]
+fn unrelated_objects_yield_inserts_only() {
+    let base = pseudo_random_bytes(1000, 5);
+    let target = pseudo_random_bytes(1000, 6);
+    let delta = round_trip(&base, &target);
+    assert!(
+        delta.len() > target.len(),
+        "all bytes are inserted, plus instruction overhead"
+    );
+}
+
+#[test]
+fn index_can_be_reused_and_limits_the_delta_size() {
+    let base = pseudo_random_bytes(4000, 7);
+    let index = delta::Index::new(&base);
+    let mut out = Vec::new();
+    for offset in &[0, 100, 1000] {
+        let target = &base[*offset..];
+        assert!(index.encode(target, usize::MAX, &mut out));
+        assert_eq!(apply(&base, &out), target);
+    }
+
+    let unrelated = pseudo_random_bytes(4000, 8);
+    assert!(
+        !index.encode(&unrelated, 1000, &mut out),
+        "the delta would be larger than allowed"
+    );
+    assert!(index.encode(&unrelated, usize::MAX, &mut out));
+    assert_eq!(apply(&base, &out), unrelated);
+}
+
+#[test]
+fn deltas_are_understood_by_git() -> crate::Result {
+    use std::io::Write;
+
+    use git_pack::data::output;
+
+    let base = pseudo_random_bytes(10_000, 9);
+    let mut target = base.clone();
+    target.splice(5000..5000, b"inserted".iter().cloned());
+    let mut delta = Vec::new();
+    delta::encode(&base, &target, &mut delta);
+
+    let compress = |data: &[u8]| -> std::io::Result<Vec<u8>> {
+        let mut out = git_features::zlib::stream::deflate::Write::new(Vec::new());
+        out.write_all(data)?;
+        out.flush()?;
+        Ok(out.into_inner())
+    };
+    let entries = vec![
+        output::Entry {
+            id: git_hash::ObjectId::from_hex(b"1111111111111111111111111111111111111111")?,
+            kind: output::entry::Kind::Base(git_object::Kind::Blob),
+            decompressed_size: base.len(),
+            compressed_data: compress(&base)?,
+        },
+        output::Entry {
+            id: git_hash::ObjectId::from_hex(b"2222222222222222222222222222222222222222")?,
+            kind: output::entry::Kind::DeltaRef { object_index: 0 },
+            decompressed_size: delta.len(),
+            compressed_data: compress(&delta)?,
+        },
+    ];
+
+    let tmp_dir = tempfile::TempDir::new()?;
+    let pack_path = tmp_dir.path().join("delta.pack");
+    let mut pack_file = std::fs::File::create(&pack_path)?;
+    for bytes in output::bytes::FromEntriesIter::new(
+        std::iter::once(Ok::<_, std::io::Error>(entries)),
+        &mut pack_file,
+        2,
+        git_pack::data::Version::V2,
+        git_hash::Kind::Sha1,
+    ) {
+        bytes?;
+    }
+
+    let index_pack = std::process::Command::new("git")
+        .args(["index-pack", "-o"])
+        .arg(tmp_dir.path().join("delta.idx"))
+        .arg(&pack_path)
+        .output()?;
+    assert!(
+        index_pack.status.success(),
+        "git can resolve the delta: {}",
+        String::from_utf8_lossy(&index_pack.stderr)
+    );
+    let verify_pack = std::process::Command::new("git")
+        .args(["verify-pack", "-v"])
+        .arg(tmp_dir.path().join("delta.idx"))
+        .output()?;
+    assert!(verify_pack.status.success());
+    assert!(
+        String::from_utf8(verify_pack.stdout)?.contains("chain length = 1: 1 object"),
+        "the target is stored as delta"
+    );
+    Ok(())
+}
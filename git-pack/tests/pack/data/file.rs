@@ -1,11 +1,9 @@
-use std::convert::TryFrom;
-
 use git_odb::pack;
 
 use crate::fixture_path;
 
 fn pack_at(at: &str) -> pack::data::File {
-    pack::data::File::try_from(fixture_path(at).as_path()).expect("valid pack file")
+    pack::data::File::at(fixture_path(at), git_hash::Kind::Sha1).expect("valid pack file")
 }
 
 mod method {
@@ -18,10 +16,7 @@ mod method {
     #[test]
     fn checksum() {
         let p = pack_at(SMALL_PACK);
-        assert_eq!(
-            hex::encode(p.checksum().sha1()),
-            "0f3ea84cd1bba10c2a03d736a460635082833e59"
-        );
+        assert_eq!(p.checksum().to_string(), "0f3ea84cd1bba10c2a03d736a460635082833e59");
     }
 
     #[test]
@@ -115,6 +115,7 @@ fn traversals() -> crate::Result {
                 decoded_and_recompressed_objects: 0,
                 missing_objects: 0,
                 objects_copied_from_pack: 16,
+                delta_compressed_objects: 0,
             },
             hex_to_id("b920bbb055e1efb9080592a409d3975738b6efb3"),
             None,
@@ -140,13 +141,14 @@ fn traversals() -> crate::Result {
             output::count::objects::Outcome {
                 input_objects: 1,
                 expanded_objects: 102,
-                decoded_objects: 18,
+                decoded_objects: 10,
                 total_objects: 103,
             },
             output::entry::iter_from_counts::Outcome {
                 decoded_and_recompressed_objects: 0,
                 missing_objects: 0,
                 objects_copied_from_pack: 103,
+                delta_compressed_objects: 0,
             },
             hex_to_id("25114bd8820b393c402cd53ad8ec7f6a84bb0633"),
             Some(hex_to_id("29ab9797aff1ca826afb699680356695d19c5acb")),
@@ -172,13 +174,14 @@ fn traversals() -> crate::Result {
             output::count::objects::Outcome {
                 input_objects: 1,
                 expanded_objects: 102,
-                decoded_objects: 18,
+                decoded_objects: 10,
                 total_objects: 103,
             },
             output::entry::iter_from_counts::Outcome {
                 decoded_and_recompressed_objects: 74,
                 missing_objects: 0,
                 objects_copied_from_pack: 29,
+                delta_compressed_objects: 0,
             },
             hex_to_id("d83d42128e40957c5174920189a0390b5a70f446"),
             None,
@@ -199,6 +202,7 @@ fn traversals() -> crate::Result {
                 decoded_and_recompressed_objects: 0,
                 missing_objects: 0,
                 objects_copied_from_pack: 868,
+                delta_compressed_objects: 0,
             },
             hex_to_id("542ad1d1c7c762ea4e36907570ff9e4b5b7dde1b"),
             None,
@@ -212,13 +216,14 @@ fn traversals() -> crate::Result {
             output::count::objects::Outcome {
                 input_objects: 16,
                 expanded_objects: 866,
-                decoded_objects: 208,
+                decoded_objects: 74,
                 total_objects: 868,
             },
             output::entry::iter_from_counts::Outcome {
                 decoded_and_recompressed_objects: 0,
                 missing_objects: 0,
                 objects_copied_from_pack: 868,
+                delta_compressed_objects: 0,
             },
             hex_to_id("542ad1d1c7c762ea4e36907570ff9e4b5b7dde1b"),
             None,
@@ -311,6 +316,129 @@ fn traversals() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn delta_compression_of_loose_objects() -> crate::Result {
+    let tmp_dir = tempfile::TempDir::new()?;
+    let objects_dir = tmp_dir.path().join("objects");
+    std::fs::create_dir_all(objects_dir.join("pack"))?;
+    let db = Arc::new(linked::Store::at(&objects_dir, git_hash::Kind::Sha1)?);
+
+    let mut content: Vec<String> = (0..200)
+        .map(|line| format!("line {} of a file that changes a little with each version", line))
+        .collect();
+    let mut versions = Vec::new();
+    for version in 0..20 {
+        content[version * 7] = format!("changed in version {}", version);
+        content.push(format!("appended in version {}", version));
+        versions.push(content.join("\n").into_bytes());
+    }
+    let unrelated_blob = b"small and unrelated".to_vec();
+    let mut counts = Vec::new();
+    for (data, name) in versions
+        .iter()
+        .map(|data| (data, &b"file"[..]))
+        .chain(Some((&unrelated_blob, &b"other"[..])))
+    {
+        let id = git_odb::Write::write_buf(&*db, git_object::Kind::Blob, data, git_hash::Kind::Sha1)?;
+        counts.push(output::Count {
+            id,
+            entry_pack_location: count::PackLocation::NotLookedUp,
+            name_hash: count::name_hash(name),
+        });
+    }
+
+    let mut pack_sizes = Vec::new();
+    for mode in &[
+        entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+        entry::iter_from_counts::Mode::PackCopyAndDeltaCompression,
+    ] {
+        let mut entries_iter = output::entry::iter_from_counts(
+            counts.clone(),
+            db.clone(),
+            || pack::cache::Never,
+            progress::Discard,
+            output::entry::iter_from_counts::Options {
+                mode: *mode,
+                ..Default::default()
+            },
+        );
+        let entries: Vec<_> = output::InOrderIter::from(entries_iter.by_ref())
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        let stats = entries_iter.finalize()?;
+        assert_eq!(stats.missing_objects, 0);
+        assert_eq!(entries.len(), counts.len());
+        let num_deltas = entries
+            .iter()
+            .filter(|e| matches!(e.kind, output::entry::Kind::DeltaRef { .. }))
+            .count();
+        assert_eq!(num_deltas, stats.delta_compressed_objects);
+        match mode {
+            entry::iter_from_counts::Mode::PackCopyAndBaseObjects => assert_eq!(num_deltas, 0),
+            entry::iter_from_counts::Mode::PackCopyAndDeltaCompression => assert_eq!(
+                num_deltas,
+                versions.len() - 1,
+                "all but the largest version are deltas, and the unrelated blob is too small to be one"
+            ),
+        }
+        for (index, entry) in entries.iter().enumerate() {
+            if let output::entry::Kind::DeltaRef { object_index } = entry.kind {
+                assert!(object_index < index, "bases are always written before their deltas");
+            }
+        }
+
+        let pack_dir = tempfile::TempDir::new()?;
+        let pack_file_path = pack_dir.path().join("new.pack");
+        let mut pack_file = std::fs::File::create(&pack_file_path)?;
+        let num_entries = entries.len() as u32;
+        for bytes in output::bytes::FromEntriesIter::new(
+            std::iter::once(Ok::<_, entry::iter_from_counts::Error<compound::find::Error>>(entries)),
+            &mut pack_file,
+            num_entries,
+            pack::data::Version::V2,
+            git_hash::Kind::Sha1,
+        ) {
+            bytes?;
+        }
+        pack_sizes.push(pack_file.metadata()?.len());
+
+        let should_interrupt = AtomicBool::new(false);
+        let bundle = pack::Bundle::write_to_directory(
+            std::io::BufReader::new(std::fs::File::open(pack_file_path)?),
+            Some(pack_dir.path()),
+            progress::Discard,
+            &should_interrupt,
+            None,
+            pack::bundle::write::Options::default(),
+        )?
+        .to_bundle()
+        .expect("directory set")?;
+        bundle.verify_integrity(
+            pack::index::verify::Mode::Sha1Crc32DecodeEncode,
+            pack::index::traverse::Algorithm::Lookup,
+            || pack::cache::Never,
+            None,
+            progress::Discard.into(),
+            Arc::new(should_interrupt),
+        )?;
+        let mut buf = Vec::new();
+        for (count, data) in counts.iter().zip(versions.iter().chain(Some(&unrelated_blob))) {
+            let index = bundle.index.lookup(count.id).expect("object present");
+            let object = bundle.get_object_by_index(index, &mut buf, &mut pack::cache::Never)?;
+            assert_eq!(object.data, &data[..]);
+        }
+    }
+    assert!(
+        pack_sizes[1] * 5 < pack_sizes[0],
+        "the pack with deltas ({} bytes) is much smaller than the one without ({} bytes)",
+        pack_sizes[1],
+        pack_sizes[0]
+    );
+    Ok(())
+}
+
 fn write_and_verify(
     db: Arc<linked::Store>,
     entries: Vec<output::Entry>,
@@ -349,7 +477,7 @@ fn write_and_verify(
         pack_file.metadata()?.len(),
         "it reports the correct amount of written bytes"
     );
-    let pack = pack::data::File::at(&pack_file_path)?;
+    let pack = pack::data::File::at(&pack_file_path, git_hash::Kind::Sha1)?;
     let should_interrupt = AtomicBool::new(false);
     let hash = pack.verify_checksum(progress::Discard, &should_interrupt)?;
     assert_eq!(
@@ -373,6 +501,7 @@ fn write_and_verify(
         )?
         .data_path
         .expect("directory set"),
+        git_hash::Kind::Sha1,
     )?;
     if let Some(thin_pack_checksum) = expected_thin_pack_hash {
         let actual_checksum = bundle
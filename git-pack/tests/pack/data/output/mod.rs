@@ -7,7 +7,7 @@ use git_pack::data::output;
 fn size_of_entry() {
     assert_eq!(
         std::mem::size_of::<output::Entry>(),
-        80,
+        112,
         "The size of the structure shouldn't change unexpectedly"
     )
 }
@@ -16,7 +16,7 @@ fn size_of_entry() {
 fn size_of_count() {
     assert_eq!(
         std::mem::size_of::<output::Count>(),
-        56,
+        72,
         "The size of the structure shouldn't change unexpectedly"
     )
 }
@@ -32,7 +32,9 @@ fn db(kind: DbKind) -> crate::Result<Arc<linked::Store>> {
             .join(".git")
             .join("objects"),
     };
-    linked::Store::at(path).map_err(Into::into).map(Into::into)
+    linked::Store::at(path, git_hash::Kind::Sha1)
+        .map_err(Into::into)
+        .map(Into::into)
 }
 
 mod count_and_entries;
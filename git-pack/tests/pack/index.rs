@@ -16,7 +16,7 @@ mod file {
 
             #[test]
             fn lookup() -> Result<(), Box<dyn std::error::Error>> {
-                let idx = index::File::at(&fixture_path(INDEX_V1))?;
+                let idx = index::File::at(&fixture_path(INDEX_V1), git_hash::Kind::Sha1)?;
                 for (id, desired_index, assertion) in &[
                     (&b"036bd66fe9b6591e959e6df51160e636ab1a682e"[..], Some(0), "first"),
                     (b"f7f791d96b9a34ef0f08db4b007c5309b9adc3d6", Some(65), "close to last"),
@@ -46,7 +46,7 @@ mod file {
 
             #[test]
             fn lookup() -> Result<(), Box<dyn std::error::Error>> {
-                let idx = index::File::at(&fixture_path(INDEX_V2))?;
+                let idx = index::File::at(&fixture_path(INDEX_V2), git_hash::Kind::Sha1)?;
                 for (id, desired_index, assertion) in &[
                     (&b"0ead45fc727edcf5cadca25ef922284f32bb6fc1"[..], Some(0), "first"),
                     (b"e800b9c207e17f9b11e321cc1fba5dfe08af4222", Some(29), "last"),
@@ -67,6 +67,145 @@ mod file {
                 }
                 Ok(())
             }
+
+            #[test]
+            fn lookup_prefix() -> Result<(), Box<dyn std::error::Error>> {
+                let idx = index::File::at(&fixture_path(INDEX_V2), git_hash::Kind::Sha1)?;
+                assert_eq!(idx.lookup_prefix(git_hash::Prefix::from_hex("0ead")?), 0..1);
+                assert_eq!(
+                    idx.lookup_prefix(git_hash::Prefix::from_hex("ffff")?),
+                    30..30,
+                    "no match is an empty range at the insertion point"
+                );
+                let first = idx.oid_at_index(0).to_owned().to_string();
+                assert_eq!(
+                    idx.lookup_prefix(git_hash::Prefix::from_hex(&format!("{}0", first))?),
+                    1..1,
+                    "prefixes longer than the ids in the index never match"
+                );
+                for entry in idx.iter() {
+                    let index = idx.lookup(entry.oid).expect("id present");
+                    assert_eq!(
+                        idx.lookup_prefix(git_hash::Prefix::new(entry.oid, 40)?),
+                        index..index + 1
+                    );
+                    for hex_len in 4..8 {
+                        let prefix = git_hash::Prefix::new(entry.oid, hex_len)?;
+                        let range = idx.lookup_prefix(prefix);
+                        assert!(range.contains(&index));
+                        let hex = entry.oid.to_string();
+                        let matching = idx
+                            .iter()
+                            .filter(|e| e.oid.to_string().starts_with(&hex[..hex_len]))
+                            .count();
+                        assert_eq!(range.len(), matching, "{}", prefix);
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        mod v3 {
+            use std::{path::Path, sync::atomic::AtomicBool};
+
+            use git_features::progress;
+            use git_pack::index;
+
+            use crate::{fixture_path, pack::INDEX_V2};
+
+            const SHORT_ID_LEN: usize = 8;
+
+            /// Rewrite the V2 index `v2` in the V3 format, with a single object format and objects in pack order.
+            fn v3_from_v2(v2: &index::File, path: &Path) -> std::io::Result<()> {
+                let entries: Vec<_> = v2.iter().collect();
+                let mut pack_order: Vec<_> = (0..entries.len()).collect();
+                pack_order.sort_by_key(|index| entries[*index].pack_offset);
+                let mut pack_position = vec![0; entries.len()];
+                for (position, index) in pack_order.iter().enumerate() {
+                    pack_position[*index] = position as u32;
+                }
+
+                let v2_data = std::fs::read(v2.path())?;
+                let hash_len = v2.hash_kind().len_in_bytes();
+                let v2_offsets64 =
+                    &v2_data[8 + 256 * 4 + entries.len() * (hash_len + 4 * 2)..v2_data.len() - hash_len * 2];
+                let header_len = 4 * 5 + 4 * 3 + 4;
+                let trailer_offset =
+                    header_len + entries.len() * (SHORT_ID_LEN + hash_len + 4 * 3) + v2_offsets64.len();
+
+                let mut out = b"\xfftOc".to_vec();
+                for n in &[3, header_len, entries.len(), 1] {
+                    out.extend_from_slice(&(*n as u32).to_be_bytes());
+                }
+                out.extend_from_slice(b"sha1");
+                for n in &[SHORT_ID_LEN, header_len, trailer_offset] {
+                    out.extend_from_slice(&(*n as u32).to_be_bytes());
+                }
+                for entry in &entries {
+                    out.extend_from_slice(&entry.oid.as_bytes()[..SHORT_ID_LEN]);
+                }
+                for index in &pack_order {
+                    out.extend_from_slice(entries[*index].oid.as_bytes());
+                }
+                for position in &pack_position {
+                    out.extend_from_slice(&position.to_be_bytes());
+                }
+                for index in &pack_order {
+                    out.extend_from_slice(&entries[*index].crc32.expect("present in V2").to_be_bytes());
+                }
+                let v2_offsets = &v2_data[8 + 256 * 4 + entries.len() * (hash_len + 4)..][..entries.len() * 4];
+                out.extend_from_slice(v2_offsets);
+                out.extend_from_slice(v2_offsets64);
+                assert_eq!(out.len(), trailer_offset);
+                out.extend_from_slice(v2.pack_checksum().as_bytes());
+                let mut hasher = git_features::hash::hasher(v2.hash_kind());
+                hasher.update(&out);
+                out.extend_from_slice(hasher.digest().as_bytes());
+                std::fs::write(path, out)
+            }
+
+            #[test]
+            fn lookup_and_iteration_match_v2() -> Result<(), Box<dyn std::error::Error>> {
+                let v2 = index::File::at(fixture_path(INDEX_V2), git_hash::Kind::Sha1)?;
+                let dir = tempfile::tempdir()?;
+                let path = dir.path().join("v3.idx");
+                v3_from_v2(&v2, &path)?;
+
+                let idx = index::File::at(&path, git_hash::Kind::Sha1)?;
+                assert_eq!(idx.version(), index::Version::V3);
+                assert_eq!(idx.num_objects(), v2.num_objects());
+                assert_eq!(idx.pack_checksum(), v2.pack_checksum());
+                assert_eq!(
+                    idx.verify_checksum(progress::Discard, &AtomicBool::new(false))?,
+                    idx.index_checksum()
+                );
+                assert_eq!(idx.iter().collect::<Vec<_>>(), v2.iter().collect::<Vec<_>>());
+                assert_eq!(idx.sorted_offsets(), v2.sorted_offsets());
+                for entry in v2.iter() {
+                    let index = idx.lookup(entry.oid).expect("id present");
+                    assert_eq!(Some(index), v2.lookup(entry.oid));
+                    assert_eq!(idx.oid_at_index(index), entry.oid.as_ref());
+                    assert_eq!(idx.pack_offset_at_index(index), entry.pack_offset);
+                    assert_eq!(idx.crc32_at_index(index), entry.crc32);
+                    let prefix = git_hash::Prefix::new(entry.oid, 7)?;
+                    assert_eq!(idx.lookup_prefix(prefix), v2.lookup_prefix(prefix));
+                }
+                Ok(())
+            }
+
+            #[test]
+            fn a_different_object_format_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+                let v2 = index::File::at(fixture_path(INDEX_V2), git_hash::Kind::Sha1)?;
+                let dir = tempfile::tempdir()?;
+                let path = dir.path().join("v3.idx");
+                v3_from_v2(&v2, &path)?;
+
+                assert!(matches!(
+                    index::File::at(&path, git_hash::Kind::Sha256),
+                    Err(index::init::Error::UnsupportedHashKind { expected: "sha256", .. })
+                ));
+                Ok(())
+            }
         }
 
         mod any {
@@ -112,6 +251,7 @@ mod file {
                     io::BufReader::new(fs::File::open(fixture_path(data_path))?),
                     *mode,
                     *compressed,
+                    git_hash::Kind::Sha1,
                 )?;
 
                 let mut actual = Vec::<u8>::new();
@@ -286,8 +426,8 @@ mod file {
                 },
             ),
         ] {
-            let idx = index::File::at(&fixture_path(index_path))?;
-            let pack = pack::data::File::at(&fixture_path(pack_path))?;
+            let idx = index::File::at(&fixture_path(index_path), git_hash::Kind::Sha1)?;
+            let pack = pack::data::File::at(&fixture_path(pack_path), git_hash::Kind::Sha1)?;
 
             assert_eq!(pack.version(), pack::data::Version::V2);
             assert_eq!(pack.num_objects(), idx.num_objects());
@@ -390,7 +530,7 @@ mod file {
                 "0f3ea84cd1bba10c2a03d736a460635082833e59",
             ),
         ] {
-            let idx = index::File::at(&fixture_path(path))?;
+            let idx = index::File::at(&fixture_path(path), git_hash::Kind::Sha1)?;
             assert_eq!(idx.version(), *kind);
             assert_eq!(idx.num_objects(), *num_objects);
             assert_eq!(
@@ -4,7 +4,7 @@ use git_odb::pack;
 fn size_of_entry() {
     assert_eq!(
         std::mem::size_of::<pack::data::input::Entry>(),
-        104,
+        136,
         "let's keep the size in check as we have many of them"
     );
 }
@@ -30,12 +30,13 @@ mod new_from_header {
                 std::io::BufReader::new(data.as_slice()),
                 Mode::AsIs,
                 EntryDataMode::Ignore,
+                git_hash::Kind::Sha1,
             )? {
                 let entry = entry?;
 
                 let mut buf = Vec::<u8>::new();
                 entry.header.write_to(entry.decompressed_size, &mut buf)?;
-                let new_entry = pack::data::Entry::from_bytes(&buf, entry.pack_offset);
+                let new_entry = pack::data::Entry::from_bytes(&buf, entry.pack_offset, git_hash::SIZE_OF_SHA1_DIGEST);
 
                 assert_eq!(
                     new_entry.header_size(),
@@ -65,6 +66,7 @@ mod new_from_header {
                     std::io::BufReader::new(fs::File::open(fixture_path(SMALL_PACK))?),
                     *trailer_mode,
                     *compression_mode,
+                    git_hash::Kind::Sha1,
                 )?;
 
                 let num_objects = iter.len();
@@ -75,7 +77,7 @@ mod new_from_header {
                 let entry = iter.next().expect("last object")?;
                 assert_eq!(
                     entry.trailer.expect("trailer id"),
-                    pack::data::File::at(fixture_path(SMALL_PACK))?.checksum(),
+                    pack::data::File::at(fixture_path(SMALL_PACK), git_hash::Kind::Sha1)?.checksum(),
                     "last object contains the trailer - a hash over all bytes in the pack"
                 );
                 assert_eq!(iter.len(), 0);
@@ -106,12 +108,13 @@ mod new_from_header {
             std::io::BufReader::new(&pack[..pack.len() - 20]),
             Mode::Restore,
             EntryDataMode::Ignore,
+            git_hash::Kind::Sha1,
         )?;
         let num_objects = iter.len();
         assert_eq!(iter.by_ref().take(42 - 1).count(), num_objects - 1);
         assert_eq!(
             iter.next().expect("last object")?.trailer.expect("trailer id"),
-            pack::data::File::at(fixture_path(SMALL_PACK))?.checksum(),
+            pack::data::File::at(fixture_path(SMALL_PACK), git_hash::Kind::Sha1)?.checksum(),
             "the correct checksum should be restored"
         );
         Ok(())
@@ -124,6 +127,7 @@ mod new_from_header {
             std::io::BufReader::new(&pack[..pack.len() / 2]),
             Mode::Restore,
             EntryDataMode::Ignore,
+            git_hash::Kind::Sha1,
         )?;
         let mut num_objects = 0;
         while let Some(entry) = iter.next() {
@@ -0,0 +1,201 @@
+use std::{convert::TryInto, path::Path, sync::atomic::AtomicBool};
+
+use git_features::progress;
+use git_pack::{index, multi_index};
+
+use crate::{
+    fixture_path,
+    pack::{bitmap::pack_index_path, INDEX_V1, INDEX_V2, SMALL_PACK_INDEX},
+    scripted_fixture_repo_read_only,
+};
+
+const INDICES: &[&str] = &[SMALL_PACK_INDEX, INDEX_V1, INDEX_V2];
+
+fn copy_packs_into(pack_dir: &Path) -> crate::Result<Vec<index::File>> {
+    std::fs::create_dir_all(pack_dir)?;
+    INDICES
+        .iter()
+        .map(|index| {
+            let index = fixture_path(index);
+            let pack = index.with_extension("pack");
+            for path in &[&index, &pack] {
+                std::fs::copy(path, pack_dir.join(path.file_name().expect("file name")))?;
+            }
+            Ok(index::File::at(
+                pack_dir.join(index.file_name().expect("file name")),
+                git_hash::Kind::Sha1,
+            )?)
+        })
+        .collect()
+}
+
+fn write_multi_index(indices: &[index::File], path: &Path) -> crate::Result<multi_index::write::Outcome> {
+    let outcome = multi_index::File::write_from_indices(
+        indices,
+        std::fs::File::create(path)?,
+        progress::Discard,
+        &AtomicBool::new(false),
+    )?;
+    Ok(outcome)
+}
+
+#[test]
+fn write_and_read_back() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let indices = copy_packs_into(dir.path())?;
+    let path = dir.path().join("multi-pack-index");
+    let outcome = write_multi_index(&indices, &path)?;
+
+    let file = multi_index::File::at(&path)?;
+    assert_eq!(file.version(), multi_index::Version::V1);
+    assert_eq!(file.num_indices(), 3);
+    assert_eq!(
+        file.index_names(),
+        &[
+            Path::new("pack-11fdfa9e156ab73caae3b6da867192221f2089c2.idx"),
+            Path::new("pack-a2bf8e71d8c18879e499335762dd95119d93d9f1.idx"),
+            Path::new("pack-c0438c19fb16422b6bbcce24387b3264416d485b.idx"),
+        ]
+    );
+    assert_eq!(file.num_objects(), outcome.num_objects);
+    assert_eq!(file.checksum(), outcome.multi_index_checksum);
+    assert_eq!(
+        file.verify_checksum(progress::Discard, &AtomicBool::new(false))?,
+        outcome.multi_index_checksum
+    );
+
+    let mut all_ids: Vec<_> = indices.iter().flat_map(|idx| idx.iter().map(|e| e.oid)).collect();
+    all_ids.sort();
+    all_ids.dedup();
+    assert_eq!(
+        file.num_objects() as usize,
+        all_ids.len(),
+        "every object is listed once"
+    );
+    assert_eq!(
+        file.iter().map(|e| e.oid).collect::<Vec<_>>(),
+        all_ids,
+        "ids are sorted"
+    );
+
+    for entry in file.iter() {
+        let index = file.lookup(entry.oid).expect("id present");
+        assert_eq!(file.oid_at_index(index), entry.oid.as_ref());
+        let index_file = indices
+            .iter()
+            .find(|idx| idx.path().file_name() == Some(file.index_names()[entry.pack_index as usize].as_os_str()))
+            .expect("index present");
+        let entry_index = index_file
+            .lookup(entry.oid)
+            .expect("object is in the pack it's attributed to");
+        assert_eq!(index_file.pack_offset_at_index(entry_index), entry.pack_offset);
+    }
+    assert_eq!(file.lookup(git_hash::ObjectId::from_hex(&[b'f'; 40])?), None);
+
+    let prefix = git_hash::Prefix::from_hex("a706")?;
+    let range = file.lookup_prefix(prefix);
+    assert_eq!(
+        range.len(),
+        all_ids
+            .iter()
+            .filter(|id| prefix.cmp_oid(id) == std::cmp::Ordering::Equal)
+            .count()
+    );
+    Ok(())
+}
+
+#[test]
+fn write_matches_git() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let status = std::process::Command::new("git")
+        .args(["init", "--quiet"])
+        .current_dir(dir.path())
+        .status()?;
+    assert!(status.success());
+    let pack_dir = dir.path().join(".git").join("objects").join("pack");
+    let indices = copy_packs_into(&pack_dir)?;
+    let status = std::process::Command::new("git")
+        .args(["multi-pack-index", "write"])
+        .current_dir(dir.path())
+        .status()?;
+    assert!(status.success());
+
+    let path = dir.path().join("multi-pack-index");
+    let outcome = write_multi_index(&indices, &path)?;
+    let expected = multi_index::File::at(pack_dir.join("multi-pack-index"))?;
+    assert_eq!(outcome.multi_index_checksum, expected.checksum());
+    assert_eq!(std::fs::read(path)?, std::fs::read(expected.path())?);
+    Ok(())
+}
+
+#[test]
+fn write_matches_git_in_sha256_repositories() -> crate::Result {
+    let repo = scripted_fixture_repo_read_only("make_sha256_repo.sh")?;
+    let expected = multi_index::File::at(repo.join(".git").join("objects").join("pack").join("multi-pack-index"))?;
+    assert_eq!(expected.hash_kind(), git_hash::Kind::Sha256);
+    assert_eq!(
+        expected.verify_checksum(progress::Discard, &AtomicBool::new(false))?,
+        expected.checksum()
+    );
+
+    let indices = vec![index::File::at(pack_index_path(&repo)?, git_hash::Kind::Sha256)?];
+    for entry in indices[0].iter() {
+        let index = expected.lookup(entry.oid).expect("id present");
+        assert_eq!(expected.oid_at_index(index), entry.oid.as_ref());
+    }
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("multi-pack-index");
+    let outcome = write_multi_index(&indices, &path)?;
+    assert_eq!(outcome.multi_index_checksum, expected.checksum());
+    assert_eq!(std::fs::read(path)?, std::fs::read(expected.path())?);
+    Ok(())
+}
+
+#[test]
+fn index_names_must_look_like_pack_indices() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("renamed.idx");
+    std::fs::copy(fixture_path(SMALL_PACK_INDEX), &path)?;
+    let err = multi_index::File::write_from_indices(
+        Some(&index::File::at(&path, git_hash::Kind::Sha1)?),
+        std::io::sink(),
+        progress::Discard,
+        &AtomicBool::new(false),
+    )
+    .unwrap_err();
+    assert!(matches!(err, multi_index::write::Error::InvalidIndexName(p) if p == path));
+    Ok(())
+}
+
+#[test]
+fn invalid_object_offsets_are_corruption_errors() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let indices = copy_packs_into(dir.path())?;
+    let path = dir.path().join("multi-pack-index");
+    write_multi_index(&indices, &path)?;
+    let data = std::fs::read(&path)?;
+    let offsets_start = data[12..]
+        .chunks(12)
+        .find(|entry| &entry[..4] == b"OOFF")
+        .map(|entry| u64::from_be_bytes(entry[4..].try_into().expect("8 bytes")) as usize)
+        .expect("offsets chunk present");
+
+    for (position, value, expected_message) in &[
+        (offsets_start, 3, "Object 0 is in pack 3, but there are only 3 packs"),
+        (
+            offsets_start + 4,
+            0x8000_0000,
+            "Object 0 has a large offset, but there is no \"LOFF\" chunk",
+        ),
+    ] {
+        let mut data = data.clone();
+        data[*position..*position + 4].copy_from_slice(&u32::to_be_bytes(*value));
+        std::fs::write(&path, &data)?;
+        match multi_index::File::at(&path) {
+            Err(multi_index::init::Error::Corrupt { message }) => assert_eq!(message, *expected_message),
+            res => panic!("expected a corruption error, got {:?}", res.map(|_| ())),
+        }
+    }
+    Ok(())
+}
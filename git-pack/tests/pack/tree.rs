@@ -20,11 +20,12 @@ mod method {
         }
 
         fn tree(index_path: &str, pack_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-            let idx = pack::index::File::at(fixture_path(index_path))?;
+            let idx = pack::index::File::at(fixture_path(index_path), git_hash::Kind::Sha1)?;
             pack::tree::Tree::from_offsets_in_pack(
                 idx.sorted_offsets().into_iter(),
                 |ofs| *ofs,
                 fixture_path(pack_path),
+                idx.hash_kind(),
                 git_features::progress::Discard,
                 &AtomicBool::new(false),
                 |id| idx.lookup(id).map(|index| idx.pack_offset_at_index(index)),
@@ -59,7 +60,7 @@ fn using_option_as_data_does_not_increase_size_in_memory() {
     );
     assert_eq!(
         std::mem::size_of::<[TreeItemOption<Entry>; 7_500_000]>(),
-        480_000_000,
+        540_000_000,
         "it should be as small as possible"
     );
 }
@@ -79,6 +80,6 @@ fn size_of_pack_verify_data_structure() {
 
     assert_eq!(
         std::mem::size_of::<[TreeItem<EntryWithDefault>; 7_500_000]>(),
-        780_000_000
+        900_000_000
     );
 }
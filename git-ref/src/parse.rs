@@ -2,6 +2,7 @@ use bstr::{BStr, ByteSlice};
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while_m_n},
+    combinator::verify,
     error::ParseError,
     IResult,
 };
@@ -12,10 +13,13 @@ fn is_hex_digit_lc(b: u8) -> bool {
 
 /// Copy from https://github.com/Byron/gitoxide/blob/f270850ff92eab15258023b8e59346ec200303bd/git-object/src/immutable/parse.rs#L64
 pub fn hex_hash<'a, E: ParseError<&'a [u8]>>(i: &'a [u8]) -> IResult<&'a [u8], &'a BStr, E> {
-    take_while_m_n(
-        git_hash::Kind::shortest().len_in_hex(),
-        git_hash::Kind::longest().len_in_hex(),
-        is_hex_digit_lc,
+    verify(
+        take_while_m_n(
+            git_hash::Kind::shortest().len_in_hex(),
+            git_hash::Kind::longest().len_in_hex(),
+            is_hex_digit_lc,
+        ),
+        |hex: &[u8]| git_hash::Kind::from_hex_len(hex.len()).is_some(),
     )(i)
     .map(|(i, hex)| (i, hex.as_bstr()))
 }
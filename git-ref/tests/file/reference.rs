@@ -142,7 +142,7 @@ mod peel {
             "points to a tag object without actual object lookup"
         );
 
-        let odb = git_odb::linked::Store::at(store.base.join("objects"))?;
+        let odb = git_odb::linked::Store::at(store.base.join("objects"), git_hash::Kind::Sha1)?;
         assert_eq!(
             r.peel_to_id_in_place(&store, None, |oid, buf| {
                 odb.find(oid, buf, &mut git_odb::pack::cache::Never)
@@ -4,8 +4,10 @@ use git_hash::ObjectId;
 
 use crate::{
     easy,
-    easy::{object, ObjectRef},
+    easy::{object, rev_parse, ObjectRef, Oid},
+    objs::bstr::BStr,
     odb::{Find, FindExt},
+    rev_spec,
 };
 
 pub fn find_object<A: easy::Access + Sized>(
@@ -58,4 +60,20 @@ pub trait ObjectAccessExt: easy::Access + Sized {
     fn try_find_object(&self, id: impl Into<ObjectId>) -> Result<Option<ObjectRef<'_, Self>>, object::find::Error> {
         try_find_object(self, id)
     }
+
+    /// Parse the revision specification `spec` like `HEAD~3^2`, `main@{yesterday}` or `A..B` and resolve each of its
+    /// revisions to an object id.
+    fn rev_parse(&self, spec: impl AsRef<BStr>) -> Result<rev_spec::Spec<Oid<'_, Self>>, rev_parse::Error> {
+        rev_parse::spec(self, spec.as_ref())
+    }
+
+    /// Like [`rev_parse()`][ObjectAccessExt::rev_parse()], but fails unless `spec` names a single revision.
+    fn rev_parse_single(&self, spec: impl AsRef<BStr>) -> Result<Oid<'_, Self>, rev_parse::Error> {
+        let spec = spec.as_ref();
+        rev_parse::spec(self, spec)?
+            .single()
+            .ok_or_else(|| rev_parse::Error::NotASingleRevision { spec: spec.into() })
+    }
 }
+
+impl<A> ObjectAccessExt for A where A: easy::Access + Sized {}
@@ -26,8 +26,10 @@ pub(crate) mod ext;
 
 pub mod borrow;
 pub mod object;
-mod oid;
+///
+pub mod oid;
 pub mod reference;
+pub mod rev_parse;
 pub mod state;
 
 /// An [ObjectId] with access to a repository.
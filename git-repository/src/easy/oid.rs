@@ -1,7 +1,7 @@
 use crate::{
     easy,
     easy::{object::find, Object, ObjectRef, Oid},
-    hash::{oid, ObjectId},
+    hash::{self, oid, ObjectId},
 };
 
 impl<'repo, A, B> PartialEq<Oid<'repo, A>> for Oid<'repo, B> {
@@ -81,4 +81,35 @@ where
     pub fn detach(self) -> ObjectId {
         self.id
     }
+
+    /// Abbreviate this id to the shortest prefix no other object in the repository starts with, using at least as many
+    /// characters as git does with `core.abbrev=auto`.
+    pub fn shorten(&self) -> Result<hash::Prefix, shorten::Error> {
+        let repo = self.access.repo()?;
+        Ok(repo.odb.shortest_unique_prefix(&self.id, repo.odb.auto_hex_len())?)
+    }
+}
+
+///
+pub mod shorten {
+    use quick_error::quick_error;
+
+    use crate::{easy, odb};
+
+    quick_error! {
+        /// The error returned by [`Oid::shorten()`][super::Oid::shorten()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            ShortestUniquePrefix(err: odb::compound::prefix::shortest_unique::Error) {
+                display("The shortest unique prefix of an object id could not be determined")
+                from()
+                source(err)
+            }
+            BorrowRepo(err: easy::borrow::repo::Error) {
+                display("BUG: The repository could not be borrowed")
+                from()
+            }
+        }
+    }
 }
@@ -0,0 +1,434 @@
+//! Resolve [revision specifications][crate::rev_spec] to object ids.
+use std::collections::{BinaryHeap, HashSet};
+use std::convert::TryFrom;
+
+use quick_error::quick_error;
+
+use crate::{
+    easy,
+    easy::{ext::ObjectAccessExt, ext::ReferenceAccessExt, Oid},
+    hash::{self, ObjectId},
+    objs::{
+        bstr::{BStr, BString, ByteSlice},
+        immutable, Kind,
+    },
+    odb, refs,
+    rev_spec::{self, Anchor, Navigation, Peel, ReflogEntry, Rev, Spec},
+};
+
+quick_error! {
+    /// The error returned by [`ObjectAccessExt::rev_parse()`][crate::prelude::ObjectAccessExt::rev_parse()].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Parse(err: rev_spec::parse::Error) {
+            display("The revision specification could not be parsed")
+            from()
+            source(err)
+        }
+        NotFound { name: BString } {
+            display("'{}' is neither a reference nor an object id", name)
+        }
+        AmbiguousObjectId { prefix: hash::Prefix, candidates: Vec<ObjectId> } {
+            display("The abbreviated object id '{}' is ambiguous as it matches {} objects", prefix, candidates.len())
+        }
+        SymbolicReferenceTooDeep { name: BString } {
+            display("The symbolic reference '{}' could not be followed to an object as it is part of a cycle or too deep", name)
+        }
+        ReflogMissing { name: BString } {
+            display("The reference '{}' has no reflog", name)
+        }
+        ReflogEntryOutOfRange { name: BString, index: usize, available: usize } {
+            display("The reflog of '{}' has only {} entries, cannot select entry {}", name, available, index)
+        }
+        PreviousCheckoutOutOfRange { desired: usize, available: usize } {
+            display("The reflog of HEAD records only {} checkouts, cannot go back {}", available, desired)
+        }
+        ParentMissing { id: ObjectId, parent: usize } {
+            display("Commit {} has no parent number {}", id, parent)
+        }
+        PathMissing { id: ObjectId, path: BString } {
+            display("The tree of {} has no entry at '{}'", id, path)
+        }
+        MessageNotFound { pattern: BString } {
+            display("No reachable commit has a message containing '{}'", pattern)
+        }
+        NotASingleRevision { spec: BString } {
+            display("'{}' does not specify a single revision", spec)
+        }
+        Decode(err: immutable::object::decode::Error) {
+            display("A commit or tag could not be decoded")
+            from()
+            source(err)
+        }
+        PeelObject(err: easy::object::peel_to_kind::Error) {
+            display("An object could not be peeled")
+            from()
+            source(err)
+        }
+        FindExistingObject(err: easy::object::find::existing::Error) {
+            display("An object could not be found")
+            from()
+            source(err)
+        }
+        FindReference(err: easy::reference::find::Error) {
+            display("A reference could not be found")
+            from()
+            source(err)
+        }
+        FindFileReference(err: refs::file::find::Error) {
+            display("A reference could not be found")
+            from()
+            source(err)
+        }
+        PackedRefsOpen(err: refs::packed::buffer::open::Error) {
+            display("The packed-refs file could not be opened")
+            from()
+            source(err)
+        }
+        IterateReferences(err: refs::file::iter::loose_then_packed::Error) {
+            display("The references could not be iterated")
+            from()
+            source(err)
+        }
+        ReflogDecode(err: refs::file::log::iter::decode::Error) {
+            display("A reflog line could not be decoded")
+            from()
+            source(err)
+        }
+        Io(err: std::io::Error) {
+            display("A reflog, the references or loose objects could not be read")
+            from()
+            source(err)
+        }
+        BorrowState(err: easy::borrow::state::Error) {
+            display("BUG: Part of interior state could not be borrowed.")
+            from()
+            source(err)
+        }
+        BorrowRepo(err: easy::borrow::repo::Error) {
+            display("BUG: The repository could not be borrowed")
+            from()
+        }
+    }
+}
+
+/// Parse `spec` and resolve each of its revisions to an object id.
+pub(crate) fn spec<'a, A>(access: &'a A, spec: &BStr) -> Result<Spec<Oid<'a, A>>, Error>
+where
+    A: easy::Access + Sized,
+{
+    rev_spec::parse(spec)?.try_map(|rev| revision(access, rev).map(|id| Oid::from_id(id, access)))
+}
+
+fn revision<A>(access: &A, rev: Rev<'_>) -> Result<ObjectId, Error>
+where
+    A: easy::Access + Sized,
+{
+    let mut id = match rev.anchor {
+        Anchor::Name(name) => named(access, name)?,
+        Anchor::Reflog { name, entry } => reflog_entry(access, name, entry)?,
+        Anchor::PreviousCheckout(n) => previous_checkout(access, n)?,
+        Anchor::MessageSearch(pattern) => youngest_commit_with_message(access, all_tips(access)?, pattern)?,
+    };
+    for step in rev.navigation {
+        id = navigate(access, id, step)?;
+    }
+    Ok(id)
+}
+
+/// Resolve `name` as full object id, reference name or abbreviated object id, in that order.
+fn named<A>(access: &A, name: &BStr) -> Result<ObjectId, Error>
+where
+    A: easy::Access + Sized,
+{
+    let name = if name == "@" { "HEAD".into() } else { name };
+    if name.len() == access.repo()?.hash_kind.len_in_hex() {
+        if let Ok(id) = ObjectId::from_hex(name) {
+            return Ok(id);
+        }
+    }
+    if let Some(id) = reference_target(access, name)? {
+        return Ok(id);
+    }
+    if let Some(prefix) = name.to_str().ok().and_then(|hex| hash::Prefix::from_hex(hex).ok()) {
+        return abbreviated_object_id(access, prefix);
+    }
+    Err(Error::NotFound { name: name.into() })
+}
+
+/// Return the object the reference `name` points to after following symbolic references, without peeling tags like
+/// `git rev-parse` does, or `None` if there is no such reference.
+fn reference_target<A>(access: &A, name: &BStr) -> Result<Option<ObjectId>, Error>
+where
+    A: easy::Access + Sized,
+{
+    // Like git, give up on chains of symbolic references that are longer than this.
+    const MAX_SYMBOLIC_DEPTH: usize = 5;
+    if refs::PartialName::try_from(name).is_err() {
+        return Ok(None);
+    }
+    let repo = access.repo()?;
+    let packed = access.state().assure_packed_refs_uptodate(&repo.refs)?;
+    let mut reference = match repo.refs.find(name, packed.as_ref())? {
+        Some(reference) => reference,
+        None => return Ok(None),
+    };
+    for _ in 0..=MAX_SYMBOLIC_DEPTH {
+        match reference.target() {
+            refs::mutable::Target::Peeled(id) => return Ok(Some(id)),
+            refs::mutable::Target::Symbolic(target) => {
+                reference = repo
+                    .refs
+                    .find(target.borrow().as_bstr(), packed.as_ref())?
+                    .ok_or_else(|| Error::NotFound {
+                        name: target.as_bstr().into(),
+                    })?;
+            }
+        }
+    }
+    Err(Error::SymbolicReferenceTooDeep { name: name.into() })
+}
+
+fn abbreviated_object_id<A>(access: &A, prefix: hash::Prefix) -> Result<ObjectId, Error>
+where
+    A: easy::Access + Sized,
+{
+    match access.repo()?.odb.lookup_prefix(prefix) {
+        Ok(Some(id)) => Ok(id),
+        Ok(None) => Err(Error::NotFound {
+            name: prefix.to_string().into(),
+        }),
+        Err(odb::compound::prefix::lookup::Error::Ambiguous { prefix, candidates }) => {
+            Err(Error::AmbiguousObjectId { prefix, candidates })
+        }
+        Err(odb::compound::prefix::lookup::Error::Io(err)) => Err(err.into()),
+    }
+}
+
+/// Open the reflog of the reference `name`, or the one of the currently checked out branch if `None`, and call `f` with
+/// its lines from newest to oldest.
+fn with_reflog<A, T>(
+    access: &A,
+    name: Option<&BStr>,
+    f: impl FnOnce(&mut dyn Iterator<Item = Result<refs::file::log::mutable::Line, Error>>) -> Result<T, Error>,
+) -> Result<T, Error>
+where
+    A: easy::Access + Sized,
+{
+    let repo = access.repo()?;
+    let packed = access.state().assure_packed_refs_uptodate(&repo.refs)?;
+    let find = |name: &BStr| -> Result<_, Error> {
+        repo.refs
+            .find(name, packed.as_ref())?
+            .ok_or_else(|| Error::NotFound { name: name.into() })
+    };
+    let mut reference = find(name.unwrap_or_else(|| "HEAD".into()))?;
+    if name.is_none() {
+        if let refs::mutable::Target::Symbolic(branch) = reference.target() {
+            reference = find(branch.borrow().as_bstr())?;
+        }
+    }
+    let mut buf = vec![0; 16 * 1024];
+    let mut lines = reference
+        .log_iter_rev(&repo.refs, &mut buf)?
+        .ok_or_else(|| Error::ReflogMissing {
+            name: reference.name().as_bstr().into(),
+        })?
+        .map(|line| Ok(line??));
+    f(&mut lines)
+}
+
+fn reflog_entry<A>(access: &A, name: Option<&BStr>, entry: ReflogEntry) -> Result<ObjectId, Error>
+where
+    A: easy::Access + Sized,
+{
+    let display_name = || BString::from(name.unwrap_or_else(|| "HEAD".into()));
+    with_reflog(access, name, |lines| match entry {
+        ReflogEntry::Index(index) => {
+            let mut available = 0;
+            let mut oldest = None;
+            for line in lines {
+                let line = line?;
+                if available == index {
+                    return Ok(line.new_oid);
+                }
+                available += 1;
+                oldest = Some(line);
+            }
+            match oldest {
+                Some(line) if available == index && !line.previous_oid.is_null() => Ok(line.previous_oid),
+                _ => Err(Error::ReflogEntryOutOfRange {
+                    name: display_name(),
+                    index,
+                    available,
+                }),
+            }
+        }
+        ReflogEntry::Date(time) => {
+            let mut oldest = None;
+            for line in lines {
+                let line = line?;
+                if line.signature.time.time <= time {
+                    return Ok(line.new_oid);
+                }
+                oldest = Some(line);
+            }
+            let line = oldest.ok_or_else(|| Error::ReflogMissing { name: display_name() })?;
+            Ok(if line.previous_oid.is_null() {
+                line.new_oid
+            } else {
+                line.previous_oid
+            })
+        }
+    })
+}
+
+fn previous_checkout<A>(access: &A, n: usize) -> Result<ObjectId, Error>
+where
+    A: easy::Access + Sized,
+{
+    const PREFIX: &[u8] = b"checkout: moving from ";
+    let previous = with_reflog(access, Some("HEAD".into()), |lines| {
+        let mut available = 0;
+        for line in lines {
+            let line = line?;
+            let previous = match line.message.strip_prefix(PREFIX) {
+                Some(moved) => moved.find(" to ").map(|end| BString::from(&moved[..end])),
+                None => None,
+            };
+            if let Some(previous) = previous {
+                available += 1;
+                if available == n {
+                    return Ok(previous);
+                }
+            }
+        }
+        Err(Error::PreviousCheckoutOutOfRange { desired: n, available })
+    })?;
+    named(access, previous.as_bstr())
+}
+
+/// Return the ids of all references and `HEAD`.
+fn all_tips<A>(access: &A) -> Result<Vec<ObjectId>, Error>
+where
+    A: easy::Access + Sized,
+{
+    let mut tips = Vec::new();
+    {
+        let repo = access.repo()?;
+        let packed = access.state().assure_packed_refs_uptodate(&repo.refs)?;
+        for reference in repo.refs.iter(packed.as_ref())? {
+            if let refs::mutable::Target::Peeled(id) = reference?.target() {
+                tips.push(id);
+            }
+        }
+    }
+    if let Some(mut head) = access.try_find_reference("HEAD")? {
+        if let Ok(id) = head.peel_to_object_in_place() {
+            tips.push(id.detach());
+        }
+    }
+    Ok(tips)
+}
+
+/// Find the youngest commit reachable from `tips` whose message contains `pattern`, ignoring tips that aren't commits.
+fn youngest_commit_with_message<A>(access: &A, tips: Vec<ObjectId>, pattern: &BStr) -> Result<ObjectId, Error>
+where
+    A: easy::Access + Sized,
+{
+    let mut seen = HashSet::new();
+    let mut queue = BinaryHeap::new();
+    let mut push = |id: ObjectId, queue: &mut BinaryHeap<_>| -> Result<(), Error> {
+        let commit = match access.find_object(id)?.peel_to_kind(Kind::Commit) {
+            Ok(commit) => commit,
+            Err(easy::object::peel_to_kind::Error::NotFound { .. }) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        if seen.insert(commit.id) {
+            let decoded = immutable::Commit::from_bytes(&commit.data)?;
+            queue.push((
+                decoded.committer.time.time,
+                commit.id,
+                decoded.message.find(pattern).is_some(),
+                decoded.parents().collect::<Vec<_>>(),
+            ));
+        }
+        Ok(())
+    };
+    for tip in tips {
+        push(tip, &mut queue)?;
+    }
+    while let Some((_time, id, matches, parents)) = queue.pop() {
+        if matches {
+            return Ok(id);
+        }
+        for parent in parents {
+            push(parent, &mut queue)?;
+        }
+    }
+    Err(Error::MessageNotFound {
+        pattern: pattern.into(),
+    })
+}
+
+/// Return the id of the commit `id` peels to along with the ids of its parents.
+fn parents<A>(access: &A, id: ObjectId) -> Result<(ObjectId, Vec<ObjectId>), Error>
+where
+    A: easy::Access + Sized,
+{
+    let commit = access.find_object(id)?.peel_to_kind(Kind::Commit)?;
+    let parents = immutable::Commit::from_bytes(&commit.data)?.parents().collect();
+    Ok((commit.id, parents))
+}
+
+fn navigate<A>(access: &A, id: ObjectId, step: Navigation<'_>) -> Result<ObjectId, Error>
+where
+    A: easy::Access + Sized,
+{
+    Ok(match step {
+        Navigation::Parent(0) => parents(access, id)?.0,
+        Navigation::Parent(n) => {
+            let (id, parents) = parents(access, id)?;
+            *parents.get(n - 1).ok_or(Error::ParentMissing { id, parent: n })?
+        }
+        Navigation::Ancestor(n) => {
+            let mut id = id;
+            for _ in 0..n {
+                let (commit_id, parents) = parents(access, id)?;
+                id = *parents.first().ok_or(Error::ParentMissing {
+                    id: commit_id,
+                    parent: 1,
+                })?;
+            }
+            parents(access, id)?.0
+        }
+        Navigation::Peel(Peel::Tags) => {
+            let mut object = access.find_object(id)?;
+            while object.kind == Kind::Tag {
+                let target = immutable::Tag::from_bytes(&object.data)?.target();
+                drop(object);
+                object = access.find_object(target)?;
+            }
+            object.id
+        }
+        Navigation::Peel(Peel::Object) => access.find_object(id)?.id,
+        Navigation::Peel(Peel::Kind(kind)) => access.find_object(id)?.peel_to_kind(kind)?.id,
+        Navigation::MessageSearch(pattern) => youngest_commit_with_message(access, vec![id], pattern)?,
+        Navigation::Path(path) => {
+            let tree = access.find_object(id)?.peel_to_kind(Kind::Tree)?.into_tree();
+            let tree_id = tree.id;
+            let components = path.split_str("/").filter(|c| !c.is_empty());
+            if path.iter().all(|b| *b == b'/') {
+                tree_id
+            } else {
+                tree.lookup_path(components)?
+                    .ok_or_else(|| Error::PathMissing {
+                        id: tree_id,
+                        path: path.into(),
+                    })?
+                    .oid
+            }
+        }
+    })
+}
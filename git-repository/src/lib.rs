@@ -116,6 +116,8 @@ pub mod path;
 ///
 pub mod repository;
 
+pub mod rev_spec;
+
 /// A instance with access to everything a git repository entails, best imagined as container for _most_ for system resources required
 /// to interact with a `git` repository which are loaded in once the instance is created.
 ///
@@ -129,6 +131,8 @@ pub struct Repository {
     pub refs: git_ref::file::Store,
     /// A store for objects that contain data
     pub odb: git_odb::linked::Store,
+    /// The kind of hash used to identify all objects in this repository, as configured with `extensions.objectFormat`.
+    pub hash_kind: git_hash::Kind,
     /// TODO: git-config should be here - it's read a lot but not written much in must applications, so shouldn't be in `State`.
     ///       Probably it's best reload it on signal (in servers) or refresh it when it's known to have been changed similar to how
     ///       packs are refreshed. This would be `git_config::fs::Config` when ready.
@@ -183,9 +187,10 @@ pub struct EasyArc {
 /// * **It can take a long time to get a mutable `repo`….**
 ///    - Imagine a typical server operation where a pack is sent to a client. As it's a fresh clone it takes 5 minutes.
 ///      Right after the clone began somebody initiates a push. Everything goes well and as a new pack was created the server
-///      wants to tell the object database to update its packs, making the new one available. To do that, it needs mutable access to the
-///      repository instance, and obtaining it will take until the end of the ongoing clone as the latter has acquired read-access to
-///      the same repository instance. This is most certainly undesirable.
+///      wants to make it available. The object database [refreshes][crate::odb::linked::Store::refresh()] its packs with shared access,
+///      but other changes, like reloading the configuration, need mutable access to the repository instance, and obtaining it
+///      will take until the end of the ongoing clone as the latter has acquired read-access to the same repository instance.
+///      This is most certainly undesirable.
 ///   - Workarounds would be to
 ///       - acquire the read-lock each time the clone operation wants to access an object
 ///       - set a flag that triggers the server to create a new `Repository` instance next time a connection comes in which is subsequently
@@ -54,6 +54,16 @@ pub mod discover {
                 from()
                 source(err)
             }
+            Config(err: git_config::parser::ParserOrIoError<'static>) {
+                display("Could not read the repository configuration")
+                from()
+                source(err)
+            }
+            ObjectFormat(err: git_hash::kind::Error) {
+                display("The object format configured in 'extensions.objectFormat' is not supported")
+                from()
+                source(err)
+            }
         }
     }
 
@@ -64,8 +74,10 @@ pub mod discover {
                 crate::Path::WorkTree(working_tree) => (working_tree.join(".git"), Some(working_tree)),
                 crate::Path::Repository(repository) => (repository, None),
             };
+            let hash_kind = hash_kind_from_config(&git_dir)?;
             Ok(Repository {
-                odb: git_odb::linked::Store::at(git_dir.join("objects"))?,
+                odb: git_odb::linked::Store::at(git_dir.join("objects"), hash_kind)?,
+                hash_kind,
                 refs: git_ref::file::Store::at(
                     git_dir,
                     if working_tree.is_none() {
@@ -78,4 +90,18 @@ pub mod discover {
             })
         }
     }
+
+    /// Read the kind of hash used by the repository at `git_dir` from `extensions.objectFormat`, defaulting to SHA-1
+    /// if it isn't set or if there is no configuration file at all.
+    fn hash_kind_from_config(git_dir: &Path) -> Result<git_hash::Kind, Error> {
+        let config_path = git_dir.join("config");
+        if !config_path.is_file() {
+            return Ok(git_hash::Kind::default());
+        }
+        let config = git_config::file::GitConfig::open(&config_path)?;
+        Ok(match config.get_raw_value("extensions", None, "objectFormat") {
+            Ok(format) => String::from_utf8_lossy(&format).trim().parse()?,
+            Err(_) => git_hash::Kind::default(),
+        })
+    }
 }
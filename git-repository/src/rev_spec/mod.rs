@@ -0,0 +1,121 @@
+//! Parse revision specifications like `HEAD~3^2`, `main@{yesterday}` or `A..B` as described in
+//! [the git documentation][git-revisions], and resolve them to object ids with
+//! [`ObjectAccessExt::rev_parse()`][crate::prelude::ObjectAccessExt::rev_parse()].
+//!
+//! [git-revisions]: https://git-scm.com/docs/gitrevisions
+use crate::objs::bstr::BStr;
+
+///
+pub mod parse;
+pub use parse::parse;
+
+/// A revision specification, with `T` being a parsed [`Rev`] or the object id it was resolved to.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Spec<T> {
+    /// A single revision like `HEAD~2`, including all commits reachable from it.
+    Include(T),
+    /// `^rev`, excluding all commits reachable from the revision.
+    Exclude(T),
+    /// `from..to`, the commits reachable from `to` but not from `from`.
+    Range {
+        /// The revision on the left, defaulting to `HEAD`.
+        from: T,
+        /// The revision on the right, defaulting to `HEAD`.
+        to: T,
+    },
+    /// `left...right`, the commits reachable from either `left` or `right`, but not from both.
+    SymmetricDifference {
+        /// The revision on the left, defaulting to `HEAD`.
+        left: T,
+        /// The revision on the right, defaulting to `HEAD`.
+        right: T,
+    },
+}
+
+impl<T> Spec<T> {
+    /// Transform each revision of this spec with `f`, stopping at the first error.
+    pub fn try_map<U, E>(self, mut f: impl FnMut(T) -> Result<U, E>) -> Result<Spec<U>, E> {
+        Ok(match self {
+            Spec::Include(rev) => Spec::Include(f(rev)?),
+            Spec::Exclude(rev) => Spec::Exclude(f(rev)?),
+            Spec::Range { from, to } => Spec::Range {
+                from: f(from)?,
+                to: f(to)?,
+            },
+            Spec::SymmetricDifference { left, right } => Spec::SymmetricDifference {
+                left: f(left)?,
+                right: f(right)?,
+            },
+        })
+    }
+
+    /// Return the revision if this is a [single, included revision][Spec::Include], or `None` otherwise.
+    pub fn single(self) -> Option<T> {
+        match self {
+            Spec::Include(rev) => Some(rev),
+            _ => None,
+        }
+    }
+}
+
+/// A single revision, made of an anchor and the navigation steps to take from there.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Rev<'a> {
+    /// The point to start the navigation from.
+    pub anchor: Anchor<'a>,
+    /// The steps to take from the anchor, in order.
+    pub navigation: Vec<Navigation<'a>>,
+}
+
+/// The starting point of a [revision][Rev].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Anchor<'a> {
+    /// A reference name like `main` or `HEAD`, or a full or abbreviated hexadecimal object id. `@` is short for `HEAD`.
+    Name(&'a BStr),
+    /// `name@{…}`, an entry of the reflog of `name`, or of the currently checked out branch if `name` is `None`.
+    Reflog {
+        /// The name of the reference whose reflog to use.
+        name: Option<&'a BStr>,
+        /// The entry to select from the reflog.
+        entry: ReflogEntry,
+    },
+    /// `@{-n}`, the branch or commit checked out `n` checkouts ago.
+    PreviousCheckout(usize),
+    /// `:/text`, the youngest commit reachable from any reference whose message contains `text`.
+    MessageSearch(&'a BStr),
+}
+
+/// Select an entry of a reflog.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ReflogEntry {
+    /// `@{n}`, the value the reference had `n` changes ago, with `0` being its current value.
+    Index(usize),
+    /// `@{<date>}`, the value the reference had at the given time in seconds since the unix epoch.
+    Date(u32),
+}
+
+/// A step to take from one object to another.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Navigation<'a> {
+    /// `^n`, the `n`th parent of a commit, or the commit itself if `n` is `0`.
+    Parent(usize),
+    /// `~n`, the `n`th ancestor of a commit following only first parents.
+    Ancestor(usize),
+    /// `^{…}`, peel the object until a certain condition is met.
+    Peel(Peel),
+    /// `^{/text}`, the youngest commit reachable from the current one whose message contains `text`.
+    MessageSearch(&'a BStr),
+    /// `:path`, the entry at the given `path` of the tree of the current object, or the tree itself if it is empty.
+    Path(&'a BStr),
+}
+
+/// The condition for [peeling][Navigation::Peel] an object.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Peel {
+    /// `^{}`, peel tags until the object isn't a tag anymore.
+    Tags,
+    /// `^{object}`, assure the object exists.
+    Object,
+    /// `^{commit}`, `^{tree}`, `^{blob}` or `^{tag}`, peel until the object is of the given kind.
+    Kind(crate::objs::Kind),
+}
@@ -0,0 +1,332 @@
+use std::time::SystemTime;
+
+use quick_error::quick_error;
+
+use crate::{
+    objs::{
+        bstr::{BStr, BString, ByteSlice},
+        Kind,
+    },
+    rev_spec::{Anchor, Navigation, Peel, ReflogEntry, Rev, Spec},
+};
+
+quick_error! {
+    /// The error returned by [`parse()`][crate::rev_spec::parse()].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Empty {
+            display("A revision must not be empty")
+        }
+        UnclosedBrace { input: BString } {
+            display("The opening brace in '{}' was never closed", input)
+        }
+        InvalidNumber { input: BString } {
+            display("'{}' is not a valid number", input)
+        }
+        UnknownPeelTarget { target: BString } {
+            display("Cannot peel to '{}', expected one of commit, tree, blob, tag or object", target)
+        }
+        InvalidReflogSelector { input: BString } {
+            display("'{}' is neither a reflog index nor a date", input)
+        }
+        PreviousCheckoutWithName { name: BString } {
+            display("The previous checkout @{{-n}} cannot be used with a reference name, got '{}'", name)
+        }
+        Unsupported { input: BString, what: &'static str } {
+            display("{} in '{}' are not supported", what, input)
+        }
+        UnexpectedInput { input: BString } {
+            display("Could not make sense of '{}'", input)
+        }
+    }
+}
+
+/// Parse `input` as revision specification, which is a single revision optionally prefixed with `^`, or two revisions
+/// separated by `..` or `...`.
+///
+/// Relative dates in reflog selectors like `main@{2.weeks.ago}` are computed from the current time, while absolute dates like
+/// `main@{2021-08-01 12:00:00}` are interpreted as UTC unless they carry a timezone offset.
+pub fn parse<'a>(input: &'a BStr) -> Result<Spec<Rev<'a>>, Error> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("the system time is after the unix epoch")
+        .as_secs() as u32;
+    if input.is_empty() {
+        return Err(Error::Empty);
+    }
+    if let Some(rev) = input.strip_prefix(b"^") {
+        return Ok(Spec::Exclude(revision(rev.as_bstr(), now)?));
+    }
+    Ok(match find_range_separator(input) {
+        Some((pos, len)) => {
+            let side = |rev: &'a [u8]| {
+                if rev.is_empty() {
+                    Ok(Rev {
+                        anchor: Anchor::Name("HEAD".into()),
+                        navigation: Vec::new(),
+                    })
+                } else {
+                    revision(rev.as_bstr(), now)
+                }
+            };
+            let (lhs, rhs) = (side(&input[..pos])?, side(&input[pos + len..])?);
+            if len == 3 {
+                Spec::SymmetricDifference { left: lhs, right: rhs }
+            } else {
+                Spec::Range { from: lhs, to: rhs }
+            }
+        }
+        None => Spec::Include(revision(input, now)?),
+    })
+}
+
+/// Return the position and length of `..` or `...` if it separates two revisions in `input`.
+fn find_range_separator(input: &BStr) -> Option<(usize, usize)> {
+    if input.starts_with(b":") {
+        return None;
+    }
+    let mut braces = 0usize;
+    for (pos, byte) in input.iter().enumerate() {
+        match byte {
+            b'{' => braces += 1,
+            b'}' => braces = braces.saturating_sub(1),
+            b':' if braces == 0 => return None,
+            b'.' if braces == 0 && input[pos..].starts_with(b"..") => {
+                let len = if input[pos..].starts_with(b"...") { 3 } else { 2 };
+                return Some((pos, len));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn revision(input: &BStr, now: u32) -> Result<Rev<'_>, Error> {
+    if input.is_empty() {
+        return Err(Error::Empty);
+    }
+    if let Some(text) = input.strip_prefix(b":/") {
+        return Ok(Rev {
+            anchor: Anchor::MessageSearch(message_pattern(text.as_bstr())?),
+            navigation: Vec::new(),
+        });
+    }
+    if input.starts_with(b":") {
+        return Err(Error::Unsupported {
+            input: input.into(),
+            what: "Paths in the index",
+        });
+    }
+
+    let name_end = input
+        .find_byteset(b"^~:")
+        .into_iter()
+        .chain(input.find("@{"))
+        .min()
+        .unwrap_or(input.len());
+    let (name, mut rest): (_, &[u8]) = (input[..name_end].as_bstr(), &input[name_end..]);
+    let anchor = if rest.starts_with(b"@{") {
+        let end = rest
+            .find_byte(b'}')
+            .ok_or_else(|| Error::UnclosedBrace { input: input.into() })?;
+        let selector = rest[2..end].as_bstr();
+        rest = &rest[end + 1..];
+        reflog_anchor((!name.is_empty()).then(|| name), selector, now)?
+    } else if name.is_empty() {
+        return Err(Error::UnexpectedInput { input: input.into() });
+    } else {
+        Anchor::Name(name)
+    };
+
+    let mut navigation = Vec::new();
+    while let Some((&first, tail)) = rest.split_first() {
+        rest = tail;
+        navigation.push(match first {
+            b'^' if rest.starts_with(b"{") => {
+                let end = rest
+                    .find_byte(b'}')
+                    .ok_or_else(|| Error::UnclosedBrace { input: input.into() })?;
+                let target = rest[1..end].as_bstr();
+                rest = &rest[end + 1..];
+                match target.strip_prefix(b"/") {
+                    Some(text) => Navigation::MessageSearch(message_pattern(text.as_bstr())?),
+                    None => Navigation::Peel(peel_target(target)?),
+                }
+            }
+            b'^' => Navigation::Parent(number(&mut rest)?.unwrap_or(1)),
+            b'~' => Navigation::Ancestor(number(&mut rest)?.unwrap_or(1)),
+            b':' => {
+                let path = rest.as_bstr();
+                rest = &[];
+                Navigation::Path(path)
+            }
+            _ => {
+                return Err(Error::UnexpectedInput {
+                    input: input[input.len() - rest.len() - 1..].into(),
+                })
+            }
+        });
+    }
+    Ok(Rev { anchor, navigation })
+}
+
+fn reflog_anchor<'a>(name: Option<&'a BStr>, selector: &BStr, now: u32) -> Result<Anchor<'a>, Error> {
+    if let Some(n) = selector.strip_prefix(b"-") {
+        if let Some(name) = name {
+            return Err(Error::PreviousCheckoutWithName { name: name.into() });
+        }
+        let mut n = n;
+        return match number(&mut n)? {
+            Some(n) if n > 0 => Ok(Anchor::PreviousCheckout(n)),
+            _ => Err(Error::InvalidNumber { input: selector.into() }),
+        };
+    }
+    let entry = if !selector.is_empty() && selector.iter().all(u8::is_ascii_digit) {
+        ReflogEntry::Index(parse_number(selector)?)
+    } else if ["u", "upstream", "push"]
+        .iter()
+        .any(|keyword| selector.eq_ignore_ascii_case(keyword.as_bytes()))
+    {
+        return Err(Error::Unsupported {
+            input: selector.into(),
+            what: "Upstream and push branches",
+        });
+    } else {
+        ReflogEntry::Date(date(selector, now).ok_or_else(|| Error::InvalidReflogSelector { input: selector.into() })?)
+    };
+    Ok(Anchor::Reflog { name, entry })
+}
+
+fn peel_target(target: &BStr) -> Result<Peel, Error> {
+    Ok(match target.as_bytes() {
+        b"" => Peel::Tags,
+        b"object" => Peel::Object,
+        b"commit" => Peel::Kind(Kind::Commit),
+        b"tree" => Peel::Kind(Kind::Tree),
+        b"blob" => Peel::Kind(Kind::Blob),
+        b"tag" => Peel::Kind(Kind::Tag),
+        _ => return Err(Error::UnknownPeelTarget { target: target.into() }),
+    })
+}
+
+/// Return the text to search for in commit messages, with a leading `!!` standing for a literal `!`.
+fn message_pattern(text: &BStr) -> Result<&BStr, Error> {
+    match text.strip_prefix(b"!") {
+        Some(escaped) if escaped.starts_with(b"!") => Ok(escaped.as_bstr()),
+        Some(_) => Err(Error::Unsupported {
+            input: text.into(),
+            what: "Negative message searches",
+        }),
+        None if text.is_empty() => Err(Error::Empty),
+        None => Ok(text),
+    }
+}
+
+/// Parse the decimal number at the start of `input` and advance past it, or return `None` if there is none.
+fn number(input: &mut &[u8]) -> Result<Option<usize>, Error> {
+    let end = input.iter().position(|b| !b.is_ascii_digit()).unwrap_or(input.len());
+    if end == 0 {
+        return Ok(None);
+    }
+    let n = parse_number(input[..end].as_bstr())?;
+    *input = &input[end..];
+    Ok(Some(n))
+}
+
+fn parse_number(input: &BStr) -> Result<usize, Error> {
+    input
+        .to_str()
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| Error::InvalidNumber { input: input.into() })
+}
+
+/// Parse a subset of the dates understood by git, returning seconds since the unix epoch.
+///
+/// Supported are `now`, `yesterday`, `<n> <unit>s ago` with words separated by spaces or dots, and `YYYY-MM-DD` optionally
+/// followed by `HH:MM[:SS]` and a timezone offset like `+0200`.
+fn date(input: &BStr, now: u32) -> Option<u32> {
+    let input = input.to_str().ok()?.trim();
+    match input {
+        "now" => return Some(now),
+        "yesterday" => return now.checked_sub(24 * 60 * 60),
+        _ => {}
+    }
+    let words: Vec<_> = input.split(&[' ', '.'][..]).filter(|w| !w.is_empty()).collect();
+    if let [amount, unit, "ago"] = words.as_slice() {
+        let amount: u32 = amount.parse().ok()?;
+        let unit = unit.strip_suffix('s').unwrap_or(unit);
+        let seconds_per_unit = match unit {
+            "second" | "sec" => 1,
+            "minute" | "min" => 60,
+            "hour" => 60 * 60,
+            "day" => 24 * 60 * 60,
+            "week" => 7 * 24 * 60 * 60,
+            "month" => 30 * 24 * 60 * 60,
+            "year" => 365 * 24 * 60 * 60,
+            _ => return None,
+        };
+        return now.checked_sub(amount.checked_mul(seconds_per_unit)?);
+    }
+
+    let (day, time) = match input.find(&[' ', 'T'][..]) {
+        Some(pos) => (&input[..pos], Some(input[pos + 1..].trim())),
+        None => (input, None),
+    };
+    let mut day = day.splitn(3, '-').map(str::parse::<u32>);
+    let (year, month, day) = (day.next()?.ok()?, day.next()?.ok()?, day.next()?.ok()?);
+    if !(1970..=2105).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let (mut hours, mut minutes, mut seconds, mut offset) = (0, 0, 0, 0i64);
+    if let Some(time) = time {
+        let (time, tz) = match time.find(&[' ', '+', '-', 'Z'][..]) {
+            Some(pos) => (&time[..pos], Some(time[pos..].trim())),
+            None => (time, None),
+        };
+        let mut time = time.split(':').map(str::parse::<u32>);
+        hours = time.next()?.ok()?;
+        minutes = time.next()?.ok()?;
+        seconds = time.next().transpose().ok()?.unwrap_or(0);
+        if time.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+            return None;
+        }
+        match tz {
+            None | Some("Z") => {}
+            Some(tz) => {
+                let (digits, is_negative) = match (tz.strip_prefix('+'), tz.strip_prefix('-')) {
+                    (Some(digits), _) => (digits, false),
+                    (_, Some(digits)) => (digits, true),
+                    _ => return None,
+                };
+                if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let tz: i64 = digits.parse().ok()?;
+                offset = (tz / 100 * 60 + tz % 100) * 60;
+                if is_negative {
+                    offset = -offset;
+                }
+            }
+        }
+    }
+    let seconds = days_since_epoch(year, month, day) * 24 * 60 * 60
+        + i64::from(hours * 60 * 60 + minutes * 60 + seconds)
+        - offset;
+    if seconds < 0 || seconds > i64::from(u32::MAX) {
+        return None;
+    }
+    Some(seconds as u32)
+}
+
+/// The amount of days from the unix epoch to the given date of the proleptic gregorian calendar.
+fn days_since_epoch(year: u32, month: u32, day: u32) -> i64 {
+    let (year, month, day) = (i64::from(year), i64::from(month), i64::from(day));
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
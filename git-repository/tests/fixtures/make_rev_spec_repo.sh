@@ -0,0 +1,44 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+function commit_at() {
+  local date="$1"
+  shift
+  export GIT_AUTHOR_DATE="$date +0000" GIT_COMMITTER_DATE="$date +0000"
+  git commit -q "$@"
+}
+
+git checkout -q -b main
+echo a > a
+mkdir dir
+echo b > dir/b
+git add .
+commit_at "2021-01-01 00:00:00" -m "first"
+
+echo a2 > a
+git add a
+commit_at "2021-01-02 00:00:00" -m "second: fix typo"
+
+git checkout -q -b side
+echo c > c
+git add c
+commit_at "2021-01-03 00:00:00" -m "side work"
+
+git checkout -q main
+echo a3 > a
+git add a
+commit_at "2021-01-04 00:00:00" -m "third"
+
+export GIT_AUTHOR_DATE="2021-01-05 00:00:00 +0000" GIT_COMMITTER_DATE="2021-01-05 00:00:00 +0000"
+git merge -q --no-ff side -m "merge side"
+git tag -m "release" v1.0
+git tag light
+
+commit_at "2021-01-06 00:00:00" --allow-empty -m "fourth"
+git checkout -q side
+git checkout -q main
+
+printf 'object 0000\ntype commit\n' | git hash-object -t tag --literally -w --stdin > .git/corrupt-tag
@@ -0,0 +1,16 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q --object-format=sha256
+git config commit.gpgsign false
+
+git checkout -q -b main
+echo a > a
+git add a
+git commit -q -m first
+echo b > b
+git add b
+git commit -q -m second
+git tag -m "a tag object" v1
+git repack -adq
+git commit -q --allow-empty -m third
@@ -4,7 +4,7 @@ use git_repository::easy;
 fn object_ref_size_in_memory() {
     assert_eq!(
         std::mem::size_of::<easy::ObjectRef<'_, git_repository::Easy>>(),
-        56,
+        72,
         "the size of this structure should not changed unexpectedly"
     )
 }
@@ -13,7 +13,7 @@ fn object_ref_size_in_memory() {
 fn oid_size_in_memory() {
     assert_eq!(
         std::mem::size_of::<easy::Oid<'_, git_repository::Easy>>(),
-        32,
+        48,
         "the size of this structure should not changed unexpectedly"
     )
 }
@@ -11,3 +11,4 @@ mod access;
 mod discover;
 mod object;
 mod reference;
+mod rev_spec;
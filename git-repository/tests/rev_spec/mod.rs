@@ -0,0 +1,292 @@
+use git_repository::{easy::rev_parse::Error, prelude::*, rev_spec::Spec, Repository};
+
+fn repo() -> crate::Result<(std::path::PathBuf, git_repository::Easy)> {
+    let repo_path = git_testtools::scripted_fixture_repo_read_only("make_rev_spec_repo.sh")?;
+    let repo = Repository::discover(&repo_path)?;
+    Ok((repo_path, repo.into()))
+}
+
+fn git_rev_parse(repo: &std::path::Path, spec: &str) -> git_repository::hash::ObjectId {
+    let output = std::process::Command::new("git")
+        .args(&["rev-parse", "--verify", "--end-of-options", spec])
+        .current_dir(repo)
+        .output()
+        .expect("git can be executed");
+    assert!(
+        output.status.success(),
+        "{}: {}",
+        spec,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    git_testtools::hex_to_id(std::str::from_utf8(&output.stdout).expect("valid UTF-8").trim())
+}
+
+#[test]
+fn single_revisions_resolve_like_git() -> crate::Result {
+    let (path, repo) = repo()?;
+    let head = git_rev_parse(&path, "HEAD").to_string();
+    for spec in &[
+        "HEAD",
+        "@",
+        "main",
+        "refs/heads/side",
+        "HEAD~1",
+        "HEAD^",
+        "HEAD~1^2",
+        "HEAD~1^0",
+        "main~3",
+        "HEAD~1^2~1",
+        "v1.0",
+        "v1.0^{}",
+        "v1.0^{tag}",
+        "v1.0^{commit}",
+        "v1.0^{tree}",
+        "v1.0^0",
+        "light^{object}",
+        "HEAD:",
+        "HEAD:a",
+        "HEAD:dir/b",
+        "HEAD^{tree}:dir",
+        ":/fix typo",
+        ":/side",
+        "HEAD~2^{/first}",
+        "main@{0}",
+        "main@{1}",
+        "@{2}",
+        "main@{2021-01-04 12:00:00}",
+        "side@{2021-01-01}",
+        "@{-1}",
+        "@{-2}",
+        &head[..7],
+        &head[..7].to_uppercase(),
+        &head,
+    ] {
+        assert_eq!(
+            repo.rev_parse_single(*spec)?.detach(),
+            git_rev_parse(&path, spec),
+            "{}",
+            spec
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn sha256_revisions_resolve_like_git() -> crate::Result {
+    assert_eq!(
+        Repository::discover(git_testtools::scripted_fixture_repo_read_only("make_rev_spec_repo.sh")?)?.hash_kind,
+        git_repository::hash::Kind::Sha1
+    );
+    let path = git_testtools::scripted_fixture_repo_read_only("make_sha256_repo.sh")?;
+    let repo = Repository::discover(&path)?;
+    assert_eq!(repo.hash_kind, git_repository::hash::Kind::Sha256);
+    let repo: git_repository::Easy = repo.into();
+
+    let head = git_rev_parse(&path, "HEAD").to_string();
+    assert_eq!(head.len(), 64);
+    for spec in &[
+        "HEAD",
+        "main",
+        "HEAD~1",
+        "HEAD^",
+        "v1",
+        "v1^{}",
+        "v1^{commit}",
+        ":/first",
+        "main@{1}",
+        &head[..7],
+        &head[..40],
+        &head,
+    ] {
+        assert_eq!(
+            repo.rev_parse_single(*spec)?.detach(),
+            git_rev_parse(&path, spec),
+            "{}",
+            spec
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn ids_shorten_like_git() -> crate::Result {
+    let (path, repo) = repo()?;
+    let output = std::process::Command::new("git")
+        .args(&["rev-parse", "--short", "HEAD"])
+        .current_dir(&path)
+        .output()?;
+    let short = std::str::from_utf8(&output.stdout)?.trim();
+    let head = repo.rev_parse_single("HEAD")?;
+    assert_eq!(head.shorten()?.to_string(), short);
+    assert_eq!(repo.rev_parse_single(short)?, head);
+    Ok(())
+}
+
+#[test]
+fn ranges_resolve_each_side() -> crate::Result {
+    let (path, repo) = repo()?;
+    let id = |spec| git_rev_parse(&path, spec);
+    assert_eq!(
+        repo.rev_parse("HEAD~2..main")?
+            .try_map(|id| Ok::<_, Error>(id.detach()))?,
+        Spec::Range {
+            from: id("HEAD~2"),
+            to: id("main")
+        }
+    );
+    assert_eq!(
+        repo.rev_parse("..side")?.try_map(|id| Ok::<_, Error>(id.detach()))?,
+        Spec::Range {
+            from: id("HEAD"),
+            to: id("side")
+        },
+        "missing sides default to HEAD"
+    );
+    assert_eq!(
+        repo.rev_parse("side...")?.try_map(|id| Ok::<_, Error>(id.detach()))?,
+        Spec::SymmetricDifference {
+            left: id("side"),
+            right: id("HEAD")
+        }
+    );
+    assert_eq!(
+        repo.rev_parse("^v1.0")?.try_map(|id| Ok::<_, Error>(id.detach()))?,
+        Spec::Exclude(id("v1.0"))
+    );
+    assert!(matches!(
+        repo.rev_parse_single("HEAD~1..HEAD"),
+        Err(Error::NotASingleRevision { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn missing_revisions_are_structured_errors() -> crate::Result {
+    let (_path, repo) = repo()?;
+    assert!(matches!(
+        repo.rev_parse_single("does-not-exist"),
+        Err(Error::NotFound { .. })
+    ));
+    assert!(matches!(
+        repo.rev_parse_single("HEAD~1^3"),
+        Err(Error::ParentMissing { parent: 3, .. })
+    ));
+    assert!(matches!(
+        repo.rev_parse_single("main~10"),
+        Err(Error::ParentMissing { parent: 1, .. })
+    ));
+    assert!(matches!(
+        repo.rev_parse_single("HEAD:missing"),
+        Err(Error::PathMissing { .. })
+    ));
+    assert!(matches!(
+        repo.rev_parse_single(":/no commit says this"),
+        Err(Error::MessageNotFound { .. })
+    ));
+    assert!(matches!(
+        repo.rev_parse_single("main@{100}"),
+        Err(Error::ReflogEntryOutOfRange { index: 100, .. })
+    ));
+    assert!(matches!(
+        repo.rev_parse_single("@{-10}"),
+        Err(Error::PreviousCheckoutOutOfRange { desired: 10, .. })
+    ));
+    assert!(matches!(repo.rev_parse_single("HEAD^{"), Err(Error::Parse(_))));
+    Ok(())
+}
+
+#[test]
+fn corrupt_tags_are_errors_when_peeled() -> crate::Result {
+    let (path, repo) = repo()?;
+    let corrupt_tag = std::fs::read_to_string(path.join(".git").join("corrupt-tag"))?;
+    assert!(matches!(
+        repo.rev_parse_single(format!("{}^{{}}", corrupt_tag.trim()).as_str()),
+        Err(Error::Decode(_))
+    ));
+    Ok(())
+}
+
+mod parse {
+    use git_repository::{
+        objs::bstr::ByteSlice,
+        rev_spec::{parse, parse::Error, Anchor, Navigation, Peel, ReflogEntry, Rev, Spec},
+    };
+
+    fn rev(input: &str) -> Rev<'_> {
+        parse(input.as_bytes().as_bstr())
+            .expect("valid spec")
+            .single()
+            .expect("single revision")
+    }
+
+    #[test]
+    fn navigation_is_recorded_in_order() {
+        assert_eq!(
+            rev("main~2^2^{tree}:dir/file"),
+            Rev {
+                anchor: Anchor::Name("main".into()),
+                navigation: vec![
+                    Navigation::Ancestor(2),
+                    Navigation::Parent(2),
+                    Navigation::Peel(Peel::Kind(git_repository::objs::Kind::Tree)),
+                    Navigation::Path("dir/file".into()),
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn reflog_selectors() {
+        assert_eq!(
+            rev("@{3}").anchor,
+            Anchor::Reflog {
+                name: None,
+                entry: ReflogEntry::Index(3)
+            }
+        );
+        assert_eq!(
+            rev("main@{2021-08-01 12:00:00 +0200}").anchor,
+            Anchor::Reflog {
+                name: Some("main".into()),
+                entry: ReflogEntry::Date(1627812000)
+            }
+        );
+        assert_eq!(rev("@{-2}").anchor, Anchor::PreviousCheckout(2));
+        for invalid_time_zone in &["HEAD@{2021-01-01 12:00 é}", "HEAD@{2021-01-01 12:00 *0200}"] {
+            assert!(
+                matches!(
+                    parse(invalid_time_zone.as_bytes().as_bstr()),
+                    Err(Error::InvalidReflogSelector { .. })
+                ),
+                "{}",
+                invalid_time_zone
+            );
+        }
+    }
+
+    #[test]
+    fn ranges_only_split_outside_of_paths() {
+        assert!(matches!(
+            parse("HEAD:a..b".into()),
+            Ok(Spec::Include(Rev {
+                navigation,
+                ..
+            })) if navigation == vec![Navigation::Path("a..b".into())]
+        ));
+    }
+
+    #[test]
+    fn unsupported_syntax_is_rejected() {
+        assert!(matches!(parse("".into()), Err(Error::Empty)));
+        assert!(matches!(
+            parse("main@{upstream}".into()),
+            Err(Error::Unsupported { .. })
+        ));
+        assert!(matches!(parse(":0:path".into()), Err(Error::Unsupported { .. })));
+        assert!(matches!(parse("HEAD^{blob".into()), Err(Error::UnclosedBrace { .. })));
+        assert!(matches!(
+            parse("HEAD^{unknown}".into()),
+            Err(Error::UnknownPeelTarget { .. })
+        ));
+    }
+}
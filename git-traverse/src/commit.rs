@@ -2,7 +2,8 @@
 pub mod ancestors {
     use std::{
         borrow::BorrowMut,
-        collections::{BTreeSet, VecDeque},
+        cmp::{Ordering, Reverse},
+        collections::{BTreeSet, BinaryHeap, HashMap, VecDeque},
     };
 
     use git_hash::{oid, ObjectId};
@@ -22,6 +23,78 @@ pub mod ancestors {
                 source(err)
                 from()
             }
+            CommitGraph(err: git_commitgraph::file::commit::Error) {
+                display("The parents of a commit could not be read from the commit-graph")
+                source(err)
+                from()
+            }
+        }
+    }
+
+    /// The order in which commits are returned by the [Ancestors] iterator.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Sorting {
+        /// Commits are returned in the order they are discovered, which is breadth-first from the tips.
+        ///
+        /// This is the fastest mode as it doesn't need commit times.
+        BreadthFirst,
+        /// Commits are returned with the most recent commit time first, like `git log` does by default.
+        ///
+        /// Commits with the same time are returned in the order they were discovered.
+        ByCommitTimeNewestFirst,
+        /// No commit is returned before all of its children, like `git log --topo-order` does.
+        ///
+        /// All reachable commits are looked up before the first one is returned.
+        Topological,
+    }
+
+    impl Default for Sorting {
+        fn default() -> Self {
+            Sorting::BreadthFirst
+        }
+    }
+
+    /// The parents which are followed by the [Ancestors] iterator.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Parents {
+        /// Follow all parents of each commit.
+        All,
+        /// Follow only the first parent of each commit, like `git log --first-parent` does.
+        First,
+    }
+
+    impl Default for Parents {
+        fn default() -> Self {
+            Parents::All
+        }
+    }
+
+    /// A commit waiting to be returned in [`Sorting::ByCommitTimeNewestFirst`] order.
+    #[derive(Clone)]
+    struct QueuedCommit {
+        time: u64,
+        order: Reverse<usize>,
+        id: ObjectId,
+        parents: Vec<ObjectId>,
+    }
+
+    impl PartialEq for QueuedCommit {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+
+    impl Eq for QueuedCommit {}
+
+    impl PartialOrd for QueuedCommit {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for QueuedCommit {
+        fn cmp(&self, other: &Self) -> Ordering {
+            (self.time, self.order).cmp(&(other.time, other.order))
         }
     }
 
@@ -29,16 +102,32 @@ pub mod ancestors {
     #[derive(Default, Clone)]
     pub struct State {
         next: VecDeque<ObjectId>,
+        queue: BinaryHeap<QueuedCommit>,
+        num_queued: usize,
         buf: Vec<u8>,
+        parents_buf: Vec<ObjectId>,
         seen: BTreeSet<ObjectId>,
     }
 
     impl State {
         fn clear(&mut self) {
             self.next.clear();
+            self.queue.clear();
+            self.num_queued = 0;
             self.buf.clear();
+            self.parents_buf.clear();
             self.seen.clear();
         }
+
+        fn enqueue(&mut self, id: ObjectId, time: u64, parents: Vec<ObjectId>) {
+            self.queue.push(QueuedCommit {
+                time,
+                order: Reverse(self.num_queued),
+                id,
+                parents,
+            });
+            self.num_queued += 1;
+        }
     }
 
     /// An iterator over the ancestors one or more starting commits
@@ -46,6 +135,10 @@ pub mod ancestors {
         find: Find,
         predicate: Predicate,
         state: StateMut,
+        sorting: Sorting,
+        parents: Parents,
+        commit_graph: Option<git_commitgraph::Graph>,
+        is_prepared: bool,
     }
 
     impl<Find, StateMut> Ancestors<Find, fn(&oid) -> bool, StateMut>
@@ -108,46 +201,246 @@ pub mod ancestors {
                     }
                 }
             }
-            Self { find, predicate, state }
+            Self {
+                find,
+                predicate,
+                state,
+                sorting: Default::default(),
+                parents: Default::default(),
+                commit_graph: None,
+                is_prepared: false,
+            }
+        }
+
+        /// Return commits in the given `sorting` order, which is [`Sorting::BreadthFirst`] by default.
+        pub fn sorting(mut self, sorting: Sorting) -> Self {
+            self.sorting = sorting;
+            self
+        }
+
+        /// Follow the given `parents` of each commit, which are [all of them][Parents::All] by default.
+        pub fn parents(mut self, parents: Parents) -> Self {
+            self.parents = parents;
+            self
+        }
+
+        /// Read parents and commit times from `commit_graph` if it contains a commit, and only use `find` otherwise.
+        pub fn commit_graph(mut self, commit_graph: Option<git_commitgraph::Graph>) -> Self {
+            self.commit_graph = commit_graph;
+            self
         }
     }
 
-    impl<Find, Predicate, StateMut> Iterator for Ancestors<Find, Predicate, StateMut>
+    impl<Find, Predicate, StateMut> Ancestors<Find, Predicate, StateMut>
     where
         Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
         Predicate: FnMut(&oid) -> bool,
         StateMut: BorrowMut<State>,
     {
-        type Item = Result<ObjectId, Error>;
+        fn num_parents_to_follow(&self) -> usize {
+            match self.parents {
+                Parents::All => usize::MAX,
+                Parents::First => 1,
+            }
+        }
 
-        fn next(&mut self) -> Option<Self::Item> {
+        /// Queue the tips by commit time, and for topological sorting, compute the order of all commits upfront.
+        fn prepare(&mut self) -> Result<(), Error> {
+            if self.sorting == Sorting::BreadthFirst {
+                return Ok(());
+            }
             let state = self.state.borrow_mut();
-            let res = state.next.pop_front();
-            if let Some(oid) = res {
-                match (self.find)(&oid, &mut state.buf) {
-                    Some(mut commit_iter) => {
-                        if let Some(Err(decode_tree_err)) = commit_iter.next() {
-                            return Some(Err(decode_tree_err.into()));
-                        }
-                        for token in commit_iter {
-                            match token {
-                                Ok(immutable::commit::iter::Token::Parent { id }) => {
-                                    let was_inserted = state.seen.insert(id);
-                                    if was_inserted && (self.predicate)(&id) {
-                                        state.next.push_back(id);
-                                    }
-                                }
-                                Ok(_a_token_past_the_parents) => break,
-                                Err(err) => return Some(Err(err.into())),
-                            }
+            while let Some(id) = state.next.pop_front() {
+                let time = lookup(
+                    &id,
+                    self.commit_graph.as_ref(),
+                    &mut self.find,
+                    &mut state.buf,
+                    &mut state.parents_buf,
+                    true,
+                )?
+                .time;
+                let parents = state.parents_buf.clone();
+                state.enqueue(id, time, parents);
+            }
+            if self.sorting == Sorting::Topological {
+                self.sort_topologically()?;
+            }
+            Ok(())
+        }
+
+        /// Walk all commits by commit time and return them such that each commit comes after its children.
+        ///
+        /// Commits without children are placed in the order they were walked, and the parents of a commit are placed in reverse
+        /// order, like in git.
+        fn sort_topologically(&mut self) -> Result<(), Error> {
+            let mut walked = Vec::new();
+            let mut parents_by_id = HashMap::new();
+            while let Some(commit) = self.next_by_commit_time()? {
+                walked.push(commit.id);
+                parents_by_id.insert(commit.id, commit.parents);
+            }
+            let mut num_children: HashMap<_, usize> = walked.iter().map(|id| (*id, 0)).collect();
+            for parent in parents_by_id.values().flatten() {
+                if let Some(count) = num_children.get_mut(parent) {
+                    *count += 1;
+                }
+            }
+
+            let mut stack: Vec<_> = walked.into_iter().filter(|id| num_children[id] == 0).rev().collect();
+            let state = self.state.borrow_mut();
+            while let Some(id) = stack.pop() {
+                for parent in &parents_by_id[&id] {
+                    if let Some(count) = num_children.get_mut(parent) {
+                        *count -= 1;
+                        if *count == 0 {
+                            stack.push(*parent);
                         }
                     }
-                    None => return Some(Err(Error::NotFound { oid })),
                 }
+                state.next.push_back(id);
+            }
+            Ok(())
+        }
+
+        /// Return the most recent commit of the queue after queuing its parents, along with all of its parents.
+        fn next_by_commit_time(&mut self) -> Result<Option<QueuedCommit>, Error> {
+            let num_parents = self.num_parents_to_follow();
+            let state = self.state.borrow_mut();
+            let commit = match state.queue.pop() {
+                Some(commit) => commit,
+                None => return Ok(None),
+            };
+            for id in commit.parents.iter().take(num_parents) {
+                let was_inserted = state.seen.insert(*id);
+                if was_inserted && (self.predicate)(id) {
+                    let time = lookup(
+                        id,
+                        self.commit_graph.as_ref(),
+                        &mut self.find,
+                        &mut state.buf,
+                        &mut state.parents_buf,
+                        true,
+                    )?
+                    .time;
+                    let parents = state.parents_buf.clone();
+                    state.enqueue(*id, time, parents);
+                }
+            }
+            Ok(Some(commit))
+        }
+
+        fn next_by_discovery(&mut self) -> Result<Option<ObjectId>, Error> {
+            let num_parents = self.num_parents_to_follow();
+            let state = self.state.borrow_mut();
+            let id = match state.next.pop_front() {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+            lookup(
+                &id,
+                self.commit_graph.as_ref(),
+                &mut self.find,
+                &mut state.buf,
+                &mut state.parents_buf,
+                false,
+            )?;
+            for parent in state.parents_buf.iter().take(num_parents) {
+                let was_inserted = state.seen.insert(*parent);
+                if was_inserted && (self.predicate)(parent) {
+                    state.next.push_back(*parent);
+                }
+            }
+            Ok(Some(id))
+        }
+    }
+
+    /// The generation number of commits which aren't in a commit-graph, which is larger than all others.
+    pub(crate) const GENERATION_NUMBER_INFINITY: u32 = u32::MAX;
+
+    /// The ordering information of a commit returned by [`lookup()`].
+    pub(crate) struct Info {
+        pub time: u64,
+        pub generation: u32,
+    }
+
+    /// Write the parents of the commit with `id` into `parents` and return its commit time and generation number.
+    ///
+    /// The generation is [`GENERATION_NUMBER_INFINITY`] if the commit isn't in `commit_graph`, in which case the commit time
+    /// is only read if `with_time` is true, and 0 otherwise.
+    pub(crate) fn lookup<Find>(
+        id: &oid,
+        commit_graph: Option<&git_commitgraph::Graph>,
+        find: &mut Find,
+        buf: &mut Vec<u8>,
+        parents: &mut Vec<ObjectId>,
+        with_time: bool,
+    ) -> Result<Info, Error>
+    where
+        Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+    {
+        parents.clear();
+        if let Some((graph, commit)) =
+            commit_graph.and_then(|graph| graph.commit_by_id(id).map(|commit| (graph, commit)))
+        {
+            for pos in commit.iter_parents() {
+                parents.push(graph.id_at(pos?).to_owned());
+            }
+            return Ok(Info {
+                time: commit.committer_timestamp(),
+                generation: commit.generation(),
+            });
+        }
+
+        let mut info = Info {
+            time: 0,
+            generation: GENERATION_NUMBER_INFINITY,
+        };
+        let mut commit_iter = find(id, buf).ok_or_else(|| Error::NotFound { oid: id.to_owned() })?;
+        if let Some(Err(decode_tree_err)) = commit_iter.next() {
+            return Err(decode_tree_err.into());
+        }
+        for token in commit_iter {
+            match token? {
+                immutable::commit::iter::Token::Parent { id } => parents.push(id),
+                immutable::commit::iter::Token::Committer { signature } => {
+                    info.time = signature.time.time.into();
+                    break;
+                }
+                _a_token_past_the_parents if !with_time => break,
+                _author => {}
+            }
+        }
+        Ok(info)
+    }
+
+    impl<Find, Predicate, StateMut> Iterator for Ancestors<Find, Predicate, StateMut>
+    where
+        Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+        Predicate: FnMut(&oid) -> bool,
+        StateMut: BorrowMut<State>,
+    {
+        type Item = Result<ObjectId, Error>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if !self.is_prepared {
+                self.is_prepared = true;
+                if let Err(err) = self.prepare() {
+                    return Some(Err(err));
+                }
+            }
+            match self.sorting {
+                Sorting::BreadthFirst => self.next_by_discovery().transpose(),
+                Sorting::ByCommitTimeNewestFirst => self.next_by_commit_time().map(|c| c.map(|c| c.id)).transpose(),
+                Sorting::Topological => self.state.borrow_mut().next.pop_front().map(Ok),
             }
-            res.map(Ok)
         }
     }
 }
 #[doc(inline)]
 pub use ancestors::Ancestors;
+
+///
+pub mod reach;
+#[doc(inline)]
+pub use reach::Reach;
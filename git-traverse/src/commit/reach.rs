@@ -0,0 +1,316 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap},
+};
+
+use git_hash::{oid, ObjectId};
+use git_object::immutable;
+
+use crate::commit::ancestors::lookup;
+pub use crate::commit::ancestors::Error;
+
+/// The commit was reached from the first commit or commits.
+const PARENT1: u8 = 1 << 0;
+/// The commit was reached from the second commit or commits.
+const PARENT2: u8 = 1 << 1;
+/// The commit is an ancestor of a merge base, or reached from both sides, and can't contribute to the result anymore.
+const STALE: u8 = 1 << 2;
+/// The commit was added to the merge bases.
+const RESULT: u8 = 1 << 3;
+
+/// The parents and ordering information of a commit, kept for the lifetime of a [`Reach`] instance.
+struct Commit {
+    parents: Vec<ObjectId>,
+    time: u64,
+    generation: u32,
+}
+
+/// A commit to be painted, ordered by generation and commit time, with ties resolved in insertion order.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Queued {
+    generation: u32,
+    time: u64,
+    order: Reverse<usize>,
+    id: ObjectId,
+}
+
+/// Answer questions about the reachability of commits, like their merge bases or whether one is the ancestor of another.
+///
+/// Commits are walked by generation number if they are in the commit-graph, and by commit time otherwise. Without
+/// a commit-graph, commit times that go backwards in history can make the results inexact, just like they would be in git.
+///
+/// All commits looked up are cached for the lifetime of the instance, making repeated queries involving the same history
+/// cheaper, for example when comparing many branches to the same main branch.
+pub struct Reach<Find> {
+    find: Find,
+    commit_graph: Option<git_commitgraph::Graph>,
+    buf: Vec<u8>,
+    commits: HashMap<ObjectId, Commit>,
+    flags: HashMap<ObjectId, u8>,
+    queue: BinaryHeap<Queued>,
+    num_queued: usize,
+    /// The amount of entries in `queue` for each commit, as commits can be queued more than once.
+    num_queued_by_id: HashMap<ObjectId, usize>,
+    /// The amount of entries in `queue` whose commits aren't [`STALE`].
+    num_non_stale_queued: usize,
+}
+
+impl<Find> Reach<Find>
+where
+    Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+{
+    /// Create a new instance.
+    ///
+    /// * `find` - a way to lookup new object data during traversal by their ObjectId, writing their data into buffer and returning
+    ///    an iterator over commit tokens if the object is present and is a commit. Caching should be implemented within this function
+    ///    as needed. Not finding a commit results in [`Error::NotFound`].
+    pub fn new(find: Find) -> Self {
+        Reach {
+            find,
+            commit_graph: None,
+            buf: Vec::new(),
+            commits: HashMap::new(),
+            flags: HashMap::new(),
+            queue: BinaryHeap::new(),
+            num_queued: 0,
+            num_queued_by_id: HashMap::new(),
+            num_non_stale_queued: 0,
+        }
+    }
+
+    /// Read parents, commit times and generation numbers from `commit_graph` if it contains a commit, and only use `find` otherwise.
+    pub fn commit_graph(mut self, commit_graph: Option<git_commitgraph::Graph>) -> Self {
+        self.commit_graph = commit_graph;
+        self
+    }
+
+    /// Return the best common ancestor of `one` and `two`, or `None` if they don't have one, similar to `git merge-base`.
+    ///
+    /// If there are multiple best common ancestors, the one with the most recent commit time is returned.
+    pub fn merge_base(
+        &mut self,
+        one: impl Into<ObjectId>,
+        two: impl Into<ObjectId>,
+    ) -> Result<Option<ObjectId>, Error> {
+        Ok(self.merge_bases_many(one, Some(two))?.into_iter().next())
+    }
+
+    /// Return all best common ancestors of `one` and any of the `others`, similar to `git merge-base --all <one> <others>…`.
+    ///
+    /// No returned commit is the ancestor of another one, and they are sorted by commit time, the most recent one first.
+    pub fn merge_bases_many(
+        &mut self,
+        one: impl Into<ObjectId>,
+        others: impl IntoIterator<Item = impl Into<ObjectId>>,
+    ) -> Result<Vec<ObjectId>, Error> {
+        let one = one.into();
+        let others: Vec<ObjectId> = others.into_iter().map(Into::into).collect();
+        if others.contains(&one) {
+            return Ok(vec![one]);
+        }
+
+        let mut bases = Vec::new();
+        for id in self.paint_down_to_common(one, &others, 0)? {
+            if self.flags[&id] & STALE == 0 {
+                bases.push(id);
+            }
+        }
+        if bases.len() > 1 {
+            let mut independent = Vec::with_capacity(bases.len());
+            for id in &bases {
+                let mut is_redundant = false;
+                for other in bases.iter().filter(|other| *other != id) {
+                    if self.is_ancestor(*id, *other)? {
+                        is_redundant = true;
+                        break;
+                    }
+                }
+                if !is_redundant {
+                    independent.push(*id);
+                }
+            }
+            bases = independent;
+        }
+        let commits = &self.commits;
+        bases.sort_by_key(|id| Reverse(commits[id].time));
+        Ok(bases)
+    }
+
+    /// Return true if `ancestor` can be reached from `descendant`, which includes them being the same commit,
+    /// similar to `git merge-base --is-ancestor`.
+    pub fn is_ancestor(
+        &mut self,
+        ancestor: impl Into<ObjectId>,
+        descendant: impl Into<ObjectId>,
+    ) -> Result<bool, Error> {
+        let (ancestor, descendant) = (ancestor.into(), descendant.into());
+        if ancestor == descendant {
+            return Ok(true);
+        }
+        let min_generation = self.commit(&ancestor)?.generation;
+        if min_generation > self.commit(&descendant)?.generation {
+            return Ok(false);
+        }
+        self.paint_down_to_common(ancestor, &[descendant], min_generation)?;
+        Ok(self.flags[&ancestor] & PARENT2 != 0)
+    }
+
+    /// Return the amount of commits reachable from `one` but not from `two`, and the amount of commits reachable from `two`
+    /// but not from `one`, similar to `git rev-list --count --left-right <one>...<two>`.
+    ///
+    /// These are the amounts of commits `one` is ahead of and behind `two`.
+    pub fn ahead_behind(
+        &mut self,
+        one: impl Into<ObjectId>,
+        two: impl Into<ObjectId>,
+    ) -> Result<(usize, usize), Error> {
+        let (one, two) = (one.into(), two.into());
+        self.clear();
+        self.push(one, PARENT1)?;
+        self.push(two, PARENT2)?;
+        while self.has_non_stale_commits() {
+            let id = self.pop().id;
+            let mut flags = self.flags[&id];
+            if flags & (PARENT1 | PARENT2) == PARENT1 | PARENT2 {
+                flags |= STALE;
+            }
+            self.push_parents(&id, flags)?;
+        }
+
+        let (mut ahead, mut behind) = (0, 0);
+        for flags in self.flags.values() {
+            match flags & (PARENT1 | PARENT2) {
+                PARENT1 => ahead += 1,
+                PARENT2 => behind += 1,
+                _ => {}
+            }
+        }
+        Ok((ahead, behind))
+    }
+}
+
+impl<Find> Reach<Find>
+where
+    Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::CommitIter<'a>>,
+{
+    fn clear(&mut self) {
+        self.flags.clear();
+        self.queue.clear();
+        self.num_queued = 0;
+        self.num_queued_by_id.clear();
+        self.num_non_stale_queued = 0;
+    }
+
+    fn commit(&mut self, id: &oid) -> Result<&Commit, Error> {
+        Ok(match self.commits.entry(id.to_owned()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut parents = Vec::new();
+                let info = lookup(
+                    id,
+                    self.commit_graph.as_ref(),
+                    &mut self.find,
+                    &mut self.buf,
+                    &mut parents,
+                    true,
+                )?;
+                entry.insert(Commit {
+                    parents,
+                    time: info.time,
+                    generation: info.generation,
+                })
+            }
+        })
+    }
+
+    /// Add `flags` to those of the commit with `id` and queue it.
+    fn push(&mut self, id: ObjectId, flags: u8) -> Result<(), Error> {
+        let commit = self.commit(&id)?;
+        let (generation, time) = (commit.generation, commit.time);
+        let commit_flags = self.flags.entry(id).or_insert(0);
+        let was_stale = *commit_flags & STALE != 0;
+        *commit_flags |= flags;
+        let is_stale = *commit_flags & STALE != 0;
+        let num_queued = self.num_queued_by_id.entry(id).or_insert(0);
+        if !was_stale && is_stale {
+            self.num_non_stale_queued -= *num_queued;
+        }
+        *num_queued += 1;
+        if !is_stale {
+            self.num_non_stale_queued += 1;
+        }
+        self.queue.push(Queued {
+            generation,
+            time,
+            order: Reverse(self.num_queued),
+            id,
+        });
+        self.num_queued += 1;
+        Ok(())
+    }
+
+    /// Add `flags` to the parents of the commit with `id` and queue them, unless they already have all of them.
+    fn push_parents(&mut self, id: &oid, flags: u8) -> Result<(), Error> {
+        let parents = self.commit(id)?.parents.clone();
+        for parent in parents {
+            if self.flags.get(&parent).map_or(0, |f| *f) & flags != flags {
+                self.push(parent, flags)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the next commit from the queue, which must not be empty.
+    fn pop(&mut self) -> Queued {
+        let queued = self.queue.pop().expect("non-empty queue");
+        *self
+            .num_queued_by_id
+            .get_mut(&queued.id)
+            .expect("queued commits are counted") -= 1;
+        if self.flags[&queued.id] & STALE == 0 {
+            self.num_non_stale_queued -= 1;
+        }
+        queued
+    }
+
+    fn has_non_stale_commits(&self) -> bool {
+        self.num_non_stale_queued > 0
+    }
+
+    /// Walk the history of `one` and `others` by generation and commit time until all remaining commits are reachable from both,
+    /// and return the common commits whose parents were marked as stale in the order they were found.
+    ///
+    /// Commits with a generation lower than `min_generation` aren't walked, as they can't be descendants of a commit with
+    /// `min_generation`. Commits which aren't in the commit-graph have the highest possible generation.
+    fn paint_down_to_common(
+        &mut self,
+        one: ObjectId,
+        others: &[ObjectId],
+        min_generation: u32,
+    ) -> Result<Vec<ObjectId>, Error> {
+        self.clear();
+        self.push(one, PARENT1)?;
+        for other in others {
+            self.push(*other, PARENT2)?;
+        }
+
+        let mut result = Vec::new();
+        while self.has_non_stale_commits() {
+            let queued = self.pop();
+            if queued.generation < min_generation {
+                break;
+            }
+            let commit_flags = self.flags.get_mut(&queued.id).expect("queued commits have flags");
+            let mut flags = *commit_flags & (PARENT1 | PARENT2 | STALE);
+            if flags == PARENT1 | PARENT2 {
+                if *commit_flags & RESULT == 0 {
+                    *commit_flags |= RESULT;
+                    result.push(queued.id);
+                }
+                flags |= STALE;
+            }
+            self.push_parents(&queued.id, flags)?;
+        }
+        Ok(result)
+    }
+}
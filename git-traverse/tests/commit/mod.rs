@@ -1,3 +1,33 @@
+use std::{path::Path, process::Command};
+
+use git_hash::ObjectId;
+
+use crate::hex_to_id;
+
+/// Run git with `args` in `repo_dir` and return the object ids it prints, one per line, along with whether it succeeded.
+fn try_git_ids(repo_dir: &Path, args: &[&str]) -> (bool, Vec<ObjectId>) {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .args(args)
+        .env_remove("GIT_DIR")
+        .output()
+        .expect("git can be executed");
+    let ids = String::from_utf8(output.stdout)
+        .expect("valid UTF-8")
+        .lines()
+        .map(hex_to_id)
+        .collect();
+    (output.status.success(), ids)
+}
+
+/// Run git with `args` in `repo_dir` and return the object ids it prints, one per line.
+fn git_ids(repo_dir: &Path, args: &[&str]) -> Vec<ObjectId> {
+    let (success, ids) = try_git_ids(repo_dir, args);
+    assert!(success, "git {:?} failed", args);
+    ids
+}
+
 mod ancestor {
     use git_hash::{oid, ObjectId};
     use git_odb::{linked::Store, pack, FindExt};
@@ -7,7 +37,7 @@ mod ancestor {
 
     fn db() -> crate::Result<Store> {
         let dir = git_testtools::scripted_fixture_repo_read_only("make_traversal_repo_for_commits.sh")?;
-        let db = Store::at(dir.join(".git").join("objects"))?;
+        let db = Store::at(dir.join(".git").join("objects"), git_hash::Kind::Sha1)?;
         Ok(db)
     }
 
@@ -160,4 +190,279 @@ mod ancestor {
             },
         )
     }
+
+    mod sorting {
+        use git_hash::ObjectId;
+        use git_odb::{linked::Store, pack, FindExt};
+        use git_traverse::commit::{
+            ancestors::{Parents, Sorting, State},
+            Ancestors,
+        };
+
+        use crate::commit::git_ids;
+
+        /// Return all commits reachable from `tips` for each of the available commit-graphs, none, one which only contains
+        /// some of the commits, and one which contains all of them.
+        fn traverse_with_all_graphs(
+            tips: &[&str],
+            sorting: Sorting,
+            parents: Parents,
+        ) -> crate::Result<(Vec<ObjectId>, Vec<Vec<ObjectId>>)> {
+            let repo_dir =
+                git_testtools::scripted_fixture_repo_read_only("make_traversal_repo_for_commits_with_dates.sh")?;
+            let mut args = vec!["rev-parse"];
+            args.extend_from_slice(tips);
+            let tips = git_ids(&repo_dir, &args);
+            let db = Store::at(repo_dir.join(".git").join("objects"), git_hash::Kind::Sha1)?;
+
+            let mut results = Vec::new();
+            for graph_dir in &[None, Some("partial-graph"), Some("full-graph")] {
+                let graph = graph_dir
+                    .map(|dir| git_commitgraph::Graph::from_info_dir(repo_dir.join(dir)))
+                    .transpose()?;
+                let commits: Result<Vec<_>, _> = Ancestors::new(tips.iter().cloned(), State::default(), |oid, buf| {
+                    db.find_existing_commit_iter(oid, buf, &mut pack::cache::Never).ok()
+                })
+                .sorting(sorting)
+                .parents(parents)
+                .commit_graph(graph)
+                .collect();
+                results.push(commits?);
+            }
+            Ok((tips, results))
+        }
+
+        fn assert_all_match_git(tips: &[&str], sorting: Sorting, parents: Parents, git_args: &[&str]) -> crate::Result {
+            let repo_dir =
+                git_testtools::scripted_fixture_repo_read_only("make_traversal_repo_for_commits_with_dates.sh")?;
+            let mut args = vec!["rev-list"];
+            args.extend_from_slice(git_args);
+            args.extend_from_slice(tips);
+            let expected = git_ids(&repo_dir, &args);
+            let (_, results) = traverse_with_all_graphs(tips, sorting, parents)?;
+            for (graph_kind, commits) in ["no", "a partial", "a full"].iter().zip(results) {
+                assert_eq!(commits, expected, "with {} commit-graph", graph_kind);
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn by_commit_time_newest_first() -> crate::Result {
+            assert_all_match_git(&["main"], Sorting::ByCommitTimeNewestFirst, Parents::All, &[])
+        }
+
+        #[test]
+        fn by_commit_time_newest_first_with_multiple_tips() -> crate::Result {
+            assert_all_match_git(
+                &["b1", "b2", "main~1^3"],
+                Sorting::ByCommitTimeNewestFirst,
+                Parents::All,
+                &[],
+            )
+        }
+
+        #[test]
+        fn topological() -> crate::Result {
+            assert_all_match_git(&["main"], Sorting::Topological, Parents::All, &["--topo-order"])
+        }
+
+        #[test]
+        fn topological_with_multiple_tips() -> crate::Result {
+            assert_all_match_git(
+                &["b2", "main~2", "b3"],
+                Sorting::Topological,
+                Parents::All,
+                &["--topo-order"],
+            )
+        }
+
+        #[test]
+        fn first_parent_only() -> crate::Result {
+            assert_all_match_git(
+                &["main"],
+                Sorting::ByCommitTimeNewestFirst,
+                Parents::First,
+                &["--first-parent"],
+            )?;
+            assert_all_match_git(
+                &["main"],
+                Sorting::Topological,
+                Parents::First,
+                &["--first-parent", "--topo-order"],
+            )
+        }
+
+        #[test]
+        fn breadth_first_is_the_same_with_or_without_commit_graph() -> crate::Result {
+            let (tips, results) = traverse_with_all_graphs(&["main"], Sorting::BreadthFirst, Parents::All)?;
+            assert_eq!(results[0][0], tips[0]);
+            assert_eq!(results[0].len(), 12);
+            assert!(results.iter().all(|commits| *commits == results[0]));
+
+            let (_, results) = traverse_with_all_graphs(&["main"], Sorting::BreadthFirst, Parents::First)?;
+            assert_eq!(results[0].len(), 8, "only the first-parent chain is traversed");
+            assert!(results.iter().all(|commits| *commits == results[0]));
+            Ok(())
+        }
+    }
+}
+
+mod reach {
+    use std::{path::Path, process::Command};
+
+    use git_hash::ObjectId;
+    use git_odb::{linked::Store, pack, FindExt};
+    use git_traverse::commit::Reach;
+
+    use crate::commit::{git_ids, try_git_ids};
+
+    const REVS: &[&str] = &[
+        "main",
+        "main~1",
+        "main~2",
+        "x",
+        "x~1",
+        "x~2",
+        "y",
+        "y~1",
+        "y~2",
+        "unrelated",
+    ];
+
+    fn repo_dir() -> crate::Result<std::path::PathBuf> {
+        git_testtools::scripted_fixture_repo_read_only("make_repo_for_reachability.sh")
+    }
+
+    fn ids(repo_dir: &Path) -> Vec<ObjectId> {
+        let mut args = vec!["rev-parse"];
+        args.extend_from_slice(REVS);
+        git_ids(repo_dir, &args)
+    }
+
+    /// Call `f` with a new instance for each of the available commit-graphs, none, one which only contains
+    /// some of the commits, and one which contains all of them.
+    fn for_each_graph(
+        repo_dir: &Path,
+        mut f: impl FnMut(&str, &mut dyn FnMut(ObjectId, ObjectId) -> Outcome) -> crate::Result,
+    ) -> crate::Result {
+        let db = Store::at(repo_dir.join(".git").join("objects"), git_hash::Kind::Sha1)?;
+        for (graph_kind, graph_dir) in &[
+            ("no", None),
+            ("a partial", Some("partial-graph")),
+            ("a full", Some("full-graph")),
+        ] {
+            let graph = graph_dir
+                .map(|dir| git_commitgraph::Graph::from_info_dir(repo_dir.join(dir)))
+                .transpose()?;
+            let mut reach = Reach::new(|oid, buf| db.find_existing_commit_iter(oid, buf, &mut pack::cache::Never).ok())
+                .commit_graph(graph);
+            f(graph_kind, &mut |one, two| Outcome {
+                merge_bases: reach.merge_bases_many(one, Some(two)).expect("valid history"),
+                merge_base: reach.merge_base(one, two).expect("valid history"),
+                is_ancestor: reach.is_ancestor(one, two).expect("valid history"),
+                ahead_behind: reach.ahead_behind(one, two).expect("valid history"),
+            })?;
+        }
+        Ok(())
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Outcome {
+        merge_bases: Vec<ObjectId>,
+        merge_base: Option<ObjectId>,
+        is_ancestor: bool,
+        ahead_behind: (usize, usize),
+    }
+
+    fn git_outcome(repo_dir: &Path, one: &ObjectId, two: &ObjectId) -> Outcome {
+        let (one, two) = (one.to_string(), two.to_string());
+        let (_, merge_bases) = try_git_ids(repo_dir, &["merge-base", "--all", &one, &two]);
+        let (_, merge_base) = try_git_ids(repo_dir, &["merge-base", &one, &two]);
+        let (is_ancestor, _) = try_git_ids(repo_dir, &["merge-base", "--is-ancestor", &one, &two]);
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_dir)
+            .args(["rev-list", "--count", "--left-right", &format!("{}...{}", one, two)])
+            .output()
+            .expect("git can be executed");
+        let counts: Vec<usize> = String::from_utf8(output.stdout)
+            .expect("valid UTF-8")
+            .split_whitespace()
+            .map(|count| count.parse().expect("a number"))
+            .collect();
+        Outcome {
+            merge_bases,
+            merge_base: merge_base.into_iter().next(),
+            is_ancestor,
+            ahead_behind: (counts[0], counts[1]),
+        }
+    }
+
+    #[test]
+    fn all_pairs_match_git() -> crate::Result {
+        let repo_dir = repo_dir()?;
+        let ids = ids(&repo_dir);
+        let mut expected = Vec::new();
+        for one in &ids {
+            for two in &ids {
+                expected.push(git_outcome(&repo_dir, one, two));
+            }
+        }
+        assert_eq!(
+            expected.iter().filter(|outcome| outcome.merge_bases.len() == 2).count(),
+            8,
+            "the criss-cross merges and their descendants have two merge bases"
+        );
+
+        for_each_graph(&repo_dir, |graph_kind, outcome| {
+            let mut expected = expected.iter();
+            for (one, rev_one) in ids.iter().zip(REVS) {
+                for (two, rev_two) in ids.iter().zip(REVS) {
+                    assert_eq!(
+                        &outcome(*one, *two),
+                        expected.next().expect("one outcome per pair"),
+                        "{} and {} with {} commit-graph",
+                        rev_one,
+                        rev_two,
+                        graph_kind
+                    );
+                }
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn merge_bases_many() -> crate::Result {
+        let repo_dir = repo_dir()?;
+        let db = Store::at(repo_dir.join(".git").join("objects"), git_hash::Kind::Sha1)?;
+        for revs in &[
+            &["x~1", "y~1", "main"][..],
+            &["x", "y", "unrelated"],
+            &["main", "x", "y"],
+            &["x", "main~1", "y~2"],
+        ] {
+            let mut args = vec!["rev-parse"];
+            args.extend_from_slice(revs);
+            let ids = git_ids(&repo_dir, &args);
+            let mut args = vec!["merge-base", "--all"];
+            args.extend_from_slice(revs);
+            let (_, expected) = try_git_ids(&repo_dir, &args);
+
+            for graph_dir in &["partial-graph", "full-graph"] {
+                let graph = git_commitgraph::Graph::from_info_dir(repo_dir.join(graph_dir))?;
+                let mut reach =
+                    Reach::new(|oid, buf| db.find_existing_commit_iter(oid, buf, &mut pack::cache::Never).ok())
+                        .commit_graph(Some(graph));
+                assert_eq!(
+                    reach.merge_bases_many(ids[0], ids[1..].iter().cloned())?,
+                    expected,
+                    "{:?} with {}",
+                    revs,
+                    graph_dir
+                );
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,45 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function commit_at() {
+  local time="$((1600000000 + $1))"
+  shift
+  GIT_AUTHOR_DATE="$time +0000" GIT_COMMITTER_DATE="$time +0000" "$@"
+}
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+commit_at 1000 git commit -q --allow-empty -m c1
+commit_at 1010 git commit -q --allow-empty -m c2
+git branch x
+git branch y
+
+git checkout -q x
+commit_at 1020 git commit -q --allow-empty -m x1
+git checkout -q y
+commit_at 1025 git commit -q --allow-empty -m y1
+
+git checkout -q x
+commit_at 1030 git merge -q --no-ff y -m "x2, criss-cross"
+git checkout -q y
+commit_at 1035 git merge -q --no-ff x~1 -m "y2, criss-cross"
+
+git commit-graph write --no-progress --reachable
+mkdir partial-graph
+mv .git/objects/info/commit-graph partial-graph/
+
+git checkout -q x
+commit_at 1040 git commit -q --allow-empty -m x3
+git checkout -q y
+commit_at 1045 git commit -q --allow-empty -m y3
+git checkout -q main
+commit_at 1050 git commit -q --allow-empty -m c3
+
+git checkout -q --orphan unrelated
+commit_at 1060 git commit -q --allow-empty -m u1
+
+git commit-graph write --no-progress --reachable
+mkdir full-graph
+mv .git/objects/info/commit-graph full-graph/
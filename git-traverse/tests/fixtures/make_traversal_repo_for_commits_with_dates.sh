@@ -0,0 +1,50 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function commit_at() {
+  local time="$((1600000000 + $1))"
+  shift
+  GIT_AUTHOR_DATE="$time +0000" GIT_COMMITTER_DATE="$time +0000" "$@"
+}
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+commit_at 1000 git commit -q --allow-empty -m c1
+git branch b2
+commit_at 1010 git commit -q --allow-empty -m c2
+
+git checkout -q -b b1
+commit_at 1020 git commit -q --allow-empty -m b1c1
+
+git checkout -q main
+commit_at 1015 git commit -q --allow-empty -m c3
+git branch b3
+
+git checkout -q b1
+commit_at 1030 git commit -q --allow-empty -m b1c2
+
+git checkout -q b2
+commit_at 1005 git commit -q --allow-empty -m b2c1
+
+git checkout -q main
+commit_at 1040 git commit -q --allow-empty -m c4
+commit_at 1050 git merge -q --no-ff b1 -m m1
+
+git commit-graph write --no-progress --reachable
+mkdir partial-graph
+mv .git/objects/info/commit-graph partial-graph/
+
+commit_at 1045 git commit -q --allow-empty -m "c5, older than its parent"
+
+git checkout -q b3
+commit_at 1055 git commit -q --allow-empty -m b3c1
+
+git checkout -q main
+commit_at 1070 git merge -q --no-ff b2 b3 -m octopus >/dev/null
+commit_at 1080 git commit -q --allow-empty -m c6
+
+git commit-graph write --no-progress --reachable
+mkdir full-graph
+mv .git/objects/info/commit-graph full-graph/
@@ -5,7 +5,7 @@ use crate::hex_to_id;
 
 fn db() -> crate::Result<Store> {
     let dir = git_testtools::scripted_fixture_repo_read_only("make_traversal_repo_for_trees.sh")?;
-    let db = Store::at(dir.join(".git").join("objects"))?;
+    let db = Store::at(dir.join(".git").join("objects"), git_hash::Kind::Sha1)?;
     Ok(db)
 }
 
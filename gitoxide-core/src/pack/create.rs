@@ -70,6 +70,9 @@ pub struct Context<W> {
     /// If unset, counting will only use one thread and thus yield the same sequence of objects in any case.
     /// If the `thread_limit` is 1, the count is always deterministic.
     pub nondeterministic_count: bool,
+    /// If set, objects that aren't already in a pack will be delta-compressed against each other, which takes more time
+    /// but produces smaller packs. Otherwise they are written as base objects.
+    pub delta_compression: bool,
     /// If set, don't use more than this amount of threads.
     /// Otherwise, usually use as many threads as there are logical cores.
     /// A value of 0 is interpreted as no-limit
@@ -89,6 +92,7 @@ pub fn create<W>(
     Context {
         expansion,
         nondeterministic_count,
+        delta_compression,
         thread_limit,
         statistics,
         mut out,
@@ -102,6 +106,8 @@ where
     progress.init(Some(2), progress::steps());
     let tips = tips.into_iter();
     let make_cancellation_err = || anyhow!("Cancelled by user");
+    // Traversing from tips yields all ancestors, which is when bitmaps don't change the outcome of counting.
+    let use_reachability_bitmaps = input.is_none();
     let (db, input): (
         _,
         Box<dyn Iterator<Item = Result<ObjectId, input_iteration::Error>> + Send>,
@@ -194,6 +200,7 @@ where
                     thread_limit,
                     chunk_size,
                     input_object_expansion,
+                    use_reachability_bitmaps,
                 },
             )?
         } else {
@@ -204,6 +211,7 @@ where
                 progress,
                 &interrupt::IS_INTERRUPTED,
                 input_object_expansion,
+                use_reachability_bitmaps,
             )?
         };
         stats.counts = count_stats;
@@ -222,10 +230,14 @@ where
             progress,
             pack::data::output::entry::iter_from_counts::Options {
                 thread_limit,
-                mode: pack::data::output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+                mode: if delta_compression {
+                    pack::data::output::entry::iter_from_counts::Mode::PackCopyAndDeltaCompression
+                } else {
+                    pack::data::output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects
+                },
                 allow_thin_pack: false, // todo: make this configurable
                 chunk_size,
-                version: Default::default(),
+                ..Default::default()
             },
         ))
     };
@@ -311,6 +323,7 @@ fn human_output(
                 decoded_and_recompressed_objects,
                 missing_objects,
                 objects_copied_from_pack,
+                delta_compressed_objects,
             },
     }: Statistics,
     mut out: impl std::io::Write,
@@ -331,9 +344,10 @@ fn human_output(
     #[rustfmt::skip]
     writeln!(
         out,
-        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
+        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
         "decoded and recompressed", decoded_and_recompressed_objects,
         "pack-to-pack copies", objects_copied_from_pack,
+        "delta compressed", delta_compressed_objects,
         "missing objects", missing_objects,
         width = width
     )?;
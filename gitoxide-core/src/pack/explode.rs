@@ -151,6 +151,8 @@ pub struct Context {
     pub delete_pack: bool,
     pub sink_compress: bool,
     pub verify: bool,
+    /// The kind of hash used by the object ids and checksums of the pack
+    pub hash_kind: hash::Kind,
     pub should_interrupt: Arc<AtomicBool>,
 }
 
@@ -164,13 +166,14 @@ pub fn pack_or_pack_index(
         delete_pack,
         sink_compress,
         verify,
+        hash_kind,
         should_interrupt,
     }: Context,
 ) -> Result<()> {
     use anyhow::Context;
 
     let path = pack_path.as_ref();
-    let bundle = pack::Bundle::at(path).with_context(|| {
+    let bundle = pack::Bundle::at(path, hash_kind).with_context(|| {
         format!(
             "Could not find .idx or .pack file from given file at '{}'",
             path.display()
@@ -212,7 +215,7 @@ pub fn pack_or_pack_index(
                 let mut read_buf = Vec::new();
                 move |object_kind, buf, index_entry, progress| {
                     let written_id = out
-                        .write_buf(object_kind, buf, hash::Kind::Sha1)
+                        .write_buf(object_kind, buf, index_entry.oid.kind())
                         .map_err(|err| Error::Write(Box::new(err) as Box<dyn std::error::Error + Send + Sync>, object_kind, index_entry.oid))?;
                     if written_id != index_entry.oid {
                         if let object::Kind::Tree = object_kind {
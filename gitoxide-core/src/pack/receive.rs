@@ -300,6 +300,7 @@ fn receive_pack_blocking<W: io::Write>(
         thread_limit: ctx.thread_limit,
         index_kind: pack::index::Version::V2,
         iteration_mode: pack::data::input::Mode::Verify,
+        hash_kind: Default::default(),
     };
     let outcome = pack::bundle::Bundle::write_to_directory(
         input,
@@ -66,6 +66,8 @@ pub struct Context<W1: io::Write, W2: io::Write> {
     pub thread_limit: Option<usize>,
     pub mode: index::verify::Mode,
     pub algorithm: Algorithm,
+    /// The kind of hash used by the object ids and checksums of the pack
+    pub hash_kind: git_repository::hash::Kind,
     pub should_interrupt: Arc<AtomicBool>,
 }
 
@@ -76,6 +78,7 @@ impl Default for Context<Vec<u8>, Vec<u8>> {
             thread_limit: None,
             mode: index::verify::Mode::Sha1Crc32,
             algorithm: Algorithm::LessMemory,
+            hash_kind: Default::default(),
             out: Vec::new(),
             err: Vec::new(),
             should_interrupt: Default::default(),
@@ -114,6 +117,7 @@ pub fn pack_or_pack_index<W1, W2>(
         output_statistics,
         thread_limit,
         algorithm,
+        hash_kind,
         should_interrupt,
     }: Context<W1, W2>,
 ) -> Result<(ObjectId, Option<index::traverse::Outcome>)>
@@ -130,7 +134,7 @@ where
     })?;
     let res = match ext {
         "pack" => {
-            let pack = odb::pack::data::File::at(path).with_context(|| "Could not open pack file")?;
+            let pack = odb::pack::data::File::at(path, hash_kind).with_context(|| "Could not open pack file")?;
             pack.verify_checksum(
                 progress::DoOrDiscard::from(progress).add_child("Sha1 of pack"),
                 &should_interrupt,
@@ -138,9 +142,9 @@ where
             .map(|id| (id, None))?
         }
         "idx" => {
-            let idx = odb::pack::index::File::at(path).with_context(|| "Could not open pack index file")?;
+            let idx = odb::pack::index::File::at(path, hash_kind).with_context(|| "Could not open pack index file")?;
             let packfile_path = path.with_extension("pack");
-            let pack = odb::pack::data::File::at(&packfile_path)
+            let pack = odb::pack::data::File::at(&packfile_path, hash_kind)
                 .map_err(|e| {
                     writeln!(
                         err,
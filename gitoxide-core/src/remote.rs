@@ -215,17 +215,13 @@ pub mod refs {
     pub(crate) fn print(mut out: impl io::Write, refs: &[Ref]) -> io::Result<()> {
         for r in refs {
             match r {
-                Ref::Direct { path, object } => writeln!(&mut out, "{} {}", object.to_sha1_hex_string(), path),
+                Ref::Direct { path, object } => writeln!(&mut out, "{} {}", object, path),
                 Ref::Peeled { path, object, tag } => {
-                    writeln!(&mut out, "{} {} tag:{}", object.to_sha1_hex_string(), path, tag)
+                    writeln!(&mut out, "{} {} tag:{}", object, path, tag)
+                }
+                Ref::Symbolic { path, target, object } => {
+                    writeln!(&mut out, "{} {} symref-target:{}", object, path, target)
                 }
-                Ref::Symbolic { path, target, object } => writeln!(
-                    &mut out,
-                    "{} {} symref-target:{}",
-                    object.to_sha1_hex_string(),
-                    path,
-                    target
-                ),
             }?;
         }
         Ok(())
@@ -32,6 +32,7 @@ pub fn main() -> Result<()> {
             repository,
             expansion,
             nondeterministic_count,
+            delta_compression,
             statistics,
             tips,
             output_directory,
@@ -63,6 +64,7 @@ pub fn main() -> Result<()> {
                 core::pack::create::Context {
                     expansion,
                     nondeterministic_count,
+                    delta_compression,
                     statistics: if statistics { Some(OutputFormat::Human) } else { None },
                     out: stdout(),
                     thread_limit,
@@ -161,6 +163,7 @@ pub fn main() -> Result<()> {
                     delete_pack,
                     sink_compress,
                     verify,
+                    hash_kind: Default::default(),
                     should_interrupt,
                 },
             )
@@ -192,6 +195,7 @@ pub fn main() -> Result<()> {
                     },
                     out: stdout(),
                     err: stderr(),
+                    hash_kind: Default::default(),
                     should_interrupt,
                 },
             )
@@ -183,6 +183,12 @@ pub struct PackCreate {
     /// way the resulting pack is structured.
     pub nondeterministic_count: bool,
 
+    #[argh(switch)]
+    /// if set, objects that aren't yet packed will be delta-compressed against each other.
+    ///
+    /// This takes more time but produces smaller packs.
+    pub delta_compression: bool,
+
     #[argh(switch, short = 's')]
     /// if set statistical information will be presented to inform about pack creation details.
     /// It's a form of instrumentation for developers to help improve pack generation.
@@ -38,6 +38,7 @@ pub fn main() -> Result<()> {
             expansion,
             statistics,
             nondeterministic_count,
+            delta_compression,
             tips,
             output_directory,
         } => {
@@ -63,6 +64,7 @@ pub fn main() -> Result<()> {
                     let context = core::pack::create::Context {
                         thread_limit,
                         nondeterministic_count,
+                        delta_compression,
                         statistics: if statistics { Some(format) } else { None },
                         out,
                         expansion: expansion.unwrap_or_else(|| {
@@ -184,6 +186,7 @@ pub fn main() -> Result<()> {
                         delete_pack,
                         sink_compress,
                         verify,
+                        hash_kind: Default::default(),
                         should_interrupt,
                     },
                 )
@@ -218,6 +221,7 @@ pub fn main() -> Result<()> {
                         thread_limit,
                         mode,
                         algorithm,
+                        hash_kind: Default::default(),
                         should_interrupt,
                     },
                 )
@@ -63,6 +63,12 @@ pub enum Subcommands {
         /// way the resulting pack is structured.
         nondeterministic_count: bool,
 
+        #[clap(long)]
+        /// if set, objects that aren't yet packed will be delta-compressed against each other.
+        ///
+        /// This takes more time but produces smaller packs.
+        delta_compression: bool,
+
         #[clap(long, short = 's')]
         /// If set statistical information will be presented to inform about pack creation details.
         /// It's a form of instrumentation for developers to help improve pack generation.